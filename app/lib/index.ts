@@ -1,5 +1,5 @@
 import { ComputeBudgetProgram, Connection, Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY, Transaction } from "@solana/web3.js";
-import { Program, BN } from "@coral-xyz/anchor";
+import { Program, BN, utils } from "@coral-xyz/anchor";
 import type { Anyswap } from "../../target/types/anyswap";
 import type { Idl } from "@coral-xyz/anchor";
 import * as token from "@solana/spl-token";
@@ -9,6 +9,12 @@ import {
     type Provider,
 } from '@coral-xyz/anchor';
 
+// Pool 账户大小
+const POOL_SPACE = 8 + 2 + 6 + 32 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + (128 * 4) + 8 + 8 + 32 + 8 + 8 + 32 + (144 * 1024); // 148296 bytes
+// Pool 账户的魔数及其偏移（discriminator 8 字节 + token_count 2 字节）
+const POOL_MAGIC = Buffer.from("ANYS");
+const POOL_MAGIC_OFFSET = 10;
+
 export class AnySwap {
     private provider: Provider;
     private program: Program<Anyswap>;
@@ -65,7 +71,7 @@ export class AnySwap {
         const [poolAuthority] = this.getPoolAuthority(pool);
         const poolMint = this.getPoolMint(pool);
 
-        const poolSpace = 8 + 2 + 6 + 32 + 8 + 8 + 8 + (72 * 1024); // 73792 bytes
+        const poolSpace = POOL_SPACE;
         const lamports = await this.connection.getMinimumBalanceForRentExemption(poolSpace);
 
         const createAccountIx = SystemProgram.createAccount({
@@ -77,7 +83,7 @@ export class AnySwap {
         });
 
         const createPoolIx = await this.program.methods
-            .createPool(feeNumerator, feeDenominator)
+            .createPool(feeNumerator, feeDenominator, false, false, null)
             .accountsPartial({
                 poolCreator: adminPubkey,
                 pool: pool,
@@ -111,13 +117,19 @@ export class AnySwap {
     }
 
     // 添加 Token 到 Pool
+    // 已有 LP 时注入初始流动性需要传入 adminLpToken（admin 的 LP token 账户），接收上架铸造的 LP；
+    // 同时声明 liquidity 按 quote token（索引为 quoteIndex）计价的价值 liquidityValue，并传入该 token 的 vault（quoteVault）
     async addTokenToPool(
         pool: PublicKey,
         mint: PublicKey,
         weight: BN,
         liquidity: BN,
         existingVaults: PublicKey[] = [],
-        admin?: PublicKey
+        admin?: PublicKey,
+        adminLpToken?: PublicKey,
+        quoteIndex: number = 0,
+        liquidityValue: BN = new BN(0),
+        quoteVault?: PublicKey
     ): Promise<string> {
         const adminPubkey = admin || this.provider.wallet!.publicKey;
         const remainingAccounts = existingVaults.flatMap((vault) => [
@@ -125,10 +137,14 @@ export class AnySwap {
         ]);
 
         return await this.program.methods
-            .addTokenToPool(weight, liquidity)
+            .addTokenToPool(weight, liquidity, quoteIndex, liquidityValue)
             .accounts({
                 pool: pool,
                 mint: mint,
+                poolMint: this.getPoolMint(pool),
+                lpTokenProgram: token.TOKEN_PROGRAM_ID,
+                adminLpToken: adminLpToken ?? null,
+                quoteVault: quoteVault ?? null,
                 admin: adminPubkey,
                 payer: this.provider.wallet!.publicKey,
             })
@@ -142,7 +158,8 @@ export class AnySwap {
         amounts: BN[],
         userTokenAccounts: PublicKey[],
         vaultAccounts: PublicKey[],
-        owner?: PublicKey
+        owner?: PublicKey,
+        minLpOut?: BN
     ): Promise<string> {
         const ownerPubkey = owner || this.provider.wallet!.publicKey;
         const [poolAuthority] = this.getPoolAuthority(pool);
@@ -162,6 +179,7 @@ export class AnySwap {
             userPoolAta: userPoolAta,
             owner: ownerPubkey,
             tokenProgram: token.TOKEN_PROGRAM_ID,
+            lpTokenProgram: token.TOKEN_PROGRAM_ID,
         };
 
         const remainingAccounts = userTokenAccounts
@@ -172,7 +190,7 @@ export class AnySwap {
             .flat();
 
         return await this.program.methods
-            .addLiquidity(amounts)
+            .addLiquidity(amounts, minLpOut ?? new BN(0))
             .accounts(accounts)
             .remainingAccounts(remainingAccounts)
             .rpc();
@@ -204,6 +222,7 @@ export class AnySwap {
             userPoolAta: userPoolAta,
             owner: ownerPubkey,
             tokenProgram: token.TOKEN_PROGRAM_ID,
+            lpTokenProgram: token.TOKEN_PROGRAM_ID,
         };
 
         const remainingAccounts = userTokenAccounts
@@ -269,7 +288,7 @@ export class AnySwap {
             throw new Error("Too many tokens to swap");
         }
         return await this.program.methods
-            .swapAnyswap(amounts_tolerance, is_in_token)
+            .swapAnyswap(amounts_tolerance, is_in_token, null, null)
             .accountsPartial({
                 pool: pool,
                 owner: ownerPubkey,
@@ -320,10 +339,14 @@ export class AnySwap {
     }
 
     // 从 Pool 移除 Token
+    // vault 有余额时需要传入 adminToken（管理员的 token 账户），余额会在关闭 vault 前转入；
+    // vault 中有属于 LP 的余额时还需要传入 adminPoolAta（管理员的 LP 账户），从中销毁该 token 价值占比的 LP
     async removeTokenFromPool(
         pool: PublicKey,
         mint: PublicKey,
-        admin?: PublicKey
+        admin?: PublicKey,
+        adminToken?: PublicKey,
+        adminPoolAta?: PublicKey
     ): Promise<string> {
         const adminPubkey = admin || this.provider.wallet!.publicKey;
         const [poolAuthority] = this.getPoolAuthority(pool);
@@ -336,11 +359,27 @@ export class AnySwap {
                 poolAuthority: poolAuthority,
                 mint: mint,
                 vault: vault,
+                adminToken: adminToken ?? null,
+                poolMint: this.getPoolMint(pool),
+                adminPoolAta: adminPoolAta ?? null,
                 admin: adminPubkey,
+                lpTokenProgram: token.TOKEN_PROGRAM_ID,
             })
             .rpc();
     }
 
+    // 列出本程序创建的所有 Pool（只按账户大小和魔数过滤，不下载账户数据）
+    async listPools(): Promise<PublicKey[]> {
+        const accounts = await this.connection.getProgramAccounts(this.program.programId, {
+            dataSlice: { offset: 0, length: 0 },
+            filters: [
+                { dataSize: POOL_SPACE },
+                { memcmp: { offset: POOL_MAGIC_OFFSET, bytes: utils.bytes.bs58.encode(POOL_MAGIC) } },
+            ],
+        });
+        return accounts.map((account) => account.pubkey);
+    }
+
     // 获取 Pool 账户信息（公开方法）
     async getPoolInfo(pool: PublicKey) {
         const poolInfo = await this.program.account.anySwapPool.fetch(pool);
@@ -28,5 +28,113 @@ pub enum ErrorCode {
     SameTokenSwap,
     #[msg("无效的管理员")]
     InvalidAdmin,
+    #[msg("无效的 JIT 程序")]
+    InvalidJitProgram,
+    #[msg("JIT 注入无效：vault 余额不能减少")]
+    InvalidJitDeposit,
+    #[msg("未满足最短持有期，暂时不能移除流动性")]
+    HoldPeriodNotMet,
+    #[msg("无效的持有期")]
+    InvalidHoldPeriod,
+    #[msg("操作已暂停")]
+    PoolPaused,
+    #[msg("无效的 guardian")]
+    InvalidGuardian,
+    #[msg("无效的暂停标志位")]
+    InvalidPausedOps,
+    #[msg("手续费拆分比例之和超过 100%")]
+    InvalidFeeShare,
+    #[msg("该 token 的退出份额已支付")]
+    ExitAlreadyPaid,
+    #[msg("pool 的 token 列表已变化，待处理的退出无法继续")]
+    PendingExitStale,
+    #[msg("LP mint 与 pool 记录的不一致")]
+    LpMintMismatch,
+    #[msg("pool 的费率超过了用户可接受的最大费率")]
+    FeeExceedsMax,
+    #[msg("pool 中的 token 数量不足，至少需要 2 个 token")]
+    InsufficientTokens,
+    #[msg("储备超出 ln/exp 的定义域")]
+    ReserveOutOfDomain,
+    #[msg("无效的滑点：不能超过 10000 bps")]
+    InvalidSlippage,
+    #[msg("swap 参数不一致：容差为 0 的 token 不能参与 swap")]
+    InconsistentSwapParams,
+    #[msg("vault 的 authority 不是 pool_authority")]
+    InvalidVaultAuthority,
+    #[msg("询价点数量无效：需要 1 到 16 个输入数量")]
+    InvalidQuotePoints,
+    #[msg("pool 状态不一致：已有 LP 但某个 vault 余额为 0")]
+    InconsistentPoolState,
+    #[msg("无效的稳定组：至少需要 2 个不重复的 token")]
+    InvalidStableGroup,
+    #[msg("无效的放大系数")]
+    InvalidAmplification,
+    #[msg("StableSwap 迭代未收敛")]
+    StableMathDidNotConverge,
+    #[msg("不支持的 mint 精度：最多 18 位")]
+    UnsupportedDecimals,
+    #[msg("无效的 memo：不能为空且不能超过 256 字节")]
+    InvalidMemo,
+    #[msg("附带 memo 时必须提供 memo_program")]
+    MissingMemoProgram,
+    #[msg("所需输入超过了可接受的最大输入")]
+    ExcessiveInputAmount,
+    #[msg("同一个 token 在 swap 中出现了多次")]
+    DuplicateSwapToken,
+    #[msg("无效的手续费快照：快照时间必须早于当前时间")]
+    InvalidFeeSnapshot,
+    #[msg("手续费分母不能为 0；零手续费的 pool 需要显式设置 zero_fee")]
+    InvalidFee,
+    #[msg("设置了 zero_fee 时手续费分子必须为 0")]
+    ZeroFeeMismatch,
+    #[msg("目标价格无效：价格必须大于 0，且每个 token 的价值占比不能过小")]
+    InvalidTargetPrice,
+    #[msg("LP mint 的实际供应量变化与记账不一致")]
+    LpSupplyMismatch,
+    #[msg("手续费分母过大：不能超过 1e9")]
+    FeeDenominatorTooLarge,
+    #[msg("签名者不是待接受的管理员")]
+    NotPendingAdmin,
+    #[msg("传入的账户不是有效的 token 账户")]
+    InvalidTokenAccount,
+    #[msg("无效的 TWAP token 对：槽位越界、token 对未被跟踪或重复")]
+    InvalidTwapPair,
+    #[msg("无效的 TWAP 观察点：观察时间必须早于当前时间")]
+    InvalidTwapObservation,
+    #[msg("账户不可写：转账涉及的 token 账户必须以可写方式传入")]
+    AccountNotWritable,
+    #[msg("闪电贷未归还：回调结束后 vault 余额少于借出前的余额加手续费")]
+    FlashLoanNotRepaid,
+    #[msg("无效的回调程序：不能是本程序或 token 程序")]
+    InvalidCallbackProgram,
+    #[msg("输出向下取整为 0：输入相对储备过小")]
+    OutputRoundsToZero,
+    #[msg("滑点超限：铸造的 LP 少于 min_lp_out，或 swap 的输出低于下限、输入超过上限")]
+    SlippageExceeded,
+    #[msg("无效的权重：权重之和溢出，或某个 token 的归一化权重小于 MIN_WEIGHT")]
+    InvalidWeight,
+    #[msg("收取协议费时必须设置协议费接收者")]
+    InvalidProtocolFeeRecipient,
+    #[msg("pool 不为空：关闭前必须移除所有 token，且 LP 总量为 0")]
+    PoolNotEmpty,
+    #[msg("无效的 swap 路径：至少需要 2 个 token，且不能重复")]
+    InvalidRoute,
+    #[msg("无效的权重渐变：持续时间必须大于 0")]
+    InvalidWeightRamp,
+    #[msg("无效的数字字符串")]
+    InvalidNumber,
+    #[msg("swap 输入过小：扣费后的数量低于 pool 的 min_swap_amount")]
+    AmountTooSmall,
+    #[msg("一次操作涉及的 token 数量超过了 pool 的 max_tokens_per_swap")]
+    TooManyTokensInSwap,
+    #[msg("有待处理的退出：全部支付完成前不能移除 token 或关闭 pool")]
+    ExitsPending,
+    #[msg("LP mint 迁移未完成：旧 mint 上仍有流通的 LP")]
+    LpMigrationIncomplete,
+    #[msg("上架的初始流动性不足：按 pool 现货价格计价低于新 token 权重对应的价值")]
+    ListingUnderfunded,
+    #[msg("JIT 收款账户必须属于签名的 JIT 提供者")]
+    InvalidJitPayout,
 }
 
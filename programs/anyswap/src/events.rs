@@ -0,0 +1,214 @@
+use anchor_lang::prelude::*;
+use crate::state::{AddLiquidityResult, RemoveLiquidityResult};
+
+/// 累计的手续费已并入本金（compound_fees）
+#[event]
+pub struct FeesCompounded {
+    pub pool: Pubkey,
+    /// 每个 token 并入本金的手续费（按 pool 中 token 的顺序）
+    pub fees: Vec<u64>,
+}
+
+/// swap 成功执行（swap_anyswap / swap_with_slippage / swap_exact_out / swap_with_jit）
+#[event]
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    /// 交易签名者（owner），输入 token 从其账户转出
+    pub signer: Pubkey,
+    /// 输出 token 接收账户的所有者；目前 swap 的输出总是转入 signer 的 token 账户
+    pub recipient: Pubkey,
+    /// 推荐方（前端），swap 指令目前没有推荐方参数，始终为 None
+    pub referrer: Option<Pubkey>,
+    /// 参与 swap 的 token 数量
+    pub token_count: u16,
+    /// 所有输入 token 实际转入 vault 的数量之和（含手续费）
+    pub total_in: u64,
+    /// 所有输出 token 转出 vault 的数量之和
+    pub total_out: u64,
+    /// 所有输入 token 的手续费之和
+    pub total_fees: u64,
+    /// 输入 token 的 mint（按传入顺序）
+    pub in_mints: Vec<Pubkey>,
+    /// 输出 token 的 mint（按传入顺序）
+    pub out_mints: Vec<Pubkey>,
+    /// 参与 swap 的 token 的 mint（按传入顺序，以下三个字段与之一一对应）
+    pub mints: Vec<Pubkey>,
+    /// 每个 token 是输入还是输出
+    pub is_in_token: Vec<bool>,
+    /// 每个 token 实际转入 / 转出 vault 的数量（输入 token 含手续费）
+    pub amounts: Vec<u64>,
+    /// 每个 token 的手续费（已包含在输入 token 的 amounts 中，输出 token 为 0）
+    pub fees: Vec<u64>,
+}
+
+impl SwapEvent {
+    /// 由签名者、参与 swap 的 mint、方向、实际转账数量（amounts_with_fees）和手续费构造事件
+    /// 不同 mint 的数量相加只用于统计，超过 u64::MAX 时取 u64::MAX，不能让已完成的 swap 失败
+    pub fn new(
+        pool: Pubkey,
+        signer: Pubkey,
+        mints: &[Pubkey],
+        is_in_token: &[bool],
+        amounts: &[u64],
+        burn_fees: &[u64],
+    ) -> Self {
+        let mut event = SwapEvent {
+            pool,
+            signer,
+            recipient: signer,
+            referrer: None,
+            token_count: mints.len() as u16,
+            total_in: 0,
+            total_out: 0,
+            total_fees: burn_fees.iter().fold(0u64, |sum, &fee| sum.saturating_add(fee)),
+            in_mints: Vec::new(),
+            out_mints: Vec::new(),
+            mints: mints.to_vec(),
+            is_in_token: is_in_token.to_vec(),
+            amounts: amounts.to_vec(),
+            fees: burn_fees.to_vec(),
+        };
+        for ((mint, &is_in), &amount) in mints.iter().zip(is_in_token.iter()).zip(amounts.iter()) {
+            if is_in {
+                event.total_in = event.total_in.saturating_add(amount);
+                event.in_mints.push(*mint);
+            } else {
+                event.total_out = event.total_out.saturating_add(amount);
+                event.out_mints.push(*mint);
+            }
+        }
+        event
+    }
+}
+
+/// 添加流动性（add_liquidity）
+#[event]
+pub struct LiquidityAdded {
+    pub pool: Pubkey,
+    /// 提供流动性的用户
+    pub provider: Pubkey,
+    /// 铸造给用户的 LP 数量
+    pub lp_minted: u64,
+    /// 每个 token 从用户转入 vault 的数量（含手续费，按 pool 中 token 的顺序）
+    pub amounts_used: Vec<u64>,
+}
+
+impl LiquidityAdded {
+    pub fn new(pool: Pubkey, provider: Pubkey, result: &AddLiquidityResult) -> Self {
+        LiquidityAdded {
+            pool,
+            provider,
+            lp_minted: result.lp_minted,
+            amounts_used: result.amounts_used.clone(),
+        }
+    }
+}
+
+/// 移除流动性（remove_liquidity）
+#[event]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    /// 移除流动性的用户
+    pub provider: Pubkey,
+    /// 实际销毁的 LP 数量
+    pub lp_burned: u64,
+    /// 每个 token 转给用户的数量（扣费后，按 pool 中 token 的顺序）
+    pub amounts_out: Vec<u64>,
+}
+
+impl LiquidityRemoved {
+    pub fn new(pool: Pubkey, provider: Pubkey, lp_burned: u64, result: &RemoveLiquidityResult) -> Self {
+        LiquidityRemoved {
+            pool,
+            provider,
+            lp_burned,
+            amounts_out: result.amounts_out.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::liquidity::{add_liquidity_inner, remove_liquidity_inner};
+
+    #[test]
+    fn test_liquidity_events_match_results() {
+        let pool = Pubkey::new_unique();
+        let provider = Pubkey::new_unique();
+        let vaults = [1_000_000u64, 2_000_000];
+
+        let added = add_liquidity_inner(&vaults, &[10_000, 20_000], 1_000_000, 3, 1000).unwrap();
+        let event = LiquidityAdded::new(pool, provider, &added);
+        assert_eq!((event.pool, event.provider), (pool, provider));
+        assert_eq!(event.lp_minted, added.lp_minted);
+        assert_eq!(event.amounts_used, added.amounts_used);
+
+        let removed = remove_liquidity_inner(&vaults, 50_000, 1_000_000, 3, 1000).unwrap();
+        let event = LiquidityRemoved::new(pool, provider, 50_000, &removed);
+        assert_eq!((event.pool, event.provider), (pool, provider));
+        assert_eq!(event.lp_burned, 50_000);
+        assert_eq!(event.amounts_out, removed.amounts_out);
+    }
+
+    #[test]
+    fn test_swap_event_splits_by_direction() {
+        let pool = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let event = SwapEvent::new(
+            pool,
+            owner,
+            &mints,
+            &[true, false, true],
+            &[1_000, 1_500, 500],
+            &[3, 0, 2],
+        );
+        assert_eq!(event.pool, pool);
+        assert_eq!(event.token_count, 3);
+        assert_eq!(event.total_in, 1_500);
+        assert_eq!(event.total_out, 1_500);
+        assert_eq!(event.total_fees, 5);
+        assert_eq!(event.in_mints, vec![mints[0], mints[2]]);
+        assert_eq!(event.out_mints, vec![mints[1]]);
+        assert_eq!(event.mints, mints.to_vec());
+        assert_eq!(event.is_in_token, vec![true, false, true]);
+        assert_eq!(event.amounts, vec![1_000, 1_500, 500]);
+        assert_eq!(event.fees, vec![3, 0, 2]);
+    }
+
+    #[test]
+    fn test_swap_event_signer_is_transaction_owner() {
+        let pool = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let event = SwapEvent::new(
+            pool,
+            owner,
+            &[Pubkey::new_unique(), Pubkey::new_unique()],
+            &[true, false],
+            &[1_000, 990],
+            &[3, 0],
+        );
+        assert_eq!(event.signer, owner);
+        // 输出转入 owner 自己的 token 账户
+        assert_eq!(event.recipient, owner);
+        assert_eq!(event.referrer, None);
+    }
+
+    #[test]
+    fn test_swap_event_totals_saturate() {
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let event = SwapEvent::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            &mints,
+            &[true, true, false],
+            &[u64::MAX - 1, u64::MAX - 1, 1_000],
+            &[u64::MAX - 1, u64::MAX - 1, 0],
+        );
+        assert_eq!(event.total_in, u64::MAX);
+        assert_eq!(event.total_out, 1_000);
+        assert_eq!(event.total_fees, u64::MAX);
+        assert_eq!(event.amounts, vec![u64::MAX - 1, u64::MAX - 1, 1_000]);
+    }
+}
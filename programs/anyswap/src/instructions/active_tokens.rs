@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::AnySwapPool;
+
+/// 单次返回的 mint 数量上限：return data 最多 1024 字节，Vec 长度前缀占 4 字节
+pub const MAX_MINTS_PER_PAGE: usize = 31;
+
+/// 查询 pool 中的有效 token（只读）
+#[derive(Accounts)]
+pub struct GetActiveMints<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+}
+
+/// 按索引顺序返回从 offset 开始的有效 token mint（通过 return data 返回）
+///
+/// 每次最多返回 MAX_MINTS_PER_PAGE 个，返回数量不足时说明已经到达末尾；
+/// 返回的第 k 个 mint 的索引为 offset + k
+pub fn get_active_mints(ctx: Context<GetActiveMints>, offset: u16) -> Result<Vec<Pubkey>> {
+    let pool = ctx.accounts.pool.load()?;
+    let offset = offset as usize;
+    require!(
+        offset <= pool.get_token_count(),
+        ErrorCode::InvalidTokenIndex
+    );
+    Ok(pool
+        .active_tokens()
+        .skip(offset)
+        .take(MAX_MINTS_PER_PAGE)
+        .map(|(_, token)| *token.mint_pubkey())
+        .collect())
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+use crate::state::AnySwapPool;
+
+/// 查询 pool 活动时间（只读）
+#[derive(Accounts)]
+pub struct GetActivity<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+}
+
+/// pool 的创建时间和最近活动时间（通过 return data 返回）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolActivity {
+    /// 创建时间（unix 时间戳）
+    pub created_at: i64,
+    /// 最近一次 swap / 添加流动性 / 移除流动性的时间（unix 时间戳）
+    pub last_activity_at: i64,
+}
+
+/// 返回 pool 的创建时间和最近活动时间，用于识别长期不活跃的 pool
+pub fn get_activity(ctx: Context<GetActivity>) -> Result<PoolActivity> {
+    let pool = ctx.accounts.pool.load()?;
+    let (created_at, last_activity_at) = pool.get_activity();
+    Ok(PoolActivity {
+        created_at,
+        last_activity_at,
+    })
+}
@@ -1,8 +1,12 @@
 use crate::error::ErrorCode;
-use crate::state::AnySwapPool;
-use crate::state::liquidity::add_liquidity_inner;
+use crate::events::LiquidityAdded;
+use crate::state::token_account::{check_writable_pairs, load_token_account, read_token_amount};
+use crate::state::pool::PAUSE_ADD_LIQUIDITY;
+use crate::state::{AnySwapPool, LpPosition};
+use crate::state::liquidity::{add_liquidity_inner_with_weights, check_min_lp_out};
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Token, Transfer};
+use anchor_spl::token_interface::{self, TokenInterface};
 
 /// 添加流动性操作
 /// 按照 Balancer 的方式：按当前池的比例添加所有 token
@@ -21,12 +25,9 @@ pub struct AddLiquidity<'info> {
     pub pool_authority: AccountInfo<'info>,
 
     /// Pool mint - LP token
-    #[account(
-        mut,
-        seeds = [b"pool_mint", pool.key().as_ref()],
-        bump
-    )]
-    pub pool_mint: Box<Account<'info, Mint>>,
+    /// 当前的 LP mint（pool_mint PDA，或 finish_lp_migration 之后的新 mint），在指令中校验
+    #[account(mut)]
+    pub pool_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
 
     /// 用户的 LP token 账户（接收 LP token）
     #[account(
@@ -34,11 +35,25 @@ pub struct AddLiquidity<'info> {
         constraint = user_pool_ata.mint == pool_mint.key(),
         constraint = user_pool_ata.owner == owner.key()
     )]
-    pub user_pool_ata: Box<Account<'info, TokenAccount>>,
+    pub user_pool_ata: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
 
+    /// 用户的 LP 持仓记录（记录最近一次添加流动性的时间）
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = LpPosition::space(),
+        seeds = [b"lp_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub lp_position: Box<Account<'info, LpPosition>>,
+
+    #[account(mut)]
     pub owner: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+    /// LP mint 所属的 token 程序（迁移后的 LP mint 可以是 Token-2022）
+    pub lp_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 /// 添加流动性（多 token 版本）
@@ -49,15 +64,24 @@ pub struct AddLiquidity<'info> {
 /// - 必须按照 pool 中 token 的顺序传入
 /// - 例如：pool 有 [A, B, C]，则传入 [user_A, vault_A, user_B, vault_B, user_C, vault_C]
 ///
-/// amounts_in: 用户希望添加的每个 token 的数量（按 pool 中 token 的顺序）
+/// amounts_in: 用户希望添加的每个 token 的数量上限（按 pool 中 token 的顺序），
+///             实际按比例最小的 token 计算，其他 token 只取按比例需要的数量
+/// min_lp_out: 最少铸造的 LP 数量（滑点保护），0 表示不限制
 pub fn add_liquidity<'remaining: 'info, 'info>(
     ctx: Context<'_, '_, 'remaining, 'info, AddLiquidity<'info>>,
     amounts_in: Vec<u64>,
+    min_lp_out: u64,
 ) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
     let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_ADD_LIQUIDITY)?;
+    pool.check_lp_mint(ctx.accounts.pool_mint.decimals)?;
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    pool.check_canonical_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+    pool.check_min_tokens()?;
     let token_count = pool.get_token_count();
+    pool.check_tokens_per_swap(token_count)?;
 
-    require!(token_count > 0, ErrorCode::InvalidTokenCount);
     require!(
         amounts_in.len() == token_count,
         ErrorCode::InvalidTokenCount
@@ -70,41 +94,41 @@ pub fn add_liquidity<'remaining: 'info, 'info>(
         ErrorCode::InvalidTokenCount
     );
 
-    let pool_authority_key = ctx.accounts.pool_authority.key();
     let owner_key = ctx.accounts.owner.key();
 
-    // 收集所有 vault 余额
+    // 收集所有 vault 余额和权重（权重只在首次添加时用于计算 LP）
     let mut token_vault_balances: Vec<u64> = Vec::with_capacity(token_count);
+    let mut weights: Vec<u64> = Vec::with_capacity(token_count);
 
     for i in 0..token_count {
         let vault_info = &remaining_accounts[i * 2 + 1];
 
         // 验证 vault
-        let token_item = pool.get_token(i).ok_or(ErrorCode::InvalidTokenIndex)?;
+        let token_item = pool.get_token_checked(i)?;
         require!(
             vault_info.key() == *token_item.vault_pubkey(),
             ErrorCode::InvalidTokenMint
         );
 
-        // 读取 vault 账户并验证 owner 是 pool_authority
-        let vault_account = Account::<TokenAccount>::try_from(vault_info)?;
-        require!(
-            vault_account.owner == pool_authority_key,
-            ErrorCode::InvalidTokenMint
-        );
+        // 地址已与 pool 记录的 vault（由 pool_authority 控制的 PDA）比对，只需读取余额
+        // 未领取的协议费不属于 LP，不计入储备
+        let vault_amount = token_item.lp_balance(read_token_amount(vault_info)?);
 
-        token_vault_balances.push(vault_account.amount);
+        token_vault_balances.push(vault_amount);
+        weights.push(token_item.weight_at(now));
     }
 
     // 调用 add_liquidity_inner
     let total_lp_supply = pool.get_total_amount_minted();
-    let result = add_liquidity_inner(
+    let result = add_liquidity_inner_with_weights(
         &token_vault_balances,
         &amounts_in,
         total_lp_supply,
         pool.get_fee_numerator(),
         pool.get_fee_denominator(),
+        &weights,
     )?;
+    check_min_lp_out(result.lp_minted, min_lp_out)?;
 
     drop(pool);
 
@@ -113,9 +137,15 @@ pub fn add_liquidity<'remaining: 'info, 'info>(
     let current_total = pool_mut.get_total_amount_minted();
     pool_mut.set_total_amount_minted(
         current_total
-            .checked_add(result.lp_minted)
+            .checked_add(result.lp_minted as u128)
             .ok_or(ErrorCode::MathOverflow)?,
     );
+    // 加入池子的部分计入本金，多收的手续费留在 vault 中归 LP
+    for i in 0..token_count {
+        let fee = result.amounts_used[i].saturating_sub(result.amounts_in[i]);
+        pool_mut.record_principal_flow(i, result.amounts_used[i], 0, fee)?;
+    }
+    pool_mut.record_activity(now);
     drop(pool_mut);
 
     // 准备 seeds 用于签名
@@ -125,10 +155,10 @@ pub fn add_liquidity<'remaining: 'info, 'info>(
     let signer = &[&seeds[..]];
 
     // 铸造 LP token 给用户
-    token::mint_to(
+    token_interface::mint_to(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            MintTo {
+            ctx.accounts.lp_token_program.to_account_info(),
+            token_interface::MintTo {
                 to: ctx.accounts.user_pool_ata.to_account_info(),
                 mint: ctx.accounts.pool_mint.to_account_info(),
                 authority: ctx.accounts.pool_authority.to_account_info(),
@@ -139,6 +169,7 @@ pub fn add_liquidity<'remaining: 'info, 'info>(
     )?;
 
     // 转移所有 token 到对应的 vault
+    check_writable_pairs(remaining_accounts)?;
     for i in 0..token_count {
         let user_token_info = &remaining_accounts[i * 2];
         let vault_info = &remaining_accounts[i * 2 + 1];
@@ -149,7 +180,7 @@ pub fn add_liquidity<'remaining: 'info, 'info>(
         }
 
         // 验证 user_token owner
-        let user_token_account = Account::<TokenAccount>::try_from(user_token_info)?;
+        let user_token_account = load_token_account(user_token_info, i * 2)?;
         require!(
             user_token_account.owner == owner_key,
             ErrorCode::InvalidTokenMint
@@ -168,11 +199,142 @@ pub fn add_liquidity<'remaining: 'info, 'info>(
         )?;
     }
 
+    // 记录加入时间（用于最短持有期检查）
+    let lp_position = &mut ctx.accounts.lp_position;
+    lp_position.pool = pool_key;
+    lp_position.owner = owner_key;
+    lp_position.last_join_ts = now;
+    lp_position.bump = ctx.bumps.lp_position;
+
     msg!(
         "Liquidity added: {} LP tokens minted for {} tokens",
         result.lp_minted,
         token_count
     );
+    emit!(LiquidityAdded::new(pool_key, owner_key, &result));
+
+    Ok(())
+}
+
+/// 单 token 添加流动性
+///
+/// 只存入 token_index 对应的一个 token，按 Balancer 的 calcBptOutGivenExactTokenIn 铸造 LP，
+/// 超出比例的部分（相当于在池子内部换成其他 token）收取 swap 手续费
+///
+/// RemainingAccounts 结构：
+/// - (user_token_account, vault_account)：token_index 对应 token 的账户对
+///
+/// token_index: 存入 token 在 pool 中的索引
+/// amount_in: 存入数量（全部从用户转入 vault）
+/// min_lp_out: 最少铸造的 LP 数量（滑点保护），0 表示不限制
+pub fn add_liquidity_single<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, AddLiquidity<'info>>,
+    token_index: u16,
+    amount_in: u64,
+    min_lp_out: u64,
+) -> Result<()> {
+    let token_index = token_index as usize;
+    let now = Clock::get()?.unix_timestamp;
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_ADD_LIQUIDITY)?;
+    pool.check_lp_mint(ctx.accounts.pool_mint.decimals)?;
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    pool.check_canonical_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+    pool.check_min_tokens()?;
+    require!(amount_in > 0, ErrorCode::InsufficientTokenAmount);
+
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(remaining_accounts.len() == 2, ErrorCode::InvalidTokenCount);
+    let user_token_info = &remaining_accounts[0];
+    let vault_info = &remaining_accounts[1];
+
+    // 验证 vault
+    let token_item = pool.get_token_checked(token_index)?;
+    require!(
+        vault_info.key() == *token_item.vault_pubkey(),
+        ErrorCode::InvalidTokenMint
+    );
+    let vault_amount = token_item.lp_balance(read_token_amount(vault_info)?);
+
+    let result = pool.add_liquidity_single(token_index, vault_amount, amount_in, now)?;
+    check_min_lp_out(result.lp_minted, min_lp_out)?;
+    // 超出比例部分的手续费是 swap 手续费，与普通 swap 一样计提协议费
+    let protocol_fees = pool.protocol_fees(&result.burn_fees)?;
+    drop(pool);
+
+    // 更新 total_amount_minted，扣费后的部分计入本金，手续费留在 vault 中（协议费部分计提）
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    let current_total = pool_mut.get_total_amount_minted();
+    pool_mut.set_total_amount_minted(
+        current_total
+            .checked_add(result.lp_minted as u128)
+            .ok_or(ErrorCode::MathOverflow)?,
+    );
+    pool_mut.record_principal_flow(
+        token_index,
+        result.amounts_used[token_index],
+        0,
+        result.burn_fees[token_index],
+    )?;
+    pool_mut.accrue_protocol_fees_in_order(&protocol_fees)?;
+    pool_mut.record_activity(now);
+    drop(pool_mut);
+
+    // 准备 seeds 用于签名
+    let pool_key = ctx.accounts.pool.key();
+    let owner_key = ctx.accounts.owner.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+
+    // 铸造 LP token 给用户
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.lp_token_program.to_account_info(),
+            token_interface::MintTo {
+                to: ctx.accounts.user_pool_ata.to_account_info(),
+                mint: ctx.accounts.pool_mint.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer,
+        ),
+        result.lp_minted,
+    )?;
+
+    // 转入存入的 token
+    check_writable_pairs(remaining_accounts)?;
+    let user_token_account = load_token_account(user_token_info, 0)?;
+    require!(
+        user_token_account.owner == owner_key,
+        ErrorCode::InvalidTokenMint
+    );
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: user_token_info.clone(),
+                to: vault_info.clone(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount_in,
+    )?;
+
+    // 记录加入时间（用于最短持有期检查）
+    let lp_position = &mut ctx.accounts.lp_position;
+    lp_position.pool = pool_key;
+    lp_position.owner = owner_key;
+    lp_position.last_join_ts = now;
+    lp_position.bump = ctx.bumps.lp_position;
+
+    msg!(
+        "Single-sided liquidity added: {} LP tokens minted for {} of token {}, fee={}",
+        result.lp_minted,
+        amount_in,
+        token_index,
+        result.burn_fees[token_index]
+    );
+    emit!(LiquidityAdded::new(pool_key, owner_key, &result));
 
     Ok(())
 }
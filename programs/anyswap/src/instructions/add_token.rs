@@ -2,7 +2,9 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{self, Mint, Token, TokenAccount, Transfer},
+    token_interface::{self, TokenInterface},
 };
+use crate::state::liquidity::{check_listing_value, listing_lp_to_mint};
 use crate::state::AnySwapPool;
 use crate::error::ErrorCode;
 
@@ -45,6 +47,23 @@ pub struct AddTokenToPool<'info> {
     )]
     pub admin_token: Box<Account<'info, TokenAccount>>,
 
+    /// Pool mint - LP token
+    /// 当前的 LP mint（pool_mint PDA，或 finish_lp_migration 之后的新 mint），在指令中校验
+    #[account(mut)]
+    pub pool_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    /// Admin 的 LP token 账户 - 已有 LP 时注入初始流动性必须提供，接收上架铸造的 LP
+    #[account(
+        mut,
+        constraint = admin_lp_token.mint == pool_mint.key(),
+        constraint = admin_lp_token.owner == admin.key()
+    )]
+    pub admin_lp_token: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+
+    /// quote token（pool 中索引为 quote_index 的 token）的 vault - 已有 LP 时注入初始流动性必须提供，
+    /// 用于按现货价格计算池子价值（见 check_listing_value）
+    pub quote_vault: Option<Box<Account<'info, TokenAccount>>>,
+
     /// Pool 管理员 - 必须签名所有操作
     /// CHECK: 验证是否为 pool 的管理员
     pub admin: Signer<'info>,
@@ -54,6 +73,8 @@ pub struct AddTokenToPool<'info> {
     pub payer: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+    /// LP mint 所属的 token 程序（迁移后的 LP mint 可以是 Token-2022）
+    pub lp_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
@@ -65,10 +86,18 @@ pub struct AddTokenToPool<'info> {
 /// 
 /// 注意：添加新token会增加池子的总价值，不需要保持恒定乘积
 /// Admin可以选择立即提供流动性，或稍后通过add_liquidity提供
+///
+/// 已有 LP 时注入初始流动性，按新 token 的权重占比铸造 LP 给 admin（见 listing_lp_to_mint），
+/// 原有 LP 每份对应的价值不变。pool 无法为新 token 定价，此时需要声明初始流动性的价值：
+/// quote_index: 用于计价的已有 token 在 pool 中的索引
+/// liquidity_value: liquidity 按 quote token 计价的价值，低于新 token 权重对应的池子价值时以 ListingUnderfunded 拒绝
+/// 未铸造 LP 时（不提供流动性，或 pool 还没有 LP）忽略这两个参数
 pub fn add_token_to_pool(
     ctx: Context<AddTokenToPool>,
     weight: u64,
     liquidity: u64,
+    quote_index: u16,
+    liquidity_value: u64,
 ) -> Result<()> {
     // 读取 pool 信息
     let mint_key = ctx.accounts.mint.key();
@@ -88,6 +117,41 @@ pub fn add_token_to_pool(
     
     // 验证权重有效
     require!(weight > 0, ErrorCode::InvalidTokenCount);
+
+    // 验证 mint 精度
+    AnySwapPool::check_token_decimals(ctx.accounts.mint.decimals)?;
+    pool.check_lp_mint(ctx.accounts.pool_mint.decimals)?;
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    pool.check_canonical_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+
+    // 上架前的 LP 总量和当前的权重之和决定铸造给 admin 的 LP（权重渐变期间按当前权重）
+    let now = Clock::get()?.unix_timestamp;
+    let lp_supply = pool.get_total_amount_minted();
+    let listing_lp = if liquidity > 0 {
+        listing_lp_to_mint(lp_supply, pool.weight_sum_at(now)?, weight)?
+    } else {
+        0
+    };
+
+    // 铸造 LP 前按 quote token 的现货余额校验初始流动性的价值，避免按权重铸造的 LP 稀释原有 LP
+    if listing_lp > 0 {
+        let quote_vault = ctx
+            .accounts
+            .quote_vault
+            .as_ref()
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        let quote_token = pool.get_token_checked(quote_index as usize)?;
+        require!(
+            quote_vault.key() == *quote_token.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+        check_listing_value(
+            quote_token.lp_balance(quote_vault.amount),
+            quote_token.weight_at(now),
+            weight,
+            liquidity_value,
+        )?;
+    }
     
     // 如果提供了初始流动性，从admin转移到vault
     if liquidity > 0 {
@@ -116,6 +180,40 @@ pub fn add_token_to_pool(
     
     // 添加 token（设置 weight）
     let index = pool.add_token(&mint_key, &ctx.accounts.vault.key(), weight)?;
+    pool.validate_weights()?;
+    pool.set_token_decimals(index, ctx.accounts.mint.decimals)?;
+    pool.record_principal_flow(index, liquidity, 0, 0)?;
+
+    if listing_lp > 0 {
+        let admin_lp_token = ctx
+            .accounts
+            .admin_lp_token
+            .as_ref()
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        pool.set_total_amount_minted(
+            lp_supply
+                .checked_add(listing_lp as u128)
+                .ok_or(ErrorCode::MathOverflow)?,
+        );
+
+        let pool_key = ctx.accounts.pool.key();
+        let bump = ctx.bumps.pool_authority;
+        let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+        let signer = &[&seeds[..]];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.lp_token_program.to_account_info(),
+                token_interface::MintTo {
+                    to: admin_lp_token.to_account_info(),
+                    mint: ctx.accounts.pool_mint.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            ),
+            listing_lp,
+        )?;
+        msg!("Listing LP minted to admin: {}", listing_lp);
+    }
     
     msg!("Token added to pool at index: {}, mint: {}, weight: {}, vault_balance: {}", 
          index, mint_key, weight, ctx.accounts.vault.amount);
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::state::AnySwapPool;
+
+/// 提议新的管理员
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名
+    pub admin: Signer<'info>,
+}
+
+/// 接受管理员转移
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// 待接受的管理员 - 必须签名
+    pub pending_admin: Signer<'info>,
+}
+
+/// 提议将管理权转移给 new_admin，需由 new_admin 调用 accept_admin 后才生效
+/// new_admin: 新的管理员，传入默认公钥表示取消进行中的转移
+pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    pool.propose_admin(&ctx.accounts.admin.key(), &new_admin)?;
+
+    msg!("Pool pending admin set to {}", new_admin);
+    Ok(())
+}
+
+/// 待接受的管理员接受管理权
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    pool.accept_admin(&ctx.accounts.pending_admin.key())?;
+
+    msg!("Pool admin transferred to {}", ctx.accounts.pending_admin.key());
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::state::AnySwapPool;
+
+/// 关闭空的 pool
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    /// 关闭后租金退还给 admin
+    #[account(mut, close = admin)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名，接收退还的租金
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+/// 关闭 pool 账户并将租金退还给管理员
+///
+/// 要求 pool 已经为空：所有 token 都已通过 remove_token_from_pool 移除（vault 随之关闭），
+/// 且 LP 总量为 0，否则返回 PoolNotEmpty
+///
+/// 注意：pool_mint 是 SPL Token 的 mint，SPL Token 不支持关闭 mint 账户，其租金无法回收；
+/// 关闭后 pool_mint 的 mint authority（pool_authority PDA）不再能签名，供应量保持为 0
+pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+    let pool = ctx.accounts.pool.load()?;
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+    pool.check_can_close()?;
+    drop(pool);
+
+    msg!(
+        "Pool closed: {}, rent returned to admin: {}",
+        ctx.accounts.pool.key(),
+        ctx.accounts.admin.key()
+    );
+    Ok(())
+}
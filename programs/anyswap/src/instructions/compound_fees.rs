@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::events::FeesCompounded;
+use crate::state::token_account::read_token_amount;
+use crate::state::AnySwapPool;
+
+/// 将累计的手续费并入本金
+#[derive(Accounts)]
+pub struct CompoundFees<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名
+    /// CHECK: 验证是否为 pool 的管理员
+    pub admin: Signer<'info>,
+}
+
+/// 将每个 token 累计的手续费（vault 余额 - 本金）并入本金，并发出 FeesCompounded 事件
+///
+/// 只修改记账，不转移任何 token；之后 vault 余额与本金之差重新从 0 开始累计
+///
+/// RemainingAccounts 结构：
+/// - pool 中每个 token 的 vault 账户，按 pool 中 token 的顺序传入
+pub fn compound_fees<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, CompoundFees<'info>>,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+
+    let token_count = pool.get_token_count();
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(
+        remaining_accounts.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let mut vault_balances = Vec::with_capacity(token_count);
+    for (i, vault_info) in remaining_accounts.iter().enumerate() {
+        require!(
+            vault_info.key() == *pool.get_token_checked(i)?.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+        vault_balances.push(read_token_amount(vault_info)?);
+    }
+
+    let fees = pool.compound_fees(&vault_balances)?;
+    drop(pool);
+
+    msg!("Fees compounded for {} tokens", token_count);
+    emit!(FeesCompounded {
+        pool: ctx.accounts.pool.key(),
+        fees,
+    });
+    Ok(())
+}
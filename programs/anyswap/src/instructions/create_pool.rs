@@ -1,11 +1,17 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token};
-use crate::state::AnySwapPool;
-use crate::error::ErrorCode;
+use crate::state::{AnySwapPool, DEFAULT_MAX_TOKENS_PER_SWAP};
 
 /// 创建 Pool（PDA）
 /// 每个 Pool 可以包含多个 token（最多 1024 个）
 #[derive(Accounts)]
+#[instruction(
+    fee_numerator: u64,
+    fee_denominator: u64,
+    admin_less: bool,
+    zero_fee: bool,
+    lp_decimals: Option<u8>
+)]
 pub struct CreatePool<'info> {
     /// Pool creator - 用于区分不同的 pool
     /// 可以是任何账户，只要保证唯一性即可
@@ -24,12 +30,13 @@ pub struct CreatePool<'info> {
     pub pool_authority: AccountInfo<'info>,
 
     /// Pool mint - LP token，用于跟踪流动性提供者的份额
+    /// 创建时供应量为 0，mint authority 为 pool_authority PDA
     #[account(
         init,
         payer = payer,
         seeds = [b"pool_mint", pool.key().as_ref()],
         bump,
-        mint::decimals = 9,
+        mint::decimals = AnySwapPool::lp_decimals_or_default(lp_decimals),
         mint::authority = pool_authority
     )]
     pub pool_mint: Box<Account<'info, Mint>>,
@@ -50,21 +57,42 @@ pub struct CreatePool<'info> {
 /// fee_numerator: 手续费分子
 /// fee_denominator: 手续费分母
 /// 例如：fee_numerator=3, fee_denominator=1000 表示 0.3% 手续费
+/// admin_less: 显式创建无管理员的 pool（之后所有管理操作都会被拒绝）
+/// 未设置时，默认公钥作为管理员会被拒绝
+/// zero_fee: 显式创建零手续费的 pool（fee_numerator 必须为 0，fee_denominator 可以为 0）
+/// 未设置时，fee_denominator 为 0 会被拒绝
+/// lp_decimals: LP mint 的精度（不超过 MAX_TOKEN_DECIMALS），未指定时为 DEFAULT_LP_DECIMALS
+///
+/// 一次操作最多涉及的 token 数量初始为 DEFAULT_MAX_TOKENS_PER_SWAP，由管理员通过 modify_max_tokens_per_swap 调整
 pub fn create_pool(
     ctx: Context<CreatePool>,
     fee_numerator: u64,
     fee_denominator: u64,
+    admin_less: bool,
+    zero_fee: bool,
+    lp_decimals: Option<u8>,
 ) -> Result<()> {
-    require!(fee_denominator > 0, ErrorCode::MathOverflow);
-    require!(fee_numerator <= fee_denominator, ErrorCode::MathOverflow);
+    AnySwapPool::check_token_decimals(AnySwapPool::lp_decimals_or_default(lp_decimals))?;
+    let (fee_numerator, fee_denominator) =
+        AnySwapPool::resolve_fee(fee_numerator, fee_denominator, zero_fee)?;
+    let admin = AnySwapPool::resolve_admin(&ctx.accounts.admin.key(), admin_less)?;
     
     let pool = &mut ctx.accounts.pool.load_init()?;
     pool.token_count = 0;
-    pool.padding = [0u8; 6];
-    pool.admin = ctx.accounts.admin.key();
-    pool.total_amount_minted = 0;
-    pool.fee_numerator = fee_numerator;
-    pool.fee_denominator = fee_denominator;
+    pool.init_header();
+    pool.lp_decimals = ctx.accounts.pool_mint.decimals;
+    pool.admin = admin;
+    pool.set_total_amount_minted(0);
+    pool.set_fee(fee_numerator, fee_denominator)?;
+    pool.min_hold_seconds = 0;
+    pool.guardian = Pubkey::default();
+    pool.pending_admin = Pubkey::default();
+    pool.paused_ops = 0;
+    pool.migration_lp_mint = Pubkey::default();
+    pool.migration_lp_supply = 0;
+    pool.lp_mint = Pubkey::default();
+    pool.init_timestamps(Clock::get()?.unix_timestamp);
+    pool.set_max_tokens_per_swap(DEFAULT_MAX_TOKENS_PER_SWAP);
     
     // 初始化所有 token items 为零值（zero_copy 会自动处理）
     // 不需要显式初始化，因为 zero_copy 会使用未初始化的内存
@@ -73,7 +101,7 @@ pub fn create_pool(
          ctx.accounts.pool_creator.key(),
          ctx.accounts.pool.key(),
          ctx.accounts.pool_mint.key(),
-         ctx.accounts.admin.key(),
+         admin,
          fee_numerator,
          fee_denominator);
     Ok(())
@@ -0,0 +1,304 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, Transfer};
+use anchor_spl::token_interface::{self, TokenInterface};
+use crate::state::pool::PAUSE_REMOVE_LIQUIDITY;
+use crate::state::{AnySwapPool, LpPosition, PendingExit};
+use crate::state::exit::EXIT_BITMAP_BYTES;
+use crate::state::liquidity::remove_liquidity_inner;
+use crate::error::ErrorCode;
+use crate::state::token_account::{check_writable_pairs, load_token_account, read_token_amount};
+
+/// 发起大额退出：销毁 LP token，记录每个 token 应支付的数量
+#[derive(Accounts)]
+pub struct RequestExit<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool mint - LP token
+    /// 当前的 LP mint；LP mint 迁移期间也可以是迁移的目标 mint（在指令中校验）
+    #[account(mut)]
+    pub pool_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    /// 用户的 LP token 账户（销毁 LP token）
+    #[account(
+        mut,
+        constraint = user_pool_ata.mint == pool_mint.key(),
+        constraint = user_pool_ata.owner == owner.key()
+    )]
+    pub user_pool_ata: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    /// 待处理的退出记录，每个用户同时只能有一个
+    /// 空间按当前 token 数量分配，用于保存每个 token 应支付的数量
+    #[account(
+        init,
+        payer = owner,
+        space = PendingExit::space(pool.load()?.get_token_count()),
+        seeds = [b"pending_exit", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub pending_exit: Box<Account<'info, PendingExit>>,
+
+    /// 用户的 LP 持仓记录（用于最短持有期检查）
+    /// 必须已存在：通过转账获得 LP 的用户需要先调用 open_lp_position，同样要等待持有期
+    #[account(
+        seeds = [b"lp_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump = lp_position.bump,
+    )]
+    pub lp_position: Box<Account<'info, LpPosition>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    /// LP mint 所属的 token 程序（迁移后的 LP mint 可以是 Token-2022）
+    pub lp_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 处理待处理的退出：分批支付 token，全部支付后关闭退出记录
+#[derive(Accounts)]
+pub struct ProcessExit<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool authority PDA
+    /// CHECK: PDA derived from pool key, used as token account owner
+    #[account(
+        seeds = [b"anyswap_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// 待处理的退出记录
+    #[account(
+        mut,
+        seeds = [b"pending_exit", pool.key().as_ref(), owner.key().as_ref()],
+        bump = pending_exit.bump,
+        has_one = owner,
+    )]
+    pub pending_exit: Box<Account<'info, PendingExit>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    /// LP mint 所属的 token 程序（迁移后的 LP mint 可以是 Token-2022）
+    pub lp_token_program: Interface<'info, TokenInterface>,
+}
+
+/// 发起大额退出
+///
+/// 立即销毁 burn_amount 数量的 LP token，按当前储备和 LP 总量计算每个 token 应支付的数量（与一次性移除流动性相同），
+/// 这些数量从 LP 储备中划出，之后通过 process_exit 分多笔交易领取，
+/// 避免一次性转出大量 token 时超出计算预算；领取前其他 LP 的份额和 swap 价格都不受影响
+///
+/// RemainingAccounts 结构：
+/// - 每个 token 的 vault_account（只读），必须按照 pool 中 token 的顺序传入
+///
+/// burn_amount: 要退出的 LP token 数量
+pub fn request_exit<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, RequestExit<'info>>,
+    burn_amount: u64,
+) -> Result<()> {
+    require!(burn_amount > 0, ErrorCode::InsufficientTokenAmount);
+    require!(
+        ctx.accounts.user_pool_ata.amount >= burn_amount,
+        ErrorCode::InsufficientTokenAmount
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_REMOVE_LIQUIDITY)?;
+    pool.check_lp_mint(ctx.accounts.pool_mint.decimals)?;
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    let is_migrated_mint = pool.resolve_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+    let token_count = pool.get_token_count();
+    require!(token_count > 0, ErrorCode::InvalidTokenCount);
+
+    // 检查最短持有期
+    ctx.accounts
+        .lp_position
+        .check_hold_period(pool.get_min_hold_seconds(), now)?;
+
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(
+        remaining_accounts.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+
+    // 收集所有 token 的 vault 余额
+    let mut token_vault_balances: Vec<u64> = Vec::with_capacity(token_count);
+    for (i, vault_info) in remaining_accounts.iter().enumerate() {
+        let token_item = pool.get_token_checked(i)?;
+        require!(
+            vault_info.key() == *token_item.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+        // 未领取的协议费和其他用户待支付的退出份额不属于 LP，不计入储备
+        token_vault_balances.push(token_item.lp_balance(read_token_amount(vault_info)?));
+    }
+
+    let result = remove_liquidity_inner(
+        &token_vault_balances,
+        burn_amount,
+        pool.get_total_amount_minted(),
+        pool.get_fee_numerator(),
+        pool.get_fee_denominator(),
+    )?;
+    let mints_hash = pool.mints_hash(token_count)?;
+    drop(pool);
+
+    // 销毁用户的 LP token（用户自己签名销毁）
+    let supply_before = ctx.accounts.pool_mint.supply;
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.lp_token_program.to_account_info(),
+            token_interface::Burn {
+                mint: ctx.accounts.pool_mint.to_account_info(),
+                from: ctx.accounts.user_pool_ata.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        burn_amount,
+    )?;
+
+    // 按销毁后重新读取的 mint 供应量更新 total_amount_minted，并将退出份额从储备中划出
+    ctx.accounts.pool_mint.reload()?;
+    let supply_after = ctx.accounts.pool_mint.supply;
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    let burned = pool_mut.record_lp_burn(supply_before, supply_after, burn_amount)?;
+    if is_migrated_mint {
+        pool_mut.record_migrated_lp_burn(burned)?;
+    }
+    pool_mut.reserve_exit(&result.amounts_out, &result.burn_fees)?;
+    pool_mut.record_activity(now);
+    drop(pool_mut);
+
+    let pending_exit = &mut ctx.accounts.pending_exit;
+    pending_exit.pool = ctx.accounts.pool.key();
+    pending_exit.owner = ctx.accounts.owner.key();
+    pending_exit.lp_amount = burn_amount;
+    pending_exit.token_count = token_count as u16;
+    pending_exit.mints_hash = mints_hash;
+    pending_exit.paid = [0; EXIT_BITMAP_BYTES];
+    pending_exit.bump = ctx.bumps.pending_exit;
+    pending_exit.amounts = result.amounts_out;
+
+    msg!(
+        "Exit requested: {} LP tokens burned, {} tokens to be paid",
+        burn_amount,
+        token_count
+    );
+    Ok(())
+}
+
+/// 处理待处理的退出（分批支付）
+///
+/// RemainingAccounts 结构：
+/// - 每两个账户为一对：(user_token_account, vault_account)，顺序与 token_indices 一致
+///
+/// token_indices: 本次支付的 token 在 pool 中的索引，每个 token 只能支付一次
+///
+/// 支付数量在 request_exit 时已确定；全部 token 支付完成后关闭退出记录
+pub fn process_exit<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, ProcessExit<'info>>,
+    token_indices: Vec<u16>,
+) -> Result<()> {
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(
+        remaining_accounts.len() == token_indices.len() * 2,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_REMOVE_LIQUIDITY)?;
+    // 发起退出后前 token_count 个 token 发生变化时，索引不再可靠
+    // （有待处理的退出时不能移除 token，这里作为防御性检查）
+    let exit_token_count = ctx.accounts.pending_exit.token_count as usize;
+    require!(
+        exit_token_count <= pool.get_token_count()
+            && pool.mints_hash(exit_token_count)? == ctx.accounts.pending_exit.mints_hash,
+        ErrorCode::PendingExitStale
+    );
+
+    // 验证本批次 token 的 vault
+    for (i, &token_index) in token_indices.iter().enumerate() {
+        let vault_info = &remaining_accounts[i * 2 + 1];
+        let token_item = pool.get_token_checked(token_index as usize)?;
+        require!(
+            vault_info.key() == *token_item.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+    }
+    drop(pool);
+
+    let owner_key = ctx.accounts.owner.key();
+    let amounts_out = ctx.accounts.pending_exit.process_chunk(&token_indices)?;
+
+    // 准备 seeds 用于签名
+    let pool_key = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[
+        b"anyswap_authority",
+        pool_key.as_ref(),
+        &[bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    // 从 vault 转移本批次的 token 给用户
+    check_writable_pairs(remaining_accounts)?;
+    for i in 0..token_indices.len() {
+        let user_token_info = &remaining_accounts[i * 2];
+        let vault_info = &remaining_accounts[i * 2 + 1];
+
+        // 跳过数量为0的token
+        if amounts_out[i] == 0 {
+            continue;
+        }
+
+        // 验证 user_token owner
+        let user_token_account = load_token_account(user_token_info, i * 2)?;
+        require!(
+            user_token_account.owner == owner_key,
+            ErrorCode::InvalidTokenMint
+        );
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: vault_info.clone(),
+                    to: user_token_info.clone(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            ),
+            amounts_out[i],
+        )?;
+    }
+
+    let fully_paid = ctx.accounts.pending_exit.is_fully_paid();
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    for (i, &token_index) in token_indices.iter().enumerate() {
+        pool_mut.release_exit(token_index as usize, amounts_out[i])?;
+    }
+    if fully_paid {
+        pool_mut.finish_exit()?;
+    }
+    pool_mut.record_activity(Clock::get()?.unix_timestamp);
+    drop(pool_mut);
+
+    msg!("Exit processed: {} tokens paid", token_indices.len());
+
+    if !fully_paid {
+        return Ok(());
+    }
+
+    // 全部支付完成：关闭退出记录，租金返还给用户
+    ctx.accounts
+        .pending_exit
+        .close(ctx.accounts.owner.to_account_info())?;
+
+    msg!("Exit finalized: {} LP tokens", ctx.accounts.pending_exit.lp_amount);
+    Ok(())
+}
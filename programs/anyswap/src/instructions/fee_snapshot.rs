@@ -0,0 +1,135 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::fee_snapshot::annualize_fee_growth;
+use crate::state::token_account::read_token_amount;
+use crate::state::{AnySwapPool, FeeSnapshot};
+
+/// 记录 pool 当前的手续费快照
+#[derive(Accounts)]
+pub struct SnapshotFees<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// 快照账户，以当前时间戳作为 seed，同一秒内只能记录一次
+    #[account(
+        init,
+        payer = payer,
+        space = FeeSnapshot::space(),
+        seeds = [b"fee_snapshot", pool.key().as_ref(), clock.unix_timestamp.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub snapshot: Box<Account<'info, FeeSnapshot>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 查询某个快照以来的手续费增长（只读）
+#[derive(Accounts)]
+#[instruction(since_timestamp: i64)]
+pub struct GetFeeGrowth<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// since_timestamp 时记录的快照
+    #[account(
+        seeds = [b"fee_snapshot", pool.key().as_ref(), since_timestamp.to_le_bytes().as_ref()],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Box<Account<'info, FeeSnapshot>>,
+}
+
+/// 快照以来的手续费增长（通过 return data 返回）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeGrowth {
+    /// 快照到当前经过的秒数
+    pub elapsed_seconds: i64,
+    /// 每份 LP 的价值增长比例，18 位定点数
+    pub growth_per_share: u128,
+    /// 按连续复利年化的收益率估计，18 位定点数
+    pub apy: u128,
+}
+
+/// 读取 pool 中所有 token 的 vault 余额，vault 按 pool 中 token 的顺序传入
+pub(crate) fn read_vault_balances(pool: &AnySwapPool, vault_infos: &[AccountInfo]) -> Result<Vec<u64>> {
+    let token_count = pool.get_token_count();
+    require!(
+        vault_infos.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let mut vault_balances = Vec::with_capacity(token_count);
+    for (i, vault_info) in vault_infos.iter().enumerate() {
+        let token_item = pool.get_token_checked(i)?;
+        require!(
+            vault_info.key() == *token_item.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+        vault_balances.push(token_item.lp_balance(read_token_amount(vault_info)?));
+    }
+    Ok(vault_balances)
+}
+
+/// 记录当前每份 LP 对应的加权不变量，供 get_fee_growth 计算之后的手续费增长
+///
+/// 任何人都可以调用（由 payer 支付租金），通常由 keeper 定期调用
+///
+/// RemainingAccounts 结构：
+/// - pool 中每个 token 的 vault 账户，按 pool 中 token 的顺序传入
+pub fn snapshot_fees<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, SnapshotFees<'info>>,
+) -> Result<()> {
+    let pool = ctx.accounts.pool.load()?;
+    let vault_balances = read_vault_balances(&pool, ctx.remaining_accounts)?;
+    let log_value_per_share =
+        pool.log_value_per_share(&vault_balances, ctx.accounts.clock.unix_timestamp)?;
+    let lp_supply = pool.get_total_amount_minted();
+    drop(pool);
+
+    let snapshot = &mut ctx.accounts.snapshot;
+    snapshot.pool = ctx.accounts.pool.key();
+    snapshot.timestamp = ctx.accounts.clock.unix_timestamp;
+    snapshot.lp_supply = lp_supply;
+    snapshot.log_value_per_share = log_value_per_share;
+    snapshot.bump = ctx.bumps.snapshot;
+
+    msg!(
+        "Fee snapshot at {}: lp_supply {}",
+        snapshot.timestamp,
+        lp_supply
+    );
+    Ok(())
+}
+
+/// 返回 since_timestamp 的快照以来每份 LP 的手续费增长，以及按这段时间年化的 APY 估计
+///
+/// 增长只反映留在池子中的手续费；期间 add_token / remove_token / modify_weight 会改变
+/// 加权不变量，跨越这些操作的结果没有意义
+///
+/// RemainingAccounts 结构：
+/// - pool 中每个 token 的 vault 账户，按 pool 中 token 的顺序传入
+pub fn get_fee_growth<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, GetFeeGrowth<'info>>,
+    since_timestamp: i64,
+) -> Result<FeeGrowth> {
+    let now = Clock::get()?.unix_timestamp;
+    let pool = ctx.accounts.pool.load()?;
+    let vault_balances = read_vault_balances(&pool, ctx.remaining_accounts)?;
+    let log_value_per_share = pool.log_value_per_share(&vault_balances, now)?;
+    drop(pool);
+
+    let elapsed_seconds = now
+        .checked_sub(since_timestamp)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let (growth_per_share, apy) = annualize_fee_growth(
+        ctx.accounts.snapshot.log_value_per_share,
+        log_value_per_share,
+        elapsed_seconds,
+    )?;
+    Ok(FeeGrowth {
+        elapsed_seconds,
+        growth_per_share,
+        apy,
+    })
+}
@@ -0,0 +1,144 @@
+use crate::error::ErrorCode;
+use crate::state::flash_loan::{check_flash_loan_repaid, flash_loan_fee};
+use crate::state::pool::PAUSE_SWAP;
+use crate::state::AnySwapPool;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+/// 闪电贷账户结构
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool authority PDA - 用于管理所有 vault
+    /// CHECK: PDA derived from pool key, used as token account owner
+    #[account(
+        seeds = [b"anyswap_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// 借出 token 的 vault，必须属于 pool
+    #[account(
+        mut,
+        token::authority = pool_authority
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// 借款人接收 token 的账户
+    #[account(
+        mut,
+        token::mint = vault.mint
+    )]
+    pub borrower_token: Box<Account<'info, TokenAccount>>,
+
+    pub borrower: Signer<'info>,
+
+    /// 回调程序，收到 token 后被 CPI 调用，需要在返回前把本金和手续费转回 vault
+    /// CHECK: 任意可执行程序，由调用方指定
+    #[account(executable)]
+    pub callback_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// 闪电贷：在同一笔交易中从 vault 借出 token
+///
+/// 流程：
+/// 1. 记录 vault 余额，由 pool_authority 签名把 amount 转给 borrower_token
+/// 2. CPI 调用 callback_program（指令数据为 callback_data）
+/// 3. 重新读取 vault 余额，必须不少于借出前的余额加上手续费，否则返回 FlashLoanNotRepaid
+///
+/// 手续费按 pool 费率计算（向上取整），留在 vault 中归 LP。
+/// Solana 不允许回调程序重入本程序，借款期间无法在本 pool 中 swap
+///
+/// RemainingAccounts 结构：
+/// - 原样传给 callback_program 的账户
+///
+/// amount: 借出数量
+/// callback_data: 传给 callback_program 的指令数据
+pub fn flash_loan<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, FlashLoan<'info>>,
+    amount: u64,
+    callback_data: Vec<u8>,
+) -> Result<()> {
+    // 回调程序不能是本程序或 token 程序
+    let callback_program_key = ctx.accounts.callback_program.key();
+    require!(
+        callback_program_key != crate::ID && callback_program_key != ctx.accounts.token_program.key(),
+        ErrorCode::InvalidCallbackProgram
+    );
+
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_SWAP)?;
+    let token_item = pool
+        .get_token_by_mint(&ctx.accounts.vault.mint)
+        .ok_or(ErrorCode::InvalidTokenMint)?;
+    require!(
+        ctx.accounts.vault.key() == *token_item.vault_pubkey(),
+        ErrorCode::InvalidTokenMint
+    );
+    let fee = flash_loan_fee(amount, pool.get_fee_numerator(), pool.get_fee_denominator())?;
+    drop(pool);
+
+    let balance_before = ctx.accounts.vault.amount;
+    require!(amount <= balance_before, ErrorCode::InsufficientLiquidity);
+
+    // 1. 借出
+    let pool_key = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.borrower_token.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer,
+        ),
+        amount,
+    )?;
+
+    // 2. 回调
+    let callback_ix = Instruction {
+        program_id: callback_program_key,
+        accounts: ctx
+            .remaining_accounts
+            .iter()
+            .map(|a| AccountMeta {
+                pubkey: a.key(),
+                is_signer: a.is_signer,
+                is_writable: a.is_writable,
+            })
+            .collect(),
+        data: callback_data,
+    };
+    let mut callback_account_infos = ctx.remaining_accounts.to_vec();
+    callback_account_infos.push(ctx.accounts.callback_program.to_account_info());
+    invoke(&callback_ix, &callback_account_infos)?;
+
+    // 3. 重新读取 vault 余额，检查已归还本金和手续费
+    ctx.accounts.vault.reload()?;
+    let balance_after = ctx.accounts.vault.amount;
+    check_flash_loan_repaid(balance_before, balance_after, fee)?;
+
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    pool_mut.record_activity(Clock::get()?.unix_timestamp);
+    drop(pool_mut);
+
+    msg!(
+        "Flash loan: {} of {} borrowed by {}, fee={}, repaid={}",
+        amount,
+        ctx.accounts.vault.mint,
+        ctx.accounts.borrower.key(),
+        fee,
+        balance_after - balance_before + amount
+    );
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::state::{AnySwapPool, LpPosition};
+
+/// 创建 LP 持仓记录
+/// 通过转账获得 LP 的用户没有加入记录，移除流动性前需要先创建
+#[derive(Accounts)]
+pub struct OpenLpPosition<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// 用户的 LP 持仓记录，已存在时创建失败
+    #[account(
+        init,
+        payer = owner,
+        space = LpPosition::space(),
+        seeds = [b"lp_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub lp_position: Box<Account<'info, LpPosition>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// 创建 LP 持仓记录，加入时间记为当前时间
+/// 与刚加入流动性一样需要等待 min_hold_seconds 才能退出，转给新钱包的 LP 不能绕过持有期
+pub fn open_lp_position(ctx: Context<OpenLpPosition>) -> Result<()> {
+    let lp_position = &mut ctx.accounts.lp_position;
+    lp_position.pool = ctx.accounts.pool.key();
+    lp_position.owner = ctx.accounts.owner.key();
+    lp_position.last_join_ts = Clock::get()?.unix_timestamp;
+    lp_position.bump = ctx.bumps.lp_position;
+
+    msg!(
+        "LP position opened for {} at {}",
+        lp_position.owner,
+        lp_position.last_join_ts
+    );
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::liquidity::max_joinable_inner;
+use crate::state::token_account::read_token_amount;
+use crate::state::AnySwapPool;
+
+/// 查询最大可加入的流动性（只读）
+#[derive(Accounts)]
+pub struct MaxJoinable<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+}
+
+/// 最大按比例加入的结果（通过 return data 返回）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct MaxJoin {
+    /// 传给 add_liquidity 的 amounts_in（按 pool 中 token 的顺序）
+    pub amounts_in: Vec<u64>,
+    /// 按 amounts_in 加入时铸造的 LP 数量
+    pub lp_minted: u64,
+}
+
+/// 查询不超过上限（单次铸造的 LP、vault 余额上限）的最大按比例加入
+///
+/// RemainingAccounts 结构：
+/// - pool 中每个 token 的 vault 账户，按 pool 中 token 的顺序传入
+///
+/// 按返回的 amounts_in 调用 add_liquidity 不会因上限失败（储备未变化时）
+pub fn max_joinable<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, MaxJoinable<'info>>,
+) -> Result<MaxJoin> {
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_min_tokens()?;
+    let token_count = pool.get_token_count();
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(
+        remaining_accounts.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let mut vault_balances = Vec::with_capacity(token_count);
+    for (i, vault_info) in remaining_accounts.iter().enumerate() {
+        let token_item = pool.get_token_checked(i)?;
+        require!(
+            vault_info.key() == *token_item.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+        vault_balances.push(token_item.lp_balance(read_token_amount(vault_info)?));
+    }
+
+    let (amounts_in, lp_minted) = max_joinable_inner(
+        &vault_balances,
+        pool.get_total_amount_minted(),
+        pool.get_fee_numerator(),
+        pool.get_fee_denominator(),
+    )?;
+
+    Ok(MaxJoin {
+        amounts_in,
+        lp_minted,
+    })
+}
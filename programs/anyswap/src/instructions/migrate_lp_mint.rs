@@ -0,0 +1,187 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Burn, Mint, MintTo, TokenAccount, TokenInterface};
+use crate::state::AnySwapPool;
+use crate::error::ErrorCode;
+
+/// 开始 LP mint 迁移
+#[derive(Accounts)]
+pub struct MigrateLpMint<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool authority PDA - 新 mint 的 mint authority
+    /// CHECK: PDA derived from pool key
+    #[account(
+        seeds = [b"anyswap_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// 新的 LP mint（Token 或 Token-2022），必须由 pool_authority 铸造且尚未发行
+    #[account(
+        constraint = new_mint.mint_authority == Some(pool_authority.key()).into() @ ErrorCode::LpMintMismatch,
+        constraint = new_mint.supply == 0 @ ErrorCode::LpMintMismatch,
+        constraint = new_mint.freeze_authority.is_none() @ ErrorCode::LpMintMismatch,
+    )]
+    pub new_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Pool 管理员 - 必须签名
+    pub admin: Signer<'info>,
+}
+
+/// 将旧 LP 1:1 换成新 LP
+#[derive(Accounts)]
+pub struct SwapLp<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool authority PDA
+    /// CHECK: PDA derived from pool key
+    #[account(
+        seeds = [b"anyswap_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// 旧的 LP mint（当前的 LP mint，见 AnySwapPool::canonical_lp_mint，在指令中校验）
+    #[account(mut)]
+    pub pool_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// 新的 LP mint
+    #[account(mut)]
+    pub new_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// 用户的旧 LP token 账户
+    #[account(
+        mut,
+        constraint = user_old_ata.mint == pool_mint.key(),
+        constraint = user_old_ata.owner == owner.key()
+    )]
+    pub user_old_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// 用户的新 LP token 账户
+    #[account(
+        mut,
+        constraint = user_new_ata.mint == new_mint.key(),
+        constraint = user_new_ata.owner == owner.key()
+    )]
+    pub user_new_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub owner: Signer<'info>,
+
+    /// 旧 LP mint 所属的 token 程序
+    pub lp_token_program: Interface<'info, TokenInterface>,
+    /// 新 LP mint 所属的 token 程序（Token 或 Token-2022）
+    pub new_token_program: Interface<'info, TokenInterface>,
+}
+
+/// 完成 LP mint 迁移
+#[derive(Accounts)]
+pub struct FinishLpMigration<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// 旧的 LP mint（当前的 LP mint，在指令中校验），供应量必须为 0
+    pub pool_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Pool 管理员 - 必须签名
+    pub admin: Signer<'info>,
+}
+
+/// 开始 LP mint 迁移（仅管理员）
+///
+/// 迁移开始后，LP 持有人可以通过 swap_lp 把旧 LP 1:1 换成新 LP，
+/// 迁移期间移除流动性（包括 request_exit、zap_out）同时接受两个 mint，新的流动性仍铸造旧 mint；
+/// 旧 LP 全部换完后由 finish_lp_migration 把新 mint 设为当前的 LP mint
+pub fn migrate_lp_mint(ctx: Context<MigrateLpMint>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+
+    // 验证管理员权限
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+
+    // 新旧 mint 精度必须一致，1:1 兑换才不改变份额
+    pool.check_lp_mint(ctx.accounts.new_mint.decimals)?;
+
+    let new_mint = ctx.accounts.new_mint.key();
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    pool.start_lp_migration(&new_mint, &pda_mint)?;
+
+    msg!("LP mint migration started: new mint {}", new_mint);
+    Ok(())
+}
+
+/// 将用户的全部旧 LP 1:1 换成新 LP
+pub fn swap_lp(ctx: Context<SwapLp>) -> Result<()> {
+    let amount = ctx.accounts.user_old_ata.amount;
+    require!(amount > 0, ErrorCode::InsufficientTokenAmount);
+
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.check_canonical_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+    require!(
+        pool.is_migrating_lp_mint() && ctx.accounts.new_mint.key() == pool.migration_lp_mint,
+        ErrorCode::LpMintMismatch
+    );
+    pool.record_lp_swap(amount)?;
+    drop(pool);
+
+    // 销毁旧 LP（用户自己签名销毁）
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.lp_token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.pool_mint.to_account_info(),
+                from: ctx.accounts.user_old_ata.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    // 准备 seeds 用于签名
+    let pool_key = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+
+    // 铸造等量的新 LP
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.new_token_program.to_account_info(),
+            MintTo {
+                to: ctx.accounts.user_new_ata.to_account_info(),
+                mint: ctx.accounts.new_mint.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer,
+        ),
+        amount,
+    )?;
+
+    msg!("LP swapped to new mint: {}", amount);
+    Ok(())
+}
+
+/// 完成 LP mint 迁移（仅管理员）
+///
+/// 旧 mint 上的 LP 必须已全部换成新 LP 或销毁（pool 记账和 mint 供应量都为 0），
+/// 之后新 mint 成为当前的 LP mint：新的流动性铸造新 mint，旧 mint 不再被接受，
+/// 迁移状态清空后可以关闭 pool（check_can_close）或开始下一次迁移。
+/// 旧 LP 迟迟不换时，管理员可以先暂停添加流动性，避免旧 mint 继续增发
+pub fn finish_lp_migration(ctx: Context<FinishLpMigration>) -> Result<()> {
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+
+    // 验证管理员权限
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+
+    pool.check_canonical_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+    require!(
+        ctx.accounts.pool_mint.supply == 0,
+        ErrorCode::LpMigrationIncomplete
+    );
+    pool.finish_lp_migration()?;
+
+    msg!("LP mint migration finished: current mint {}", pool.canonical_lp_mint(&pda_mint));
+    Ok(())
+}
@@ -2,16 +2,76 @@ pub mod create_pool;
 pub mod add_token;
 pub mod remove_token;
 pub mod modify_weight;
+pub mod recompute_weight_sum;
 pub mod modify_fee;
+pub mod set_token_fees;
+pub mod set_stable_group;
+pub mod modify_min_hold;
+pub mod pause;
+pub mod set_guardian;
+pub mod admin_transfer;
 pub mod swap;
+pub mod swap_with_jit;
+pub mod swap_exact_out;
+pub mod quote_swap;
+pub mod validate_setup;
 pub mod add_liquidity;
 pub mod remove_liquidity;
+pub mod lp_position;
+pub mod exit_queue;
+pub mod zap;
+pub mod migrate_lp_mint;
+pub mod vault_address;
+pub mod activity;
+pub mod active_tokens;
+pub mod compound_fees;
+pub mod fee_snapshot;
+pub mod max_joinable;
+pub mod preview_amounts_used;
+pub mod twap;
+pub mod flash_loan;
+pub mod version;
+pub mod protocol_fee;
+pub mod close_pool;
+pub mod swap_route;
+pub mod modify_min_swap_amount;
+pub mod modify_max_tokens_per_swap;
 
 pub use create_pool::*;
 pub use add_token::*;
 pub use remove_token::*;
 pub use modify_weight::*;
+pub use recompute_weight_sum::*;
 pub use modify_fee::*;
+pub use set_token_fees::*;
+pub use set_stable_group::*;
+pub use modify_min_hold::*;
+pub use pause::*;
+pub use set_guardian::*;
+pub use admin_transfer::*;
 pub use swap::*;
+pub use swap_with_jit::*;
+pub use swap_exact_out::*;
+pub use quote_swap::*;
+pub use validate_setup::*;
 pub use add_liquidity::*;
-pub use remove_liquidity::*;
\ No newline at end of file
+pub use remove_liquidity::*;
+pub use lp_position::*;
+pub use exit_queue::*;
+pub use zap::*;
+pub use migrate_lp_mint::*;
+pub use vault_address::*;
+pub use activity::*;
+pub use active_tokens::*;
+pub use compound_fees::*;
+pub use fee_snapshot::*;
+pub use max_joinable::*;
+pub use preview_amounts_used::*;
+pub use twap::*;
+pub use flash_loan::*;
+pub use version::*;
+pub use protocol_fee::*;
+pub use close_pool::*;
+pub use swap_route::*;
+pub use modify_min_swap_amount::*;
+pub use modify_max_tokens_per_swap::*;
\ No newline at end of file
@@ -1,6 +1,5 @@
 use anchor_lang::prelude::*;
 use crate::state::AnySwapPool;
-use crate::error::ErrorCode;
 
 /// 修改 pool 的费率
 #[derive(Accounts)]
@@ -22,21 +21,10 @@ pub fn modify_fee(
     fee_numerator: u64,
     fee_denominator: u64,
 ) -> Result<()> {
-    require!(fee_denominator > 0, ErrorCode::MathOverflow);
-    require!(fee_numerator <= fee_denominator, ErrorCode::MathOverflow);
-    
     let pool = &mut ctx.accounts.pool.load_mut()?;
     
-    // 验证管理员权限
-    pool.verify_admin(&ctx.accounts.admin.key())?;
-    
-    // 检查费率是否合理
-    require!(fee_denominator > 0, ErrorCode::MathOverflow);
-    require!(fee_numerator > 0, ErrorCode::MathOverflow);
-    require!(fee_numerator <= fee_denominator, ErrorCode::MathOverflow);
-    // 修改费率
-    pool.fee_numerator = fee_numerator;
-    pool.fee_denominator = fee_denominator;
+    // 验证管理员权限，校验整组费率后一次写入
+    pool.modify_fee(&ctx.accounts.admin.key(), fee_numerator, fee_denominator)?;
     
     msg!("Pool fee updated to {}/{}", fee_numerator, fee_denominator);
     Ok(())
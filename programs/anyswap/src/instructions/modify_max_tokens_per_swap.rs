@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use crate::state::AnySwapPool;
+
+/// 修改 pool 一次操作最多涉及的 token 数量
+#[derive(Accounts)]
+pub struct ModifyMaxTokensPerSwap<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名修改操作
+    /// CHECK: 验证是否为 pool 的管理员
+    pub admin: Signer<'info>,
+}
+
+/// 修改 pool 一次操作最多涉及的 token 数量
+/// max_tokens_per_swap: swap 中参与的 token 数量上限，0 表示不限制；
+///   增减流动性涉及 pool 的全部 token，同样受该上限约束，设置时不应小于 pool 的 token 数量
+pub fn modify_max_tokens_per_swap(
+    ctx: Context<ModifyMaxTokensPerSwap>,
+    max_tokens_per_swap: u16,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+
+    // 验证管理员权限
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+
+    pool.set_max_tokens_per_swap(max_tokens_per_swap);
+
+    msg!("Pool max tokens per swap updated to {}", max_tokens_per_swap);
+    Ok(())
+}
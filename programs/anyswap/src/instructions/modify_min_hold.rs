@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+use crate::state::AnySwapPool;
+use crate::error::ErrorCode;
+
+/// 修改 pool 的最短持有期
+#[derive(Accounts)]
+pub struct ModifyMinHold<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名修改操作
+    /// CHECK: 验证是否为 pool 的管理员
+    pub admin: Signer<'info>,
+}
+
+/// 修改 pool 的最短持有期
+/// min_hold_seconds: 添加流动性后至少需要等待的秒数才能移除流动性，0 表示不限制
+pub fn modify_min_hold_seconds(
+    ctx: Context<ModifyMinHold>,
+    min_hold_seconds: i64,
+) -> Result<()> {
+    require!(min_hold_seconds >= 0, ErrorCode::InvalidHoldPeriod);
+
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+
+    // 验证管理员权限
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+
+    pool.set_min_hold_seconds(min_hold_seconds);
+
+    msg!("Pool min hold period updated to {}s", min_hold_seconds);
+    Ok(())
+}
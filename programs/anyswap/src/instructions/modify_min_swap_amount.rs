@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use crate::state::AnySwapPool;
+
+/// 修改 pool 的最小 swap 数量
+#[derive(Accounts)]
+pub struct ModifyMinSwapAmount<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名修改操作
+    /// CHECK: 验证是否为 pool 的管理员
+    pub admin: Signer<'info>,
+}
+
+/// 修改 pool 的最小 swap 数量
+/// min_swap_amount: swap 中每个输入 token 扣费后的最小数量，0 表示不限制
+pub fn modify_min_swap_amount(
+    ctx: Context<ModifyMinSwapAmount>,
+    min_swap_amount: u64,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+
+    // 验证管理员权限
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+
+    pool.set_min_swap_amount(min_swap_amount);
+
+    msg!("Pool min swap amount updated to {}", min_swap_amount);
+    Ok(())
+}
@@ -16,7 +16,8 @@ pub struct ModifyTokenWeight<'info> {
 /// 修改 token 的 weight
 /// new_weight: 新的权重值
 /// remaining_accounts: token的mint账户列表
-/// 注意：修改 weight 会影响池的恒定乘积和，需要谨慎操作
+/// 注意：修改 weight 会影响池的恒定乘积和，需要谨慎操作；价格会立即跳变，
+/// 需要平滑调整时使用 start_weight_ramp。修改会取消该 token 进行中的权重渐变
 pub fn modify_token_weight(
     ctx: Context<ModifyTokenWeight>,
     new_weights: Vec<u64>,
@@ -31,14 +32,47 @@ pub fn modify_token_weight(
         let mint_key = account.key();
         let token_index = pool.find_token_index(&mint_key)
             .ok_or(ErrorCode::InvalidTokenMint)?;
-        let token = pool.get_token_mut(token_index)
-            .ok_or(ErrorCode::InvalidTokenIndex)?;
-        let old_weight = token.get_weight();
+        let token = pool.get_token_mut_checked(token_index)?;
+        let old_weight = token.weight;
         token.set_weight(new_weights[index]);
         msg!("Token weight modified: mint: {}, old_weight: {}, new_weight: {}", 
              mint_key, old_weight, new_weights[index]);
     }
+    pool.validate_weights()?;
+    pool.recompute_weight_sum()?;
 
     Ok(())
 }
 
+/// 一次设置 pool 中所有 token 的权重
+/// new_weights: 按 pool 中 token 的顺序排列的新权重，数量必须等于 token 数量，每个权重大于 0
+/// 所有权重在同一次修改中写入，swap 不会读到部分修改的权重组合；会取消所有进行中的权重渐变
+pub fn rebalance_weights(ctx: Context<ModifyTokenWeight>, new_weights: Vec<u64>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    pool.rebalance_weights(&ctx.accounts.admin.key(), &new_weights)?;
+
+    msg!("Pool weights rebalanced: {:?}", new_weights);
+    Ok(())
+}
+
+/// 开始 token 的权重渐变（LBP）：在 duration 秒内从当前权重线性变化到 end_weight
+/// 渐变期间 swap 读取按时间插值的权重，见 AnySwapPool::start_weight_ramp
+pub fn start_weight_ramp(
+    ctx: Context<ModifyTokenWeight>,
+    mint: Pubkey,
+    end_weight: u64,
+    duration: i64,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    let now = Clock::get()?.unix_timestamp;
+    pool.start_weight_ramp(&ctx.accounts.admin.key(), &mint, end_weight, duration, now)?;
+
+    msg!(
+        "Weight ramp started: mint: {}, end_weight: {}, from {} to {}",
+        mint,
+        end_weight,
+        now,
+        now + duration
+    );
+    Ok(())
+}
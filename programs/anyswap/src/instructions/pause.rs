@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use crate::state::pool::PAUSE_ALL;
+use crate::state::AnySwapPool;
+
+/// 暂停 pool 操作（管理员或 guardian）
+#[derive(Accounts)]
+pub struct PausePool<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// 管理员或 guardian - 必须签名
+    pub authority: Signer<'info>,
+}
+
+/// 暂停 pool 的所有操作（swap、添加流动性、移除流动性）
+pub fn pause_pool(ctx: Context<PausePool>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    let paused_ops = pool.get_paused_ops() | PAUSE_ALL;
+    pool.set_paused_ops(&ctx.accounts.authority.key(), paused_ops)?;
+
+    msg!("Pool paused by {}", ctx.accounts.authority.key());
+    Ok(())
+}
+
+/// 管理员的紧急开关
+/// paused: 是否暂停 swap 和添加流动性，false 时恢复所有操作
+/// allow_withdraw_when_paused: 暂停期间是否仍允许移除流动性
+pub fn set_pause(
+    ctx: Context<PausePool>,
+    paused: bool,
+    allow_withdraw_when_paused: bool,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    pool.set_pause(&ctx.accounts.authority.key(), paused, allow_withdraw_when_paused)?;
+
+    msg!(
+        "Pool paused: {}, allow withdraw: {}",
+        paused,
+        allow_withdraw_when_paused
+    );
+    Ok(())
+}
+
+/// 设置暂停的操作
+/// paused_ops: PAUSE_* 标志位组合
+/// 管理员可以任意设置；guardian 只能增加暂停的操作
+pub fn set_paused_ops(ctx: Context<PausePool>, paused_ops: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    pool.set_paused_ops(&ctx.accounts.authority.key(), paused_ops)?;
+
+    msg!("Pool paused ops set to {:#b}", paused_ops);
+    Ok(())
+}
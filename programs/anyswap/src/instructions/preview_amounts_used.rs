@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+use crate::instructions::fee_snapshot::read_vault_balances;
+use crate::state::AnySwapPool;
+
+/// 预览添加流动性实际使用的数量（只读）
+#[derive(Accounts)]
+pub struct PreviewAmountsUsed<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+}
+
+/// 返回按 amounts_in 调用 add_liquidity 时实际从用户拿走的每个 token 数量（含手续费，通过 return data 返回）
+///
+/// 按比例加入时部分 token 可能用不完，钱包可以按返回值精确设置授权额度
+///
+/// RemainingAccounts 结构：
+/// - pool 中每个 token 的 vault 账户，按 pool 中 token 的顺序传入
+pub fn preview_amounts_used<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, PreviewAmountsUsed<'info>>,
+    amounts_in: Vec<u64>,
+) -> Result<Vec<u64>> {
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_min_tokens()?;
+    let vault_balances = read_vault_balances(&pool, ctx.remaining_accounts)?;
+    pool.preview_amounts_used(&vault_balances, &amounts_in)
+}
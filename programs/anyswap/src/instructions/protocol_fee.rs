@@ -0,0 +1,135 @@
+use crate::error::ErrorCode;
+use crate::state::token_account::{check_writable_pairs, load_token_account};
+use crate::state::AnySwapPool;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, Transfer};
+
+/// 设置协议费
+#[derive(Accounts)]
+pub struct SetProtocolFee<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名
+    /// CHECK: 验证是否为 pool 的管理员
+    pub admin: Signer<'info>,
+}
+
+/// 设置协议费：swap 手续费中 numerator / denominator 归协议，numerator 为 0 时关闭协议费
+/// recipient: 协议费接收者，collect_protocol_fees 只能转入其 token 账户
+///
+/// 所有收取 swap 手续费的路径都计提协议费：swap、精确输出、池内多跳、JIT swap，
+/// 以及 zap 和单 token 加入 / 移除流动性中的内部 swap
+///
+/// 只影响之后的 swap，已计提的协议费仍由 collect_protocol_fees 转给新的接收者
+pub fn set_protocol_fee(
+    ctx: Context<SetProtocolFee>,
+    numerator: u64,
+    denominator: u64,
+    recipient: Pubkey,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.set_protocol_fee(&ctx.accounts.admin.key(), numerator, denominator, &recipient)?;
+
+    msg!(
+        "Protocol fee set to {}/{}, recipient: {}",
+        numerator,
+        denominator,
+        recipient
+    );
+    Ok(())
+}
+
+/// 领取协议费
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool authority PDA - 用于管理所有 vault
+    /// CHECK: PDA derived from pool key, used as token account owner
+    #[account(
+        seeds = [b"anyswap_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// 将已计提的协议费从 vault 转给协议费接收者，并清零计提
+///
+/// 任何人都可以调用，协议费只能转入接收者（pool.protocol_fee_recipient）的 token 账户
+///
+/// RemainingAccounts 结构：
+/// - 每两个账户为一对：(vault_account, recipient_token_account)，只传入需要领取的 token
+pub fn collect_protocol_fees<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, CollectProtocolFees<'info>>,
+) -> Result<()> {
+    let remaining_accounts = ctx.remaining_accounts;
+    let pairs = remaining_accounts.chunks_exact(2);
+    require!(
+        pairs.len() > 0 && pairs.remainder().is_empty(),
+        ErrorCode::InvalidTokenCount
+    );
+    check_writable_pairs(remaining_accounts)?;
+
+    let pool_key = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    let recipient = *pool.get_protocol_fee_recipient();
+    require!(
+        recipient != Pubkey::default(),
+        ErrorCode::InvalidProtocolFeeRecipient
+    );
+
+    for (i, pair) in pairs.enumerate() {
+        let vault_info = &pair[0];
+        let recipient_info = &pair[1];
+
+        let vault_account = load_token_account(vault_info, i * 2)?;
+        require!(
+            vault_account.owner == ctx.accounts.pool_authority.key(),
+            ErrorCode::InvalidTokenMint
+        );
+        let token_index = pool
+            .find_token_index(&vault_account.mint)
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        require!(
+            vault_info.key() == *pool.get_token_checked(token_index)?.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+
+        let recipient_account = load_token_account(recipient_info, i * 2 + 1)?;
+        require!(
+            recipient_account.owner == recipient,
+            ErrorCode::InvalidProtocolFeeRecipient
+        );
+        require!(
+            recipient_account.mint == vault_account.mint,
+            ErrorCode::InvalidTokenMint
+        );
+
+        let amount = pool.take_protocol_fee(token_index)?;
+        if amount == 0 {
+            continue;
+        }
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: vault_info.clone(),
+                    to: recipient_info.clone(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        msg!("Protocol fee collected: mint: {}, amount: {}", vault_account.mint, amount);
+    }
+    Ok(())
+}
@@ -0,0 +1,326 @@
+use crate::error::ErrorCode;
+use crate::state::fees::{split_fees, FeeBreakdown};
+use crate::math::i256::I256;
+use crate::state::swap::{
+    check_swap_invariant, effective_fee_bps, spot_price, spot_price_with_fee, swap_curve_inner,
+};
+use crate::state::token_account::load_token_account;
+use crate::state::AnySwapPool;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+
+/// 询价账户结构（只读）
+#[derive(Accounts)]
+pub struct QuoteSwap<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool authority PDA - 用于验证 vault
+    /// CHECK: PDA derived from pool key, used as token account owner
+    #[account(
+        seeds = [b"anyswap_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+}
+
+/// swap 询价结果（通过 return data 返回）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SwapQuote {
+    /// 每个 token 的数量（输入为扣费后的实际输入，输出为实际输出）
+    pub amounts: Vec<u64>,
+    /// 每个输入 token 的手续费
+    pub burn_fees: Vec<u64>,
+    /// 实际支付的手续费占交易额的 bps
+    pub effective_fee_bps: u64,
+    /// 手续费拆分（LP / 协议 / 推荐人）
+    pub fee_breakdown: FeeBreakdown,
+}
+
+/// swap 安全检查结果（通过 return data 返回）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SwapSafety {
+    /// 不变量是否保持（下降不超过计算误差）
+    pub safe: bool,
+    /// invariant_after - invariant_before（18 位定点数；稳定组内为 StableSwap 的 D）
+    pub invariant_delta: I256,
+}
+
+/// 现货价格（通过 set_return_data 返回）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SpotPrice {
+    /// 不含手续费的现货价格（18 位定点数）
+    pub spot_price: u128,
+    /// 含手续费的现货价格（18 位定点数）
+    pub spot_price_after_fee: u128,
+}
+
+/// 读取并验证参与询价的 vault 账户，返回余额、now 时刻的权重和 mint
+pub(crate) fn load_quote_vaults<'a>(
+    pool: &AnySwapPool,
+    vaults: &'a [AccountInfo<'a>],
+    pool_authority_key: &Pubkey,
+    now: i64,
+) -> Result<(Vec<u64>, Vec<u64>, Vec<Pubkey>)> {
+    let mut token_vaults_amount = Vec::with_capacity(vaults.len());
+    let mut weights = Vec::with_capacity(vaults.len());
+    let mut mints = Vec::with_capacity(vaults.len());
+    for (i, vault_info) in vaults.iter().enumerate() {
+        let vault_account = load_token_account(vault_info, i)?;
+        require!(
+            vault_account.owner == *pool_authority_key,
+            ErrorCode::InvalidTokenMint
+        );
+        let token_item = pool
+            .get_token_by_mint(&vault_account.mint)
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        require!(
+            vault_info.key() == *token_item.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+        token_vaults_amount.push(token_item.lp_balance(vault_account.amount));
+        weights.push(token_item.weight_at(now));
+        mints.push(vault_account.mint);
+    }
+    pool.check_swap_mints(&mints)?;
+    Ok((token_vaults_amount, weights, mints))
+}
+
+/// 读取并验证一对 token 的 vault 账户：[vault_in, vault_out] 必须分别是 mint_in 和 mint_out 的 vault
+fn load_pair_vaults<'a>(
+    pool: &AnySwapPool,
+    vaults: &'a [AccountInfo<'a>],
+    pool_authority_key: &Pubkey,
+    mint_in: Pubkey,
+    mint_out: Pubkey,
+    now: i64,
+) -> Result<(Vec<u64>, Vec<u64>, Vec<Pubkey>)> {
+    require!(mint_in != mint_out, ErrorCode::InvalidTokenMint);
+    require!(vaults.len() == 2, ErrorCode::InvalidTokenCount);
+    for (vault_info, mint) in vaults.iter().zip([mint_in, mint_out]) {
+        let token_item = pool
+            .get_token_by_mint(&mint)
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        require!(
+            vault_info.key() == *token_item.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+    }
+    load_quote_vaults(pool, vaults, pool_authority_key, now)
+}
+
+/// swap 询价（不转账，只返回结果）
+///
+/// RemainingAccounts 结构：
+/// - 每个参与 swap 的 token 一个 vault 账户，顺序与 amounts_tolerance 一致
+///
+/// amounts_tolerance: 每个 token 的容差（输入为上限，输出为下限）
+/// is_in_token: 标记每个 token 是输入还是输出
+/// referral_fee_bps: 推荐人从手续费中分得的比例（bps），没有推荐人时为 0
+///
+/// amounts 已扣除全部手续费；fee_breakdown 只拆分手续费的归属，三部分之和等于 burn_fees
+pub fn quote_swap_detailed<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, QuoteSwap<'info>>,
+    amounts_tolerance: Vec<u64>,
+    is_in_token: Vec<bool>,
+    referral_fee_bps: u64,
+) -> Result<SwapQuote> {
+    let token_count = amounts_tolerance.len();
+    require!(token_count > 0, ErrorCode::InvalidTokenCount);
+    require!(
+        is_in_token.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+    require!(
+        ctx.remaining_accounts.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let pool = ctx.accounts.pool.load()?;
+    let (token_vaults_amount, weights, mints) = load_quote_vaults(
+        &pool,
+        ctx.remaining_accounts,
+        &ctx.accounts.pool_authority.key(),
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    // 询价时假设用户余额足够
+    let swap_result = pool.swap_directional(
+        &mints,
+        &is_in_token,
+        &amounts_tolerance,
+        &amounts_tolerance,
+        &token_vaults_amount,
+        &weights,
+    )?;
+
+    let fee_bps = effective_fee_bps(&is_in_token, &amounts_tolerance, &swap_result.burn_fees)?;
+    // 按 pool 配置的协议费拆分，未设置协议费时全部手续费在 LP 与推荐人之间拆分
+    let (protocol_numerator, protocol_denominator) = pool.protocol_fee_rate();
+    let fee_breakdown = split_fees(
+        &swap_result.burn_fees,
+        protocol_numerator,
+        protocol_denominator,
+        referral_fee_bps,
+    )?;
+
+    Ok(SwapQuote {
+        amounts: swap_result.amounts,
+        burn_fees: swap_result.burn_fees,
+        effective_fee_bps: fee_bps,
+        fee_breakdown,
+    })
+}
+
+/// 询价曲线：同一对 token 在多个输入数量下的输出（不转账，通过 return data 返回）
+///
+/// RemainingAccounts 结构：
+/// - [vault_in, vault_out]，分别为 mint_in 和 mint_out 的 vault
+///
+/// amounts_in: 输入数量（扣费前），最多 MAX_QUOTE_CURVE_POINTS 个
+///
+/// 每个输出与单独对该输入数量询价（1 进 1 出）的结果一致
+pub fn quote_swap_curve<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, QuoteSwap<'info>>,
+    mint_in: Pubkey,
+    mint_out: Pubkey,
+    amounts_in: Vec<u64>,
+) -> Result<Vec<u64>> {
+    let pool = ctx.accounts.pool.load()?;
+    let (token_vaults_amount, weights, mints) = load_pair_vaults(
+        &pool,
+        ctx.remaining_accounts,
+        &ctx.accounts.pool_authority.key(),
+        mint_in,
+        mint_out,
+        Clock::get()?.unix_timestamp,
+    )?;
+    let (fee_numerator, fee_denominator) = pool.swap_fee_rates(&mints, &[true, false])?[0];
+    let amplification = pool.stable_pair_amplification(&mints, &[true, false]);
+
+    swap_curve_inner(
+        token_vaults_amount[0],
+        token_vaults_amount[1],
+        weights[0],
+        weights[1],
+        &amounts_in,
+        fee_numerator,
+        fee_denominator,
+        amplification,
+    )
+}
+
+/// 含手续费的现货价格（18 位定点数，通过 return data 返回）
+///
+/// RemainingAccounts 结构：
+/// - [vault_in, vault_out]，分别为 mint_in 和 mint_out 的 vault
+///
+/// 返回在无穷小交易下每单位 mint_out 需要支付的 mint_in 数量：spot_price / (1 - fee)，
+/// fee 为该方向实际生效的费率（考虑 token 的买入 / 卖出费率）。价格按加权曲线计算
+pub fn get_spot_price_with_fee<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, QuoteSwap<'info>>,
+    mint_in: Pubkey,
+    mint_out: Pubkey,
+) -> Result<u128> {
+    let pool = ctx.accounts.pool.load()?;
+    let (token_vaults_amount, weights, mints) = load_pair_vaults(
+        &pool,
+        ctx.remaining_accounts,
+        &ctx.accounts.pool_authority.key(),
+        mint_in,
+        mint_out,
+        Clock::get()?.unix_timestamp,
+    )?;
+    let (fee_numerator, fee_denominator) = pool.swap_fee_rates(&mints, &[true, false])?[0];
+
+    let price = spot_price(
+        token_vaults_amount[0],
+        weights[0],
+        token_vaults_amount[1],
+        weights[1],
+    )?;
+    spot_price_with_fee(price, fee_numerator, fee_denominator)
+}
+
+/// swap 安全检查（不转账）：模拟 swap 并比较参与 token 在 swap 前后的不变量
+///
+/// RemainingAccounts 结构与 quote_swap_detailed 相同
+///
+/// 询价时假设用户余额足够；返回不变量是否保持以及不变量的变化量
+pub fn check_swap_safe<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, QuoteSwap<'info>>,
+    amounts_tolerance: Vec<u64>,
+    is_in_token: Vec<bool>,
+) -> Result<SwapSafety> {
+    let token_count = amounts_tolerance.len();
+    require!(
+        is_in_token.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+    require!(
+        ctx.remaining_accounts.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let pool = ctx.accounts.pool.load()?;
+    let (token_vaults_amount, weights, mints) = load_quote_vaults(
+        &pool,
+        ctx.remaining_accounts,
+        &ctx.accounts.pool_authority.key(),
+        Clock::get()?.unix_timestamp,
+    )?;
+    let swap_result = pool.swap_directional(
+        &mints,
+        &is_in_token,
+        &amounts_tolerance,
+        &amounts_tolerance,
+        &token_vaults_amount,
+        &weights,
+    )?;
+
+    let (safe, invariant_delta) = check_swap_invariant(
+        &is_in_token,
+        &swap_result.amounts,
+        &token_vaults_amount,
+        &weights,
+        pool.stable_pair_amplification(&mints, &is_in_token),
+    )?;
+    Ok(SwapSafety {
+        safe,
+        invariant_delta,
+    })
+}
+
+/// 查询现货价格（轻量，只读）：不含手续费和含手续费的价格通过 set_return_data 返回，
+/// 客户端通过模拟交易读取
+///
+/// RemainingAccounts 结构：
+/// - [vault_in, vault_out]，分别为 mint_in 和 mint_out 的 vault
+pub fn query_spot_price<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, QuoteSwap<'info>>,
+    mint_in: Pubkey,
+    mint_out: Pubkey,
+) -> Result<()> {
+    let pool = ctx.accounts.pool.load()?;
+    let (token_vaults_amount, weights, mints) = load_pair_vaults(
+        &pool,
+        ctx.remaining_accounts,
+        &ctx.accounts.pool_authority.key(),
+        mint_in,
+        mint_out,
+        Clock::get()?.unix_timestamp,
+    )?;
+    let (fee_numerator, fee_denominator) = pool.swap_fee_rates(&mints, &[true, false])?[0];
+
+    let price = spot_price(
+        token_vaults_amount[0],
+        weights[0],
+        token_vaults_amount[1],
+        weights[1],
+    )?;
+    let result = SpotPrice {
+        spot_price: price,
+        spot_price_after_fee: spot_price_with_fee(price, fee_numerator, fee_denominator)?,
+    };
+    set_return_data(&result.try_to_vec()?);
+    Ok(())
+}
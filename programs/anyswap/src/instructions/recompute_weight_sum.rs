@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+use crate::state::AnySwapPool;
+
+/// 重新计算 pool 的权重之和
+#[derive(Accounts)]
+pub struct RecomputeWeightSum<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名
+    pub admin: Signer<'info>,
+}
+
+/// 从 token 列表重新计算并写入 weight_sum
+/// 用于手动迁移权重或 token 数据后修复失效的缓存
+pub fn recompute_weight_sum(ctx: Context<RecomputeWeightSum>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+
+    let old_sum = pool.get_weight_sum();
+    let new_sum = pool.recompute_weight_sum()?;
+
+    msg!("Weight sum recomputed: {} -> {}", old_sum, new_sum);
+    Ok(())
+}
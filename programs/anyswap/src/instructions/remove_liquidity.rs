@@ -1,8 +1,12 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Burn, Mint, Token, TokenAccount, Transfer};
-use crate::state::AnySwapPool;
+use anchor_spl::token::{self, Token, Transfer};
+use anchor_spl::token_interface::{self, TokenInterface};
+use crate::state::pool::PAUSE_REMOVE_LIQUIDITY;
+use crate::state::{AnySwapPool, LpPosition};
 use crate::state::liquidity::remove_liquidity_inner;
 use crate::error::ErrorCode;
+use crate::events::LiquidityRemoved;
+use crate::state::token_account::{check_writable_pairs, load_token_account, read_token_amount};
 
 /// 移除流动性操作
 /// 按照 Balancer 的方式：按 LP token 比例移除所有 token
@@ -21,12 +25,9 @@ pub struct RemoveLiquidity<'info> {
     pub pool_authority: AccountInfo<'info>,
 
     /// Pool mint - LP token
-    #[account(
-        mut,
-        seeds = [b"pool_mint", pool.key().as_ref()],
-        bump
-    )]
-    pub pool_mint: Box<Account<'info, Mint>>,
+    /// 当前的 LP mint；LP mint 迁移期间也可以是迁移的目标 mint（在指令中校验）
+    #[account(mut)]
+    pub pool_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
 
     /// 用户的 LP token 账户（销毁 LP token）
     #[account(
@@ -34,11 +35,22 @@ pub struct RemoveLiquidity<'info> {
         constraint = user_pool_ata.mint == pool_mint.key(),
         constraint = user_pool_ata.owner == owner.key()
     )]
-    pub user_pool_ata: Box<Account<'info, TokenAccount>>,
+    pub user_pool_ata: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
 
+    /// 用户的 LP 持仓记录（用于最短持有期检查）
+    /// 必须已存在：通过转账获得 LP 的用户需要先调用 open_lp_position，同样要等待持有期
+    #[account(
+        seeds = [b"lp_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump = lp_position.bump,
+    )]
+    pub lp_position: Box<Account<'info, LpPosition>>,
+
+    #[account(mut)]
     pub owner: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+    /// LP mint 所属的 token 程序（迁移后的 LP mint 可以是 Token-2022）
+    pub lp_token_program: Interface<'info, TokenInterface>,
 }
 
 /// 移除流动性（多 token 版本）
@@ -61,13 +73,22 @@ pub fn remove_liquidity<'remaining: 'info, 'info>(
     );
 
     let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_REMOVE_LIQUIDITY)?;
+    pool.check_lp_mint(ctx.accounts.pool_mint.decimals)?;
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    let is_migrated_mint = pool.resolve_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+    pool.check_min_tokens()?;
     let token_count = pool.get_token_count();
-    
-    require!(token_count > 0, ErrorCode::InvalidTokenCount);
+    pool.check_tokens_per_swap(token_count)?;
+
+    // 检查最短持有期
+    ctx.accounts
+        .lp_position
+        .check_hold_period(pool.get_min_hold_seconds(), Clock::get()?.unix_timestamp)?;
     
     let total_minted = pool.get_total_amount_minted();
     require!(
-        total_minted >= burn_amount,
+        total_minted >= burn_amount as u128,
         ErrorCode::MathOverflow
     );
 
@@ -78,7 +99,6 @@ pub fn remove_liquidity<'remaining: 'info, 'info>(
         ErrorCode::InvalidTokenCount
     );
 
-    let pool_authority_key = ctx.accounts.pool_authority.key();
     let owner_key = ctx.accounts.owner.key();
 
     // 收集所有 vault 余额
@@ -88,20 +108,17 @@ pub fn remove_liquidity<'remaining: 'info, 'info>(
         let vault_info = &remaining_accounts[i * 2 + 1];
         
         // 验证 vault
-        let token_item = pool.get_token(i).ok_or(ErrorCode::InvalidTokenIndex)?;
+        let token_item = pool.get_token_checked(i)?;
         require!(
             vault_info.key() == *token_item.vault_pubkey(),
             ErrorCode::InvalidTokenMint
         );
 
-        // 读取 vault 账户并验证 owner 是 pool_authority
-        let vault_account = Account::<TokenAccount>::try_from(vault_info)?;
-        require!(
-            vault_account.owner == pool_authority_key,
-            ErrorCode::InvalidTokenMint
-        );
+        // 地址已与 pool 记录的 vault（由 pool_authority 控制的 PDA）比对，只需读取余额
+        // 未领取的协议费不属于 LP，不计入储备
+        let vault_amount = token_item.lp_balance(read_token_amount(vault_info)?);
 
-        token_vault_balances.push(vault_account.amount);
+        token_vault_balances.push(vault_amount);
     }
 
     // 调用 remove_liquidity_inner
@@ -126,6 +143,7 @@ pub fn remove_liquidity<'remaining: 'info, 'info>(
     let signer = &[&seeds[..]];
 
     // 从 vault 转移所有 token 给用户
+    check_writable_pairs(remaining_accounts)?;
     for i in 0..token_count {
         let user_token_info = &remaining_accounts[i * 2];
         let vault_info = &remaining_accounts[i * 2 + 1];
@@ -136,7 +154,7 @@ pub fn remove_liquidity<'remaining: 'info, 'info>(
         }
 
         // 验证 user_token owner
-        let user_token_account = Account::<TokenAccount>::try_from(user_token_info)?;
+        let user_token_account = load_token_account(user_token_info, i * 2)?;
         require!(
             user_token_account.owner == owner_key,
             ErrorCode::InvalidTokenMint
@@ -157,10 +175,11 @@ pub fn remove_liquidity<'remaining: 'info, 'info>(
     }
 
     // 销毁用户的 LP token（用户自己签名销毁）
-    token::burn(
+    let supply_before = ctx.accounts.pool_mint.supply;
+    token_interface::burn(
         CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Burn {
+            ctx.accounts.lp_token_program.to_account_info(),
+            token_interface::Burn {
                 mint: ctx.accounts.pool_mint.to_account_info(),
                 from: ctx.accounts.user_pool_ata.to_account_info(),
                 authority: ctx.accounts.owner.to_account_info(),
@@ -169,14 +188,18 @@ pub fn remove_liquidity<'remaining: 'info, 'info>(
         burn_amount,
     )?;
 
-    // 更新 total_amount_minted
+    // 按销毁后重新读取的 mint 供应量更新 total_amount_minted
+    ctx.accounts.pool_mint.reload()?;
+    let supply_after = ctx.accounts.pool_mint.supply;
     let mut pool_mut = ctx.accounts.pool.load_mut()?;
-    let current_total = pool_mut.get_total_amount_minted();
-    pool_mut.set_total_amount_minted(
-        current_total
-            .checked_sub(burn_amount)
-            .ok_or(ErrorCode::MathOverflow)?
-    );
+    let burned = pool_mut.record_lp_burn(supply_before, supply_after, burn_amount)?;
+    for i in 0..token_count {
+        pool_mut.record_principal_flow(i, 0, result.amounts_out[i], result.burn_fees[i])?;
+    }
+    pool_mut.record_activity(Clock::get()?.unix_timestamp);
+    if is_migrated_mint {
+        pool_mut.record_migrated_lp_burn(burned)?;
+    }
 
     let total_fees: u64 = result.burn_fees.iter().sum();
 
@@ -186,6 +209,138 @@ pub fn remove_liquidity<'remaining: 'info, 'info>(
         token_count,
         total_fees
     );
+    emit!(LiquidityRemoved::new(
+        ctx.accounts.pool.key(),
+        owner_key,
+        burned,
+        &result
+    ));
+
+    Ok(())
+}
+
+/// 单 token 移除流动性
+///
+/// 销毁 burn_amount 个 LP，只取出 token_index 对应的一个 token，
+/// 按 Balancer 的 calcTokenOutGivenExactBptIn 计算，超出比例的部分收取 swap 手续费
+///
+/// RemainingAccounts 结构：
+/// - (user_token_account, vault_account)：token_index 对应 token 的账户对
+///
+/// token_index: 取出 token 在 pool 中的索引
+/// burn_amount: 要销毁的 LP token 数量
+/// min_amount_out: 最少获得的 token 数量（滑点保护）
+pub fn remove_liquidity_single<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, RemoveLiquidity<'info>>,
+    token_index: u16,
+    burn_amount: u64,
+    min_amount_out: u64,
+) -> Result<()> {
+    let token_index = token_index as usize;
+    // 检查用户 LP token 余额
+    require!(
+        ctx.accounts.user_pool_ata.amount >= burn_amount,
+        ErrorCode::InsufficientTokenAmount
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_REMOVE_LIQUIDITY)?;
+    pool.check_lp_mint(ctx.accounts.pool_mint.decimals)?;
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    let is_migrated_mint = pool.resolve_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+    pool.check_min_tokens()?;
+
+    // 检查最短持有期
+    ctx.accounts
+        .lp_position
+        .check_hold_period(pool.get_min_hold_seconds(), now)?;
+
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(remaining_accounts.len() == 2, ErrorCode::InvalidTokenCount);
+    let user_token_info = &remaining_accounts[0];
+    let vault_info = &remaining_accounts[1];
+
+    // 验证 vault
+    let token_item = pool.get_token_checked(token_index)?;
+    require!(
+        vault_info.key() == *token_item.vault_pubkey(),
+        ErrorCode::InvalidTokenMint
+    );
+    let vault_amount = token_item.lp_balance(read_token_amount(vault_info)?);
+
+    let result = pool.remove_liquidity_single(token_index, vault_amount, burn_amount, now)?;
+    let amount_out = result.amounts_out[token_index];
+    require!(
+        amount_out >= min_amount_out,
+        ErrorCode::InsufficientOutputAmount
+    );
+    // 超出比例部分的手续费是 swap 手续费，与普通 swap 一样计提协议费
+    let protocol_fees = pool.protocol_fees(&result.burn_fees)?;
+    drop(pool);
+
+    let pool_key = ctx.accounts.pool.key();
+    let owner_key = ctx.accounts.owner.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+
+    // 从 vault 转出 token 给用户
+    check_writable_pairs(remaining_accounts)?;
+    let user_token_account = load_token_account(user_token_info, 0)?;
+    require!(
+        user_token_account.owner == owner_key,
+        ErrorCode::InvalidTokenMint
+    );
+    if amount_out > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: vault_info.clone(),
+                    to: user_token_info.clone(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            ),
+            amount_out,
+        )?;
+    }
+
+    // 销毁用户的 LP token（用户自己签名销毁）
+    let supply_before = ctx.accounts.pool_mint.supply;
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.lp_token_program.to_account_info(),
+            token_interface::Burn {
+                mint: ctx.accounts.pool_mint.to_account_info(),
+                from: ctx.accounts.user_pool_ata.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        burn_amount,
+    )?;
+
+    // 按销毁后重新读取的 mint 供应量更新 total_amount_minted
+    ctx.accounts.pool_mint.reload()?;
+    let supply_after = ctx.accounts.pool_mint.supply;
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    let burned = pool_mut.record_lp_burn(supply_before, supply_after, burn_amount)?;
+    pool_mut.record_principal_flow(token_index, 0, amount_out, result.burn_fees[token_index])?;
+    pool_mut.accrue_protocol_fees_in_order(&protocol_fees)?;
+    pool_mut.record_activity(now);
+    if is_migrated_mint {
+        pool_mut.record_migrated_lp_burn(burned)?;
+    }
+
+    msg!(
+        "Single-sided liquidity removed: {} LP tokens burned, {} of token {} returned (fee: {})",
+        burn_amount,
+        amount_out,
+        token_index,
+        result.burn_fees[token_index]
+    );
+    emit!(LiquidityRemoved::new(pool_key, owner_key, burned, &result));
 
     Ok(())
 }
@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount, CloseAccount};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, CloseAccount, Transfer};
+use anchor_spl::token_interface::{self, TokenInterface};
 use crate::state::AnySwapPool;
+use crate::state::liquidity::removed_token_lp_to_burn;
 use crate::error::ErrorCode;
 
 /// 从 pool 中移除 token
@@ -30,59 +32,81 @@ pub struct RemoveTokenFromPool<'info> {
     )]
     pub vault: Box<Account<'info, TokenAccount>>,
 
+    /// 管理员的 token 账户 - vault 有余额时必须提供，余额在关闭 vault 前转入该账户
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = admin,
+    )]
+    pub admin_token: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Pool mint - LP token
+    /// 当前的 LP mint；LP mint 迁移期间也可以是迁移的目标 mint（在指令中校验）
+    #[account(mut)]
+    pub pool_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    /// 管理员的 LP token 账户 - vault 中有属于 LP 的余额时必须提供，从中销毁该 token 价值占比的 LP
+    #[account(
+        mut,
+        constraint = admin_pool_ata.mint == pool_mint.key(),
+        constraint = admin_pool_ata.owner == admin.key()
+    )]
+    pub admin_pool_ata: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+
     /// Pool 管理员 - 必须签名所有操作
     /// CHECK: 验证是否为 pool 的管理员
     #[account(mut)]
     pub admin: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+    /// LP mint 所属的 token 程序（迁移后的 LP mint 可以是 Token-2022）
+    pub lp_token_program: Interface<'info, TokenInterface>,
 }
 
 /// 从 pool 中移除 token
-/// 注意：移除 token 前需要确保 vault 中没有余额
+/// vault 有余额时必须传入 admin_token，余额全部转给管理员后再关闭 vault
+/// 该 token 未领取的协议费包含在 vault 余额中，同样转给管理员；需要时先调用 collect_protocol_fees
+///
+/// vault 中有属于 LP 的余额时，管理员取走的是全体 LP 的份额：按被移除 token 的价值占比
+/// （权重 / 权重之和，见 removed_token_lp_to_burn）从 admin_pool_ata 销毁 LP 并减少 total_amount_minted，
+/// 剩余 LP 每份对应的价值不变；管理员持有的 LP 不足时无法移除。
+/// 权重之和由 pool.remove_token 重新计算
 pub fn remove_token_from_pool(ctx: Context<RemoveTokenFromPool>) -> Result<()> {
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
     let pool = &mut ctx.accounts.pool.load_mut()?;
     
-    // 验证管理员权限
+    // 验证管理员权限（只有管理员可以取走 vault 中的余额）
     pool.verify_admin(&ctx.accounts.admin.key())?;
-    
-    // 检查 vault 余额必须为 0
-    require!(
-        ctx.accounts.vault.amount == 0,
-        ErrorCode::InsufficientTokenAmount
-    );
-    
+    pool.check_lp_mint(ctx.accounts.pool_mint.decimals)?;
+    let is_migrated_mint = pool.resolve_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+
     let mint_key = ctx.accounts.mint.key();
     let token_index = pool.find_token_index(&mint_key)
         .ok_or(ErrorCode::InvalidTokenMint)?;
     
-    // 检查是否是最后一个 token
-    let token_count = pool.get_token_count();
-    require!(token_count > 0, ErrorCode::InvalidTokenCount);
-    
     // 验证 vault 地址是否匹配
-    let token_item = pool.get_token(token_index).ok_or(ErrorCode::InvalidTokenIndex)?;
+    let token_item = pool.get_token_checked(token_index)?;
     require!(
         ctx.accounts.vault.key() == *token_item.vault_pubkey(),
         ErrorCode::InvalidTokenMint
     );
-    
-    // 如果是最后一个 token，直接减少计数
-    if token_index == token_count - 1 {
-        pool.token_count -= 1;
+
+    // 按移除前的权重计算需要销毁的 LP；vault 中只有未领取的协议费时不属于 LP，不需要销毁
+    let now = Clock::get()?.unix_timestamp;
+    let vault_amount = ctx.accounts.vault.amount;
+    let lp_to_burn = if token_item.lp_balance(vault_amount) > 0 {
+        removed_token_lp_to_burn(
+            pool.get_total_amount_minted(),
+            pool.weight_sum_at(now)?,
+            token_item.weight_at(now),
+        )?
     } else {
-        // 如果不是最后一个，将最后一个 token 移动到当前位置
-        let last_index = token_count - 1;
-        
-        // 先获取最后一个 token 的数据（通过索引访问，避免借用冲突）
-        let last_token_data = pool.tokens[last_index];
-        
-        // 复制最后一个 token 到当前位置
-        pool.tokens[token_index] = last_token_data;
-        
-        // 减少计数
-        pool.token_count -= 1;
-    }
+        0
+    };
+    
+    // 将最后一个 token 移动到当前位置并减少计数
+    pool.remove_token(token_index)?;
+    pool.validate_weights()?;
     
     // 准备 seeds 用于签名
     let pool_key = ctx.accounts.pool.key();
@@ -93,6 +117,63 @@ pub fn remove_token_from_pool(ctx: Context<RemoveTokenFromPool>) -> Result<()> {
         &[bump],
     ];
     let signer = &[&seeds[..]];
+
+    // 管理员销毁被移除 token 价值占比的 LP（管理员自己签名销毁）
+    if lp_to_burn > 0 {
+        let admin_pool_ata = ctx
+            .accounts
+            .admin_pool_ata
+            .as_ref()
+            .ok_or(ErrorCode::InsufficientTokenAmount)?;
+        require!(
+            admin_pool_ata.amount >= lp_to_burn,
+            ErrorCode::InsufficientTokenAmount
+        );
+        let supply_before = ctx.accounts.pool_mint.supply;
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.lp_token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.pool_mint.to_account_info(),
+                    from: admin_pool_ata.to_account_info(),
+                    authority: ctx.accounts.admin.to_account_info(),
+                },
+            ),
+            lp_to_burn,
+        )?;
+
+        // 按销毁后重新读取的 mint 供应量更新 total_amount_minted
+        ctx.accounts.pool_mint.reload()?;
+        let supply_after = ctx.accounts.pool_mint.supply;
+        let burned = pool.record_lp_burn(supply_before, supply_after, lp_to_burn)?;
+        if is_migrated_mint {
+            pool.record_migrated_lp_burn(burned)?;
+        }
+        msg!("Burned {} LP tokens from admin for the removed token", burned);
+    }
+    
+    // 再将 vault 余额转给管理员，关闭 token 账户要求余额为 0
+    if vault_amount > 0 {
+        // vault 有余额时必须提供接收余额的管理员 token 账户
+        let admin_token = ctx
+            .accounts
+            .admin_token
+            .as_ref()
+            .ok_or(ErrorCode::InsufficientTokenAmount)?;
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: admin_token.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            ),
+            vault_amount,
+        )?;
+        msg!("Drained {} from vault to admin token account", vault_amount);
+    }
     
     // 关闭 vault 账户，将租金退还给 admin
     anchor_spl::token::close_account(
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use crate::state::AnySwapPool;
+
+/// 设置 pool 的 guardian
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名
+    pub admin: Signer<'info>,
+}
+
+/// 设置 guardian
+/// guardian: 新的 guardian，传入默认公钥表示移除
+pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+
+    // 验证管理员权限
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+
+    pool.set_guardian(&guardian);
+
+    msg!("Pool guardian set to {}", guardian);
+    Ok(())
+}
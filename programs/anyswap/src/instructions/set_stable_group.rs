@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use crate::state::AnySwapPool;
+
+/// 设置 pool 的稳定组
+#[derive(Accounts)]
+pub struct SetStableGroup<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名
+    pub admin: Signer<'info>,
+}
+
+/// 设置稳定组
+/// stable_group: 稳定组中 token 的索引（pool 中 token 的顺序），为空时取消稳定组
+/// amplification: StableSwap 的放大系数 A
+///
+/// 稳定组内 1 进 1 出的 swap 使用 StableSwap 曲线，跨组或多 token 的 swap 仍使用加权恒定乘积；
+/// 增减流动性和 zap 不受影响
+pub fn set_stable_group(
+    ctx: Context<SetStableGroup>,
+    stable_group: Vec<u16>,
+    amplification: u64,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+    let group: Vec<usize> = stable_group.iter().map(|&index| index as usize).collect();
+    pool.set_stable_group(&group, amplification)?;
+
+    msg!(
+        "Stable group updated: {} tokens, amplification {}",
+        stable_group.len(),
+        pool.get_amplification()
+    );
+    Ok(())
+}
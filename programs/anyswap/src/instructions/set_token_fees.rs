@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use crate::state::AnySwapPool;
+
+/// 设置 token 的方向费率
+#[derive(Accounts)]
+pub struct SetTokenFees<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名费率修改操作
+    pub admin: Signer<'info>,
+}
+
+/// 设置 token 的买入 / 卖出费率
+/// mint: 要设置的 token
+/// buy_fee_bps: 买入该 token（作为 swap 输出）时的费率，0 表示使用 pool 费率
+/// sell_fee_bps: 卖出该 token（作为 swap 输入）时的费率，0 表示使用 pool 费率
+/// 手续费仍从输入 token 中扣除，优先级见 directional_fee_rates
+pub fn set_token_fees(
+    ctx: Context<SetTokenFees>,
+    mint: Pubkey,
+    buy_fee_bps: u16,
+    sell_fee_bps: u16,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    pool.verify_admin(&ctx.accounts.admin.key())?;
+    pool.set_token_fees(&mint, buy_fee_bps, sell_fee_bps)?;
+
+    msg!(
+        "Token {} fees updated: buy {} bps, sell {} bps",
+        mint,
+        buy_fee_bps,
+        sell_fee_bps
+    );
+    Ok(())
+}
@@ -1,7 +1,12 @@
 use crate::error::ErrorCode;
-use crate::state::{AnySwapPool, SwapProtocol};
+use crate::events::SwapEvent;
+use crate::state::fees::amounts_with_fees;
+use crate::state::swap::{check_memo, check_output_slippage};
+use crate::state::token_account::{check_vault_authority, check_writable_pairs, load_token_account};
+use crate::state::AnySwapPool;
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::memo::{self, BuildMemo, Memo};
+use anchor_spl::token::{self, Token, Transfer};
 
 /// AnySwap 交换账户结构
 #[derive(Accounts)]
@@ -20,63 +25,62 @@ pub struct Swap<'info> {
     pub owner: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
-}
-
-/// AnySwap 交换代币
-/// 使用加权恒定乘积公式：sum(weight_i * ln(vault_i)) = constant
-///
-/// RemainingAccounts 结构：
-/// - 每两个账户为一对：(user_token_account, vault_account)
-/// - 必须按照 pool 中 token 的顺序传入
-/// - 例如：pool 有 [A, B, C]，则传入 [user_A, vault_A, user_B, vault_B, user_C, vault_C]
-///
-/// amounts_tolerance: 每个 token 的容差（输入为上限，输出为下限）
-/// is_in_token: 标记每个 token 是输入还是输出
-pub fn swap_anyswap<'remaining: 'info, 'info>(
-    ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
-    amounts_tolerance: Vec<u64>,
-    is_in_token: Vec<bool>,
-) -> Result<()> {
-    let pool = ctx.accounts.pool.load()?;
-    let token_count = amounts_tolerance.len();
 
-    require!(token_count > 0, ErrorCode::InvalidTokenCount);
-    require!(
-        is_in_token.len() == token_count,
-        ErrorCode::InvalidTokenCount
-    );
+    /// SPL Memo 程序，仅在 swap 附带 memo 时需要
+    pub memo_program: Option<Program<'info, Memo>>,
+}
 
-    // 验证 RemainingAccounts 数量：每个 token 需要 2 个账户（user_token, vault）
-    let remaining_accounts = ctx.remaining_accounts;
-    require!(
-        remaining_accounts.len() == token_count * 2,
-        ErrorCode::InvalidTokenCount
-    );
+/// swap 模拟结果（通过 return data 返回）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SwapSimulation {
+    /// 每个 token 实际转入 / 转出 vault 的数量（输入 token 含手续费）
+    pub amounts: Vec<u64>,
+    /// 每个 token 的手续费（输出 token 为 0）
+    pub fees: Vec<u64>,
+}
 
-    let pool_authority_key = ctx.accounts.pool_authority.key();
-    let owner_key = ctx.accounts.owner.key();
+/// 从 RemainingAccounts 读取的 swap 数据（按传入顺序）
+pub(crate) struct SwapAccountsData {
+    pub user_vaults_amount: Vec<u64>,
+    pub token_vaults_amount: Vec<u64>,
+    pub weights: Vec<u64>,
+    pub mints: Vec<Pubkey>,
+}
 
-    // 收集所有数据
-    let mut user_vaults_amount: Vec<u64> = Vec::with_capacity(token_count);
-    let mut token_vaults_amount: Vec<u64> = Vec::with_capacity(token_count);
-    let mut weights: Vec<u64> = Vec::with_capacity(token_count);
+/// 读取并验证 swap 的 (user_token_account, vault_account) 账户对
+/// 每个 vault 必须属于 pool，user token 账户必须属于 owner 且 mint 一致
+/// 权重为 now 时刻的权重（权重渐变期间按时间插值，见 AnySwapItem::weight_at）
+pub(crate) fn load_swap_accounts<'a>(
+    pool: &AnySwapPool,
+    pairs: &'a [AccountInfo<'a>],
+    pool_authority_key: &Pubkey,
+    owner_key: &Pubkey,
+    now: i64,
+) -> Result<SwapAccountsData> {
+    let token_count = pairs.len() / 2;
+    let mut data = SwapAccountsData {
+        user_vaults_amount: Vec::with_capacity(token_count),
+        token_vaults_amount: Vec::with_capacity(token_count),
+        weights: Vec::with_capacity(token_count),
+        mints: Vec::with_capacity(token_count),
+    };
 
     for i in 0..token_count {
-        let user_token_info = &remaining_accounts[i * 2];
-        let vault_info = &remaining_accounts[i * 2 + 1];
+        let user_token_info = &pairs[i * 2];
+        let vault_info = &pairs[i * 2 + 1];
 
         // 读取vault账户，获取其mint地址
-        let vault_account = Account::<TokenAccount>::try_from(vault_info)?;
+        let vault_account = load_token_account(vault_info, i * 2 + 1)?;
         require!(
-            vault_account.owner == pool_authority_key,
+            vault_account.owner == *pool_authority_key,
             ErrorCode::InvalidTokenMint
         );
-        
+
         // 通过mint地址在pool中查找对应的token
         let mint_key = vault_account.mint;
         let token_item = pool.get_token_by_mint(&mint_key)
             .ok_or(ErrorCode::InvalidTokenMint)?;
-        
+
         // 验证 vault 地址是否匹配
         require!(
             vault_info.key() == *token_item.vault_pubkey(),
@@ -84,40 +88,38 @@ pub fn swap_anyswap<'remaining: 'info, 'info>(
         );
 
         // 读取用户token账户
-        let user_account = Account::<TokenAccount>::try_from(user_token_info)?;
-        require!(user_account.owner == owner_key, ErrorCode::InvalidTokenMint);
+        let user_account = load_token_account(user_token_info, i * 2)?;
+        require!(user_account.owner == *owner_key, ErrorCode::InvalidTokenMint);
         require!(user_account.mint == mint_key, ErrorCode::InvalidTokenMint);
-        user_vaults_amount.push(user_account.amount);
+        data.user_vaults_amount.push(user_account.amount);
 
-        // 收集vault余额和权重
-        token_vaults_amount.push(vault_account.amount);
-        weights.push(token_item.get_weight());
+        // 收集vault余额（不含未领取的协议费）和权重
+        data.token_vaults_amount.push(token_item.lp_balance(vault_account.amount));
+        data.weights.push(token_item.weight_at(now));
+        data.mints.push(mint_key);
     }
+    pool.check_swap_mints(&data.mints)?;
 
-    // 调用 swap_inner
-    let swap_result = pool.swap(
-        &is_in_token,
-        &amounts_tolerance,
-        &user_vaults_amount,
-        &token_vaults_amount,
-        &weights,
-        pool.get_fee_numerator(),
-        pool.get_fee_denominator(),
-    )?;
-
-    drop(pool);
-
-    // 准备 seeds 用于签名
-    let pool_key = ctx.accounts.pool.key();
-    let bump = ctx.bumps.pool_authority;
-    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
-    let signer = &[&seeds[..]];
+    Ok(data)
+}
 
-    // 执行转账
-    for i in 0..token_count {
-        let user_token_info = &remaining_accounts[i * 2];
-        let vault_info = &remaining_accounts[i * 2 + 1];
-        let amount = swap_result.amounts[i];
+/// 按 swap 结果执行转账
+/// 输入 token：从用户转到 vault（owner 签名）
+/// 输出 token：从 vault 转到用户（pool_authority PDA 签名）
+pub(crate) fn transfer_swap_amounts<'info>(
+    token_program: &AccountInfo<'info>,
+    pool_authority: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    pairs: &[AccountInfo<'info>],
+    is_in_token: &[bool],
+    amounts: &[u64],
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    check_writable_pairs(&pairs[..is_in_token.len() * 2])?;
+    for i in 0..is_in_token.len() {
+        let user_token_info = &pairs[i * 2];
+        let vault_info = &pairs[i * 2 + 1];
+        let amount = amounts[i];
 
         msg!("Token {}: amount={}, is_in={}", i, amount, is_in_token[i]);
 
@@ -131,25 +133,26 @@ pub fn swap_anyswap<'remaining: 'info, 'info>(
             // 输入token：从用户转到vault
             token::transfer(
                 CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
+                    token_program.clone(),
                     Transfer {
                         from: user_token_info.clone(),
                         to: vault_info.clone(),
-                        authority: ctx.accounts.owner.to_account_info(),
+                        authority: owner.clone(),
                     },
                 ),
                 amount,
             )?;
         } else {
             msg!("Transferring {} from vault to user (output)", amount);
-            // 输出token：从vault转到用户
+            // 输出token：从vault转到用户，由 pool_authority 签名，先确认它确实是 vault 的 authority
+            check_vault_authority(vault_info, pool_authority.key)?;
             token::transfer(
                 CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
+                    token_program.clone(),
                     Transfer {
                         from: vault_info.clone(),
                         to: user_token_info.clone(),
-                        authority: ctx.accounts.pool_authority.to_account_info(),
+                        authority: pool_authority.clone(),
                     },
                     signer,
                 ),
@@ -157,29 +160,235 @@ pub fn swap_anyswap<'remaining: 'info, 'info>(
             )?;
         }
     }
+    Ok(())
+}
 
-    // 计算输入和输出总量用于日志
-    let total_in: u64 = is_in_token
-        .iter()
-        .enumerate()
-        .filter(|(_, &is_in)| is_in)
-        .map(|(i, _)| swap_result.amounts[i])
-        .sum();
-    let total_out: u64 = is_in_token
-        .iter()
-        .enumerate()
-        .filter(|(_, &is_in)| !is_in)
-        .map(|(i, _)| swap_result.amounts[i])
-        .sum();
-    let total_fees: u64 = swap_result.burn_fees.iter().sum();
+/// AnySwap 交换代币
+/// 使用加权恒定乘积公式：sum(weight_i * ln(vault_i)) = constant
+///
+/// RemainingAccounts 结构：
+/// - 每两个账户为一对：(user_token_account, vault_account)
+/// - 只传入参与 swap 的 token，顺序与 amounts_tolerance 一致（token 由 vault 的 mint 确定，不要求 pool 中的顺序）
+/// - 例如：pool 有 [A, B, C]，A -> C 时传入 [user_A, vault_A, user_C, vault_C]
+/// - 参与的 token 数量不能超过 pool 的 token 数量，同一个 token 不能出现两次
+/// - 参与的 token 数量不能超过 pool 的 max_tokens_per_swap（设置时），否则返回 TooManyTokensInSwap
+///
+/// amounts_tolerance: 每个 token 的容差（输入为上限，输出为下限），转账前检查，不满足时返回 SlippageExceeded
+///   每个输入扣费后不能低于 pool 的 min_swap_amount（AmountTooSmall），输出不能为 0（OutputRoundsToZero）
+/// is_in_token: 标记每个 token 是输入还是输出
+/// max_fee_bps: 可接受的最大费率（bps），任一输入 token 实际使用的费率（含 buy / sell 覆盖）更高时以 FeeExceedsMax 拒绝
+/// memo: 可选的备注（最多 MAX_MEMO_LEN 字节），转账完成后由 owner 签名写入 SPL Memo 程序一次，
+///       用于链下对账；提供 memo 时必须传入 memo_program
+///
+/// 输入 token 的手续费随输入一起转入 vault（即转入 amounts_tolerance 中的输入上限），归 LP 的部分留在 vault 中；
+/// 设置了协议费（set_protocol_fee）时，手续费中归协议的部分计提，不计入 LP 的储备
+pub fn swap_anyswap<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
+    amounts_tolerance: Vec<u64>,
+    is_in_token: Vec<bool>,
+    max_fee_bps: Option<u16>,
+    memo: Option<String>,
+) -> Result<()> {
+    execute_swap(ctx, amounts_tolerance, is_in_token, max_fee_bps, None, memo)
+}
 
+/// AnySwap 交换代币（按百分比滑点）
+///
+/// RemainingAccounts 结构与 swap_anyswap 相同
+///
+/// amounts: 输入 token 为输入数量；输出 token 为询价（quote_swap_detailed）得到的预期输出
+/// is_in_token: 标记每个 token 是输入还是输出
+/// slippage_bps: 可接受的滑点（bps），每个输出的下限为 预期输出 * (1 - slippage_bps / 10000)
+pub fn swap_with_slippage<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
+    amounts: Vec<u64>,
+    is_in_token: Vec<bool>,
+    slippage_bps: u16,
+) -> Result<()> {
+    execute_swap(ctx, amounts, is_in_token, None, Some(slippage_bps), None)
+}
+
+/// 模拟 swap：账户和校验与 swap_anyswap 完全相同，但不转账、不修改 pool，
+/// 结果通过 return data 返回，钱包可以用 simulateTransaction 预览
+///
+/// 返回的 amounts 即相同状态下 swap_anyswap 实际转账的数量
+pub fn simulate_swap<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
+    amounts_tolerance: Vec<u64>,
+    is_in_token: Vec<bool>,
+) -> Result<SwapSimulation> {
+    let pool = ctx.accounts.pool.load()?;
+    let token_count = amounts_tolerance.len();
+    pool.check_swap_allowed(token_count)?;
+    require!(
+        is_in_token.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+    require!(
+        ctx.remaining_accounts.len() == token_count * 2,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let accounts_data = load_swap_accounts(
+        &pool,
+        ctx.remaining_accounts,
+        &ctx.accounts.pool_authority.key(),
+        &ctx.accounts.owner.key(),
+        Clock::get()?.unix_timestamp,
+    )?;
+    let swap_result = pool.checked_swap(
+        &accounts_data.mints,
+        &is_in_token,
+        &amounts_tolerance,
+        &accounts_data.user_vaults_amount,
+        &accounts_data.token_vaults_amount,
+        &accounts_data.weights,
+    )?;
+
+    Ok(SwapSimulation {
+        amounts: amounts_with_fees(&swap_result.amounts, &swap_result.burn_fees)?,
+        fees: swap_result.burn_fees,
+    })
+}
+
+/// 执行 swap：校验、计算、（可选）滑点检查、转账
+fn execute_swap<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
+    amounts_tolerance: Vec<u64>,
+    is_in_token: Vec<bool>,
+    max_fee_bps: Option<u16>,
+    slippage_bps: Option<u16>,
+    memo: Option<String>,
+) -> Result<()> {
+    if let Some(memo) = &memo {
+        check_memo(memo)?;
+        require!(
+            ctx.accounts.memo_program.is_some(),
+            ErrorCode::MissingMemoProgram
+        );
+    }
+
+    let pool = ctx.accounts.pool.load()?;
+    let token_count = amounts_tolerance.len();
+    pool.check_swap_allowed(token_count)?;
+    require!(
+        is_in_token.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+
+    // 验证 RemainingAccounts 数量：每个 token 需要 2 个账户（user_token, vault）
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(
+        remaining_accounts.len() == token_count * 2,
+        ErrorCode::InvalidTokenCount
+    );
+
+    // 收集所有数据
+    let now = Clock::get()?.unix_timestamp;
+    let accounts_data = load_swap_accounts(
+        &pool,
+        remaining_accounts,
+        &ctx.accounts.pool_authority.key(),
+        &ctx.accounts.owner.key(),
+        now,
+    )?;
+    pool.check_max_fee(max_fee_bps, &accounts_data.mints, &is_in_token)?;
+
+    // 每个 token 使用各自的方向费率；在任何转账之前检查滑点：
+    // 按百分比（swap_with_slippage）或按 amounts_tolerance 的上下限（checked_swap）
+    let swap_result = if let Some(slippage_bps) = slippage_bps {
+        pool.check_min_swap_amount(&accounts_data.mints, &is_in_token, &amounts_tolerance)?;
+        let swap_result = pool.swap_directional(
+            &accounts_data.mints,
+            &is_in_token,
+            &amounts_tolerance,
+            &accounts_data.user_vaults_amount,
+            &accounts_data.token_vaults_amount,
+            &accounts_data.weights,
+        )?;
+        check_output_slippage(
+            &is_in_token,
+            &amounts_tolerance,
+            &swap_result.amounts,
+            slippage_bps,
+        )?;
+        swap_result
+    } else {
+        pool.checked_swap(
+            &accounts_data.mints,
+            &is_in_token,
+            &amounts_tolerance,
+            &accounts_data.user_vaults_amount,
+            &accounts_data.token_vaults_amount,
+            &accounts_data.weights,
+        )?
+    };
+
+    let protocol_fees = pool.protocol_fees(&swap_result.burn_fees)?;
+    let transfer_amounts = amounts_with_fees(&swap_result.amounts, &swap_result.burn_fees)?;
+    drop(pool);
+
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    pool_mut.record_swap_principal(
+        &accounts_data.mints,
+        &is_in_token,
+        &swap_result.amounts,
+        &swap_result.burn_fees,
+    )?;
+    pool_mut.accrue_protocol_fees(&accounts_data.mints, &protocol_fees)?;
+    pool_mut.record_twap(
+        now,
+        &accounts_data.mints,
+        &is_in_token,
+        &accounts_data.token_vaults_amount,
+        &swap_result.amounts,
+        &accounts_data.weights,
+    )?;
+    pool_mut.record_activity(now);
+    drop(pool_mut);
+
+    // 准备 seeds 用于签名
+    let pool_key = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+
+    // 执行转账
+    transfer_swap_amounts(
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.pool_authority,
+        &ctx.accounts.owner.to_account_info(),
+        remaining_accounts,
+        &is_in_token,
+        &transfer_amounts,
+        signer,
+    )?;
+
+    // 每次 swap 最多写入一条 memo
+    if let (Some(memo), Some(memo_program)) = (&memo, &ctx.accounts.memo_program) {
+        memo::build_memo(
+            CpiContext::new(memo_program.to_account_info(), BuildMemo {})
+                .with_remaining_accounts(vec![ctx.accounts.owner.to_account_info()]),
+            memo.as_bytes(),
+        )?;
+    }
+
+    let event = SwapEvent::new(
+        pool_key,
+        ctx.accounts.owner.key(),
+        &accounts_data.mints,
+        &is_in_token,
+        &transfer_amounts,
+        &swap_result.burn_fees,
+    );
     msg!(
         "AnySwap: {} tokens swapped, {} in -> {} out (total fees: {})",
         token_count,
-        total_in,
-        total_out,
-        total_fees
+        event.total_in,
+        event.total_out,
+        event.total_fees
     );
+    emit!(event);
 
     Ok(())
 }
@@ -0,0 +1,135 @@
+use crate::error::ErrorCode;
+use crate::events::SwapEvent;
+use crate::instructions::swap::{load_swap_accounts, transfer_swap_amounts, Swap};
+use crate::state::fees::amounts_with_fees;
+use crate::state::pool::PAUSE_SWAP;
+use crate::state::swap::{calc_in_given_out, gross_up_for_fee};
+use anchor_lang::prelude::*;
+
+/// 精确输出的 swap：指定输出数量，由 calc_in_given_out 反推需要的输入
+///
+/// RemainingAccounts 结构：
+/// - [user_token_in, vault_in, user_token_out, vault_out]
+///
+/// amount_out: 用户需要得到的输出数量
+/// max_amount_in: 可接受的最大输入（扣费前，与 swap_anyswap 中输入的容差含义一致）
+/// max_fee_bps: 可接受的最大费率（bps），输入 token 实际使用的费率更高时以 FeeExceedsMax 拒绝
+///
+/// 与 swap_anyswap 一致，反推出的输入扣费后不能低于 pool 的 min_swap_amount（AmountTooSmall）
+///
+/// 使用加权恒定乘积曲线，稳定组内的 token 对不支持精确输出
+pub fn swap_exact_out<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
+    amount_out: u64,
+    max_amount_in: u64,
+    max_fee_bps: Option<u16>,
+) -> Result<()> {
+    require!(amount_out > 0, ErrorCode::InconsistentSwapParams);
+    require!(
+        ctx.remaining_accounts.len() == 4,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_SWAP)?;
+    pool.check_min_tokens()?;
+    pool.check_tokens_per_swap(2)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let accounts_data = load_swap_accounts(
+        &pool,
+        ctx.remaining_accounts,
+        &ctx.accounts.pool_authority.key(),
+        &ctx.accounts.owner.key(),
+        now,
+    )?;
+    require!(
+        accounts_data.mints[0] != accounts_data.mints[1],
+        ErrorCode::SameTokenSwap
+    );
+
+    let is_in_token = [true, false];
+    require!(
+        pool.stable_pair_amplification(&accounts_data.mints, &is_in_token)
+            .is_none(),
+        ErrorCode::InvalidStableGroup
+    );
+    pool.check_max_fee(max_fee_bps, &accounts_data.mints, &is_in_token)?;
+
+    // 扣费后的输入由曲线反推（向上取整），扣费前的输入用于和 max_amount_in 比较
+    let amount_in = calc_in_given_out(
+        accounts_data.token_vaults_amount[0],
+        accounts_data.token_vaults_amount[1],
+        accounts_data.weights[0],
+        accounts_data.weights[1],
+        amount_out,
+    )?;
+    let (fee_numerator, fee_denominator) =
+        pool.swap_fee_rates(&accounts_data.mints, &is_in_token)?[0];
+    let amount_in_before_fee = gross_up_for_fee(amount_in, fee_numerator, fee_denominator)?;
+    pool.check_min_swap_amount(
+        &accounts_data.mints,
+        &is_in_token,
+        &[amount_in_before_fee, amount_out],
+    )?;
+    require!(
+        amount_in_before_fee <= max_amount_in,
+        ErrorCode::ExcessiveInputAmount
+    );
+    require!(
+        accounts_data.user_vaults_amount[0] >= amount_in_before_fee,
+        ErrorCode::InsufficientTokenAmount
+    );
+    let fees = [amount_in_before_fee - amount_in, 0];
+    let protocol_fees = pool.protocol_fees(&fees)?;
+    drop(pool);
+
+    // 与 swap_anyswap 一致：输入转入扣费前的数量（手续费随输入进入 vault），输出为精确的 amount_out
+    let amounts = [amount_in, amount_out];
+    let transfer_amounts = amounts_with_fees(&amounts, &fees)?;
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    pool_mut.record_swap_principal(&accounts_data.mints, &is_in_token, &amounts, &fees)?;
+    pool_mut.accrue_protocol_fees(&accounts_data.mints, &protocol_fees)?;
+    pool_mut.record_twap(
+        now,
+        &accounts_data.mints,
+        &is_in_token,
+        &accounts_data.token_vaults_amount,
+        &amounts,
+        &accounts_data.weights,
+    )?;
+    pool_mut.record_activity(now);
+    drop(pool_mut);
+
+    let pool_key = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+
+    transfer_swap_amounts(
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.pool_authority,
+        &ctx.accounts.owner.to_account_info(),
+        ctx.remaining_accounts,
+        &is_in_token,
+        &transfer_amounts,
+        signer,
+    )?;
+
+    msg!(
+        "AnySwap exact out: {} in (max {}, fee {}) -> {} out",
+        amount_in,
+        max_amount_in,
+        amount_in_before_fee - amount_in,
+        amount_out
+    );
+    emit!(SwapEvent::new(
+        pool_key,
+        ctx.accounts.owner.key(),
+        &accounts_data.mints,
+        &is_in_token,
+        &transfer_amounts,
+        &fees,
+    ));
+    Ok(())
+}
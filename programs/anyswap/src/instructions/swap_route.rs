@@ -0,0 +1,153 @@
+use crate::error::ErrorCode;
+use crate::events::SwapEvent;
+use crate::instructions::swap::{transfer_swap_amounts, Swap};
+use crate::state::fees::amounts_with_fees;
+use crate::state::pool::PAUSE_SWAP;
+use crate::state::token_account::load_token_account;
+use anchor_lang::prelude::*;
+
+/// 池内多跳 swap：沿 path 中的 token 索引逐跳交换（A -> B -> C），见 AnySwapPool::swap_route
+///
+/// RemainingAccounts 结构：
+/// - [user_token_in, user_token_out, vault_path[0], vault_path[1], ..., vault_path[n-1]]
+///
+/// path: 路径上 token 在 pool 中的索引，至少 2 个且不能重复，长度不能超过 pool 的 max_tokens_per_swap
+/// amount_in: 第一个 token 的输入（扣费前，与 swap_anyswap 中输入的容差含义一致）
+/// min_amount_out: 最后一个 token 的最小输出，不满足时返回 SlippageExceeded
+/// max_fee_bps: 可接受的最大费率（bps），任一跳输入 token 实际使用的费率更高时以 FeeExceedsMax 拒绝
+///
+/// 与 swap_anyswap 一致，第一跳的输入扣费后不能低于 pool 的 min_swap_amount（AmountTooSmall）
+///
+/// 只有第一个 token 从用户转入、最后一个 token 转给用户，中间 token 留在各自的 vault 中；
+/// 第一跳的手续费随输入一起转入，每一跳的协议费计提到该跳的输入 token
+pub fn swap_route<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
+    path: Vec<u16>,
+    amount_in: u64,
+    min_amount_out: u64,
+    max_fee_bps: Option<u16>,
+) -> Result<()> {
+    let path: Vec<usize> = path.into_iter().map(|index| index as usize).collect();
+    require!(
+        ctx.remaining_accounts.len() == path.len() + 2,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_SWAP)?;
+    pool.check_min_tokens()?;
+    pool.check_route(&path)?;
+    pool.check_tokens_per_swap(path.len())?;
+
+    // 读取路径上每个 vault 的余额（不含未领取的协议费），按 pool 中的顺序放入 reserves
+    let pool_authority_key = ctx.accounts.pool_authority.key();
+    let vault_infos = &ctx.remaining_accounts[2..];
+    let mut reserves = vec![0u64; pool.get_token_count()];
+    let mut mints = Vec::with_capacity(path.len());
+    let mut weights = Vec::with_capacity(path.len());
+    let mut vaults_before = Vec::with_capacity(path.len());
+    for (i, (&index, vault_info)) in path.iter().zip(vault_infos.iter()).enumerate() {
+        let token = pool.get_token_checked(index)?;
+        require!(
+            vault_info.key() == *token.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+        let vault_account = load_token_account(vault_info, i + 2)?;
+        require!(
+            vault_account.owner == pool_authority_key,
+            ErrorCode::InvalidTokenMint
+        );
+        reserves[index] = token.lp_balance(vault_account.amount);
+        vaults_before.push(reserves[index]);
+        mints.push(*token.mint_pubkey());
+        weights.push(token.weight_at(now));
+    }
+
+    // 用户的输入 / 输出账户必须属于 owner，且 mint 分别为路径的首尾 token
+    let owner_key = ctx.accounts.owner.key();
+    let user_in = load_token_account(&ctx.remaining_accounts[0], 0)?;
+    let user_out = load_token_account(&ctx.remaining_accounts[1], 1)?;
+    require!(
+        user_in.owner == owner_key && user_in.mint == mints[0],
+        ErrorCode::InvalidTokenMint
+    );
+    require!(
+        user_out.owner == owner_key && user_out.mint == mints[path.len() - 1],
+        ErrorCode::InvalidTokenMint
+    );
+    require!(user_in.amount >= amount_in, ErrorCode::InsufficientTokenAmount);
+    pool.check_min_swap_amount(&mints[..2], &[true, false], &[amount_in, 0])?;
+    // 每一跳按该跳的方向费率扣费（见 AnySwapPool::swap_route）
+    for hop in mints.windows(2) {
+        pool.check_max_fee(max_fee_bps, hop, &[true, false])?;
+    }
+
+    let route = pool.swap_route(&path, amount_in, &reserves, now)?;
+    let amount_out = route.amount_out();
+    require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+    let protocol_fees = pool.protocol_fees(&route.hop_fees)?;
+    drop(pool);
+
+    // 净资金流：第一个 token 转入，最后一个 token 转出，中间 token 不变
+    let is_in_token = [true, false];
+    let end_mints = [mints[0], mints[path.len() - 1]];
+    let amounts = [route.hop_amounts_in[0], amount_out];
+    let end_fees = [route.hop_fees[0], 0];
+    let transfer_amounts = amounts_with_fees(&amounts, &end_fees)?;
+
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    pool_mut.record_swap_principal(&end_mints, &is_in_token, &amounts, &end_fees)?;
+    pool_mut.accrue_protocol_fees(&mints[..path.len() - 1], &protocol_fees)?;
+    let mut path_is_in = vec![false; path.len()];
+    path_is_in[0] = true;
+    let mut path_amounts = vec![0u64; path.len()];
+    path_amounts[0] = amounts[0];
+    path_amounts[path.len() - 1] = amount_out;
+    pool_mut.record_twap(now, &mints, &path_is_in, &vaults_before, &path_amounts, &weights)?;
+    pool_mut.record_activity(now);
+    drop(pool_mut);
+
+    let pool_key = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+
+    let pairs = [
+        ctx.remaining_accounts[0].clone(),
+        vault_infos[0].clone(),
+        ctx.remaining_accounts[1].clone(),
+        vault_infos[path.len() - 1].clone(),
+    ];
+    transfer_swap_amounts(
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.pool_authority,
+        &ctx.accounts.owner.to_account_info(),
+        &pairs,
+        &is_in_token,
+        &transfer_amounts,
+        signer,
+    )?;
+
+    // 事件按路径上的全部 token 记录：首尾 token 为实际转账数量，中间 token 的数量为 0，
+    // 手续费记在每一跳的输入 token 上
+    let mut path_fees = route.hop_fees.clone();
+    path_fees.push(0);
+    path_amounts[0] = transfer_amounts[0];
+    msg!(
+        "AnySwap route: {} hops, {} in -> {} out (min {})",
+        path.len() - 1,
+        amount_in,
+        amount_out,
+        min_amount_out
+    );
+    emit!(SwapEvent::new(
+        pool_key,
+        owner_key,
+        &mints,
+        &path_is_in,
+        &path_amounts,
+        &path_fees,
+    ));
+    Ok(())
+}
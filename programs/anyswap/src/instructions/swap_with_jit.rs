@@ -0,0 +1,266 @@
+use crate::error::ErrorCode;
+use crate::events::SwapEvent;
+use crate::instructions::swap::{load_swap_accounts, transfer_swap_amounts};
+use crate::state::fees::amounts_with_fees;
+use crate::state::jit::{check_jit_payout, settle_jit_inner};
+use crate::state::token_account::load_token_account;
+use crate::state::AnySwapPool;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token::{self, Token, Transfer};
+
+/// AnySwap JIT 交换账户结构
+#[derive(Accounts)]
+pub struct SwapWithJit<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool authority PDA - 用于管理所有 vault
+    /// CHECK: PDA derived from pool key, used as token account owner
+    #[account(
+        seeds = [b"anyswap_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    pub owner: Signer<'info>,
+
+    /// JIT 提供者程序，swap 前被 CPI 调用以向 vault 注入流动性
+    /// CHECK: 任意可执行程序，由调用方指定
+    #[account(executable)]
+    pub jit_program: UncheckedAccount<'info>,
+
+    /// JIT 流动性提供者 - 必须签名，JIT 收款账户必须属于该账户
+    pub jit_provider: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// 读取 vault 当前余额（CPI 之后需要重新读取），与 load_swap_accounts 一致扣除未领取的协议费
+fn read_vault_amounts<'a>(
+    pool: &AnySwapPool,
+    pairs: &'a [AccountInfo<'a>],
+    mints: &[Pubkey],
+) -> Result<Vec<u64>> {
+    let token_count = pairs.len() / 2;
+    let mut amounts = Vec::with_capacity(token_count);
+    for i in 0..token_count {
+        let vault_account = load_token_account(&pairs[i * 2 + 1], i * 2 + 1)?;
+        let token_item = pool
+            .get_token_by_mint(&mints[i])
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        amounts.push(token_item.lp_balance(vault_account.amount));
+    }
+    Ok(amounts)
+}
+
+/// AnySwap 交换代币（带 JIT 流动性）
+///
+/// 流程：
+/// 1. 记录 vault 余额
+/// 2. CPI 调用 jit_program（指令数据为 jit_data），由 JIT 提供者向 vault 注入流动性
+/// 3. 按注入后的储备执行用户的 swap
+/// 4. 按 JIT 提供者注入的份额，从 vault 返还 swap 后对应的储备给 JIT 提供者
+///
+/// RemainingAccounts 结构：
+/// - 前 token_count * 2 个：(user_token_account, vault_account) 对，同 swap_anyswap
+/// - 接下来 token_count 个：JIT 提供者的收款 token 账户，与上面的 token 顺序一致，所有者必须是签名的 jit_provider
+/// - 其余账户：原样传给 jit_program
+///
+/// amounts_tolerance: 每个 token 的容差（输入为上限，输出为下限），按 JIT 注入后的储备检查，
+///   不满足时返回 SlippageExceeded；其余检查与 swap_anyswap 相同（max_tokens_per_swap、min_swap_amount 等）
+/// is_in_token: 标记每个 token 是输入还是输出
+/// jit_data: 传给 jit_program 的指令数据
+pub fn swap_with_jit<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, SwapWithJit<'info>>,
+    amounts_tolerance: Vec<u64>,
+    is_in_token: Vec<bool>,
+    jit_data: Vec<u8>,
+) -> Result<()> {
+    let token_count = amounts_tolerance.len();
+    require!(
+        is_in_token.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+
+    // 验证 RemainingAccounts 数量：每个 token 需要 2 个 swap 账户 + 1 个 JIT 收款账户
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(
+        remaining_accounts.len() >= token_count * 3,
+        ErrorCode::InvalidTokenCount
+    );
+    let swap_accounts = &remaining_accounts[..token_count * 2];
+    let payout_accounts = &remaining_accounts[token_count * 2..token_count * 3];
+    let jit_accounts = &remaining_accounts[token_count * 3..];
+
+    // JIT 程序不能是本程序或 token 程序
+    let jit_program_key = ctx.accounts.jit_program.key();
+    require!(
+        jit_program_key != crate::ID && jit_program_key != ctx.accounts.token_program.key(),
+        ErrorCode::InvalidJitProgram
+    );
+
+    let pool_authority_key = ctx.accounts.pool_authority.key();
+    let owner_key = ctx.accounts.owner.key();
+
+    // 1. 验证账户并记录 JIT 之前的 vault 余额
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_swap_allowed(token_count)?;
+    let now = Clock::get()?.unix_timestamp;
+    let accounts_before =
+        load_swap_accounts(&pool, swap_accounts, &pool_authority_key, &owner_key, now)?;
+    drop(pool);
+
+    // JIT 收款账户的 mint 必须与对应 token 一致，且属于签名的 JIT 提供者
+    let jit_provider_key = ctx.accounts.jit_provider.key();
+    for (i, payout_info) in payout_accounts.iter().enumerate() {
+        let payout_account = load_token_account(payout_info, token_count * 2 + i)?;
+        check_jit_payout(
+            &payout_account.mint,
+            &payout_account.owner,
+            &accounts_before.mints[i],
+            &jit_provider_key,
+        )?;
+    }
+
+    // 2. CPI 调用 JIT 提供者
+    let jit_ix = Instruction {
+        program_id: jit_program_key,
+        accounts: jit_accounts
+            .iter()
+            .map(|a| AccountMeta {
+                pubkey: a.key(),
+                is_signer: a.is_signer,
+                is_writable: a.is_writable,
+            })
+            .collect(),
+        data: jit_data,
+    };
+    let mut jit_account_infos = jit_accounts.to_vec();
+    jit_account_infos.push(ctx.accounts.jit_program.to_account_info());
+    invoke(&jit_ix, &jit_account_infos)?;
+
+    // 3. 按 JIT 之后的储备执行 swap，检查与 swap_anyswap 相同：
+    //    输出按 JIT 改变后的储备计算，低于用户的下限时整笔交易失败
+    let pool = ctx.accounts.pool.load()?;
+    let vaults_after_deposit = read_vault_amounts(&pool, swap_accounts, &accounts_before.mints)?;
+    let swap_result = pool.checked_swap(
+        &accounts_before.mints,
+        &is_in_token,
+        &amounts_tolerance,
+        &accounts_before.user_vaults_amount,
+        &vaults_after_deposit,
+        &accounts_before.weights,
+    )?;
+    let transfer_amounts = amounts_with_fees(&swap_result.amounts, &swap_result.burn_fees)?;
+    let protocol_fees = pool.protocol_fees(&swap_result.burn_fees)?;
+    drop(pool);
+
+    // JIT 注入按本金计入，swap 的手续费随输入转入 vault 并按手续费记录
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    for (i, mint) in accounts_before.mints.iter().enumerate() {
+        let index = pool_mut
+            .find_token_index(mint)
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        let deposit = vaults_after_deposit[i]
+            .checked_sub(accounts_before.token_vaults_amount[i])
+            .ok_or(ErrorCode::InvalidJitDeposit)?;
+        pool_mut.record_principal_flow(index, deposit, 0, 0)?;
+    }
+    pool_mut.record_swap_principal(
+        &accounts_before.mints,
+        &is_in_token,
+        &swap_result.amounts,
+        &swap_result.burn_fees,
+    )?;
+    pool_mut.accrue_protocol_fees(&accounts_before.mints, &protocol_fees)?;
+    pool_mut.record_twap(
+        now,
+        &accounts_before.mints,
+        &is_in_token,
+        &vaults_after_deposit,
+        &swap_result.amounts,
+        &accounts_before.weights,
+    )?;
+    pool_mut.record_activity(now);
+    drop(pool_mut);
+
+    // 准备 seeds 用于签名
+    let pool_key = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+
+    transfer_swap_amounts(
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.pool_authority,
+        &ctx.accounts.owner.to_account_info(),
+        swap_accounts,
+        &is_in_token,
+        &transfer_amounts,
+        signer,
+    )?;
+
+    // 4. 结算 JIT 提供者
+    let pool = ctx.accounts.pool.load()?;
+    let vaults_after_swap = read_vault_amounts(&pool, swap_accounts, &accounts_before.mints)?;
+    drop(pool);
+    let settlement = settle_jit_inner(
+        &accounts_before.token_vaults_amount,
+        &vaults_after_deposit,
+        &vaults_after_swap,
+    )?;
+
+    // 返还中属于 JIT 手续费分成的部分从累计手续费中支付，其余按本金流出（协议费不参与分成）
+    let lp_fees: Vec<u64> = swap_result
+        .burn_fees
+        .iter()
+        .zip(protocol_fees.iter())
+        .map(|(&fee, &protocol)| fee - protocol)
+        .collect();
+    let principal_payouts = settlement.principal_payouts(&lp_fees)?;
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    for (i, mint) in accounts_before.mints.iter().enumerate() {
+        let index = pool_mut
+            .find_token_index(mint)
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        pool_mut.record_principal_flow(index, 0, principal_payouts[i], 0)?;
+    }
+    drop(pool_mut);
+
+    for (i, &payout) in settlement.payouts.iter().enumerate() {
+        if payout == 0 {
+            continue;
+        }
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: swap_accounts[i * 2 + 1].clone(),
+                    to: payout_accounts[i].clone(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            ),
+            payout,
+        )?;
+    }
+
+    msg!(
+        "AnySwap JIT: {} tokens swapped, jit share={}, payouts={:?}",
+        token_count,
+        settlement.share,
+        settlement.payouts
+    );
+    emit!(SwapEvent::new(
+        pool_key,
+        ctx.accounts.owner.key(),
+        &accounts_before.mints,
+        &is_in_token,
+        &transfer_amounts,
+        &swap_result.burn_fees,
+    ));
+
+    Ok(())
+}
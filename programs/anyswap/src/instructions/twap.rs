@@ -0,0 +1,159 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::token_account::read_token_amount;
+use crate::state::twap::log_spot_price;
+use crate::state::{AnySwapPool, TwapObservation};
+
+/// 配置被跟踪 TWAP 的 token 对（仅管理员）
+#[derive(Accounts)]
+pub struct SetTwapPair<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool 管理员 - 必须签名
+    pub admin: Signer<'info>,
+}
+
+/// 记录 token 对当前的累计对数价格
+#[derive(Accounts)]
+#[instruction(mint_a: Pubkey, mint_b: Pubkey)]
+pub struct RecordTwapObservation<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// 观察点账户，以当前时间戳作为 seed，同一 token 对同一秒内只能记录一次
+    #[account(
+        init,
+        payer = payer,
+        space = TwapObservation::space(),
+        seeds = [
+            b"twap",
+            pool.key().as_ref(),
+            mint_a.as_ref(),
+            mint_b.as_ref(),
+            clock.unix_timestamp.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub observation: Box<Account<'info, TwapObservation>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 查询某个观察点以来的 TWAP（只读）
+#[derive(Accounts)]
+#[instruction(token_a: Pubkey, token_b: Pubkey, since_ts: i64)]
+pub struct QueryTwap<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// since_ts 时记录的观察点
+    #[account(
+        seeds = [
+            b"twap",
+            pool.key().as_ref(),
+            token_a.as_ref(),
+            token_b.as_ref(),
+            since_ts.to_le_bytes().as_ref()
+        ],
+        bump = observation.bump
+    )]
+    pub observation: Box<Account<'info, TwapObservation>>,
+}
+
+/// 配置 TWAP 槽位 slot 跟踪 (mint_a, mint_b) 的价格（1 单位 mint_a 值多少 mint_b）
+/// mint_a 为默认公钥时清空该槽位
+///
+/// RemainingAccounts 结构（清空槽位时不需要）：
+/// - mint_a 的 vault 账户
+/// - mint_b 的 vault 账户
+pub fn set_twap_pair(
+    ctx: Context<SetTwapPair>,
+    slot: u8,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool.load_mut()?;
+    let log_price = if mint_a == Pubkey::default() {
+        Default::default()
+    } else {
+        require!(ctx.remaining_accounts.len() == 2, ErrorCode::InvalidTokenCount);
+        let mut balances = [0u64; 2];
+        let mut weights = [0u64; 2];
+        for (i, mint) in [mint_a, mint_b].iter().enumerate() {
+            let token = pool
+                .get_token_by_mint(mint)
+                .ok_or(ErrorCode::InvalidTokenMint)?;
+            require!(
+                ctx.remaining_accounts[i].key() == *token.vault_pubkey(),
+                ErrorCode::InvalidTokenMint
+            );
+            balances[i] = token.lp_balance(read_token_amount(&ctx.remaining_accounts[i])?);
+            weights[i] = token.weight_at(now);
+        }
+        log_spot_price(balances[0], weights[0], balances[1], weights[1])?
+    };
+
+    pool.set_twap_pair(
+        &ctx.accounts.admin.key(),
+        slot as usize,
+        &mint_a,
+        &mint_b,
+        log_price,
+        now,
+    )?;
+
+    msg!("TWAP slot {} tracks {} / {}", slot, mint_a, mint_b);
+    Ok(())
+}
+
+/// 记录 token 对当前的累计对数价格，供 query_twap 计算之后的 TWAP
+///
+/// 任何人都可以调用（由 payer 支付租金），通常由 keeper 定期调用
+pub fn record_twap_observation(
+    ctx: Context<RecordTwapObservation>,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+) -> Result<()> {
+    let now = ctx.accounts.clock.unix_timestamp;
+    let pool = ctx.accounts.pool.load()?;
+    let slot = pool
+        .find_twap_pair(&mint_a, &mint_b)
+        .ok_or(ErrorCode::InvalidTwapPair)?;
+    let log_price_cumulative = pool.twap_cumulative_at(slot, now)?;
+    drop(pool);
+
+    let observation = &mut ctx.accounts.observation;
+    observation.pool = ctx.accounts.pool.key();
+    observation.mint_a = mint_a;
+    observation.mint_b = mint_b;
+    observation.timestamp = now;
+    observation.log_price_cumulative = log_price_cumulative;
+    observation.bump = ctx.bumps.observation;
+
+    msg!("TWAP observation at {}: {} / {}", now, mint_a, mint_b);
+    Ok(())
+}
+
+/// 返回 since_ts 的观察点以来 1 单位 token_a 值多少 token_b 的时间加权平均价格
+/// （18 位定点数，通过 return data 返回）
+///
+/// 平均为几何平均；槽位在这段时间内被重新配置时结果没有意义
+pub fn query_twap(
+    ctx: Context<QueryTwap>,
+    token_a: Pubkey,
+    token_b: Pubkey,
+    since_ts: i64,
+) -> Result<u128> {
+    let pool = ctx.accounts.pool.load()?;
+    pool.query_twap(
+        &token_a,
+        &token_b,
+        since_ts,
+        ctx.accounts.observation.log_price_cumulative,
+        Clock::get()?.unix_timestamp,
+    )
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use crate::state::setup::validate_setup_inner;
+use crate::state::SetupValidation;
+
+/// 校验 pool 配置（只读，不需要任何账户）
+#[derive(Accounts)]
+pub struct ValidateSetup {}
+
+/// 校验一组 token 权重和费率配置
+/// 在正式创建 pool、添加 token 之前调用，一次性检查所有配置
+/// weights: 每个 token 的权重
+/// fee_numerator: 手续费分子
+/// fee_denominator: 手续费分母
+/// 返回 SetupValidation { valid, reason }，reason 见 setup_reason
+pub fn validate_setup(
+    _ctx: Context<ValidateSetup>,
+    weights: Vec<u64>,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<SetupValidation> {
+    let result = validate_setup_inner(&weights, fee_numerator, fee_denominator);
+    msg!("Setup validation: valid={}, reason={}", result.valid, result.reason);
+    Ok(result)
+}
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+use crate::state::pda::derive_vault;
+use crate::state::AnySwapPool;
+
+/// 查询 vault 地址（只读）
+#[derive(Accounts)]
+pub struct GetVaultAddress<'info> {
+    pub pool: AccountLoader<'info, AnySwapPool>,
+}
+
+/// 返回 pool 中 mint 对应的 vault 地址（通过 return data 返回）
+/// 地址只由 seeds 决定，不要求该 token 已经加入 pool
+pub fn get_vault_address(ctx: Context<GetVaultAddress>, mint: Pubkey) -> Result<Pubkey> {
+    let (vault, _) = derive_vault(&ctx.accounts.pool.key(), &mint);
+    msg!("Vault address: {}", vault);
+    Ok(vault)
+}
@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+use crate::state::ProgramVersion;
+
+/// 查询程序版本（只读，不需要任何账户）
+#[derive(Accounts)]
+pub struct GetVersion {}
+
+/// 返回程序版本 (major, minor, patch) 和支持的功能位（见 state::version 中的 FEATURE_*）
+pub fn get_version(_ctx: Context<GetVersion>) -> Result<ProgramVersion> {
+    let version = ProgramVersion::current();
+    msg!(
+        "AnySwap v{}.{}.{}, features={:#x}",
+        version.major,
+        version.minor,
+        version.patch,
+        version.features
+    );
+    Ok(version)
+}
@@ -0,0 +1,456 @@
+use crate::error::ErrorCode;
+use crate::state::token_account::{check_writable, check_writable_pairs, load_token_account, read_token_amount};
+use crate::state::pool::{PAUSE_ADD_LIQUIDITY, PAUSE_REMOVE_LIQUIDITY, PAUSE_SWAP};
+use crate::state::{AnySwapPool, LpPosition};
+use crate::state::zap::{zap_in_inner, zap_out_inner};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{self, TokenInterface};
+
+/// 单 token 加入流动性（zap in）
+/// 内部先把输入 token 按权重 swap 成其他 token，再按比例加入流动性
+#[derive(Accounts)]
+pub struct ZapIn<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool authority PDA
+    /// CHECK: PDA derived from pool key, used as token account owner
+    #[account(
+        seeds = [b"anyswap_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// Pool mint - LP token
+    /// 当前的 LP mint（pool_mint PDA，或 finish_lp_migration 之后的新 mint），在指令中校验
+    #[account(mut)]
+    pub pool_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    /// 用户的 LP token 账户（接收 LP token）
+    #[account(
+        mut,
+        constraint = user_pool_ata.mint == pool_mint.key(),
+        constraint = user_pool_ata.owner == owner.key()
+    )]
+    pub user_pool_ata: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    /// 用户的 LP 持仓记录（记录最近一次添加流动性的时间）
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = LpPosition::space(),
+        seeds = [b"lp_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub lp_position: Box<Account<'info, LpPosition>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    /// LP mint 所属的 token 程序（迁移后的 LP mint 可以是 Token-2022）
+    pub lp_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 单 token 移除流动性（zap out）
+/// 内部先按比例移除所有 token，再把它们 swap 成输出 token
+#[derive(Accounts)]
+pub struct ZapOut<'info> {
+    #[account(mut)]
+    pub pool: AccountLoader<'info, AnySwapPool>,
+
+    /// Pool authority PDA
+    /// CHECK: PDA derived from pool key, used as token account owner
+    #[account(
+        seeds = [b"anyswap_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// Pool mint - LP token
+    /// 当前的 LP mint；LP mint 迁移期间也可以是迁移的目标 mint（在指令中校验）
+    #[account(mut)]
+    pub pool_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    /// 用户的 LP token 账户（销毁 LP token）
+    #[account(
+        mut,
+        constraint = user_pool_ata.mint == pool_mint.key(),
+        constraint = user_pool_ata.owner == owner.key()
+    )]
+    pub user_pool_ata: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    /// 用户的 LP 持仓记录（用于最短持有期检查）
+    /// 必须已存在：通过转账获得 LP 的用户需要先调用 open_lp_position，同样要等待持有期
+    #[account(
+        seeds = [b"lp_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump = lp_position.bump,
+    )]
+    pub lp_position: Box<Account<'info, LpPosition>>,
+
+    /// 用户接收输出 token 的账户
+    #[account(
+        mut,
+        constraint = user_token_out.owner == owner.key()
+    )]
+    pub user_token_out: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    /// LP mint 所属的 token 程序（迁移后的 LP mint 可以是 Token-2022）
+    pub lp_token_program: Interface<'info, TokenInterface>,
+}
+
+/// 读取并验证 pool 中所有 token 的 vault，返回余额和 now 时刻的权重
+/// pairs 为 (user_token_account, vault_account) 账户对，按 pool 中 token 的顺序
+fn load_pool_vaults<'a>(
+    pool: &AnySwapPool,
+    pairs: &'a [AccountInfo<'a>],
+    now: i64,
+) -> Result<(Vec<u64>, Vec<u64>)> {
+    let token_count = pool.get_token_count();
+    let mut token_vault_balances: Vec<u64> = Vec::with_capacity(token_count);
+    let mut weights: Vec<u64> = Vec::with_capacity(token_count);
+
+    for i in 0..token_count {
+        let vault_info = &pairs[i * 2 + 1];
+
+        // 验证 vault
+        let token_item = pool.get_token_checked(i)?;
+        require!(
+            vault_info.key() == *token_item.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+
+        // 地址已与 pool 记录的 vault（由 pool_authority 控制的 PDA）比对，只需读取余额
+        // 未领取的协议费不属于 LP，不计入储备
+        let vault_amount = token_item.lp_balance(read_token_amount(vault_info)?);
+
+        token_vault_balances.push(vault_amount);
+        weights.push(token_item.weight_at(now));
+    }
+    Ok((token_vault_balances, weights))
+}
+
+/// 单 token 加入流动性
+///
+/// 内部 swap 和加入流动性涉及 pool 的全部 token，token 数量不能超过 pool 的 max_tokens_per_swap
+///
+/// RemainingAccounts 结构：
+/// - 每两个账户为一对：(user_token_account, vault_account)
+/// - 必须按照 pool 中 token 的顺序传入（需要其他 token 的账户以退还零头）
+///
+/// mint_in: 输入 token 的 mint
+/// amount_in: 输入 token 的数量（上限，实际使用的数量可能略少）
+/// min_lp_out: 最少获得的 LP 数量（滑点保护）
+pub fn zap_in<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, ZapIn<'info>>,
+    mint_in: Pubkey,
+    amount_in: u64,
+    min_lp_out: u64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_SWAP)?;
+    pool.check_not_paused(PAUSE_ADD_LIQUIDITY)?;
+    pool.check_lp_mint(ctx.accounts.pool_mint.decimals)?;
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    pool.check_canonical_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+    let token_count = pool.get_token_count();
+    require!(token_count > 0, ErrorCode::InvalidTokenCount);
+    pool.check_tokens_per_swap(token_count)?;
+
+    let input_index = pool
+        .find_token_index(&mint_in)
+        .ok_or(ErrorCode::InvalidTokenMint)?;
+
+    // 验证 RemainingAccounts 数量：每个 token 需要 2 个账户（user_token, vault）
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(
+        remaining_accounts.len() == token_count * 2,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let owner_key = ctx.accounts.owner.key();
+    let (token_vault_balances, weights) =
+        load_pool_vaults(&pool, remaining_accounts, now)?;
+
+    let result = zap_in_inner(
+        input_index,
+        amount_in,
+        &token_vault_balances,
+        &weights,
+        pool.get_total_amount_minted(),
+        pool.get_fee_numerator(),
+        pool.get_fee_denominator(),
+        pool.protocol_fee_rate(),
+    )?;
+    require!(
+        result.lp_minted >= min_lp_out,
+        ErrorCode::InsufficientOutputAmount
+    );
+
+    drop(pool);
+
+    // 更新 total_amount_minted
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    let current_total = pool_mut.get_total_amount_minted();
+    pool_mut.set_total_amount_minted(
+        current_total
+            .checked_add(result.lp_minted as u128)
+            .ok_or(ErrorCode::MathOverflow)?,
+    );
+    for i in 0..token_count {
+        if i == input_index {
+            pool_mut.record_principal_flow(i, result.amount_in_used, 0, result.fees[i])?;
+        } else {
+            pool_mut.record_principal_flow(i, 0, result.refunds[i], result.fees[i])?;
+        }
+    }
+    pool_mut.accrue_protocol_fees_in_order(&result.protocol_fees)?;
+    pool_mut.record_activity(now);
+    drop(pool_mut);
+
+    // 准备 seeds 用于签名
+    let pool_key = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+
+    // 铸造 LP token 给用户
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.lp_token_program.to_account_info(),
+            token_interface::MintTo {
+                to: ctx.accounts.user_pool_ata.to_account_info(),
+                mint: ctx.accounts.pool_mint.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer,
+        ),
+        result.lp_minted,
+    )?;
+
+    check_writable_pairs(remaining_accounts)?;
+    for i in 0..token_count {
+        let user_token_info = &remaining_accounts[i * 2];
+        let vault_info = &remaining_accounts[i * 2 + 1];
+        let amount = if i == input_index {
+            result.amount_in_used
+        } else {
+            result.refunds[i]
+        };
+
+        // 跳过不需要转移的token
+        if amount == 0 {
+            continue;
+        }
+
+        // 验证 user_token owner
+        let user_token_account = load_token_account(user_token_info, i * 2)?;
+        require!(
+            user_token_account.owner == owner_key,
+            ErrorCode::InvalidTokenMint
+        );
+
+        if i == input_index {
+            // 输入 token：从用户转到 vault
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: user_token_info.clone(),
+                        to: vault_info.clone(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        } else {
+            // 内部 swap 得到但没有用完的部分：从 vault 退还给用户
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: vault_info.clone(),
+                        to: user_token_info.clone(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    signer,
+                ),
+                amount,
+            )?;
+        }
+    }
+
+    // 记录加入时间（用于最短持有期检查）
+    let lp_position = &mut ctx.accounts.lp_position;
+    lp_position.pool = pool_key;
+    lp_position.owner = owner_key;
+    lp_position.last_join_ts = now;
+    lp_position.bump = ctx.bumps.lp_position;
+
+    msg!(
+        "Zap in: {} of token {} used, {} LP tokens minted",
+        result.amount_in_used,
+        input_index,
+        result.lp_minted
+    );
+
+    Ok(())
+}
+
+/// 单 token 移除流动性
+///
+/// 移除流动性和内部 swap 涉及 pool 的全部 token，token 数量不能超过 pool 的 max_tokens_per_swap
+///
+/// RemainingAccounts 结构：
+/// - pool 中每个 token 的 vault 账户，按 pool 中 token 的顺序
+///
+/// 只有输出 token 从 vault 转出，其他 token 在内部 swap 中回到了 vault
+///
+/// burn_amount: 要销毁的 LP token 数量
+/// mint_out: 输出 token 的 mint
+/// min_amount_out: 最少获得的输出 token 数量（滑点保护）
+pub fn zap_out<'remaining: 'info, 'info>(
+    ctx: Context<'_, '_, 'remaining, 'info, ZapOut<'info>>,
+    burn_amount: u64,
+    mint_out: Pubkey,
+    min_amount_out: u64,
+) -> Result<()> {
+    // 检查用户 LP token 余额
+    require!(
+        ctx.accounts.user_pool_ata.amount >= burn_amount,
+        ErrorCode::InsufficientTokenAmount
+    );
+    require!(
+        ctx.accounts.user_token_out.mint == mint_out,
+        ErrorCode::InvalidTokenMint
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = ctx.accounts.pool.load()?;
+    pool.check_not_paused(PAUSE_SWAP)?;
+    pool.check_not_paused(PAUSE_REMOVE_LIQUIDITY)?;
+    pool.check_lp_mint(ctx.accounts.pool_mint.decimals)?;
+    let pda_mint = AnySwapPool::find_lp_mint_pda(&ctx.accounts.pool.key(), ctx.program_id);
+    let is_migrated_mint = pool.resolve_lp_mint(&ctx.accounts.pool_mint.key(), &pda_mint)?;
+    let token_count = pool.get_token_count();
+    require!(token_count > 0, ErrorCode::InvalidTokenCount);
+    pool.check_tokens_per_swap(token_count)?;
+
+    let output_index = pool
+        .find_token_index(&mint_out)
+        .ok_or(ErrorCode::InvalidTokenMint)?;
+
+    // 检查最短持有期
+    ctx.accounts
+        .lp_position
+        .check_hold_period(pool.get_min_hold_seconds(), now)?;
+
+    let remaining_accounts = ctx.remaining_accounts;
+    require!(
+        remaining_accounts.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let mut token_vault_balances: Vec<u64> = Vec::with_capacity(token_count);
+    let mut weights: Vec<u64> = Vec::with_capacity(token_count);
+    for (i, vault_info) in remaining_accounts.iter().enumerate() {
+        // 验证 vault
+        let token_item = pool.get_token_checked(i)?;
+        require!(
+            vault_info.key() == *token_item.vault_pubkey(),
+            ErrorCode::InvalidTokenMint
+        );
+
+        // 地址已与 pool 记录的 vault（由 pool_authority 控制的 PDA）比对，只需读取余额
+        // 未领取的协议费不属于 LP，不计入储备
+        let vault_amount = token_item.lp_balance(read_token_amount(vault_info)?);
+
+        token_vault_balances.push(vault_amount);
+        weights.push(token_item.weight_at(now));
+    }
+
+    let result = zap_out_inner(
+        output_index,
+        burn_amount,
+        &token_vault_balances,
+        &weights,
+        pool.get_total_amount_minted(),
+        pool.get_fee_numerator(),
+        pool.get_fee_denominator(),
+        pool.protocol_fee_rate(),
+    )?;
+    require!(
+        result.amount_out >= min_amount_out,
+        ErrorCode::InsufficientOutputAmount
+    );
+
+    drop(pool);
+
+    // 准备 seeds 用于签名
+    let pool_key = ctx.accounts.pool.key();
+    let bump = ctx.bumps.pool_authority;
+    let seeds = &[b"anyswap_authority", pool_key.as_ref(), &[bump]];
+    let signer = &[&seeds[..]];
+
+    // 从 vault 转移输出 token 给用户
+    check_writable(&remaining_accounts[output_index], output_index)?;
+    if result.amount_out > 0 {
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: remaining_accounts[output_index].clone(),
+                    to: ctx.accounts.user_token_out.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            ),
+            result.amount_out,
+        )?;
+    }
+
+    // 销毁用户的 LP token（用户自己签名销毁）
+    let supply_before = ctx.accounts.pool_mint.supply;
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.lp_token_program.to_account_info(),
+            token_interface::Burn {
+                mint: ctx.accounts.pool_mint.to_account_info(),
+                from: ctx.accounts.user_pool_ata.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ),
+        burn_amount,
+    )?;
+
+    // 按销毁后重新读取的 mint 供应量更新 total_amount_minted
+    ctx.accounts.pool_mint.reload()?;
+    let supply_after = ctx.accounts.pool_mint.supply;
+    let mut pool_mut = ctx.accounts.pool.load_mut()?;
+    let burned = pool_mut.record_lp_burn(supply_before, supply_after, burn_amount)?;
+    if is_migrated_mint {
+        pool_mut.record_migrated_lp_burn(burned)?;
+    }
+    for i in 0..result.fees.len() {
+        let vault_out = if i == output_index { result.amount_out } else { 0 };
+        pool_mut.record_principal_flow(i, 0, vault_out, result.fees[i])?;
+    }
+    pool_mut.accrue_protocol_fees_in_order(&result.protocol_fees)?;
+    pool_mut.record_activity(now);
+
+    msg!(
+        "Zap out: {} LP tokens burned, {} of token {} returned",
+        burn_amount,
+        result.amount_out,
+        output_index
+    );
+
+    Ok(())
+}
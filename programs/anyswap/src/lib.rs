@@ -1,34 +1,83 @@
+// 关闭默认的 anchor feature 时只编译不依赖 anchor 的 math 模块，供链下工具复用
+#[cfg(feature = "anchor")]
 use anchor_lang::prelude::*;
 
+#[cfg(feature = "anchor")]
 pub mod instructions;
+#[cfg(feature = "anchor")]
 pub mod state;
+#[cfg(feature = "anchor")]
 pub mod error;
 pub mod math;
+#[cfg(feature = "anchor")]
+pub mod events;
 
+#[cfg(feature = "anchor")]
 use instructions::*;
+#[cfg(feature = "anchor")]
+use state::SetupValidation;
+#[cfg(feature = "anchor")]
+use state::ProgramVersion;
+#[cfg(feature = "anchor")]
 declare_id!("3GBxn5VSThpKNyUgaQ96xjSXD2zJ1164LzK28MXv4MDC");
 
+#[cfg(feature = "anchor")]
 #[program]
 pub mod anyswap {
     use super::*;
 
     /// 创建 Pool（PDA）
+    /// admin_less: 显式创建无管理员的 pool
+    /// zero_fee: 显式创建零手续费的 pool
     pub fn create_pool(
         ctx: Context<CreatePool>,
         fee_numerator: u64,
         fee_denominator: u64,
+        admin_less: bool,
+        zero_fee: bool,
+        lp_decimals: Option<u8>,
     ) -> Result<()> {
-        instructions::create_pool(ctx, fee_numerator, fee_denominator)
+        instructions::create_pool(
+            ctx,
+            fee_numerator,
+            fee_denominator,
+            admin_less,
+            zero_fee,
+            lp_decimals,
+        )
+    }
+
+    /// 关闭空的 pool（只有 admin 可以调用），租金退还给 admin
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        instructions::close_pool(ctx)
+    }
+
+    /// 校验 pool 配置（只读），返回是否有效以及结果码
+    pub fn validate_setup(
+        ctx: Context<ValidateSetup>,
+        weights: Vec<u64>,
+        fee_numerator: u64,
+        fee_denominator: u64,
+    ) -> Result<SetupValidation> {
+        instructions::validate_setup(ctx, weights, fee_numerator, fee_denominator)
+    }
+
+    /// 查询程序版本和支持的功能位（只读），客户端据此降级处理不支持的功能
+    pub fn get_version(ctx: Context<GetVersion>) -> Result<ProgramVersion> {
+        instructions::get_version(ctx)
     }
 
     /// 添加 token 到 AnySwap Pool
     /// RemainingAccounts: 如果 pool 中已有 token，需要传入现有 vault 的账户信息
+    /// quote_index / liquidity_value: 已有 LP 时注入初始流动性，声明 liquidity 按 quote token 计价的价值
     pub fn add_token_to_pool<'remaining: 'info, 'info>(
         ctx: Context<'_, '_, 'remaining, 'info, AddTokenToPool<'info>>,
         weight: u64,
         liquidity: u64,
+        quote_index: u16,
+        liquidity_value: u64,
     ) -> Result<()> {
-        instructions::add_token_to_pool(ctx, weight, liquidity)
+        instructions::add_token_to_pool(ctx, weight, liquidity, quote_index, liquidity_value)
     }
 
     /// 从 AnySwap Pool 移除 token
@@ -46,6 +95,26 @@ pub mod anyswap {
         instructions::modify_token_weight(ctx, new_weights)
     }
 
+    /// 一次设置所有 token 的权重（按 pool 中 token 的顺序）
+    pub fn rebalance_weights(ctx: Context<ModifyTokenWeight>, new_weights: Vec<u64>) -> Result<()> {
+        instructions::rebalance_weights(ctx, new_weights)
+    }
+
+    /// 开始 token 的权重渐变：在 duration 秒内从当前权重线性变化到 end_weight
+    pub fn start_weight_ramp(
+        ctx: Context<ModifyTokenWeight>,
+        mint: Pubkey,
+        end_weight: u64,
+        duration: i64,
+    ) -> Result<()> {
+        instructions::start_weight_ramp(ctx, mint, end_weight, duration)
+    }
+
+    /// 从 token 列表重新计算 pool 的权重之和（迁移后的修复工具）
+    pub fn recompute_weight_sum(ctx: Context<RecomputeWeightSum>) -> Result<()> {
+        instructions::recompute_weight_sum(ctx)
+    }
+
     /// 修改 pool 的费率
     pub fn modify_fee(
         ctx: Context<ModifyFee>,
@@ -55,25 +124,310 @@ pub mod anyswap {
         instructions::modify_fee(ctx, fee_numerator, fee_denominator)
     }
 
+    /// 设置 token 的买入 / 卖出费率（bps），0 表示使用 pool 费率
+    pub fn set_token_fees(
+        ctx: Context<SetTokenFees>,
+        mint: Pubkey,
+        buy_fee_bps: u16,
+        sell_fee_bps: u16,
+    ) -> Result<()> {
+        instructions::set_token_fees(ctx, mint, buy_fee_bps, sell_fee_bps)
+    }
+
+    /// 设置稳定组（token 索引）和放大系数 A，组内 1 进 1 出的 swap 使用 StableSwap 曲线
+    pub fn set_stable_group(
+        ctx: Context<SetStableGroup>,
+        stable_group: Vec<u16>,
+        amplification: u64,
+    ) -> Result<()> {
+        instructions::set_stable_group(ctx, stable_group, amplification)
+    }
+
+    /// 修改 pool 的最短持有期（秒），0 表示不限制
+    pub fn modify_min_hold_seconds(
+        ctx: Context<ModifyMinHold>,
+        min_hold_seconds: i64,
+    ) -> Result<()> {
+        instructions::modify_min_hold_seconds(ctx, min_hold_seconds)
+    }
+
+    /// 修改 pool 的最小 swap 数量（每个输入扣费后），0 表示不限制
+    pub fn modify_min_swap_amount(
+        ctx: Context<ModifyMinSwapAmount>,
+        min_swap_amount: u64,
+    ) -> Result<()> {
+        instructions::modify_min_swap_amount(ctx, min_swap_amount)
+    }
+
+    /// 修改 pool 一次 swap / 增减流动性最多涉及的 token 数量，0 表示不限制
+    pub fn modify_max_tokens_per_swap(
+        ctx: Context<ModifyMaxTokensPerSwap>,
+        max_tokens_per_swap: u16,
+    ) -> Result<()> {
+        instructions::modify_max_tokens_per_swap(ctx, max_tokens_per_swap)
+    }
+
+    /// 设置 guardian（仅管理员）
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        instructions::set_guardian(ctx, guardian)
+    }
+
+    /// 提议新的管理员（仅管理员），需由新管理员调用 accept_admin 后生效
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
+    }
+
+    /// 接受管理员转移（仅待接受的管理员）
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
+
+    /// 暂停 pool 的所有操作（管理员或 guardian）
+    pub fn pause_pool(ctx: Context<PausePool>) -> Result<()> {
+        instructions::pause_pool(ctx)
+    }
+
+    /// 设置暂停的操作（管理员可任意设置，guardian 只能增加暂停项）
+    pub fn set_paused_ops(ctx: Context<PausePool>, paused_ops: u64) -> Result<()> {
+        instructions::set_paused_ops(ctx, paused_ops)
+    }
+
+    /// 紧急开关（仅管理员）：暂停或恢复 swap 和流动性操作
+    /// allow_withdraw_when_paused: 暂停期间是否仍允许移除流动性
+    pub fn set_pause(
+        ctx: Context<PausePool>,
+        paused: bool,
+        allow_withdraw_when_paused: bool,
+    ) -> Result<()> {
+        instructions::set_pause(ctx, paused, allow_withdraw_when_paused)
+    }
+
     /// AnySwap 交换代币
     /// amounts_tolerance: 每个 token 的容差（输入为上限，输出为下限）
     /// is_in_token: 标记每个 token 是输入还是输出
+    /// max_fee_bps: 可接受的最大费率（bps），输入 token 实际使用的费率更高时拒绝 swap，None 表示不限制
+    /// memo: 可选的备注，转账后写入 SPL Memo 程序（需要传入 memo_program）
     pub fn swap_anyswap<'remaining: 'info, 'info>(
         ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
         amounts_tolerance: Vec<u64>,
         is_in_token: Vec<bool>,
+        max_fee_bps: Option<u16>,
+        memo: Option<String>,
+    ) -> Result<()> {
+        instructions::swap_anyswap(ctx, amounts_tolerance, is_in_token, max_fee_bps, memo)
+    }
+
+    /// AnySwap 交换代币（按百分比滑点）
+    /// amounts: 输入 token 为输入数量，输出 token 为询价得到的预期输出
+    /// slippage_bps: 每个输出允许低于预期输出的比例（bps）
+    pub fn swap_with_slippage<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
+        amounts: Vec<u64>,
+        is_in_token: Vec<bool>,
+        slippage_bps: u16,
+    ) -> Result<()> {
+        instructions::swap_with_slippage(ctx, amounts, is_in_token, slippage_bps)
+    }
+
+    /// 模拟 swap（账户与 swap_anyswap 相同，不转账），返回 SwapSimulation
+    /// RemainingAccounts: 同 swap_anyswap
+    pub fn simulate_swap<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
+        amounts_tolerance: Vec<u64>,
+        is_in_token: Vec<bool>,
+    ) -> Result<SwapSimulation> {
+        instructions::simulate_swap(ctx, amounts_tolerance, is_in_token)
+    }
+
+    /// AnySwap 精确输出交换：指定输出数量，输入由曲线反推
+    /// max_amount_in: 可接受的最大输入（扣费前）
+    /// max_fee_bps: 可接受的最大费率（bps），同 swap_anyswap
+    /// RemainingAccounts: [user_token_in, vault_in, user_token_out, vault_out]
+    pub fn swap_exact_out<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
+        amount_out: u64,
+        max_amount_in: u64,
+        max_fee_bps: Option<u16>,
+    ) -> Result<()> {
+        instructions::swap_exact_out(ctx, amount_out, max_amount_in, max_fee_bps)
+    }
+
+    /// AnySwap 池内多跳交换：沿 path 中的 token 索引逐跳交换，只转入第一个 token、转出最后一个 token
+    /// max_fee_bps: 可接受的最大费率（bps），每一跳分别检查，None 表示不限制
+    /// RemainingAccounts: [user_token_in, user_token_out, 路径上每个 token 的 vault]
+    pub fn swap_route<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, Swap<'info>>,
+        path: Vec<u16>,
+        amount_in: u64,
+        min_amount_out: u64,
+        max_fee_bps: Option<u16>,
+    ) -> Result<()> {
+        instructions::swap_route(ctx, path, amount_in, min_amount_out, max_fee_bps)
+    }
+
+    /// AnySwap 交换代币（带 JIT 流动性）
+    /// swap 前 CPI 调用 jit_program 注入流动性，swap 后按份额返还给 JIT 提供者（jit_provider 签名）
+    /// RemainingAccounts: swap 账户对，JIT 收款账户（属于 jit_provider），以及传给 jit_program 的账户
+    pub fn swap_with_jit<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, SwapWithJit<'info>>,
+        amounts_tolerance: Vec<u64>,
+        is_in_token: Vec<bool>,
+        jit_data: Vec<u8>,
+    ) -> Result<()> {
+        instructions::swap_with_jit(ctx, amounts_tolerance, is_in_token, jit_data)
+    }
+
+    /// 闪电贷：从 vault 借出 amount，CPI 调用 callback_program 后要求归还本金和手续费
+    /// RemainingAccounts: 原样传给 callback_program 的账户
+    pub fn flash_loan<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, FlashLoan<'info>>,
+        amount: u64,
+        callback_data: Vec<u8>,
+    ) -> Result<()> {
+        instructions::flash_loan(ctx, amount, callback_data)
+    }
+
+    /// 设置协议费：swap 手续费中 numerator / denominator 归协议（只有 admin 可以调用）
+    /// recipient: 协议费接收者
+    pub fn set_protocol_fee(
+        ctx: Context<SetProtocolFee>,
+        numerator: u64,
+        denominator: u64,
+        recipient: Pubkey,
+    ) -> Result<()> {
+        instructions::set_protocol_fee(ctx, numerator, denominator, recipient)
+    }
+
+    /// 将已计提的协议费转给协议费接收者
+    /// RemainingAccounts: (vault_account, recipient_token_account) 对
+    pub fn collect_protocol_fees<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, CollectProtocolFees<'info>>,
     ) -> Result<()> {
-        instructions::swap_anyswap(ctx, amounts_tolerance, is_in_token)
+        instructions::collect_protocol_fees(ctx)
+    }
+
+    /// swap 询价，返回每个 token 的数量、手续费、实际费率（bps）和手续费拆分
+    /// referral_fee_bps: 推荐人从手续费中分得的比例（bps）
+    /// RemainingAccounts: 每个参与 swap 的 token 的 vault 账户
+    pub fn quote_swap_detailed<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, QuoteSwap<'info>>,
+        amounts_tolerance: Vec<u64>,
+        is_in_token: Vec<bool>,
+        referral_fee_bps: u64,
+    ) -> Result<SwapQuote> {
+        instructions::quote_swap_detailed(ctx, amounts_tolerance, is_in_token, referral_fee_bps)
+    }
+
+    /// 询价曲线：同一对 token 在多个输入数量下的输出
+    /// RemainingAccounts: [vault_in, vault_out]
+    pub fn quote_swap_curve<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, QuoteSwap<'info>>,
+        mint_in: Pubkey,
+        mint_out: Pubkey,
+        amounts_in: Vec<u64>,
+    ) -> Result<Vec<u64>> {
+        instructions::quote_swap_curve(ctx, mint_in, mint_out, amounts_in)
+    }
+
+    /// swap 安全检查：模拟 swap 并返回不变量是否保持及其变化量
+    /// RemainingAccounts: 每个参与 swap 的 token 一个 vault 账户
+    pub fn check_swap_safe<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, QuoteSwap<'info>>,
+        amounts_tolerance: Vec<u64>,
+        is_in_token: Vec<bool>,
+    ) -> Result<SwapSafety> {
+        instructions::check_swap_safe(ctx, amounts_tolerance, is_in_token)
+    }
+
+    /// 含手续费的现货价格（18 位定点数）
+    /// RemainingAccounts: [vault_in, vault_out]
+    pub fn get_spot_price_with_fee<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, QuoteSwap<'info>>,
+        mint_in: Pubkey,
+        mint_out: Pubkey,
+    ) -> Result<u128> {
+        instructions::get_spot_price_with_fee(ctx, mint_in, mint_out)
+    }
+
+    /// 查询现货价格（不含 / 含手续费），结果通过 set_return_data 返回 SpotPrice
+    /// RemainingAccounts: [vault_in, vault_out]
+    pub fn query_spot_price<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, QuoteSwap<'info>>,
+        mint_in: Pubkey,
+        mint_out: Pubkey,
+    ) -> Result<()> {
+        instructions::query_spot_price(ctx, mint_in, mint_out)
+    }
+
+    /// 查询不超过上限的最大按比例加入（amounts_in 和铸造的 LP）
+    /// RemainingAccounts: pool 中每个 token 的 vault 账户（按顺序）
+    pub fn max_joinable<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, MaxJoinable<'info>>,
+    ) -> Result<MaxJoin> {
+        instructions::max_joinable(ctx)
+    }
+
+    /// 预览 add_liquidity 实际从用户拿走的每个 token 数量（含手续费），用于精确授权
+    /// RemainingAccounts: pool 中每个 token 的 vault 账户（按顺序）
+    pub fn preview_amounts_used<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, PreviewAmountsUsed<'info>>,
+        amounts_in: Vec<u64>,
+    ) -> Result<Vec<u64>> {
+        instructions::preview_amounts_used(ctx, amounts_in)
+    }
+
+    /// 配置 TWAP 槽位跟踪 (mint_a, mint_b) 的价格（仅管理员），mint_a 为默认公钥时清空槽位
+    /// RemainingAccounts: mint_a 和 mint_b 的 vault 账户（清空槽位时不需要）
+    pub fn set_twap_pair(
+        ctx: Context<SetTwapPair>,
+        slot: u8,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+    ) -> Result<()> {
+        instructions::set_twap_pair(ctx, slot, mint_a, mint_b)
+    }
+
+    /// 记录 (mint_a, mint_b) 当前的累计对数价格，供 query_twap 使用（任何人可调用）
+    pub fn record_twap_observation(
+        ctx: Context<RecordTwapObservation>,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+    ) -> Result<()> {
+        instructions::record_twap_observation(ctx, mint_a, mint_b)
+    }
+
+    /// 查询 since_ts 的观察点以来 token_a 以 token_b 计价的时间加权平均价格（只读）
+    pub fn query_twap(
+        ctx: Context<QueryTwap>,
+        token_a: Pubkey,
+        token_b: Pubkey,
+        since_ts: i64,
+    ) -> Result<u128> {
+        instructions::query_twap(ctx, token_a, token_b, since_ts)
     }
 
     /// 添加流动性（多 token 版本，按 Balancer 方式）
     /// pivot_amount: 基准 token 的添加数量
+    /// min_lp_out: 最少铸造的 LP 数量（滑点保护），0 表示不限制
     /// RemainingAccounts: 每两个账户为一对 (user_token_account, vault_account)
     pub fn add_liquidity<'remaining: 'info, 'info>(
         ctx: Context<'_, '_, 'remaining, 'info, AddLiquidity<'info>>,
         amounts_in: Vec<u64>,
+        min_lp_out: u64,
     ) -> Result<()> {
-        instructions::add_liquidity(ctx, amounts_in)
+        instructions::add_liquidity(ctx, amounts_in, min_lp_out)
+    }
+
+    /// 单 token 添加流动性（Balancer calcBptOutGivenExactTokenIn），超出比例的部分收取手续费
+    /// min_lp_out: 最少铸造的 LP 数量（滑点保护），0 表示不限制
+    /// RemainingAccounts: token_index 对应 token 的 (user_token_account, vault_account)
+    pub fn add_liquidity_single<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, AddLiquidity<'info>>,
+        token_index: u16,
+        amount_in: u64,
+        min_lp_out: u64,
+    ) -> Result<()> {
+        instructions::add_liquidity_single(ctx, token_index, amount_in, min_lp_out)
     }
 
     /// 移除流动性（多 token 版本，按 Balancer 方式）
@@ -85,4 +439,119 @@ pub mod anyswap {
     ) -> Result<()> {
         instructions::remove_liquidity(ctx, burn_amount)
     }
+
+    /// 单 token 移除流动性（Balancer calcTokenOutGivenExactBptIn），超出比例的部分收取手续费
+    /// min_amount_out: 最少获得的 token 数量（滑点保护）
+    /// RemainingAccounts: token_index 对应 token 的 (user_token_account, vault_account)
+    pub fn remove_liquidity_single<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, RemoveLiquidity<'info>>,
+        token_index: u16,
+        burn_amount: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        instructions::remove_liquidity_single(ctx, token_index, burn_amount, min_amount_out)
+    }
+
+    /// 创建 LP 持仓记录（通过转账获得 LP 的用户移除流动性之前调用），同样需要等待最短持有期
+    pub fn open_lp_position(ctx: Context<OpenLpPosition>) -> Result<()> {
+        instructions::open_lp_position(ctx)
+    }
+
+    /// 发起大额退出：立即销毁 LP token 并记录每个 token 应支付的数量，之后通过 process_exit 分批领取
+    /// burn_amount: 要退出的 LP token 数量
+    /// RemainingAccounts: 每个 token 的 vault_account，按 pool 中 token 的顺序
+    pub fn request_exit<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, RequestExit<'info>>,
+        burn_amount: u64,
+    ) -> Result<()> {
+        instructions::request_exit(ctx, burn_amount)
+    }
+
+    /// 处理待处理的退出，支付 token_indices 对应的 token，全部支付后关闭退出记录
+    /// RemainingAccounts: 每两个账户为一对 (user_token_account, vault_account)，顺序与 token_indices 一致
+    pub fn process_exit<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, ProcessExit<'info>>,
+        token_indices: Vec<u16>,
+    ) -> Result<()> {
+        instructions::process_exit(ctx, token_indices)
+    }
+
+    /// 单 token 加入流动性：内部按权重 swap 后加入，一次调用获得 LP
+    /// min_lp_out: 最少获得的 LP 数量
+    /// RemainingAccounts: 每两个账户为一对 (user_token_account, vault_account)
+    pub fn zap_in<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, ZapIn<'info>>,
+        mint_in: Pubkey,
+        amount_in: u64,
+        min_lp_out: u64,
+    ) -> Result<()> {
+        instructions::zap_in(ctx, mint_in, amount_in, min_lp_out)
+    }
+
+    /// 单 token 移除流动性：按比例移除后在内部 swap 成 mint_out
+    /// min_amount_out: 最少获得的输出 token 数量
+    /// RemainingAccounts: pool 中每个 token 的 vault 账户
+    pub fn zap_out<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, ZapOut<'info>>,
+        burn_amount: u64,
+        mint_out: Pubkey,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        instructions::zap_out(ctx, burn_amount, mint_out, min_amount_out)
+    }
+
+    /// 开始 LP mint 迁移（仅管理员），迁移期间移除流动性同时接受新旧 mint
+    pub fn migrate_lp_mint(ctx: Context<MigrateLpMint>) -> Result<()> {
+        instructions::migrate_lp_mint(ctx)
+    }
+
+    /// 将用户的全部旧 LP 1:1 换成新 LP
+    pub fn swap_lp(ctx: Context<SwapLp>) -> Result<()> {
+        instructions::swap_lp(ctx)
+    }
+
+    /// 完成 LP mint 迁移（仅管理员）：旧 mint 上没有 LP 后，新 mint 成为当前的 LP mint
+    pub fn finish_lp_migration(ctx: Context<FinishLpMigration>) -> Result<()> {
+        instructions::finish_lp_migration(ctx)
+    }
+
+    /// 查询 pool 中某个 mint 对应的 vault 地址（只读）
+    pub fn get_vault_address(ctx: Context<GetVaultAddress>, mint: Pubkey) -> Result<Pubkey> {
+        instructions::get_vault_address(ctx, mint)
+    }
+
+    /// 查询 pool 的创建时间和最近活动时间（只读）
+    pub fn get_activity(ctx: Context<GetActivity>) -> Result<PoolActivity> {
+        instructions::get_activity(ctx)
+    }
+
+    /// 按索引顺序查询从 offset 开始的有效 token mint（只读，每次最多 31 个）
+    pub fn get_active_mints(ctx: Context<GetActiveMints>, offset: u16) -> Result<Vec<Pubkey>> {
+        instructions::get_active_mints(ctx, offset)
+    }
+
+    /// 将累计的手续费并入本金（仅管理员），只修改记账
+    /// RemainingAccounts: pool 中每个 token 的 vault，按 pool 中 token 的顺序
+    pub fn compound_fees<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, CompoundFees<'info>>,
+    ) -> Result<()> {
+        instructions::compound_fees(ctx)
+    }
+
+    /// 记录当前每份 LP 的手续费快照（PDA，seeds 含当前时间戳）
+    /// RemainingAccounts: pool 中每个 token 的 vault，按 pool 中 token 的顺序
+    pub fn snapshot_fees<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, SnapshotFees<'info>>,
+    ) -> Result<()> {
+        instructions::snapshot_fees(ctx)
+    }
+
+    /// 查询 since_timestamp 的快照以来每份 LP 的手续费增长和年化 APY（只读）
+    /// RemainingAccounts: pool 中每个 token 的 vault，按 pool 中 token 的顺序
+    pub fn get_fee_growth<'remaining: 'info, 'info>(
+        ctx: Context<'_, '_, 'remaining, 'info, GetFeeGrowth<'info>>,
+        since_timestamp: i64,
+    ) -> Result<FeeGrowth> {
+        instructions::get_fee_growth(ctx, since_timestamp)
+    }
 }
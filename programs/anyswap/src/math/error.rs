@@ -0,0 +1,95 @@
+//! math 模块的错误类型
+//!
+//! math 模块不依赖 anchor_lang，链下工具（路由、模拟器）可以在关闭 anchor feature 时单独使用。
+//! 链上通过 From 转换为对应的 ErrorCode，`?` 可以直接传播
+
+use core::fmt;
+
+/// math 模块的错误，每个变体对应一个同名的 ErrorCode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathError {
+    /// 数学运算溢出
+    MathOverflow,
+    /// 储备超出 ln/exp 的定义域
+    ReserveOutOfDomain,
+    /// 流动性不足
+    InsufficientLiquidity,
+    /// 无效的稳定组
+    InvalidStableGroup,
+    /// 无效的放大系数
+    InvalidAmplification,
+    /// StableSwap 迭代未收敛
+    StableMathDidNotConverge,
+    /// 无效的数字字符串
+    InvalidNumber,
+}
+
+/// math 模块的 Result
+pub type Result<T> = core::result::Result<T, MathError>;
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            MathError::MathOverflow => "数学运算溢出",
+            MathError::ReserveOutOfDomain => "储备超出 ln/exp 的定义域",
+            MathError::InsufficientLiquidity => "流动性不足",
+            MathError::InvalidStableGroup => "无效的稳定组",
+            MathError::InvalidAmplification => "无效的放大系数",
+            MathError::StableMathDidNotConverge => "StableSwap 迭代未收敛",
+            MathError::InvalidNumber => "无效的数字字符串",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl std::error::Error for MathError {}
+
+/// 与 anchor 的 require! 相同：条件不满足时返回 Err($err)
+macro_rules! ensure {
+    ($cond:expr, $err:expr $(,)?) => {
+        if !($cond) {
+            return Err($err);
+        }
+    };
+}
+pub(crate) use ensure;
+
+#[cfg(feature = "anchor")]
+impl From<MathError> for anchor_lang::error::Error {
+    fn from(err: MathError) -> Self {
+        use crate::error::ErrorCode;
+        let code = match err {
+            MathError::MathOverflow => ErrorCode::MathOverflow,
+            MathError::ReserveOutOfDomain => ErrorCode::ReserveOutOfDomain,
+            MathError::InsufficientLiquidity => ErrorCode::InsufficientLiquidity,
+            MathError::InvalidStableGroup => ErrorCode::InvalidStableGroup,
+            MathError::InvalidAmplification => ErrorCode::InvalidAmplification,
+            MathError::StableMathDidNotConverge => ErrorCode::StableMathDidNotConverge,
+            MathError::InvalidNumber => ErrorCode::InvalidNumber,
+        };
+        code.into()
+    }
+}
+
+#[cfg(all(test, feature = "anchor"))]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn test_math_error_maps_to_error_code() {
+        let err: anchor_lang::error::Error = MathError::ReserveOutOfDomain.into();
+        assert_eq!(err, ErrorCode::ReserveOutOfDomain.into());
+
+        // `?` 在 anchor 的 Result 中自动转换
+        fn checked(x: u64) -> anchor_lang::Result<u64> {
+            let f = || -> Result<u64> {
+                ensure!(x > 0, MathError::MathOverflow);
+                Ok(x)
+            };
+            Ok(f()?)
+        }
+        assert_eq!(checked(1).unwrap(), 1);
+        assert_eq!(checked(0).unwrap_err(), ErrorCode::MathOverflow.into());
+    }
+}
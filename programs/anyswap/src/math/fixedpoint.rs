@@ -12,10 +12,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use anchor_lang::prelude::*;
 use primitive_types::U256;
 
-use crate::error::ErrorCode;
+use super::error::{ensure, MathError, Result};
+use super::i256::I256;
 use super::logexpmath::LogExpMath;
 
 /* solhint-disable private-vars-leading-underscore */
@@ -36,29 +36,29 @@ impl FixedPoint {
     pub fn add(a: U256, b: U256) -> Result<U256> {
         // Fixed Point addition is the same as regular checked addition
 
-        let c = a.checked_add(b).ok_or(ErrorCode::MathOverflow)?;
-        require!(c >= a, ErrorCode::MathOverflow);
+        let c = a.checked_add(b).ok_or(MathError::MathOverflow)?;
+        ensure!(c >= a, MathError::MathOverflow);
         Ok(c)
     }
 
     pub fn sub(a: U256, b: U256) -> Result<U256> {
         // Fixed Point subtraction is the same as regular checked subtraction
 
-        require!(b <= a, ErrorCode::MathOverflow);
-        let c = a.checked_sub(b).ok_or(ErrorCode::MathOverflow)?;
+        ensure!(b <= a, MathError::MathOverflow);
+        let c = a.checked_sub(b).ok_or(MathError::MathOverflow)?;
         Ok(c)
     }
 
     pub fn mul_down(a: U256, b: U256) -> Result<U256> {
-        let product = a.checked_mul(b).ok_or(ErrorCode::MathOverflow)?;
-        require!(a.is_zero() || product.checked_div(a).map(|d| d == b).unwrap_or(false), ErrorCode::MathOverflow);
+        let product = a.checked_mul(b).ok_or(MathError::MathOverflow)?;
+        ensure!(a.is_zero() || product.checked_div(a).map(|d| d == b).unwrap_or(false), MathError::MathOverflow);
 
         Ok(product / Self::ONE)
     }
 
     pub fn mul_up(a: U256, b: U256) -> Result<U256> {
-        let product = a.checked_mul(b).ok_or(ErrorCode::MathOverflow)?;
-        require!(a.is_zero() || product.checked_div(a).map(|d| d == b).unwrap_or(false), ErrorCode::MathOverflow);
+        let product = a.checked_mul(b).ok_or(MathError::MathOverflow)?;
+        ensure!(a.is_zero() || product.checked_div(a).map(|d| d == b).unwrap_or(false), MathError::MathOverflow);
 
         // The traditional divUp formula is:
         // divUp(x, y) := (x + y - 1) / y
@@ -71,26 +71,26 @@ impl FixedPoint {
         if product.is_zero() {
             Ok(U256::zero())
         } else {
-            let product_minus_one = product.checked_sub(U256::one()).ok_or(ErrorCode::MathOverflow)?;
+            let product_minus_one = product.checked_sub(U256::one()).ok_or(MathError::MathOverflow)?;
             let div_result = product_minus_one / Self::ONE;
-            Ok(div_result.checked_add(U256::one()).ok_or(ErrorCode::MathOverflow)?)
+            Ok(div_result.checked_add(U256::one()).ok_or(MathError::MathOverflow)?)
         }
     }
 
     pub fn div_down(a: U256, b: U256) -> Result<U256> {
-        require!(!b.is_zero(), ErrorCode::MathOverflow);
+        ensure!(!b.is_zero(), MathError::MathOverflow);
 
-        let a_inflated = a.checked_mul(Self::ONE).ok_or(ErrorCode::MathOverflow)?;
-        require!(a.is_zero() || a_inflated.checked_div(a).map(|d| d == Self::ONE).unwrap_or(false), ErrorCode::MathOverflow); // mul overflow
+        let a_inflated = a.checked_mul(Self::ONE).ok_or(MathError::MathOverflow)?;
+        ensure!(a.is_zero() || a_inflated.checked_div(a).map(|d| d == Self::ONE).unwrap_or(false), MathError::MathOverflow); // mul overflow
 
         Ok(a_inflated / b)
     }
 
     pub fn div_up(a: U256, b: U256) -> Result<U256> {
-        require!(!b.is_zero(), ErrorCode::MathOverflow);
+        ensure!(!b.is_zero(), MathError::MathOverflow);
 
-        let a_inflated = a.checked_mul(Self::ONE).ok_or(ErrorCode::MathOverflow)?;
-        require!(a.is_zero() || a_inflated.checked_div(a).map(|d| d == Self::ONE).unwrap_or(false), ErrorCode::MathOverflow); // mul overflow
+        let a_inflated = a.checked_mul(Self::ONE).ok_or(MathError::MathOverflow)?;
+        ensure!(a.is_zero() || a_inflated.checked_div(a).map(|d| d == Self::ONE).unwrap_or(false), MathError::MathOverflow); // mul overflow
 
         // The traditional divUp formula is:
         // divUp(x, y) := (x + y - 1) / y
@@ -103,9 +103,9 @@ impl FixedPoint {
         if a_inflated.is_zero() {
             Ok(U256::zero())
         } else {
-            let a_inflated_minus_one = a_inflated.checked_sub(U256::one()).ok_or(ErrorCode::MathOverflow)?;
+            let a_inflated_minus_one = a_inflated.checked_sub(U256::one()).ok_or(MathError::MathOverflow)?;
             let div_result = a_inflated_minus_one / b;
-            Ok(div_result.checked_add(U256::one()).ok_or(ErrorCode::MathOverflow)?)
+            Ok(div_result.checked_add(U256::one()).ok_or(MathError::MathOverflow)?)
         }
     }
 
@@ -157,6 +157,42 @@ impl FixedPoint {
         }
     }
 
+    /**
+     * @dev Returns the square root of x, assuming x is a fixed point number, rounding down.
+     *
+     * Computed as isqrt(x * ONE) with an integer Newton iteration, much cheaper than pow_down(x, 0.5e18).
+     * If x * ONE overflows, falls back to isqrt(x) * 1e9, which loses the last 9 digits of precision.
+     */
+    pub fn sqrt(x: U256) -> Result<U256> {
+        if x.is_zero() {
+            return Ok(U256::zero());
+        }
+        match x.checked_mul(Self::ONE) {
+            Some(x_inflated) => Ok(Self::isqrt(x_inflated)),
+            None => Ok(Self::isqrt(x) * U256::from(1_000_000_000u64)),
+        }
+    }
+
+    /**
+     * @dev Integer square root floor(sqrt(n)).
+     *
+     * The seed 2^ceil(bits / 2) is never below the root, so the Newton sequence decreases monotonically and stops
+     * at the first non-decreasing step, within about log2(bits) + 1 iterations (at most 9 for 256 bits).
+     */
+    fn isqrt(n: U256) -> U256 {
+        if n.is_zero() {
+            return U256::zero();
+        }
+        let mut x = U256::one() << n.bits().div_ceil(2);
+        loop {
+            let y = (x + n / x) >> 1;
+            if y >= x {
+                return x;
+            }
+            x = y;
+        }
+    }
+
     /**
      * @dev Returns the complement of a value (1 - x), capped to 0 if x is larger than 1.
      *
@@ -172,5 +208,151 @@ impl FixedPoint {
             Ok(U256::zero())
         }
     }
+
+    /**
+     * @dev Returns |a - b|.
+     */
+    pub fn abs_diff(a: U256, b: U256) -> U256 {
+        if a > b {
+            a - b
+        } else {
+            b - a
+        }
+    }
+
+    /**
+     * @dev Returns true if a is within a relative error of `max_rel` (a fixed point number, e.g. 0.01e18 for 1%) of
+     * the reference value b, that is |a - b| <= b * max_rel. Same relative bound as `MAX_POW_RELATIVE_ERROR` in
+     * pow_down / pow_up, but compared exactly in 512 bits instead of rounding b * max_rel.
+     */
+    pub fn within_relative_error(a: U256, b: U256, max_rel: U256) -> bool {
+        Self::abs_diff(a, b).full_mul(Self::ONE) <= b.full_mul(max_rel)
+    }
+
+    /**
+     * @dev Returns the weighted geometric mean prod(reserves[i] ^ (weights[i] / sum(weights))), assuming reserves are
+     * fixed point numbers. Weights are relative and normalized by their sum.
+     *
+     * Computed as exp(sum(weights[i] * ln(reserves[i])) / sum(weights)), so only one exp is needed instead of one pow
+     * per reserve. Returns 0 if any reserve is 0.
+     */
+    pub fn geometric_mean(reserves: &[U256], weights: &[U256]) -> Result<U256> {
+        ensure!(
+            !reserves.is_empty() && reserves.len() == weights.len(),
+            MathError::MathOverflow
+        );
+        if reserves.iter().any(|reserve| reserve.is_zero()) {
+            return Ok(U256::zero());
+        }
+
+        let mut weight_sum = I256::ZERO;
+        let mut weighted_ln_sum = I256::ZERO;
+        for (&reserve, &weight) in reserves.iter().zip(weights.iter()) {
+            let weight = I256::try_from(weight)?;
+            let ln_reserve = LogExpMath::ln(I256::try_from(reserve)?)?;
+            weight_sum = weight_sum.checked_add(&weight)?;
+            weighted_ln_sum = weighted_ln_sum.checked_add(&weight.checked_mul(&ln_reserve)?)?;
+        }
+        ensure!(weight_sum > I256::ZERO, MathError::MathOverflow);
+
+        let mean_ln = weighted_ln_sum.checked_div(&weight_sum)?;
+        LogExpMath::exp(mean_ln)?.to_u256()
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqrt_exact() {
+        assert_eq!(FixedPoint::sqrt(U256::zero()).unwrap(), U256::zero());
+        assert_eq!(FixedPoint::sqrt(FixedPoint::ONE).unwrap(), FixedPoint::ONE);
+        assert_eq!(FixedPoint::sqrt(FixedPoint::FOUR).unwrap(), FixedPoint::TWO);
+        // sqrt(2.25) = 1.5
+        let x = U256::from(2_250_000_000_000_000_000u64);
+        assert_eq!(FixedPoint::sqrt(x).unwrap(), U256::from(1_500_000_000_000_000_000u64));
+        // 最小单位：sqrt(1e-18) = 1e-9
+        assert_eq!(FixedPoint::sqrt(U256::one()).unwrap(), U256::from(1_000_000_000u64));
+    }
+
+    #[test]
+    fn test_abs_diff_and_relative_error() {
+        let a = U256::from(1_000_000u64);
+        assert_eq!(FixedPoint::abs_diff(a, a), U256::zero());
+        assert_eq!(FixedPoint::abs_diff(a, U256::from(999_000u64)), U256::from(1_000u64));
+        assert_eq!(FixedPoint::abs_diff(U256::from(999_000u64), a), U256::from(1_000u64));
+        assert_eq!(FixedPoint::abs_diff(U256::zero(), U256::MAX), U256::MAX);
+
+        // 1% 容差：相对参考值 1_000_000，误差 10_000 以内
+        let one_percent = U256::from(10_000_000_000_000_000u64);
+        assert!(FixedPoint::within_relative_error(a, a, U256::zero()));
+        assert!(FixedPoint::within_relative_error(U256::from(1_010_000u64), a, one_percent));
+        assert!(FixedPoint::within_relative_error(U256::from(990_000u64), a, one_percent));
+        assert!(!FixedPoint::within_relative_error(U256::from(1_010_001u64), a, one_percent));
+        assert!(!FixedPoint::within_relative_error(U256::from(989_999u64), a, one_percent));
+        // 乘积超过 256 位时不溢出
+        assert!(FixedPoint::within_relative_error(U256::MAX, U256::MAX - 1, one_percent));
+    }
+
+    #[test]
+    fn test_geometric_mean() {
+        let one = FixedPoint::ONE;
+        let reserves = [one * U256::from(2u64), one * U256::from(8u64)];
+        // 等权重：sqrt(2 * 8) = 4
+        let mean = FixedPoint::geometric_mean(&reserves, &[U256::one(), U256::one()]).unwrap();
+        assert!(FixedPoint::abs_diff(mean, FixedPoint::FOUR) < U256::from(1_000u64), "mean = {}", mean);
+
+        // 权重按总和归一化，与逐个 pow_down 相乘的结果一致
+        let weights = [U256::from(3u64), U256::from(1u64)];
+        let mean = FixedPoint::geometric_mean(&reserves, &weights).unwrap();
+        let expected = FixedPoint::mul_down(
+            FixedPoint::pow_down(reserves[0], U256::from(750_000_000_000_000_000u64)).unwrap(),
+            FixedPoint::pow_down(reserves[1], U256::from(250_000_000_000_000_000u64)).unwrap(),
+        )
+        .unwrap();
+        // 相对误差不超过 1e-12
+        let max_rel = U256::from(1_000_000u64);
+        assert!(FixedPoint::within_relative_error(mean, expected, max_rel), "mean = {}, expected = {}", mean, expected);
+
+        assert!(FixedPoint::geometric_mean(&[reserves[0], U256::zero()], &weights).unwrap().is_zero());
+        assert!(FixedPoint::geometric_mean(&reserves, &[U256::one()]).is_err());
+        assert!(FixedPoint::geometric_mean(&reserves, &[U256::zero(), U256::zero()]).is_err());
+    }
+
+    #[test]
+    fn test_sqrt_matches_pow() {
+        let half = U256::from(500_000_000_000_000_000u64);
+        let values = [
+            U256::from(3u64),
+            U256::from(123_456_789u64),
+            U256::from(2_000_000_000_000_000_000u64),
+            U256::from(7_777_777_777_777_777_777u64),
+            FixedPoint::ONE * U256::from(1_000_000_000u64),
+            FixedPoint::ONE * U256::from(123_456_789_012_345u64),
+        ];
+        for x in values {
+            let root = FixedPoint::sqrt(x).unwrap();
+            // 向下取整：root^2 <= x * ONE < (root + 1)^2
+            let inflated = x * FixedPoint::ONE;
+            assert!(root * root <= inflated);
+            assert!((root + U256::one()) * (root + U256::one()) > inflated);
+
+            // pow_down 在 LogExpMath::pow 的结果（相对误差 MAX_POW_RELATIVE_ERROR）上再减去同样的误差界，
+            // 因此与精确的平方根最多相差两倍误差界
+            let pow = FixedPoint::pow_down(x, half).unwrap();
+            let diff = FixedPoint::abs_diff(root, pow);
+            let max_error = FixedPoint::mul_up(root, FixedPoint::MAX_POW_RELATIVE_ERROR).unwrap() + U256::one();
+            assert!(diff <= max_error * 2, "x = {}: sqrt {} vs pow {}", x, root, pow);
+        }
+    }
+
+    #[test]
+    fn test_sqrt_large_values_do_not_panic() {
+        // x * ONE 溢出时退化为 isqrt(x) * 1e9
+        let root = FixedPoint::sqrt(U256::MAX).unwrap();
+        let expected = U256::from(1_000_000_000u64) * (U256::one() << 128);
+        assert!(root < expected);
+        assert!(expected - root <= U256::from(1_000_000_000u64));
+    }
+}
@@ -1,9 +1,8 @@
 // SPDX-License-Identifier: MIT
 
-use anchor_lang::prelude::*;
 use primitive_types::U256;
 
-use crate::error::ErrorCode;
+use super::error::{ensure, MathError, Result};
 
 /**
  * @dev A 256-bit signed integer implementation using U256 as underlying storage.
@@ -60,6 +59,18 @@ impl I256 {
         self.value >= sign_bit
     }
 
+    /**
+     * @dev Two's complement of an absolute value (the bits of -abs).
+     * Zero maps to zero: a negative result that truncates to 0 (e.g. -1 / 3) must not wrap.
+     */
+    fn negate_abs(abs: U256) -> U256 {
+        if abs.is_zero() {
+            U256::zero()
+        } else {
+            U256::MAX - abs + U256::one()
+        }
+    }
+
     /**
      * @dev Returns the absolute value
      */
@@ -82,19 +93,19 @@ impl I256 {
         let self_abs = self.abs()?;
         let other_abs = other.abs()?;
         
-        let abs_product = self_abs.checked_mul(other_abs).ok_or(ErrorCode::MathOverflow)?;
-        
-        // Check if result fits in int256 range
-        let max_abs = Self::MIN.abs()?;
-        require!(abs_product <= max_abs, ErrorCode::MathOverflow);
+        let abs_product = self_abs.checked_mul(other_abs).ok_or(MathError::MathOverflow)?;
         
         let self_neg = self.is_negative();
         let other_neg = other.is_negative();
         let result_neg = self_neg != other_neg;
+
+        // Check if result fits in int256 range: |MIN| = 2^255 only for negative results
+        let max_abs = if result_neg { Self::MIN.abs()? } else { Self::MAX.value };
+        ensure!(abs_product <= max_abs, MathError::MathOverflow);
         
         if result_neg {
             // Negative result: convert to two's complement
-            let complement = U256::MAX - abs_product + U256::one();
+            let complement = Self::negate_abs(abs_product);
             Ok(I256 { value: complement })
         } else {
             // Positive result
@@ -106,11 +117,11 @@ impl I256 {
      * @dev Checked division, returns Result instead of panicking
      */
     pub fn checked_div(&self, other: &Self) -> Result<Self> {
-        require!(!other.value.is_zero(), ErrorCode::MathOverflow);
+        ensure!(!other.value.is_zero(), MathError::MathOverflow);
         
-        // Handle MIN / -1 case (would overflow)
-        if self.value == Self::MIN.value && other.value == U256::one() && other.is_negative() {
-            return Err(ErrorCode::MathOverflow.into());
+        // Handle MIN / -1 case (would overflow); -1 is all ones in two's complement
+        if self.value == Self::MIN.value && other.value == U256::MAX {
+            return Err(MathError::MathOverflow);
         }
         
         let self_abs = self.abs()?;
@@ -124,7 +135,7 @@ impl I256 {
         
         if result_neg {
             // Negative result: convert to two's complement
-            let complement = U256::MAX - quotient + U256::one();
+            let complement = Self::negate_abs(quotient);
             Ok(I256 { value: complement })
         } else {
             // Positive result
@@ -132,12 +143,141 @@ impl I256 {
         }
     }
 
+    /**
+     * @dev Checked addition, returns Result instead of panicking.
+     * Same overflow rule as `Add`: same-sign operands whose result changes sign.
+     */
+    pub fn checked_add(&self, other: &Self) -> Result<Self> {
+        let result = I256 {
+            value: self.value.overflowing_add(other.value).0,
+        };
+        let self_neg = self.is_negative();
+        ensure!(
+            self_neg != other.is_negative() || result.is_negative() == self_neg,
+            MathError::MathOverflow
+        );
+        Ok(result)
+    }
+
+    /**
+     * @dev Checked subtraction, returns Result instead of panicking.
+     * a - b only overflows when a and b have different signs and the result takes b's sign;
+     * unlike `Sub` this does not negate b, so b == MIN works whenever the result fits.
+     */
+    pub fn checked_sub(&self, other: &Self) -> Result<Self> {
+        let result = I256 {
+            value: self.value.overflowing_sub(other.value).0,
+        };
+        let self_neg = self.is_negative();
+        ensure!(
+            self_neg == other.is_negative() || result.is_negative() == self_neg,
+            MathError::MathOverflow
+        );
+        Ok(result)
+    }
+
+    /**
+     * @dev Checked truncated remainder: the result has the sign of the dividend, like `%`.
+     * Returns an error instead of panicking when dividing by zero.
+     */
+    pub fn checked_rem(&self, other: &Self) -> Result<Self> {
+        ensure!(!other.value.is_zero(), MathError::MathOverflow);
+        let remainder = self.abs()? % other.abs()?;
+        if self.is_negative() {
+            Ok(I256 { value: Self::negate_abs(remainder) })
+        } else {
+            Ok(I256 { value: remainder })
+        }
+    }
+
+    /**
+     * @dev Floor division: rounds the quotient toward negative infinity.
+     * Differs from `/` and `checked_div` (which truncate toward zero) only when the
+     * operands have different signs and the division is inexact: (-7).div_floor(2) == -4
+     * while -7 / 2 == -3.
+     */
+    pub fn div_floor(&self, other: &Self) -> Result<Self> {
+        let quotient = self.checked_div(other)?;
+        let remainder = self.checked_rem(other)?;
+        if !remainder.value.is_zero() && self.is_negative() != other.is_negative() {
+            // |quotient| < 2^255 here, so subtracting one cannot overflow
+            Ok(quotient - I256::from(1i128))
+        } else {
+            Ok(quotient)
+        }
+    }
+
+    /**
+     * @dev Euclidean remainder: always in [0, |other|), unlike `%` which takes the sign of
+     * the dividend. (-7).rem_euclid(2) == 1 and (-7).rem_euclid(-2) == 1.
+     */
+    pub fn rem_euclid(&self, other: &Self) -> Result<Self> {
+        let remainder = self.checked_rem(other)?;
+        if remainder.is_negative() {
+            // remainder + |other| is in (0, |other|); the wrapping add is exact in two's complement
+            // even when other == MIN and |other| == 2^255 is not representable
+            Ok(I256 {
+                value: remainder.value.overflowing_add(other.abs()?).0,
+            })
+        } else {
+            Ok(remainder)
+        }
+    }
+
+    /**
+     * @dev Euclidean division: the quotient q such that self == q * other + self.rem_euclid(other).
+     * Equals div_floor for positive divisors; for negative divisors it rounds toward
+     * positive infinity: (-7).div_euclid(2) == -4, (-7).div_euclid(-2) == 4.
+     */
+    pub fn div_euclid(&self, other: &Self) -> Result<Self> {
+        let quotient = self.checked_div(other)?;
+        let remainder = self.checked_rem(other)?;
+        if !remainder.is_negative() {
+            Ok(quotient)
+        } else if other.is_negative() {
+            Ok(quotient + I256::from(1i128))
+        } else {
+            Ok(quotient - I256::from(1i128))
+        }
+    }
+
+    /**
+     * @dev Checked left shift: multiplies by 2^shift.
+     * Fails when shift >= 256 or when any significant bit (including the sign) is shifted out,
+     * i.e. whenever the result would differ from self * 2^shift.
+     */
+    pub fn checked_shl(&self, shift: u32) -> Result<Self> {
+        ensure!(shift < 256, MathError::MathOverflow);
+        let result = I256 {
+            value: self.value << shift as usize,
+        };
+        ensure!(result.checked_shr(shift)? == *self, MathError::MathOverflow);
+        Ok(result)
+    }
+
+    /**
+     * @dev Checked arithmetic right shift: sign-extends, so negative values round toward
+     * negative infinity like div_floor by 2^shift ((-7) >> 1 == -4). Fails when shift >= 256.
+     */
+    pub fn checked_shr(&self, shift: u32) -> Result<Self> {
+        ensure!(shift < 256, MathError::MathOverflow);
+        let shifted = self.value >> shift as usize;
+        if self.is_negative() {
+            // Fill the vacated high bits with the sign bit
+            Ok(I256 {
+                value: shifted | !(U256::MAX >> shift as usize),
+            })
+        } else {
+            Ok(I256 { value: shifted })
+        }
+    }
+
     /**
      * @dev Checked negation, returns Result instead of panicking
      */
     pub fn checked_neg(&self) -> Result<Self> {
         if self.value == Self::MIN.value {
-            return Err(ErrorCode::MathOverflow.into());
+            return Err(MathError::MathOverflow);
         }
         // Two's complement negation: invert and add 1
         let inverted = !self.value;
@@ -146,11 +286,71 @@ impl I256 {
         })
     }
 
+    /**
+     * @dev Integer power by exponentiation-by-squaring, every multiply is checked.
+     * exp == 0 returns 1 (including 0^0); negative bases keep the sign of exp's parity.
+     */
+    pub fn pow_int(&self, exp: u32) -> Result<Self> {
+        let mut result = I256::from(1i128);
+        let mut base = *self;
+        let mut exp = exp;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.checked_mul(&base)?;
+            }
+            exp >>= 1;
+            // 最后一次平方的结果不会被使用，跳过以免误报溢出
+            if exp > 0 {
+                base = base.checked_mul(&base)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /**
+     * @dev Saturating addition: clamps to MAX / MIN instead of panicking.
+     * Overflow is detected like `Add`: same-sign operands whose result changes sign.
+     */
+    pub fn saturating_add(&self, other: &Self) -> Self {
+        self.checked_add(other)
+            .unwrap_or_else(|_| Self::saturated(self.is_negative()))
+    }
+
+    /**
+     * @dev Saturating subtraction: clamps to MAX / MIN instead of panicking.
+     * a - b only overflows when a and b have different signs and the result takes b's sign.
+     * Works on the raw two's complement values, so b == MIN needs no negation.
+     */
+    pub fn saturating_sub(&self, other: &Self) -> Self {
+        self.checked_sub(other)
+            .unwrap_or_else(|_| Self::saturated(self.is_negative()))
+    }
+
+    /**
+     * @dev Saturating multiplication: clamps to MAX / MIN instead of returning an error.
+     * The overflow direction is the sign of the exact product.
+     */
+    pub fn saturating_mul(&self, other: &Self) -> Self {
+        self.checked_mul(other)
+            .unwrap_or_else(|_| Self::saturated(self.is_negative() != other.is_negative()))
+    }
+
+    /**
+     * @dev The bound an overflowing result is clamped to: MIN for negative, MAX for positive.
+     */
+    fn saturated(negative: bool) -> Self {
+        if negative {
+            Self::MIN
+        } else {
+            Self::MAX
+        }
+    }
+
     /**
      * @dev Converts I256 to U256 (only works for non-negative values)
      */
     pub fn to_u256(&self) -> Result<U256> {
-        require!(!self.is_negative(), ErrorCode::MathOverflow);
+        ensure!(!self.is_negative(), MathError::MathOverflow);
         Ok(self.value)
     }
 }
@@ -161,7 +361,7 @@ impl Default for I256 {
     }
 }
 
-use core::ops::{Add, Sub, Mul, Div, Neg, Rem, AddAssign, SubAssign, MulAssign, DivAssign};
+use core::ops::{Add, Sub, Mul, Div, Neg, Rem, Shl, Shr, AddAssign, SubAssign, MulAssign, DivAssign};
 use core::convert::{From, TryFrom};
 use std::iter::Sum;
 use core::cmp::{PartialOrd, Ord, Ordering};
@@ -176,10 +376,10 @@ impl Neg for I256 {
         if self.value == Self::MIN.value {
             panic!("I256: negation overflow");
         }
-        // Two's complement negation: invert and add 1
+        // Two's complement negation: invert and add 1（0 取反后为 U256::MAX，加 1 回绕为 0）
         let inverted = !self.value;
         I256 {
-            value: inverted + U256::one(),
+            value: inverted.overflowing_add(U256::one()).0,
         }
     }
 }
@@ -233,7 +433,7 @@ impl Add for I256 {
                         let diff = self_abs - other_abs;
                         if self_neg {
                             // 结果为负，转换为补码
-                            U256::MAX - diff + U256::one()
+                            Self::negate_abs(diff)
                         } else {
                             // 结果为正
                             diff
@@ -243,7 +443,7 @@ impl Add for I256 {
                         let diff = other_abs - self_abs;
                         if other_neg {
                             // 结果为负，转换为补码
-                            U256::MAX - diff + U256::one()
+                            Self::negate_abs(diff)
                         } else {
                             // 结果为正
                             diff
@@ -313,7 +513,7 @@ impl Mul for I256 {
         
         if result_neg {
             // Negative result: convert to two's complement
-            let complement = U256::MAX - abs_product + U256::one();
+            let complement = Self::negate_abs(abs_product);
             I256 { value: complement }
         } else {
             // Positive result
@@ -355,7 +555,7 @@ impl Div for I256 {
         
         if result_neg {
             // Negative result: convert to two's complement
-            let complement = U256::MAX - quotient + U256::one();
+            let complement = Self::negate_abs(quotient);
             I256 { value: complement }
         } else {
             // Positive result
@@ -383,7 +583,7 @@ impl Rem for I256 {
         // Remainder has the same sign as the dividend
         if self.is_negative() {
             // Negative result: convert to two's complement
-            let complement = U256::MAX - remainder + U256::one();
+            let complement = Self::negate_abs(remainder);
             I256 { value: complement }
         } else {
             // Positive result
@@ -392,6 +592,30 @@ impl Rem for I256 {
     }
 }
 
+/**
+ * @dev Left shift operator (<<), panics on overflow (see checked_shl)
+ */
+impl Shl<u32> for I256 {
+    type Output = Self;
+
+    fn shl(self, shift: u32) -> Self::Output {
+        self.checked_shl(shift)
+            .unwrap_or_else(|_| panic!("I256: shift left overflow"))
+    }
+}
+
+/**
+ * @dev Arithmetic right shift operator (>>), sign-extending; panics when shift >= 256
+ */
+impl Shr<u32> for I256 {
+    type Output = Self;
+
+    fn shr(self, shift: u32) -> Self::Output {
+        self.checked_shr(shift)
+            .unwrap_or_else(|_| panic!("I256: shift right overflow"))
+    }
+}
+
 /**
  * @dev AddAssign operator (+=)
  */
@@ -476,11 +700,11 @@ impl From<u64> for I256 {
  * @dev From u128 (only works for values <= 2^127 - 1)
  */
 impl TryFrom<u128> for I256 {
-    type Error = anchor_lang::error::Error;
+    type Error = MathError;
 
     fn try_from(value: u128) -> Result<Self> {
         if value > i128::MAX as u128 {
-            return Err(ErrorCode::MathOverflow.into());
+            return Err(MathError::MathOverflow);
         }
         Ok(I256 {
             value: U256::from(value),
@@ -492,12 +716,12 @@ impl TryFrom<u128> for I256 {
  * @dev From U256 (only works for values <= 2^255 - 1)
  */
 impl TryFrom<U256> for I256 {
-    type Error = anchor_lang::error::Error;
+    type Error = MathError;
 
     fn try_from(value: U256) -> Result<Self> {
         // Check if value fits in positive int256 range
         let max_positive = U256([0xFFFFFFFFFFFFFFFF, 0xFFFFFFFFFFFFFFFF, 0xFFFFFFFFFFFFFFFF, 0x7FFFFFFFFFFFFFFF]);
-        require!(value <= max_positive, ErrorCode::MathOverflow);
+        ensure!(value <= max_positive, MathError::MathOverflow);
         Ok(I256 { value })
     }
 }
@@ -506,14 +730,134 @@ impl TryFrom<U256> for I256 {
  * @dev Into U256 (only works for non-negative values)
  */
 impl TryFrom<I256> for U256 {
-    type Error = anchor_lang::error::Error;
+    type Error = MathError;
 
     fn try_from(value: I256) -> Result<Self> {
-        require!(!value.is_negative(), ErrorCode::MathOverflow);
+        ensure!(!value.is_negative(), MathError::MathOverflow);
         Ok(value.value)
     }
 }
 
+/**
+ * @dev Decimal formatting of the signed value (e.g. "-42"), honouring width / sign flags.
+ * Debug still prints the raw two's complement limbs.
+ */
+impl core::fmt::Display for I256 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // abs() only fails for MIN, whose magnitude 2^255 is its own two's complement
+        let abs = self.abs().unwrap_or(Self::MIN.value);
+        f.pad_integral(!self.is_negative(), "", &abs.to_string())
+    }
+}
+
+/**
+ * @dev Parses a signed decimal string ("-42", "+7", "123"), the inverse of Display.
+ * Errors with InvalidNumber on empty input or non-digit characters and with MathOverflow
+ * when the value is outside [MIN, MAX].
+ */
+impl core::str::FromStr for I256 {
+    type Err = MathError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        ensure!(
+            !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()),
+            MathError::InvalidNumber
+        );
+        let abs = U256::from_dec_str(digits).map_err(|_| MathError::MathOverflow)?;
+        if negative {
+            ensure!(abs <= Self::MIN.value, MathError::MathOverflow);
+            Ok(I256 { value: Self::negate_abs(abs) })
+        } else {
+            I256::try_from(abs)
+        }
+    }
+}
+
+/**
+ * @dev Byte conversions: 32 bytes, little-endian two's complement (same layout as Rust's i128::to_le_bytes)
+ */
+impl I256 {
+    pub fn to_le_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, limb) in self.value.0.iter().enumerate() {
+            bytes[i * 8..(i + 1) * 8].copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let mut chunk = [0u8; 8];
+            chunk.copy_from_slice(&bytes[i * 8..(i + 1) * 8]);
+            *limb = u64::from_le_bytes(chunk);
+        }
+        I256 { value: U256(limbs) }
+    }
+}
+
+/**
+ * @dev Borsh serialization as a fixed 32-byte value, so I256 can appear in instruction args and return data
+ */
+#[cfg(feature = "anchor")]
+impl anchor_lang::AnchorSerialize for I256 {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+#[cfg(feature = "anchor")]
+impl anchor_lang::AnchorDeserialize for I256 {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut bytes = [0u8; 32];
+        reader.read_exact(&mut bytes)?;
+        Ok(Self::from_le_bytes(bytes))
+    }
+}
+
+/**
+ * @dev Borsh size for `#[derive(InitSpace)]` accounts that store an I256
+ */
+#[cfg(feature = "anchor")]
+impl anchor_lang::Space for I256 {
+    const INIT_SPACE: usize = 32;
+}
+
+/**
+ * @dev IDL representation: a tuple struct wrapping `[u8; 32]` (little-endian two's complement),
+ * which matches the borsh layout above and lets typed clients decode it
+ */
+#[cfg(feature = "idl-build")]
+impl anchor_lang::IdlBuild for I256 {
+    fn create_type() -> Option<anchor_lang::idl::types::IdlTypeDef> {
+        use anchor_lang::idl::types::*;
+        Some(IdlTypeDef {
+            name: Self::get_full_path(),
+            docs: vec![
+                "256-bit signed integer, little-endian two's complement".into(),
+            ],
+            serialization: IdlSerialization::default(),
+            repr: None,
+            generics: vec![],
+            ty: IdlTypeDefTy::Struct {
+                fields: Some(IdlDefinedFields::Tuple(vec![IdlType::Array(
+                    Box::new(IdlType::U8),
+                    IdlArrayLen::Value(32),
+                )])),
+            },
+        })
+    }
+
+    fn get_full_path() -> String {
+        format!("{}::{}", module_path!(), "I256")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -650,6 +994,18 @@ mod tests {
         assert_eq!(result.abs().unwrap(), U256::from(20u64));
     }
 
+    #[test]
+    fn test_negative_result_truncates_to_zero() {
+        // 负数结果截断为 0 时不能产生 "-0"
+        let neg_one = I256::from(-1i128);
+        let three = I256::from(3i128);
+        assert_eq!(neg_one / three, I256::ZERO);
+        assert_eq!(neg_one.checked_div(&three).unwrap(), I256::ZERO);
+        assert_eq!(I256::ZERO * neg_one, I256::ZERO);
+        assert_eq!(I256::from(-3i128) % three, I256::ZERO);
+        assert_eq!(I256::from(-5i128) + I256::from(5i128), I256::ZERO);
+    }
+
     #[test]
     #[should_panic(expected = "I256: division by zero")]
     fn test_div_by_zero() {
@@ -683,6 +1039,65 @@ mod tests {
         assert_eq!(result.value, U256::from(100u64));
     }
 
+    #[test]
+    fn test_pow_int() {
+        assert_eq!(I256::from(-3i128).pow_int(3).unwrap(), I256::from(-27i128));
+        assert_eq!(I256::from(-3i128).pow_int(4).unwrap(), I256::from(81i128));
+        assert_eq!(I256::from(2i128).pow_int(10).unwrap(), I256::from(1024i128));
+        assert_eq!(I256::from(7i128).pow_int(0).unwrap(), I256::from(1i128));
+        assert_eq!(I256::ZERO.pow_int(0).unwrap(), I256::from(1i128));
+        assert_eq!(I256::ZERO.pow_int(5).unwrap(), I256::ZERO);
+        assert_eq!(I256::from(-1i128).pow_int(u32::MAX).unwrap(), I256::from(-1i128));
+
+        // 2^254 在范围内，2^255 超过 I256::MAX；(-2)^255 恰好为 I256::MIN
+        let two = I256::from(2i128);
+        assert_eq!(two.pow_int(254).unwrap().value, U256::one() << 254);
+        assert_eq!(two.pow_int(255), Err(MathError::MathOverflow));
+        assert_eq!(I256::from(-2i128).pow_int(255).unwrap(), I256::MIN);
+        assert_eq!(I256::from(-2i128).pow_int(256), Err(MathError::MathOverflow));
+        assert_eq!(I256::MAX.pow_int(1).unwrap(), I256::MAX);
+        assert_eq!(I256::MAX.pow_int(2), Err(MathError::MathOverflow));
+    }
+
+    #[test]
+    fn test_saturating_ops() {
+        let one = I256::from(1i128);
+        let minus_one = I256::from(-1i128);
+        assert_eq!(I256::MAX.saturating_add(&one), I256::MAX);
+        assert_eq!(I256::MIN.saturating_sub(&one), I256::MIN);
+        assert_eq!(I256::MIN.saturating_add(&minus_one), I256::MIN);
+        assert_eq!(I256::MAX.saturating_sub(&minus_one), I256::MAX);
+        assert_eq!(I256::MAX.saturating_add(&I256::MAX), I256::MAX);
+        assert_eq!(I256::MIN.saturating_add(&I256::MIN), I256::MIN);
+        // 0 - MIN 超过 MAX，-1 - MIN 恰好为 MAX，MIN - 0 不溢出
+        assert_eq!(I256::ZERO.saturating_sub(&I256::MIN), I256::MAX);
+        assert_eq!(minus_one.saturating_sub(&I256::MIN), I256::MAX);
+        assert_eq!(I256::MIN.saturating_sub(&I256::ZERO), I256::MIN);
+
+        // 不溢出时与普通运算一致
+        let a = I256::from(-17i128);
+        let b = I256::from(5i128);
+        assert_eq!(a.saturating_add(&b), a + b);
+        assert_eq!(a.saturating_sub(&b), a - b);
+        assert_eq!(b.saturating_sub(&a), b - a);
+        assert_eq!(a.saturating_mul(&b), a * b);
+        assert_eq!(I256::MIN.saturating_add(&I256::MAX), minus_one);
+
+        // 乘法按乘积的符号饱和
+        let two = I256::from(2i128);
+        assert_eq!(I256::MAX.saturating_mul(&two), I256::MAX);
+        assert_eq!(I256::MAX.saturating_mul(&-two), I256::MIN);
+        assert_eq!(I256::MIN.saturating_mul(&minus_one), I256::MAX);
+        assert_eq!(I256::MIN.saturating_mul(&I256::MIN), I256::MAX);
+        assert_eq!(I256::MIN.saturating_mul(&one), I256::MIN);
+    }
+
+    #[test]
+    fn test_neg_zero() {
+        assert_eq!(-I256::ZERO, I256::ZERO);
+        assert_eq!(I256::ZERO - I256::ZERO, I256::ZERO);
+    }
+
     #[test]
     #[should_panic(expected = "I256: negation overflow")]
     fn test_neg_overflow() {
@@ -748,6 +1163,120 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_display_and_from_str() {
+        assert_eq!(format!("{}", I256::from(-42i128)), "-42");
+        assert_eq!(format!("{}", I256::ZERO), "0");
+        assert_eq!(format!("{:>5}", I256::from(-7i128)), "   -7");
+        assert_eq!(format!("{:+}", I256::from(7i128)), "+7");
+        assert_eq!("-42".parse::<I256>().unwrap(), I256::from(-42i128));
+        assert_eq!("+7".parse::<I256>().unwrap(), I256::from(7i128));
+
+        // 边界值往返
+        for value in [I256::MIN, I256::MAX, I256::from(-1i128), I256::from(i64::MIN as i128)] {
+            assert_eq!(value.to_string().parse::<I256>().unwrap(), value);
+        }
+        assert_eq!(
+            I256::MIN.to_string(),
+            "-57896044618658097711785492504343953926634992332820282019728792003956564819968"
+        );
+
+        // MAX + 1 和 MIN - 1 溢出
+        let max_plus_one = "57896044618658097711785492504343953926634992332820282019728792003956564819968";
+        assert_eq!(max_plus_one.parse::<I256>(), Err(MathError::MathOverflow));
+        assert_eq!(
+            "-57896044618658097711785492504343953926634992332820282019728792003956564819969".parse::<I256>(),
+            Err(MathError::MathOverflow)
+        );
+        assert_eq!("1".repeat(100).parse::<I256>(), Err(MathError::MathOverflow));
+
+        for invalid in ["", "-", "+", "1.5", "0x10", " 1", "--1", "1e3"] {
+            assert_eq!(invalid.parse::<I256>(), Err(MathError::InvalidNumber), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_checked_add_sub() {
+        let i = |v: i128| I256::from(v);
+        assert_eq!(i(-5).checked_add(&i(3)).unwrap(), i(-2));
+        assert_eq!(i(-5).checked_sub(&i(3)).unwrap(), i(-8));
+        assert_eq!(I256::MAX.checked_add(&I256::MIN).unwrap(), i(-1));
+        // b == MIN 不需要取反，结果可表示时不报错
+        assert_eq!(i(-1).checked_sub(&I256::MIN).unwrap(), I256::MAX);
+
+        assert!(I256::MAX.checked_add(&i(1)).is_err());
+        assert!(I256::MIN.checked_add(&i(-1)).is_err());
+        assert!(I256::MIN.checked_sub(&i(1)).is_err());
+        assert!(I256::MAX.checked_sub(&i(-1)).is_err());
+        assert!(I256::ZERO.checked_sub(&I256::MIN).is_err());
+    }
+
+    #[test]
+    fn test_checked_rem() {
+        let i = |v: i128| I256::from(v);
+        assert_eq!(i(-7).checked_rem(&i(2)).unwrap(), i(-7) % i(2));
+        assert_eq!(i(7).checked_rem(&i(-2)).unwrap(), i(1));
+        assert_eq!(I256::MIN.checked_rem(&i(-1)).unwrap(), I256::ZERO);
+        assert!(i(7).checked_rem(&I256::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_div_floor_and_euclid() {
+        let i = |v: i128| I256::from(v);
+        // `/` 向零截断，div_floor 向负无穷取整
+        assert_eq!(i(-7) / i(2), i(-3));
+        assert_eq!(i(-7).div_floor(&i(2)).unwrap(), i(-4));
+        assert_eq!(i(7).div_floor(&i(-2)).unwrap(), i(-4));
+        assert_eq!(i(-7).div_floor(&i(-2)).unwrap(), i(3));
+        assert_eq!(i(7).div_floor(&i(2)).unwrap(), i(3));
+        assert_eq!(i(-8).div_floor(&i(2)).unwrap(), i(-4));
+        assert_eq!(i(-1).div_floor(&i(3)).unwrap(), i(-1));
+
+        // 欧几里得余数总是非负
+        for (a, b, q, r) in [(-7, 2, -4, 1), (-7, -2, 4, 1), (7, -2, -3, 1), (7, 2, 3, 1), (-6, 3, -2, 0)] {
+            assert_eq!(i(a).div_euclid(&i(b)).unwrap(), i(q));
+            assert_eq!(i(a).rem_euclid(&i(b)).unwrap(), i(r));
+            assert_eq!(i(q) * i(b) + i(r), i(a));
+        }
+        // |MIN| 不能表示为 I256，余数仍然正确
+        assert_eq!(i(-1).rem_euclid(&I256::MIN).unwrap(), I256::MAX);
+        assert_eq!(I256::MIN.div_floor(&i(2)).unwrap() * i(2), I256::MIN);
+
+        assert!(i(1).div_floor(&I256::ZERO).is_err());
+        assert!(i(1).rem_euclid(&I256::ZERO).is_err());
+        assert!(I256::MIN.div_euclid(&i(-1)).is_err());
+    }
+
+    #[test]
+    fn test_shifts() {
+        let i = |v: i128| I256::from(v);
+        assert_eq!(i(-8) >> 1, i(-4));
+        assert_eq!(i(1) << 10, i(1024));
+        assert_eq!(i(-3) << 2, i(-12));
+        // 算术右移向负无穷取整
+        assert_eq!(i(-7) >> 1, i(-4));
+        assert_eq!(i(-7) >> 1, i(-7).div_floor(&i(2)).unwrap());
+        assert_eq!(i(7) >> 1, i(3));
+        assert_eq!(i(-1) >> 255, i(-1));
+        assert_eq!(I256::MAX >> 255, I256::ZERO);
+        assert_eq!(i(5) >> 0, i(5));
+
+        // 1 << 255 会改变符号，-1 << 255 恰好是 MIN
+        assert!(i(1).checked_shl(255).is_err());
+        assert_eq!(i(-1).checked_shl(255).unwrap(), I256::MIN);
+        assert!(I256::MAX.checked_shl(1).is_err());
+        assert!(I256::MIN.checked_shl(1).is_err());
+        assert_eq!(i(1).checked_shl(254).unwrap().checked_shr(254).unwrap(), i(1));
+        assert!(i(1).checked_shl(256).is_err());
+        assert!(i(1).checked_shr(256).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "I256: shift left overflow")]
+    fn test_shl_overflow() {
+        let _ = I256::MAX << 1;
+    }
+
     #[test]
     fn test_checked_neg_success() {
         let a = I256::try_from(U256::from(100u64)).unwrap();
@@ -869,13 +1398,101 @@ mod tests {
         a /= b;
         assert_eq!(a.value, U256::from(20u64));
     }
+
+    #[test]
+    fn test_le_bytes_roundtrip() {
+        for v in [0i128, 1, -1, 123_456_789, -987_654_321_000] {
+            let x = I256::from(v);
+            assert_eq!(I256::from_le_bytes(x.to_le_bytes()), x);
+            // 低 16 字节与 i128 的小端表示一致，高位为符号扩展
+            let bytes = x.to_le_bytes();
+            assert_eq!(&bytes[..16], &v.to_le_bytes());
+            let ext = if v < 0 { 0xffu8 } else { 0u8 };
+            assert!(bytes[16..].iter().all(|&b| b == ext));
+        }
+        assert_eq!(I256::from_le_bytes(I256::MAX.to_le_bytes()), I256::MAX);
+        assert_eq!(I256::from_le_bytes(I256::MIN.to_le_bytes()), I256::MIN);
+    }
+
+    #[cfg(feature = "anchor")]
+    #[test]
+    fn test_borsh_roundtrip() {
+        use anchor_lang::prelude::*;
+
+        let value: I256 = (-123456789i128).into();
+        let data = value.try_to_vec().unwrap();
+        assert_eq!(data.len(), <I256 as anchor_lang::Space>::INIT_SPACE);
+        assert_eq!(data, value.to_le_bytes().to_vec());
+        assert_eq!(I256::try_from_slice(&data).unwrap(), value);
+
+        // 可以作为 InitSpace 账户的字段
+        #[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+        struct Accumulator {
+            last_ts: i64,
+            accumulator: I256,
+        }
+        assert_eq!(Accumulator::INIT_SPACE, 8 + 32);
+    }
+
+    #[cfg(feature = "anchor")]
+    #[test]
+    fn test_return_data_roundtrip() {
+        use anchor_lang::prelude::*;
+
+        // 模拟指令返回数据：程序端 borsh 序列化，客户端按 IDL 布局反序列化
+        #[derive(AnchorSerialize, AnchorDeserialize, Debug, PartialEq)]
+        struct InvariantReturn {
+            token_count: u16,
+            invariant: I256,
+            delta: I256,
+        }
+
+        let ret = InvariantReturn {
+            token_count: 3,
+            invariant: I256::from(42_000_000_000_000_000_000i128),
+            delta: I256::from(-5i128),
+        };
+        let data = ret.try_to_vec().unwrap();
+        assert_eq!(data.len(), 2 + 32 + 32);
+        // delta 为 32 字节小端补码
+        let mut expected_delta = [0xffu8; 32];
+        expected_delta[0] = 0xfb;
+        assert_eq!(&data[34..], &expected_delta);
+
+        let decoded = InvariantReturn::try_from_slice(&data).unwrap();
+        assert_eq!(decoded, ret);
+
+        // 长度不足时反序列化失败
+        assert!(InvariantReturn::try_from_slice(&data[..40]).is_err());
+    }
+
+    #[cfg(feature = "idl-build")]
+    #[test]
+    fn test_idl_type() {
+        use anchor_lang::idl::types::*;
+        use anchor_lang::IdlBuild;
+
+        let ty = I256::create_type().unwrap();
+        assert!(ty.name.ends_with("I256"));
+        match ty.ty {
+            IdlTypeDefTy::Struct {
+                fields: Some(IdlDefinedFields::Tuple(fields)),
+            } => {
+                assert_eq!(
+                    fields,
+                    vec![IdlType::Array(Box::new(IdlType::U8), IdlArrayLen::Value(32))]
+                );
+            }
+            _ => panic!("I256 应表示为 [u8; 32] 的元组结构"),
+        }
+    }
 }
 
 /**
  * @dev Into i128 (may lose precision for values outside i128 range)
  */
 impl TryFrom<I256> for i128 {
-    type Error = anchor_lang::error::Error;
+    type Error = MathError;
 
     fn try_from(value: I256) -> Result<Self> {
         if value.is_negative() {
@@ -884,22 +1501,22 @@ impl TryFrom<I256> for i128 {
             let abs = inverted + U256::one();
             // Check if abs fits in u128
             if abs > U256::from(u128::MAX) {
-                return Err(ErrorCode::MathOverflow.into());
+                return Err(MathError::MathOverflow);
             }
             // Convert to u128 then to i128
-            let abs_u128 = u128::try_from(abs).map_err(|_| ErrorCode::MathOverflow)?;
+            let abs_u128 = u128::try_from(abs).map_err(|_| MathError::MathOverflow)?;
             if abs_u128 > (i128::MAX as u128) + 1 {
-                return Err(ErrorCode::MathOverflow.into());
+                return Err(MathError::MathOverflow);
             }
             Ok(-(abs_u128 as i128))
         } else {
             // Check if value fits in u128
             if value.value > U256::from(u128::MAX) {
-                return Err(ErrorCode::MathOverflow.into());
+                return Err(MathError::MathOverflow);
             }
-            let low = u128::try_from(value.value).map_err(|_| ErrorCode::MathOverflow)?;
+            let low = u128::try_from(value.value).map_err(|_| MathError::MathOverflow)?;
             if low > i128::MAX as u128 {
-                return Err(ErrorCode::MathOverflow.into());
+                return Err(MathError::MathOverflow);
             }
             Ok(low as i128)
         }
@@ -12,14 +12,44 @@
 // COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
 // OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
-use anchor_lang::prelude::*;
 use primitive_types::U256;
 
 use super::i256::I256;
-use crate::error::ErrorCode;
+use super::error::{ensure, MathError, Result};
 
 /* solhint-disable */
 
+/// 测试用：统计 ln / exp 的调用次数（每个测试线程独立计数）
+#[cfg(test)]
+#[cfg_attr(not(feature = "anchor"), allow(dead_code))] // reset / get 只在 state 的测试中使用
+pub(crate) mod op_counter {
+    use std::cell::Cell;
+
+    thread_local! {
+        static LN_CALLS: Cell<usize> = const { Cell::new(0) };
+        static EXP_CALLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    pub fn record_ln() {
+        LN_CALLS.with(|c| c.set(c.get() + 1));
+    }
+
+    pub fn record_exp() {
+        EXP_CALLS.with(|c| c.set(c.get() + 1));
+    }
+
+    /// 清零计数
+    pub fn reset() {
+        LN_CALLS.with(|c| c.set(0));
+        EXP_CALLS.with(|c| c.set(0));
+    }
+
+    /// 返回 (ln_calls, exp_calls)
+    pub fn get() -> (usize, usize) {
+        (LN_CALLS.with(|c| c.get()), EXP_CALLS.with(|c| c.get()))
+    }
+}
+
 /**
  * @dev Exponentiation and logarithm functions for 18 decimal fixed point numbers (both base and exponent/argument).
  *
@@ -185,6 +215,11 @@ pub const A11: I256 = I256 {
     value: U256([0xc548670b9510e7ac, 0x5, 0, 0]),
 }; // e^(x11)
 
+// LN_2 = ln(2) * 1e18 = 693_147_180_559_945_309, used by the binary-base log2 / exp2
+pub const LN_2: I256 = I256 {
+    value: U256([0x099e8db03256ce5d, 0, 0, 0]),
+};
+
 impl LogExpMath {
     /**
      * @dev Exponentiation (x^y) with unsigned 18 decimal fixed point base and exponent.
@@ -216,7 +251,7 @@ impl LogExpMath {
         // MILD_EXPONENT_BOUND = 2^254 / ONE_20
         let one_20_u256 = ONE_20.to_u256()?;
         let mild_exponent_bound = U256::from(2u64).pow(U256::from(254u64)) / one_20_u256;
-        require!(y < mild_exponent_bound, ErrorCode::MathOverflow);
+        ensure!(y < mild_exponent_bound, MathError::MathOverflow);
         let y_int256 = I256::try_from(y)?;
 
         let logx_times_y;
@@ -236,24 +271,86 @@ impl LogExpMath {
         let logx_times_y = logx_times_y / ONE_18;
 
         // Finally, we compute exp(y * ln(x)) to arrive at x^y
-        require!(
+        ensure!(
             MIN_NATURAL_EXPONENT <= logx_times_y && logx_times_y <= MAX_NATURAL_EXPONENT,
-            ErrorCode::MathOverflow
+            MathError::MathOverflow
         );
 
         let exp_result = Self::exp(logx_times_y)?;
         Ok(exp_result.to_u256()?)
     }
 
+    /**
+     * @dev Computes x^y for every y in `ys`, sharing a single ln(x) across all exponents.
+     *
+     * Each element is computed exactly as `pow(x, ys[i])` would, but ln(x) (ln_36 or ln_internal, depending on the
+     * range of x) is only evaluated once, leaving one multiplication and one exp per exponent.
+     */
+    pub fn pow_many(x: U256, ys: &[U256]) -> Result<Vec<U256>> {
+        let one_18 = ONE_18.to_u256()?;
+        if x.is_zero() {
+            return Ok(ys
+                .iter()
+                .map(|y| if y.is_zero() { one_18 } else { U256::zero() })
+                .collect());
+        }
+
+        let x_int256 = I256::try_from(x)?;
+        let one_20_u256 = ONE_20.to_u256()?;
+        let mild_exponent_bound = U256::from(2u64).pow(U256::from(254u64)) / one_20_u256;
+
+        // ln(x) is computed once: either split into its high and low 18 decimals (ln_36), or as a plain 18 decimal
+        // value (ln_internal). See `pow` for why the ln_36 result is multiplied in two halves.
+        enum LnX {
+            Ln36 { high: I256, low: I256 },
+            Ln18(I256),
+        }
+        let ln_x = if LN_36_LOWER_BOUND < x_int256 && x_int256 < LN_36_UPPER_BOUND {
+            let ln_36_x = Self::ln_36(x_int256)?;
+            LnX::Ln36 {
+                high: ln_36_x / ONE_18,
+                low: ln_36_x % ONE_18,
+            }
+        } else {
+            LnX::Ln18(Self::ln_internal(x_int256)?)
+        };
+
+        let mut results = Vec::with_capacity(ys.len());
+        for &y in ys.iter() {
+            if y.is_zero() {
+                results.push(one_18);
+                continue;
+            }
+            ensure!(y < mild_exponent_bound, MathError::MathOverflow);
+            let y_int256 = I256::try_from(y)?;
+
+            let logx_times_y = match ln_x {
+                LnX::Ln36 { high, low } => (high * y_int256) + ((low * y_int256) / ONE_18),
+                LnX::Ln18(ln_x) => ln_x * y_int256,
+            };
+            let logx_times_y = logx_times_y / ONE_18;
+
+            ensure!(
+                MIN_NATURAL_EXPONENT <= logx_times_y && logx_times_y <= MAX_NATURAL_EXPONENT,
+                MathError::MathOverflow
+            );
+            results.push(Self::exp(logx_times_y)?.to_u256()?);
+        }
+        Ok(results)
+    }
+
     /**
      * @dev Natural exponentiation (e^x) with signed 18 decimal fixed point exponent.
      *
      * Reverts if `x` is smaller than MIN_NATURAL_EXPONENT, or larger than `MAX_NATURAL_EXPONENT`.
      */
     pub fn exp(x: I256) -> Result<I256> {
-        require!(
+        #[cfg(test)]
+        op_counter::record_exp();
+
+        ensure!(
             x >= MIN_NATURAL_EXPONENT && x <= MAX_NATURAL_EXPONENT,
-            ErrorCode::MathOverflow
+            MathError::MathOverflow
         );
 
         const ZERO: I256 = I256 {
@@ -464,15 +561,39 @@ impl LogExpMath {
         Ok((log_arg * ONE_18) / log_base)
     }
 
+    /**
+     * @dev Binary logarithm (log2(arg)) with signed 18 decimal fixed point argument: ln(arg) / ln(2).
+     */
+    pub fn log2(arg: I256) -> Result<I256> {
+        Ok((Self::ln(arg)? * ONE_18) / LN_2)
+    }
+
+    /**
+     * @dev Binary exponentiation (2^x) with signed 18 decimal fixed point exponent: exp(x * ln(2)).
+     *
+     * Reverts if x * ln(2) is smaller than MIN_NATURAL_EXPONENT, or larger than MAX_NATURAL_EXPONENT.
+     */
+    pub fn exp2(x: I256) -> Result<I256> {
+        let exponent = x.checked_mul(&LN_2)? / ONE_18;
+        ensure!(
+            exponent >= MIN_NATURAL_EXPONENT && exponent <= MAX_NATURAL_EXPONENT,
+            MathError::MathOverflow
+        );
+        Self::exp(exponent)
+    }
+
     /**
      * @dev Natural logarithm (ln(a)) with signed 18 decimal fixed point argument.
      */
     pub fn ln(a: I256) -> Result<I256> {
+        #[cfg(test)]
+        op_counter::record_ln();
+
         // The real natural logarithm is not defined for negative numbers or zero.
         const ZERO: I256 = I256 {
             value: U256([0, 0, 0, 0]),
         };
-        require!(a > ZERO, ErrorCode::MathOverflow);
+        ensure!(a > ZERO, MathError::MathOverflow);
         if LN_36_LOWER_BOUND < a && a < LN_36_UPPER_BOUND {
             Ok(Self::ln_36(a)? / ONE_18)
         } else {
@@ -731,6 +852,7 @@ impl LogExpMath {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::math::fixedpoint::FixedPoint;
     use primitive_types::U256;
 
     #[test]
@@ -814,15 +936,45 @@ mod tests {
         let exp = U256::from(2_000_000_000_000_000_000u64); // 2e18
         let result = LogExpMath::pow(base, exp).unwrap();
         let expected = U256::from(4_000_000_000_000_000_000u64); // 4e18
-        let diff = if result > expected {
-            result - expected
-        } else {
-            expected - result
-        };
+        let diff = FixedPoint::abs_diff(result, expected);
         // Allow 1% error
         assert!(diff < expected / U256::from(100u64));
     }
 
+    #[test]
+    fn test_pow_many_matches_pow() {
+        let exps = [
+            U256::zero(),
+            U256::from(1u64),
+            U256::from(200_000_000_000_000_000u64), // 0.2
+            U256::from(500_000_000_000_000_000u64), // 0.5
+            U256::from(1_000_000_000_000_000_000u64), // 1
+            U256::from(2_500_000_000_000_000_000u64), // 2.5
+        ];
+        let bases = [
+            U256::from(1_000_000_000_000_000u64),         // 0.001，ln_internal
+            U256::from(1_050_000_000_000_000_000u64),     // 1.05，ln_36
+            U256::from(3_000_000_000_000_000_000u64),     // 3
+            U256::from(1_000_000_000_000_000_000u64) * U256::from(1_000_000_000u64), // 1e9
+        ];
+        for &base in bases.iter() {
+            let results = LogExpMath::pow_many(base, &exps).unwrap();
+            assert_eq!(results.len(), exps.len());
+            for (&exp, &result) in exps.iter().zip(results.iter()) {
+                let expected = LogExpMath::pow(base, exp).unwrap();
+                assert!(FixedPoint::abs_diff(result, expected) <= U256::one(), "pow_many({}, {}) = {}, pow = {}", base, exp, result, expected);
+            }
+        }
+
+        // 0 的正数次幂为 0，0^0 为 1
+        let results = LogExpMath::pow_many(U256::zero(), &exps[..2]).unwrap();
+        assert_eq!(results, vec![ONE_18.to_u256().unwrap(), U256::zero()]);
+        // 任一指数超出 exp 的定义域时整体失败
+        let too_large = U256::from(1_000u64) * U256::from(1_000_000_000_000_000_000u64);
+        assert!(LogExpMath::pow_many(U256::from(3_000_000_000_000_000_000u64), &[exps[4], too_large]).is_err());
+        assert!(LogExpMath::pow_many(U256::from(3_000_000_000_000_000_000u64), &[]).unwrap().is_empty());
+    }
+
     #[test]
     fn test_exp_negative() {
         // Test exp(-1e18) should be close to 1/e ≈ 0.367879
@@ -934,11 +1086,7 @@ mod tests {
         let exp = U256::from(500_000_000_000_000_000u64); // 0.5e18
         let result = LogExpMath::pow(base, exp).unwrap();
         let expected = U256::from(2_000_000_000_000_000_000u64); // 2e18
-        let diff = if result > expected {
-            result - expected
-        } else {
-            expected - result
-        };
+        let diff = FixedPoint::abs_diff(result, expected);
         // Allow 1% error
         assert!(diff < expected / U256::from(100u64));
     }
@@ -974,4 +1122,30 @@ mod tests {
         let too_small = MIN_NATURAL_EXPONENT - ONE_18;
         let _ = LogExpMath::exp(too_small).unwrap();
     }
+
+    #[test]
+    fn test_log2_exp2() {
+        let one = I256::from(1_000_000_000_000_000_000i128);
+        let within_tenth_percent = |result: I256, expected: I256| {
+            let diff = if result > expected { result - expected } else { expected - result };
+            diff.value * U256::from(1000u64) <= expected.abs().unwrap()
+        };
+
+        let log = LogExpMath::log2(I256::from(8i128) * one).unwrap();
+        assert!(within_tenth_percent(log, I256::from(3i128) * one), "log2(8) = {:?}", log);
+        let exp = LogExpMath::exp2(I256::from(3i128) * one).unwrap();
+        assert!(within_tenth_percent(exp, I256::from(8i128) * one), "exp2(3) = {:?}", exp);
+
+        // 小于 1 的参数得到负的 log2，exp2 为其逆运算
+        let log = LogExpMath::log2(one / I256::from(4i128)).unwrap();
+        assert!(within_tenth_percent(log, I256::from(-2i128) * one));
+        let exp = LogExpMath::exp2(I256::from(-2i128) * one).unwrap();
+        assert!(within_tenth_percent(exp, one / I256::from(4i128)));
+
+        // 2^x 的指数按 ln(2) 缩放后超出 exp 的定义域：130 / ln(2) ≈ 187.5
+        assert!(LogExpMath::exp2(I256::from(187i128) * one).is_ok());
+        assert!(LogExpMath::exp2(I256::from(188i128) * one).is_err());
+        assert!(LogExpMath::exp2(I256::MAX).is_err());
+        assert!(LogExpMath::log2(I256::ZERO).is_err());
+    }
 }
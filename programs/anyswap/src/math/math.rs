@@ -1,9 +1,8 @@
 // SPDX-License-Identifier: MIT
 
-use anchor_lang::prelude::*;
 use primitive_types::U256;
 
-use crate::error::ErrorCode;
+use super::error::{ensure, MathError, Result};
 
 /**
  * @dev Wrappers over Solidity's arithmetic operations with added overflow checks.
@@ -32,8 +31,8 @@ impl Math {
      * @dev Returns the addition of two unsigned integers of 256 bits, reverting on overflow.
      */
     pub fn add(a: U256, b: U256) -> Result<U256> {
-        let c = a.checked_add(b).ok_or(ErrorCode::MathOverflow)?;
-        require!(c >= a, ErrorCode::MathOverflow);
+        let c = a.checked_add(b).ok_or(MathError::MathOverflow)?;
+        ensure!(c >= a, MathError::MathOverflow);
         Ok(c)
     }
 
@@ -41,10 +40,10 @@ impl Math {
      * @dev Returns the addition of two signed integers, reverting on overflow.
      */
     pub fn add_signed(a: i128, b: i128) -> Result<i128> {
-        let c = a.checked_add(b).ok_or(ErrorCode::MathOverflow)?;
-        require!(
+        let c = a.checked_add(b).ok_or(MathError::MathOverflow)?;
+        ensure!(
             (b >= 0 && c >= a) || (b < 0 && c < a),
-            ErrorCode::MathOverflow
+            MathError::MathOverflow
         );
         Ok(c)
     }
@@ -53,8 +52,8 @@ impl Math {
      * @dev Returns the subtraction of two unsigned integers of 256 bits, reverting on overflow.
      */
     pub fn sub(a: U256, b: U256) -> Result<U256> {
-        require!(b <= a, ErrorCode::MathOverflow);
-        let c = a.checked_sub(b).ok_or(ErrorCode::MathOverflow)?;
+        ensure!(b <= a, MathError::MathOverflow);
+        let c = a.checked_sub(b).ok_or(MathError::MathOverflow)?;
         Ok(c)
     }
 
@@ -62,10 +61,10 @@ impl Math {
      * @dev Returns the subtraction of two signed integers, reverting on overflow.
      */
     pub fn sub_signed(a: i128, b: i128) -> Result<i128> {
-        let c = a.checked_sub(b).ok_or(ErrorCode::MathOverflow)?;
-        require!(
+        let c = a.checked_sub(b).ok_or(MathError::MathOverflow)?;
+        ensure!(
             (b >= 0 && c <= a) || (b < 0 && c > a),
-            ErrorCode::MathOverflow
+            MathError::MathOverflow
         );
         Ok(c)
     }
@@ -88,8 +87,8 @@ impl Math {
     }
 
     pub fn mul(a: U256, b: U256) -> Result<U256> {
-        let c = a.checked_mul(b).ok_or(ErrorCode::MathOverflow)?;
-        require!(a.is_zero() || c.checked_div(a).map(|d| d == b).unwrap_or(false), ErrorCode::MathOverflow);
+        let c = a.checked_mul(b).ok_or(MathError::MathOverflow)?;
+        ensure!(a.is_zero() || c.checked_div(a).map(|d| d == b).unwrap_or(false), MathError::MathOverflow);
         Ok(c)
     }
 
@@ -102,21 +101,21 @@ impl Math {
     }
 
     pub fn div_down(a: U256, b: U256) -> Result<U256> {
-        require!(!b.is_zero(), ErrorCode::MathOverflow);
+        ensure!(!b.is_zero(), MathError::MathOverflow);
         Ok(a / b)
     }
 
     pub fn div_up(a: U256, b: U256) -> Result<U256> {
-        require!(!b.is_zero(), ErrorCode::MathOverflow);
+        ensure!(!b.is_zero(), MathError::MathOverflow);
 
         // Equivalent to:
         // result = a == 0 ? 0 : 1 + (a - 1) / b;
         if a.is_zero() {
             Ok(U256::zero())
         } else {
-            let a_minus_one = a.checked_sub(U256::one()).ok_or(ErrorCode::MathOverflow)?;
+            let a_minus_one = a.checked_sub(U256::one()).ok_or(MathError::MathOverflow)?;
             let div_result = a_minus_one / b;
-            Ok(div_result.checked_add(U256::one()).ok_or(ErrorCode::MathOverflow)?)
+            Ok(div_result.checked_add(U256::one()).ok_or(MathError::MathOverflow)?)
         }
     }
 }
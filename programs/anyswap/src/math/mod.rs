@@ -1,8 +1,13 @@
+pub mod error;
 pub mod math;
 pub mod fixedpoint;
 pub mod logexpmath;
 // pub mod logexpmath2;
 pub mod i256;
+pub mod stable;
+pub mod scale;
+#[cfg(test)]
+pub mod reference;
 
 // 运行测试: cargo test --manifest-path programs/anyswap/Cargo.toml test_three_token_swap --lib
 #[cfg(test)]
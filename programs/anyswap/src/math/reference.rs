@@ -0,0 +1,171 @@
+//! 加权 pool swap 的高精度参考实现（仅用于差分测试）
+//!
+//! 使用 num-bigint 的大整数做有理数运算，两 token swap 的输出为：
+//! amount_out = vault_out * (1 - (vault_in / (vault_in + amount_in)) ^ (weight_in / weight_out))
+//!
+//! 指数 weight_in / weight_out 约分为 p / q 后，分数次幂通过整数 q 次方根精确计算（向下取整到 1 / SCALE）
+
+use num_bigint::BigUint;
+
+/// 参考实现的定点精度（10^36）
+fn scale() -> BigUint {
+    BigUint::from(10u32).pow(36)
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// 两 token swap 的参考输出
+///
+/// 手续费与 swap_inner 一致：fee = floor(amount_in * fee_numerator / fee_denominator)
+///
+/// Returns:
+///     (扣费后的实际输入, 输出数量向下取整)
+pub fn reference_swap_out(
+    vault_in: u64,
+    vault_out: u64,
+    weight_in: u64,
+    weight_out: u64,
+    amount_in: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> (u64, u64) {
+    let fee = (amount_in as u128 * fee_numerator as u128 / fee_denominator as u128) as u64;
+    let amount_in_after_fee = amount_in - fee;
+
+    let g = gcd(weight_in, weight_out);
+    let p = (weight_in / g) as u32;
+    let q = (weight_out / g) as u32;
+
+    let s = scale();
+    // ratio^(p/q) * SCALE = (SCALE^q * vault_in^p / (vault_in + amount_in)^p)^(1/q)
+    let numerator = s.pow(q) * BigUint::from(vault_in).pow(p);
+    let denominator = BigUint::from(vault_in as u128 + amount_in_after_fee as u128).pow(p);
+    let power_scaled = (numerator / denominator).nth_root(q);
+
+    // power_scaled 向下取整，输出 vault_out * (SCALE - power_scaled) / SCALE 最多偏大 vault_out / SCALE
+    let out_scaled = BigUint::from(vault_out) * (&s - power_scaled);
+    let out = out_scaled / &s;
+    let out: u64 = out.try_into().unwrap();
+    (amount_in_after_fee, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "anchor")]
+    use crate::state::swap::swap_inner;
+
+    // 与 swap_inner 的差分测试依赖 state 模块，关闭 anchor feature 时只运行参考实现本身的测试
+
+    /// 差分测试允许的误差：执行结果最多比参考值少 1 个单位（新储备向上取整）再加 vault_out 的 1e-12，
+    /// 且不能比参考值多
+    #[cfg(feature = "anchor")]
+    const MAX_UNDER_UNITS: u64 = 1;
+    #[cfg(feature = "anchor")]
+    const MAX_UNDER_RELATIVE: f64 = 1e-12;
+
+    /// xorshift64，固定种子保证用例可复现
+    #[cfg(feature = "anchor")]
+    struct Rng(u64);
+
+    #[cfg(feature = "anchor")]
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            let mut x = self.0;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            self.0 = x;
+            x
+        }
+
+        fn range(&mut self, low: u64, high: u64) -> u64 {
+            low + self.next() % (high - low + 1)
+        }
+
+        /// 数量级均匀分布的随机数
+        fn log_range(&mut self, low_exp: u32, high_exp: u32) -> u64 {
+            let exp = self.range(low_exp as u64, high_exp as u64) as u32;
+            let base = 10u64.pow(exp);
+            self.range(base, base.saturating_mul(10) - 1)
+        }
+    }
+
+    #[test]
+    fn test_reference_known_value() {
+        // 50/50 pool：out = 1000 * (1 - 1000 / 1100) = 90.909...
+        assert_eq!(reference_swap_out(1_000, 1_000, 1, 1, 100, 0, 1), (100, 90));
+        // 80/20 pool：out = 1e9 * (1 - (1e9 / 1.1e9)^4) = 316_986_544.6...
+        assert_eq!(
+            reference_swap_out(1_000_000_000, 1_000_000_000, 80, 20, 100_000_000, 0, 1),
+            (100_000_000, 316_986_544)
+        );
+    }
+
+    #[cfg(feature = "anchor")]
+    #[test]
+    fn test_swap_matches_reference() {
+        let mut rng = Rng(0x5eed_1234_abcd_9876);
+        let mut max_under = 0u64;
+        let mut failures = Vec::new();
+
+        for case in 0..2_000 {
+            let vault_in = rng.log_range(3, 12);
+            let vault_out = rng.log_range(3, 12);
+            let weight_in = rng.range(1, 60);
+            let weight_out = rng.range(1, 60);
+            // 输入不超过储备的一半
+            let amount_in = rng.range(1, vault_in * 5);
+            let fee_denominator = 10_000;
+            let fee_numerator = rng.range(0, 100);
+
+            let (expected_in, expected_out) = reference_swap_out(
+                vault_in,
+                vault_out,
+                weight_in,
+                weight_out,
+                amount_in,
+                fee_numerator,
+                fee_denominator,
+            );
+            let result = match swap_inner(
+                &[true, false],
+                &[amount_in, 0],
+                &[amount_in, 0],
+                &[vault_in, vault_out],
+                &[weight_in, weight_out],
+                fee_numerator,
+                fee_denominator,
+            ) {
+                Ok(result) => result,
+                Err(err) => {
+                    failures.push(format!("case {}: swap 失败 {:?}", case, err));
+                    continue;
+                }
+            };
+            assert_eq!(result.amounts[0], expected_in);
+
+            let executed = result.amounts[1];
+            let allowed_under =
+                MAX_UNDER_UNITS + (vault_out as f64 * MAX_UNDER_RELATIVE) as u64;
+            if executed > expected_out || expected_out - executed > allowed_under {
+                failures.push(format!(
+                    "case {}: vaults=({}, {}) weights=({}, {}) amount_in={} fee={}/{} executed={} reference={}",
+                    case, vault_in, vault_out, weight_in, weight_out, amount_in,
+                    fee_numerator, fee_denominator, executed, expected_out
+                ));
+            } else {
+                max_under = max_under.max(expected_out - executed);
+            }
+        }
+
+        println!("最大偏差（低于参考值）: {}", max_under);
+        assert!(failures.is_empty(), "{} 个用例偏差超出容差:\n{}", failures.len(), failures.join("\n"));
+    }
+}
@@ -0,0 +1,92 @@
+use primitive_types::U256;
+
+use super::error::{ensure, MathError, Result};
+
+/// 1e18：18 位定点数的 1（与 FixedPoint::ONE、LogExpMath 的 ONE_18 相同）
+pub const E18: U256 = U256([1_000_000_000_000_000_000, 0, 0, 0]);
+/// 1e20：LogExpMath 内部 20 位精度的 1
+pub const E20: U256 = U256([0x6bc75e2d63100000, 0x5, 0, 0]);
+/// 1e36：LogExpMath 中 ln_36 使用的 36 位精度的 1
+pub const E36: U256 = U256([0xb34b9f1000000000, 0x00c097ce7bc90715, 0, 0]);
+
+/// U256 能表示的 10 的最大次幂：10^77 < 2^256 < 10^78
+pub const MAX_POW10_EXPONENT: u32 = 77;
+
+/// 10^exp，exp 超过 MAX_POW10_EXPONENT 时返回 MathOverflow
+pub fn pow10(exp: u32) -> Result<U256> {
+    ensure!(exp <= MAX_POW10_EXPONENT, MathError::MathOverflow);
+    Ok(U256::from(10u64).pow(U256::from(exp)))
+}
+
+/// 将 from_decimals 位精度的 value 放大到 to_decimals 位精度（to_decimals >= from_decimals）
+/// 精度相同时原样返回；乘法溢出时返回 MathOverflow
+pub fn scale_up(value: U256, from_decimals: u32, to_decimals: u32) -> Result<U256> {
+    ensure!(to_decimals >= from_decimals, MathError::MathOverflow);
+    let factor = pow10(to_decimals - from_decimals)?;
+    value.checked_mul(factor).ok_or(MathError::MathOverflow)
+}
+
+/// 将 from_decimals 位精度的 value 缩小到 to_decimals 位精度（from_decimals >= to_decimals），向下取整
+/// 精度相同时原样返回；差值超过 MAX_POW10_EXPONENT 时结果必为 0
+pub fn scale_down(value: U256, from_decimals: u32, to_decimals: u32) -> Result<U256> {
+    ensure!(from_decimals >= to_decimals, MathError::MathOverflow);
+    let diff = from_decimals - to_decimals;
+    if diff > MAX_POW10_EXPONENT {
+        return Ok(U256::zero());
+    }
+    Ok(value / pow10(diff)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::fixedpoint::FixedPoint;
+    use crate::math::logexpmath::{ONE_18, ONE_20, ONE_36};
+
+    #[test]
+    fn test_constants_match_existing() {
+        assert_eq!(E18, FixedPoint::ONE);
+        assert_eq!(E18, ONE_18.as_raw());
+        assert_eq!(E20, ONE_20.as_raw());
+        assert_eq!(E36, ONE_36.as_raw());
+        assert_eq!(E18, pow10(18).unwrap());
+        assert_eq!(E20, pow10(20).unwrap());
+        assert_eq!(E36, pow10(36).unwrap());
+    }
+
+    #[test]
+    fn test_pow10() {
+        assert_eq!(pow10(0).unwrap(), U256::one());
+        assert_eq!(pow10(9).unwrap(), U256::from(1_000_000_000u64));
+        assert!(pow10(MAX_POW10_EXPONENT).is_ok());
+        assert_eq!(pow10(MAX_POW10_EXPONENT + 1), Err(MathError::MathOverflow));
+    }
+
+    #[test]
+    fn test_scale_up_and_down() {
+        let value = U256::from(123_456_789u64);
+        // 精度相同时不变
+        for decimals in [0u32, 6, 9, 18, 36] {
+            assert_eq!(scale_up(value, decimals, decimals).unwrap(), value);
+            assert_eq!(scale_down(value, decimals, decimals).unwrap(), value);
+        }
+
+        // 放大后再缩小回到原值
+        for (from, to) in [(0u32, 18u32), (6, 18), (9, 18), (6, 9), (18, 36), (0, 60)] {
+            let up = scale_up(value, from, to).unwrap();
+            assert_eq!(up, value * pow10(to - from).unwrap());
+            assert_eq!(scale_down(up, to, from).unwrap(), value);
+        }
+
+        // 缩小时向下取整
+        assert_eq!(scale_down(U256::from(1_999_999u64), 6, 0).unwrap(), U256::one());
+        assert_eq!(scale_down(U256::from(999_999u64), 6, 0).unwrap(), U256::zero());
+        assert_eq!(scale_down(U256::MAX, 78, 0).unwrap(), U256::zero());
+
+        // 方向错误或溢出
+        assert!(scale_up(value, 18, 6).is_err());
+        assert!(scale_down(value, 6, 18).is_err());
+        assert!(scale_up(U256::MAX, 0, 1).is_err());
+        assert!(scale_up(value, 0, 78).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! StableSwap（Curve）不变量，用于 pool 中的稳定币组
+//!
+//! 不变量 D 满足：A * n^n * sum(x_i) + D = A * n^n * D + D^(n+1) / (n^n * prod(x_i))
+//!
+//! A 越大曲线越平，余额接近平衡时价格接近 1:1；A 趋于 0 时退化为恒定乘积。
+//! D 和交换后的余额 y 都用牛顿迭代求解，全部使用 U256 整数运算
+
+use primitive_types::U256;
+
+use super::error::{ensure, MathError, Result};
+
+/// 放大系数 A 的最小值
+pub const MIN_AMPLIFICATION: u64 = 1;
+/// 放大系数 A 的最大值
+pub const MAX_AMPLIFICATION: u64 = 5_000;
+/// 牛顿迭代的最大次数
+const MAX_ITERATIONS: usize = 255;
+
+fn mul(a: U256, b: U256) -> Result<U256> {
+    a.checked_mul(b).ok_or(MathError::MathOverflow)
+}
+
+fn div(a: U256, b: U256) -> Result<U256> {
+    ensure!(!b.is_zero(), MathError::MathOverflow);
+    Ok(a / b)
+}
+
+fn within_one(a: U256, b: U256) -> bool {
+    if a > b {
+        a - b <= U256::one()
+    } else {
+        b - a <= U256::one()
+    }
+}
+
+/// A * n^n
+fn amp_times_n_pow_n(amplification: u64, n: usize) -> Result<U256> {
+    ensure!(
+        (MIN_AMPLIFICATION..=MAX_AMPLIFICATION).contains(&amplification),
+        MathError::InvalidAmplification
+    );
+    let n = U256::from(n);
+    let mut ann = U256::from(amplification);
+    for _ in 0..n.as_usize() {
+        ann = mul(ann, n)?;
+    }
+    Ok(ann)
+}
+
+/// 计算不变量 D
+///
+/// Args:
+///     amplification: 放大系数 A
+///     balances: 稳定组中每个 token 的余额（至少 2 个，均大于 0）
+pub fn compute_invariant(amplification: u64, balances: &[u64]) -> Result<U256> {
+    let n = balances.len();
+    ensure!(n >= 2, MathError::InvalidStableGroup);
+    ensure!(
+        balances.iter().all(|&x| x > 0),
+        MathError::ReserveOutOfDomain
+    );
+    let ann = amp_times_n_pow_n(amplification, n)?;
+    let n_u256 = U256::from(n);
+    let sum = balances
+        .iter()
+        .fold(U256::zero(), |acc, &x| acc + U256::from(x));
+
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // d_p = D^(n+1) / (n^n * prod(x_i))
+        let mut d_p = d;
+        for &x in balances.iter() {
+            d_p = div(mul(d_p, d)?, mul(U256::from(x), n_u256)?)?;
+        }
+        let prev = d;
+        // D = (Ann * S + n * d_p) * D / ((Ann - 1) * D + (n + 1) * d_p)
+        let numerator = mul(mul(ann, sum)? + mul(d_p, n_u256)?, d)?;
+        let denominator = mul(ann - U256::one(), d)? + mul(n_u256 + U256::one(), d_p)?;
+        d = div(numerator, denominator)?;
+        if within_one(d, prev) {
+            return Ok(d);
+        }
+    }
+    Err(MathError::StableMathDidNotConverge)
+}
+
+/// 给定不变量 D 和除 token_out 以外的余额，求 token_out 的余额 y
+///
+/// y^2 + (b - D) * y = c，其中 b = S' + D / Ann，c = D^(n+1) / (n^n * prod'(x) * Ann)
+fn compute_balance_given_invariant(
+    ann: U256,
+    balances: &[u64],
+    token_out: usize,
+    invariant: U256,
+) -> Result<U256> {
+    let n = U256::from(balances.len());
+    let mut c = invariant;
+    let mut sum = U256::zero();
+    for (k, &x) in balances.iter().enumerate() {
+        if k == token_out {
+            continue;
+        }
+        sum += U256::from(x);
+        c = div(mul(c, invariant)?, mul(U256::from(x), n)?)?;
+    }
+    c = div(mul(c, invariant)?, mul(ann, n)?)?;
+    let b = sum + div(invariant, ann)?;
+
+    let mut y = invariant;
+    for _ in 0..MAX_ITERATIONS {
+        let prev = y;
+        // y = (y^2 + c) / (2y + b - D)
+        let denominator = (mul(y, U256::from(2u64))? + b)
+            .checked_sub(invariant)
+            .ok_or(MathError::MathOverflow)?;
+        y = div(mul(y, y)? + c, denominator)?;
+        if within_one(y, prev) {
+            return Ok(y);
+        }
+    }
+    Err(MathError::StableMathDidNotConverge)
+}
+
+/// 稳定组内的 swap：token_in 增加 amount_in 后 token_out 的输出
+///
+/// 新余额向上多取 1 个单位（与 Curve 相同），即输出向下取整，对池子有利
+///
+/// Args:
+///     amplification: 放大系数 A
+///     balances: 稳定组中每个 token 的余额
+///     token_in / token_out: 输入 / 输出 token 在 balances 中的下标
+///     amount_in: 扣费后的输入数量
+pub fn compute_out_given_in(
+    amplification: u64,
+    balances: &[u64],
+    token_in: usize,
+    token_out: usize,
+    amount_in: u64,
+) -> Result<u64> {
+    ensure!(
+        token_in != token_out && token_in < balances.len() && token_out < balances.len(),
+        MathError::InvalidStableGroup
+    );
+    let invariant = compute_invariant(amplification, balances)?;
+    let ann = amp_times_n_pow_n(amplification, balances.len())?;
+
+    let mut balances_after = balances.to_vec();
+    balances_after[token_in] = balances[token_in]
+        .checked_add(amount_in)
+        .ok_or(MathError::ReserveOutOfDomain)?;
+    let y = compute_balance_given_invariant(ann, &balances_after, token_out, invariant)?;
+
+    let balance_out_after = y + U256::one();
+    let balance_out = U256::from(balances[token_out]);
+    ensure!(
+        balance_out_after <= balance_out,
+        MathError::InsufficientLiquidity
+    );
+    Ok((balance_out - balance_out_after).as_u64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invariant_balanced_equals_sum() {
+        // 余额相等时 D = sum(x_i)
+        for amp in [1u64, 100, MAX_AMPLIFICATION] {
+            let d = compute_invariant(amp, &[1_000_000, 1_000_000, 1_000_000]).unwrap();
+            assert!(within_one(d, U256::from(3_000_000u64)), "amp {}: {}", amp, d);
+        }
+    }
+
+    #[test]
+    fn test_out_given_in_flat_near_balance() {
+        let balances = [1_000_000_000u64, 1_000_000_000];
+        let out_low = compute_out_given_in(1, &balances, 0, 1, 10_000_000).unwrap();
+        let out_high = compute_out_given_in(1000, &balances, 0, 1, 10_000_000).unwrap();
+        // A 越大越接近 1:1，且输出不会超过输入
+        assert!(out_high > out_low);
+        assert!(out_high <= 10_000_000);
+        assert!(out_high > 9_990_000);
+
+        // 输出保持不变量不减
+        let d_before = compute_invariant(1000, &balances).unwrap();
+        let d_after =
+            compute_invariant(1000, &[1_010_000_000, 1_000_000_000 - out_high]).unwrap();
+        assert!(d_after + U256::one() >= d_before);
+    }
+
+    #[test]
+    fn test_invalid_amplification_and_group() {
+        assert!(compute_invariant(0, &[1, 1]).is_err());
+        assert!(compute_invariant(MAX_AMPLIFICATION + 1, &[1, 1]).is_err());
+        assert!(compute_invariant(100, &[1_000]).is_err());
+        assert!(compute_out_given_in(100, &[1_000, 1_000], 0, 0, 1).is_err());
+    }
+}
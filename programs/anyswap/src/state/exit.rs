@@ -0,0 +1,157 @@
+use anchor_lang::prelude::*;
+use super::pool::MAX_TOKENS;
+use crate::error::ErrorCode;
+
+/// 已支付 token 的位图大小（每个 token 一位）
+pub const EXIT_BITMAP_BYTES: usize = MAX_TOKENS / 8;
+
+/// 待处理的大额退出（每个 pool、每个用户一个 PDA）
+/// seeds = [b"pending_exit", pool, owner]
+///
+/// request_exit 时立即销毁 LP，并按当时的储备和 LP 总量记录每个 token 应支付的数量，
+/// 这些数量从 LP 储备中划出（见 AnySwapPool::reserve_exit），之后 process_exit 分多笔交易逐个 token 支付，
+/// 全部 token 支付完成后关闭该账户
+#[account]
+#[derive(Debug)]
+pub struct PendingExit {
+    /// 所属 pool
+    pub pool: Pubkey,
+    /// 退出用户
+    pub owner: Pubkey,
+    /// 已销毁的 LP token 数量
+    pub lp_amount: u64,
+    /// 发起退出时 pool 的 token 数量
+    pub token_count: u16,
+    /// 发起退出时前 token_count 个 token 的 mint 列表哈希（见 AnySwapPool::mints_hash）
+    pub mints_hash: [u8; 32],
+    /// 已支付的 token 位图，第 i 位对应 pool 中索引为 i 的 token
+    pub paid: [u8; EXIT_BITMAP_BYTES],
+    /// PDA bump
+    pub bump: u8,
+    /// 每个 token 应支付的数量（发起退出时的快照），索引与 pool 中的 token 一致
+    pub amounts: Vec<u64>,
+}
+
+impl PendingExit {
+    /// 计算账户所需的空间大小，token_count 为发起退出时 pool 的 token 数量
+    pub fn space(token_count: usize) -> usize {
+        8 + // discriminator
+        32 + // pool
+        32 + // owner
+        8 + // lp_amount
+        2 + // token_count
+        32 + // mints_hash
+        EXIT_BITMAP_BYTES + // paid
+        1 + // bump
+        4 + token_count * 8 // amounts
+    }
+
+    /// 索引为 index 的 token 是否已支付
+    pub fn is_paid(&self, index: usize) -> bool {
+        self.paid[index / 8] & (1 << (index % 8)) != 0
+    }
+
+    /// 标记索引为 index 的 token 已支付，重复支付会报错
+    pub fn mark_paid(&mut self, index: usize) -> Result<()> {
+        require!(
+            index < self.token_count as usize,
+            ErrorCode::InvalidTokenIndex
+        );
+        require!(!self.is_paid(index), ErrorCode::ExitAlreadyPaid);
+        self.paid[index / 8] |= 1 << (index % 8);
+        Ok(())
+    }
+
+    /// 是否所有 token 都已支付
+    pub fn is_fully_paid(&self) -> bool {
+        (0..self.token_count as usize).all(|i| self.is_paid(i))
+    }
+
+    /// 处理一批 token 的退出支付，返回与 token_indices 一一对应的支付数量
+    ///
+    /// 支付数量在发起退出时已确定，不受之后的 swap、增减流动性或其他退出影响
+    pub fn process_chunk(&mut self, token_indices: &[u16]) -> Result<Vec<u64>> {
+        require!(!token_indices.is_empty(), ErrorCode::InvalidTokenCount);
+        let mut amounts = Vec::with_capacity(token_indices.len());
+        for &index in token_indices.iter() {
+            self.mark_paid(index as usize)?;
+            amounts.push(self.amounts[index as usize]);
+        }
+        Ok(amounts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::liquidity::remove_liquidity_inner;
+
+    fn pending(amounts: Vec<u64>) -> PendingExit {
+        PendingExit {
+            pool: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            lp_amount: 100,
+            token_count: amounts.len() as u16,
+            mints_hash: [0; 32],
+            paid: [0; EXIT_BITMAP_BYTES],
+            bump: 255,
+            amounts,
+        }
+    }
+
+    #[test]
+    fn test_exit_processed_in_two_chunks() {
+        let vaults = [1_000_000u64, 2_000_000u64, 3_000_000u64];
+        let total_supply = 1_000_000u128;
+
+        // 发起退出时按一次性移除的结果记录快照
+        let expected = remove_liquidity_inner(&vaults, 250_000, total_supply, 3, 1000).unwrap();
+        let mut exit = pending(expected.amounts_out.clone());
+
+        // 第一笔交易支付 token 0 和 2
+        let first = exit.process_chunk(&[0, 2]).unwrap();
+        assert!(!exit.is_fully_paid());
+
+        // 第二笔交易支付 token 1
+        let second = exit.process_chunk(&[1]).unwrap();
+        assert!(exit.is_fully_paid());
+
+        assert_eq!(first, vec![expected.amounts_out[0], expected.amounts_out[2]]);
+        assert_eq!(second, vec![expected.amounts_out[1]]);
+        let total_out: u64 = first.iter().chain(second.iter()).sum();
+        assert_eq!(total_out, 249_250 + 498_500 + 747_750);
+    }
+
+    #[test]
+    fn test_exit_rejects_double_payment() {
+        let mut exit = pending(vec![10, 20]);
+        assert_eq!(exit.process_chunk(&[1]).unwrap(), vec![20]);
+        assert_eq!(
+            exit.process_chunk(&[1]).err(),
+            Some(error!(ErrorCode::ExitAlreadyPaid))
+        );
+        // 同一批次中重复的索引
+        assert!(exit.process_chunk(&[0, 0]).is_err());
+        // 越界索引
+        assert_eq!(
+            exit.process_chunk(&[2]).err(),
+            Some(error!(ErrorCode::InvalidTokenIndex))
+        );
+        assert!(exit.process_chunk(&[]).is_err());
+    }
+
+    #[test]
+    fn test_exit_bitmap_high_index() {
+        let mut exit = pending(vec![1; MAX_TOKENS]);
+        exit.mark_paid(MAX_TOKENS - 1).unwrap();
+        assert!(exit.is_paid(MAX_TOKENS - 1));
+        assert!(!exit.is_paid(MAX_TOKENS - 2));
+        assert!(!exit.is_fully_paid());
+    }
+
+    #[test]
+    fn test_exit_space_fits_cpi_allocation_limit() {
+        // request_exit 通过 CPI 创建账户，单次最多分配 10240 字节
+        assert!(PendingExit::space(MAX_TOKENS) <= 10240);
+    }
+}
@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::math::i256::I256;
+use crate::math::logexpmath::{LogExpMath, ONE_18};
+use crate::math::scale::E18;
+use crate::state::swap::weighted_log_invariant;
+use crate::state::AnySwapPool;
+use primitive_types::U256;
+
+/// 一年的秒数（按 365 天计），用于把手续费增长年化
+pub const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+
+/// 手续费快照（每个 pool、每个时间戳一个 PDA）
+/// seeds = [b"fee_snapshot", pool, timestamp.to_le_bytes()]
+///
+/// 记录某一时刻每份 LP 对应的加权不变量（取对数），两个快照之差即为这段时间内
+/// 手续费带来的每份 LP 价值增长：
+/// - swap 沿曲线移动储备，不改变加权不变量（取整误差除外）
+/// - 按比例添加 / 移除流动性时不变量与 LP 总量同比例变化
+/// - 只有留在池子中的手续费（如添加流动性的手续费）会让每份 LP 的不变量增加
+#[account]
+#[derive(Debug)]
+pub struct FeeSnapshot {
+    /// 所属 pool
+    pub pool: Pubkey,
+    /// 快照时间戳（秒），同时作为 PDA seed
+    pub timestamp: i64,
+    /// 快照时的 LP 总量
+    pub lp_supply: u128,
+    /// ln(每份 LP 对应的加权不变量)，18 位定点数
+    pub log_value_per_share: I256,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl FeeSnapshot {
+    /// 计算账户所需的空间大小
+    pub fn space() -> usize {
+        8 + // discriminator
+        32 + // pool
+        8 + // timestamp
+        16 + // lp_supply
+        32 + // log_value_per_share
+        1 // bump
+    }
+}
+
+impl AnySwapPool {
+    /// 计算 ln(每份 LP 对应的加权不变量)，18 位定点数
+    ///
+    /// 加权不变量 V = prod(vault_i ^ (weight_i / weight_sum))，结果为 ln(V) - ln(lp_supply)。
+    /// vault_balances 按 pool 中 token 的顺序传入；稳定组同样按加权不变量估算
+    /// 权重渐变期间按 now 时刻的权重计算
+    pub fn log_value_per_share(&self, vault_balances: &[u64], now: i64) -> Result<I256> {
+        require!(
+            vault_balances.len() == self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+        let lp_supply = self.get_total_amount_minted();
+        require!(lp_supply > 0, ErrorCode::InsufficientLiquidity);
+        require!(self.get_weight_sum() > 0, ErrorCode::InvalidTokenCount);
+
+        // 使用归一化权重 weight / weight_sum（18 位定点数）
+        let weights = self
+            .active_tokens()
+            .map(|(index, _)| self.current_normalized_weight(index, now))
+            .collect::<Result<Vec<u64>>>()?;
+        let invariant = weighted_log_invariant(vault_balances, &weights)?;
+
+        let scaled_supply = U256::from(lp_supply) * E18;
+        let ln_supply = LogExpMath::ln(I256::try_from(scaled_supply)?)?;
+        Ok(invariant.checked_div(&ONE_18)? - ln_supply)
+    }
+}
+
+/// 根据两个快照的 ln(每份 LP 价值) 计算手续费增长和年化收益
+///
+/// - growth_per_share = exp(after - before) - 1
+/// - apy = exp((after - before) * SECONDS_PER_YEAR / elapsed_seconds) - 1（按连续复利年化）
+///
+/// 结果均为 18 位定点数；ln 的计算误差可能让差值略小于 0，此时按 0 处理
+///
+/// Returns:
+///     (growth_per_share, apy)
+pub fn annualize_fee_growth(
+    log_value_before: I256,
+    log_value_after: I256,
+    elapsed_seconds: i64,
+) -> Result<(u128, u128)> {
+    require!(elapsed_seconds > 0, ErrorCode::InvalidFeeSnapshot);
+    let delta = log_value_after - log_value_before;
+    if delta <= I256::ZERO {
+        return Ok((0, 0));
+    }
+
+    let annual_delta = delta
+        .checked_mul(&I256::from(SECONDS_PER_YEAR as i128))?
+        .checked_div(&I256::from(elapsed_seconds as i128))?;
+    let growth = LogExpMath::exp(delta)? - ONE_18;
+    let apy = LogExpMath::exp(annual_delta)? - ONE_18;
+    Ok((to_u128(growth)?, to_u128(apy)?))
+}
+
+fn to_u128(value: I256) -> Result<u128> {
+    u128::try_from(value.to_u256()?).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::liquidity::add_liquidity_inner;
+    use crate::state::swap::swap_inner;
+
+    const ONE: u128 = 1_000_000_000_000_000_000;
+
+    fn two_token_pool(lp_supply: u128) -> Box<AnySwapPool> {
+        let mut pool: Box<AnySwapPool> = Box::new(bytemuck::Zeroable::zeroed());
+        for _ in 0..2 {
+            pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 50).unwrap();
+        }
+        pool.set_total_amount_minted(lp_supply);
+        pool
+    }
+
+    #[test]
+    fn test_fee_growth_between_snapshots() {
+        let mut pool = two_token_pool(1_000_000_000);
+        let mut vaults = vec![1_000_000_000u64, 1_000_000_000u64];
+        let before = pool.log_value_per_share(&vaults, 0).unwrap();
+
+        // 两个快照之间：10 次按比例加入，每次约 1% 的储备，0.3% 的手续费留在池子中
+        for _ in 0..10 {
+            let supply = pool.get_total_amount_minted();
+            let amounts_in = [vaults[0] / 100, vaults[1] / 100];
+            let added = add_liquidity_inner(&vaults, &amounts_in, supply, 3, 1000).unwrap();
+            for (vault, used) in vaults.iter_mut().zip(added.amounts_used.iter()) {
+                *vault += used;
+            }
+            pool.set_total_amount_minted(supply + added.lp_minted as u128);
+        }
+        let after = pool.log_value_per_share(&vaults, 0).unwrap();
+
+        // 快照间隔 30 天
+        let elapsed = 30 * 24 * 60 * 60;
+        let (growth, apy) = annualize_fee_growth(before, after, elapsed).unwrap();
+        // 10 次 * 1% * 0.3% 约为 0.03% 的每份 LP 增长
+        assert!(growth > ONE / 10_000 * 2 && growth < ONE / 10_000 * 4, "growth {}", growth);
+        // 一年约 12.2 个 30 天，按复利年化后略高于线性年化
+        let linear = growth * SECONDS_PER_YEAR as u128 / elapsed as u128;
+        assert!(apy > linear && apy < linear * 101 / 100, "apy {} linear {}", apy, linear);
+
+        // swap 的手续费不进入 vault，来回 swap 几乎不改变每份 LP 的价值
+        for round in 0..10 {
+            let is_in = [round % 2 == 0, round % 2 == 1];
+            let tolerance = if is_in[0] { [10_000_000, 1] } else { [1, 10_000_000] };
+            let result =
+                swap_inner(&is_in, &tolerance, &tolerance, &vaults, &[50, 50], 3, 1000).unwrap();
+            for ((vault, &amount), &x) in vaults.iter_mut().zip(result.amounts.iter()).zip(is_in.iter()) {
+                if x {
+                    *vault += amount;
+                } else {
+                    *vault -= amount;
+                }
+            }
+        }
+        let after_swaps = pool.log_value_per_share(&vaults, 0).unwrap();
+        let (swap_growth, _) = annualize_fee_growth(after, after_swaps, elapsed).unwrap();
+        assert!(swap_growth < ONE / 1_000_000, "swap growth {}", swap_growth);
+    }
+
+    #[test]
+    fn test_fee_growth_invalid_inputs() {
+        let zero = I256::ZERO;
+        // 时间间隔必须为正
+        assert!(annualize_fee_growth(zero, zero, 0).is_err());
+        assert!(annualize_fee_growth(zero, zero, -1).is_err());
+        // 差值为负（ln 误差）时按 0 处理
+        assert_eq!(annualize_fee_growth(I256::from(5i128), zero, 10).unwrap(), (0, 0));
+        // 没有 LP 时无法计算每份价值
+        assert!(two_token_pool(0).log_value_per_share(&[1_000, 1_000], 0).is_err());
+        assert!(two_token_pool(1_000).log_value_per_share(&[1_000], 0).is_err());
+    }
+}
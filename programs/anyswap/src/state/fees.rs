@@ -0,0 +1,299 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use super::pool::AnySwapPool;
+
+/// 基点（万分之一）的分母
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// swap 手续费的拆分（每个 token 一项）
+/// lp_fees + protocol_fees + referral_fees = burn_fees
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FeeBreakdown {
+    /// 留在池子中归 LP 的部分
+    pub lp_fees: Vec<u64>,
+    /// 协议费部分
+    pub protocol_fees: Vec<u64>,
+    /// 推荐人部分
+    pub referral_fees: Vec<u64>,
+}
+
+impl FeeBreakdown {
+    /// 每个 token 的总手续费
+    pub fn total_fees(&self) -> Vec<u64> {
+        self.lp_fees
+            .iter()
+            .zip(self.protocol_fees.iter())
+            .zip(self.referral_fees.iter())
+            .map(|((lp, protocol), referral)| lp + protocol + referral)
+            .collect()
+    }
+}
+
+/// 将 swap 的手续费拆分为 LP / 协议 / 推荐人三部分
+///
+/// 协议费和推荐费按手续费的比例计算（向下取整），余下的部分归 LP：
+/// - protocol_i = fee_i * protocol_numerator / protocol_denominator
+/// - referral_i = fee_i * referral_fee_bps / 10000
+/// - lp_i = fee_i - protocol_i - referral_i
+///
+/// 拆分只改变手续费的归属，不改变用户支付的总手续费，因此 swap 的输出不受影响
+pub fn split_fees(
+    burn_fees: &[u64],
+    protocol_numerator: u64,
+    protocol_denominator: u64,
+    referral_fee_bps: u64,
+) -> Result<FeeBreakdown> {
+    require!(protocol_denominator > 0, ErrorCode::MathOverflow);
+    require!(
+        protocol_numerator as u128 * BPS_DENOMINATOR as u128
+            + referral_fee_bps as u128 * protocol_denominator as u128
+            <= BPS_DENOMINATOR as u128 * protocol_denominator as u128,
+        ErrorCode::InvalidFeeShare
+    );
+
+    let mut breakdown = FeeBreakdown {
+        lp_fees: Vec::with_capacity(burn_fees.len()),
+        protocol_fees: Vec::with_capacity(burn_fees.len()),
+        referral_fees: Vec::with_capacity(burn_fees.len()),
+    };
+    for &fee in burn_fees.iter() {
+        let protocol = protocol_share(fee, protocol_numerator, protocol_denominator);
+        let referral = (fee as u128 * referral_fee_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        let lp = fee
+            .checked_sub(protocol)
+            .and_then(|x| x.checked_sub(referral))
+            .ok_or(ErrorCode::MathOverflow)?;
+        breakdown.lp_fees.push(lp);
+        breakdown.protocol_fees.push(protocol);
+        breakdown.referral_fees.push(referral);
+    }
+    Ok(breakdown)
+}
+
+/// 一笔手续费中归协议的部分：fee * numerator / denominator（向下取整，与 split_fees 一致）
+/// 调用方保证 numerator <= denominator 且 denominator > 0（见 protocol_fee_rate）
+pub fn protocol_share(fee: u64, protocol_numerator: u64, protocol_denominator: u64) -> u64 {
+    (fee as u128 * protocol_numerator as u128 / protocol_denominator as u128) as u64
+}
+
+/// swap 的实际转账数量：输入 token 在扣费后的数量之外再转入全部手续费
+///
+/// 手续费随输入一起转入 vault：归 LP 的部分留在 vault 中，由 record_swap_principal 记为手续费
+/// （不计入本金，见 AnySwapItem::accrued_fee）；协议费部分计提后由 collect_protocol_fees
+/// 转给协议费接收者。输出 token 的手续费为 0，数量不变
+pub fn amounts_with_fees(amounts: &[u64], burn_fees: &[u64]) -> Result<Vec<u64>> {
+    require!(amounts.len() == burn_fees.len(), ErrorCode::InvalidTokenCount);
+    amounts
+        .iter()
+        .zip(burn_fees.iter())
+        .map(|(&amount, &fee)| amount.checked_add(fee).ok_or(ErrorCode::MathOverflow.into()))
+        .collect()
+}
+
+impl AnySwapPool {
+    /// 管理员设置协议费：swap 手续费中 numerator / denominator 归协议，numerator 为 0 时不收取
+    /// 收取协议费时 recipient 不能是默认公钥
+    pub fn set_protocol_fee(
+        &mut self,
+        admin: &Pubkey,
+        numerator: u64,
+        denominator: u64,
+        recipient: &Pubkey,
+    ) -> Result<()> {
+        self.verify_admin(admin)?;
+        Self::check_fee_denominator(denominator)?;
+        require!(denominator > 0, ErrorCode::InvalidFee);
+        require!(numerator <= denominator, ErrorCode::InvalidFeeShare);
+        require!(
+            numerator == 0 || *recipient != Pubkey::default(),
+            ErrorCode::InvalidProtocolFeeRecipient
+        );
+        self.protocol_fee_numerator = numerator;
+        self.protocol_fee_denominator = denominator;
+        self.protocol_fee_recipient = *recipient;
+        Ok(())
+    }
+
+    /// 获取协议费接收者
+    pub fn get_protocol_fee_recipient(&self) -> &Pubkey {
+        &self.protocol_fee_recipient
+    }
+
+    /// 协议费比例 (分子, 分母)；未设置协议费时为 (0, 1)
+    pub fn protocol_fee_rate(&self) -> (u64, u64) {
+        if self.protocol_fee_numerator == 0 {
+            (0, 1)
+        } else {
+            (self.protocol_fee_numerator, self.protocol_fee_denominator)
+        }
+    }
+
+    /// 每个 token 的手续费中归协议的部分（见 split_fees，向下取整）；未设置协议费时全为 0
+    pub fn protocol_fees(&self, burn_fees: &[u64]) -> Result<Vec<u64>> {
+        let (numerator, denominator) = self.protocol_fee_rate();
+        Ok(split_fees(burn_fees, numerator, denominator, 0)?.protocol_fees)
+    }
+
+    /// 按 mint 计提 swap 的协议费，protocol_fees 与 mints 一一对应
+    pub fn accrue_protocol_fees(&mut self, mints: &[Pubkey], protocol_fees: &[u64]) -> Result<()> {
+        require!(mints.len() == protocol_fees.len(), ErrorCode::InvalidTokenCount);
+        for (mint, &fee) in mints.iter().zip(protocol_fees.iter()) {
+            if fee == 0 {
+                continue;
+            }
+            let index = self
+                .find_token_index(mint)
+                .ok_or(ErrorCode::InvalidTokenMint)?;
+            self.accrue_protocol_fee(index, fee)?;
+        }
+        Ok(())
+    }
+
+    /// 按 pool 中 token 的顺序计提协议费（zap、单 token 加入 / 移除流动性等内部 swap），
+    /// protocol_fees 与 pool 中的 token 一一对应
+    pub fn accrue_protocol_fees_in_order(&mut self, protocol_fees: &[u64]) -> Result<()> {
+        require!(
+            protocol_fees.len() == self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+        for (index, &fee) in protocol_fees.iter().enumerate() {
+            if fee > 0 {
+                self.accrue_protocol_fee(index, fee)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn accrue_protocol_fee(&mut self, index: usize, fee: u64) -> Result<()> {
+        let token = self.get_token_mut_checked(index)?;
+        let accrued = token
+            .get_accrued_protocol_fee()
+            .checked_add(fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+        token.set_accrued_protocol_fee(accrued);
+        Ok(())
+    }
+
+    /// 取出 token 已计提的协议费并清零（collect_protocol_fees 转账前调用）
+    pub fn take_protocol_fee(&mut self, index: usize) -> Result<u64> {
+        let token = self.get_token_mut_checked(index)?;
+        let accrued = token.get_accrued_protocol_fee();
+        token.set_accrued_protocol_fee(0);
+        Ok(accrued)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::swap::swap_inner;
+
+    #[test]
+    fn test_split_fees_all_types() {
+        // 2进1出，手续费 0.3%，协议拿 1/6，推荐人拿 10%
+        let is_in = [true, true, false];
+        let amount_tolerance = [1_000_000u64, 333_333u64, 1u64];
+        let vaults = [10_000_000u64, 20_000_000u64, 30_000_000u64];
+        let weights = [50u64, 30u64, 20u64];
+        let result = swap_inner(
+            &is_in,
+            &amount_tolerance,
+            &amount_tolerance,
+            &vaults,
+            &weights,
+            30,
+            10000,
+        )
+        .unwrap();
+
+        let breakdown = split_fees(&result.burn_fees, 1, 6, 1_000).unwrap();
+
+        // 拆分之和等于总手续费
+        assert_eq!(breakdown.total_fees(), result.burn_fees);
+        assert_eq!(breakdown.protocol_fees, vec![500u64, 166, 0]);
+        assert_eq!(breakdown.referral_fees, vec![300u64, 99, 0]);
+        assert_eq!(breakdown.lp_fees, vec![2_200u64, 734, 0]);
+
+        // 输出已扣除全部手续费：输入 token 的净输入 = 容差 - 总手续费
+        let total_fees = breakdown.total_fees();
+        for (i, &tolerance) in amount_tolerance.iter().take(2).enumerate() {
+            assert_eq!(result.amounts[i], tolerance - total_fees[i]);
+        }
+        // 拆分不影响 swap 输出：不收手续费时的输出严格更大
+        let no_fee = swap_inner(
+            &is_in,
+            &amount_tolerance,
+            &amount_tolerance,
+            &vaults,
+            &weights,
+            0,
+            10000,
+        )
+        .unwrap();
+        assert!(no_fee.amounts[2] > result.amounts[2]);
+    }
+
+    #[test]
+    fn test_split_fees_lp_only() {
+        let breakdown = split_fees(&[100, 0, 7], 0, 1, 0).unwrap();
+        assert_eq!(breakdown.lp_fees, vec![100u64, 0, 7]);
+        assert_eq!(breakdown.protocol_fees, vec![0u64, 0, 0]);
+        assert_eq!(breakdown.referral_fees, vec![0u64, 0, 0]);
+    }
+
+    #[test]
+    fn test_protocol_fee_accrues_configured_split() {
+        let admin = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let mut pool: Box<AnySwapPool> = Box::new(bytemuck::Zeroable::zeroed());
+        pool.admin = admin;
+        pool.set_fee(3, 1000).unwrap();
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        for mint in mints.iter() {
+            pool.add_token(mint, &Pubkey::new_unique(), 50).unwrap();
+        }
+
+        // 非管理员不能设置；收取协议费时必须有接收者；比例不能超过 100%
+        assert!(pool.set_protocol_fee(&recipient, 1, 6, &recipient).is_err());
+        assert!(pool.set_protocol_fee(&admin, 1, 6, &Pubkey::default()).is_err());
+        assert!(pool.set_protocol_fee(&admin, 7, 6, &recipient).is_err());
+        assert!(pool.set_protocol_fee(&admin, 1, 0, &recipient).is_err());
+        assert_eq!(pool.protocol_fees(&[3_000, 0]).unwrap(), vec![0, 0]);
+        pool.set_protocol_fee(&admin, 1, 6, &recipient).unwrap();
+
+        let is_in = [true, false];
+        let tolerance = [1_000_000u64, 1];
+        let vaults = [10_000_000u64, 10_000_000];
+        let result = pool
+            .swap_directional(&mints, &is_in, &tolerance, &tolerance, &vaults, &[50, 50])
+            .unwrap();
+        assert_eq!(result.burn_fees, vec![3_000, 0]);
+
+        let protocol_fees = pool.protocol_fees(&result.burn_fees).unwrap();
+        assert_eq!(protocol_fees, vec![500, 0]);
+        pool.accrue_protocol_fees(&mints, &protocol_fees).unwrap();
+        let transfer = amounts_with_fees(&result.amounts, &result.burn_fees).unwrap();
+        assert_eq!(transfer[0], tolerance[0]);
+        assert_eq!(transfer[1], result.amounts[1]);
+
+        // 手续费全部进入 vault，协议费部分不计入 LP 的储备
+        let vault_in = vaults[0] + transfer[0];
+        assert_eq!(pool.tokens[0].get_accrued_protocol_fee(), 500);
+        assert_eq!(pool.tokens[0].lp_balance(vault_in), vaults[0] + result.amounts[0] + 2_500);
+        assert_eq!(pool.tokens[1].get_accrued_protocol_fee(), 0);
+
+        // 第二次 swap 继续累加，领取后清零
+        pool.accrue_protocol_fees(&mints, &protocol_fees).unwrap();
+        assert_eq!(pool.take_protocol_fee(0).unwrap(), 1_000);
+        assert_eq!(pool.tokens[0].get_accrued_protocol_fee(), 0);
+        assert_eq!(pool.take_protocol_fee(1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_split_fees_share_exceeds_total() {
+        // 协议 60% + 推荐 50% > 100%
+        assert!(split_fees(&[100], 3, 5, 5_000).is_err());
+        assert!(split_fees(&[100], 0, 1, 10_001).is_err());
+        assert!(split_fees(&[100], 1, 2, 5_000).is_ok());
+    }
+}
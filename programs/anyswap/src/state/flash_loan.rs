@@ -0,0 +1,61 @@
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+
+/// 闪电贷手续费：amount * fee_numerator / fee_denominator，向上取整
+///
+/// 向上取整保证借出任意数量（包括很小的数量）时池子不会少收手续费
+pub fn flash_loan_fee(amount: u64, fee_numerator: u64, fee_denominator: u64) -> Result<u64> {
+    require!(fee_denominator > 0, ErrorCode::InvalidFee);
+    let numerator = (amount as u128)
+        .checked_mul(fee_numerator as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let fee = numerator.div_ceil(fee_denominator as u128);
+    u64::try_from(fee).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// 检查回调结束后 vault 余额至少恢复到借出前的余额加上手续费
+///
+/// balance_before: 借出前的 vault 余额
+/// balance_after: 回调结束后重新读取的 vault 余额
+pub fn check_flash_loan_repaid(balance_before: u64, balance_after: u64, fee: u64) -> Result<()> {
+    let required = balance_before
+        .checked_add(fee)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(balance_after >= required, ErrorCode::FlashLoanNotRepaid);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flash_loan_repaid_by_callback() {
+        // 池子费率 0.3%
+        let balance_before = 1_000_000u64;
+        let amount = 500_000u64;
+        let fee = flash_loan_fee(amount, 3, 1000).unwrap();
+        assert_eq!(fee, 1_500);
+
+        // 借出后 vault 只剩一半，回调归还本金和手续费
+        let vault = balance_before - amount;
+        let repaid = vault + amount + fee;
+        assert!(check_flash_loan_repaid(balance_before, repaid, fee).is_ok());
+
+        // 少还 1 个单位
+        let err = check_flash_loan_repaid(balance_before, repaid - 1, fee).unwrap_err();
+        assert_eq!(err, ErrorCode::FlashLoanNotRepaid.into());
+        // 只归还本金
+        assert!(check_flash_loan_repaid(balance_before, vault + amount, fee).is_err());
+    }
+
+    #[test]
+    fn test_flash_loan_fee_rounds_up() {
+        // 1 * 3 / 1000 向上取整为 1
+        assert_eq!(flash_loan_fee(1, 3, 1000).unwrap(), 1);
+        assert_eq!(flash_loan_fee(1000, 3, 1000).unwrap(), 3);
+        // 零手续费 pool 不收费
+        assert_eq!(flash_loan_fee(1_000_000, 0, 1).unwrap(), 0);
+        assert!(flash_loan_fee(1, 1, 0).is_err());
+    }
+}
@@ -14,12 +14,43 @@ pub struct AnySwapItem {
     /// Mint account 地址 - 该 token 的 mint 地址 (32 bytes)
     pub mint_account: Pubkey, // 32 bytes
     /// 权重 (weight) - 不变量，用于恒定乘积和公式计算 (8 bytes)
-    /// weight 在添加 token 时设置，之后保持不变
+    /// weight 在添加 token 时设置，modify_token_weight 立即修改；
+    /// 权重渐变（start_weight_ramp）期间为渐变的目标权重，某一时刻的实际权重见 weight_at
     pub weight: u64, // 64 bits (8 bytes)
+    /// 本金储备 (8 bytes) - vault 中不含累计手续费的部分
+    /// vault 余额减去本金即为累计的手续费，compound_fees 将其并入本金
+    pub principal_reserve: u64, // 8 bytes
+    /// 买入该 token（作为 swap 输出）时的费率 (bps)，0 表示使用 pool 费率
+    pub buy_fee_bps: u16, // 2 bytes
+    /// 卖出该 token（作为 swap 输入）时的费率 (bps)，0 表示使用 pool 费率
+    pub sell_fee_bps: u16, // 2 bytes
+    /// 是否属于稳定组（1 为是），组内 swap 使用 StableSwap 曲线
+    pub stable: u8, // 1 byte
+    /// token mint 的精度，添加 token 时从 mint 账户读取（布局版本 9 之前添加的 token 为 0）
+    pub decimals: u8, // 1 byte
+    /// 填充到 8 字节对齐
+    pub _padding: [u8; 2], // 2 bytes
+    /// 归一化权重 weight / weight_sum（18 位定点数，缓存）(8 bytes)
+    /// 由 pool 在权重或权重之和变化时刷新（见 AnySwapPool::refresh_normalized_weights）
+    pub normalized_weight: u64, // 8 bytes
+    /// 已计提、尚未领取的协议费 (8 bytes)
+    /// 协议费随 swap 转入 vault，但不属于 LP，计算储备时从 vault 余额中扣除（见 lp_balance）
+    pub accrued_protocol_fee: u64, // 8 bytes
+    /// 权重渐变的起始权重 (8 bytes)
+    pub start_weight: u64, // 8 bytes
+    /// 权重渐变的目标权重 (8 bytes)
+    pub end_weight: u64, // 8 bytes
+    /// 权重渐变的开始时间 (8 bytes)
+    pub start_ts: i64, // 8 bytes
+    /// 权重渐变的结束时间，0 表示没有权重渐变 (8 bytes)
+    pub end_ts: i64, // 8 bytes
+    /// 已从 LP 储备中划出、等待 process_exit 支付给退出用户的数量 (8 bytes)
+    /// request_exit 时计入，支付后扣减；与协议费一样不属于 LP，计算储备时从 vault 余额中扣除（见 lp_balance）
+    pub exit_reserved: u64, // 8 bytes
 }
 
 // 验证结构体大小和对齐（Solana 要求 8 字节对齐）
-const_assert_eq!(size_of::<AnySwapItem>(), 32 + 32 + 8); // 72 bytes
+const_assert_eq!(size_of::<AnySwapItem>(), 32 + 32 + 8 + 8 + 2 + 2 + 1 + 1 + 2 + 8 + 8 + 8 + 8 + 8 + 8 + 8); // 144 bytes
 const_assert_eq!(size_of::<AnySwapItem>() % 8, 0); // 必须是 8 的倍数
 
 impl AnySwapItem {
@@ -38,14 +69,141 @@ impl AnySwapItem {
         &self.mint_account
     }
 
-    /// 获取 weight 值
-    pub fn get_weight(&self) -> u64 {
-        self.weight
-    }
-
-    /// 设置 weight 值（仅在添加 token 时调用）
+    /// 设置 weight 值（添加 token、modify_token_weight 时调用），同时取消进行中的权重渐变
     pub fn set_weight(&mut self, weight: u64) {
         self.weight = weight;
+        self.start_weight = 0;
+        self.end_weight = 0;
+        self.start_ts = 0;
+        self.end_ts = 0;
+    }
+
+    /// 是否设置了权重渐变（渐变结束后仍为 true，直到 set_weight 取消）
+    pub fn is_weight_ramping(&self) -> bool {
+        self.end_ts != 0
+    }
+
+    /// now 时刻权重是否仍在渐变中（实际权重与配置的 weight 不同）
+    /// 渐变结束后 weight_at 等于 weight，缓存的权重之和与归一化权重重新有效
+    pub fn is_ramp_active(&self, now: i64) -> bool {
+        self.is_weight_ramping() && now < self.end_ts
+    }
+
+    /// 开始权重渐变：从 start_ts 时的 start_weight 线性变化到 end_ts 时的 end_weight
+    /// weight 同时设为 end_weight，渐变结束后保持不变
+    pub fn start_weight_ramp(&mut self, start_weight: u64, end_weight: u64, start_ts: i64, end_ts: i64) {
+        self.weight = end_weight;
+        self.start_weight = start_weight;
+        self.end_weight = end_weight;
+        self.start_ts = start_ts;
+        self.end_ts = end_ts;
+    }
+
+    /// now 时刻的权重：渐变开始前为 start_weight，结束后为 end_weight，之间线性插值（向下取整）
+    /// 没有权重渐变时为 weight；now 由调用方从 Clock 读取（每条指令读取一次）
+    pub fn weight_at(&self, now: i64) -> u64 {
+        if !self.is_weight_ramping() {
+            return self.weight;
+        }
+        if now <= self.start_ts {
+            return self.start_weight;
+        }
+        if now >= self.end_ts {
+            return self.end_weight;
+        }
+        let elapsed = (now - self.start_ts) as i128;
+        let duration = (self.end_ts - self.start_ts) as i128;
+        let delta = self.end_weight as i128 - self.start_weight as i128;
+        (self.start_weight as i128 + (delta * elapsed).div_euclid(duration)) as u64
+    }
+
+    /// 获取缓存的归一化权重 weight / weight_sum（18 位定点数）
+    pub fn get_normalized_weight(&self) -> u64 {
+        self.normalized_weight
+    }
+
+    /// 获取本金储备
+    pub fn get_principal_reserve(&self) -> u64 {
+        self.principal_reserve
+    }
+
+    /// 设置本金储备
+    pub fn set_principal_reserve(&mut self, amount: u64) {
+        self.principal_reserve = amount;
+    }
+
+    /// 按一次操作的资金流更新本金储备
+    /// vault_in / vault_out: 转入 / 转出 vault 的数量；fee: 留在 vault 中归 LP 的手续费
+    /// principal = principal + vault_in - vault_out - fee（最小为 0）
+    pub fn apply_principal_change(&mut self, vault_in: u64, vault_out: u64, fee: u64) {
+        let principal = (self.principal_reserve as u128 + vault_in as u128)
+            .saturating_sub(vault_out as u128 + fee as u128);
+        self.principal_reserve = principal.min(u64::MAX as u128) as u64;
+    }
+
+    /// 累计的手续费：vault 余额超出本金的部分（不含未领取的协议费）
+    pub fn accrued_fee(&self, vault_balance: u64) -> u64 {
+        self.lp_balance(vault_balance)
+            .saturating_sub(self.principal_reserve)
+    }
+
+    /// 获取已计提、尚未领取的协议费
+    pub fn get_accrued_protocol_fee(&self) -> u64 {
+        self.accrued_protocol_fee
+    }
+
+    /// 设置已计提的协议费
+    pub fn set_accrued_protocol_fee(&mut self, amount: u64) {
+        self.accrued_protocol_fee = amount;
+    }
+
+    /// 获取等待退出支付的数量
+    pub fn get_exit_reserved(&self) -> u64 {
+        self.exit_reserved
+    }
+
+    /// 设置等待退出支付的数量
+    pub fn set_exit_reserved(&mut self, amount: u64) {
+        self.exit_reserved = amount;
+    }
+
+    /// 归 LP 的 vault 余额：vault 余额减去未领取的协议费和等待退出支付的数量
+    /// swap 定价和增减流动性都以该值作为储备
+    pub fn lp_balance(&self, vault_balance: u64) -> u64 {
+        vault_balance
+            .saturating_sub(self.accrued_protocol_fee)
+            .saturating_sub(self.exit_reserved)
+    }
+
+    /// 获取方向费率 (buy_fee_bps, sell_fee_bps)
+    pub fn get_directional_fees(&self) -> (u16, u16) {
+        (self.buy_fee_bps, self.sell_fee_bps)
+    }
+
+    /// 设置方向费率，0 表示使用 pool 费率
+    pub fn set_directional_fees(&mut self, buy_fee_bps: u16, sell_fee_bps: u16) {
+        self.buy_fee_bps = buy_fee_bps;
+        self.sell_fee_bps = sell_fee_bps;
+    }
+
+    /// 是否属于稳定组
+    pub fn is_stable(&self) -> bool {
+        self.stable != 0
+    }
+
+    /// 设置是否属于稳定组
+    pub fn set_stable(&mut self, stable: bool) {
+        self.stable = stable as u8;
+    }
+
+    /// 获取 token mint 的精度
+    pub fn get_decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// 设置 token mint 的精度
+    pub fn set_decimals(&mut self, decimals: u8) {
+        self.decimals = decimals;
     }
 
     /// 设置 vault account
@@ -62,7 +220,20 @@ impl AnySwapItem {
     pub fn space() -> usize {
         32 + // vault_account (Pubkey)
         32 + // mint_account (Pubkey)
-        8 // weight
+        8 + // weight
+        8 + // principal_reserve
+        2 + // buy_fee_bps
+        2 + // sell_fee_bps
+        1 + // stable
+        1 + // decimals
+        2 + // _padding
+        8 + // normalized_weight
+        8 + // accrued_protocol_fee
+        8 + // start_weight
+        8 + // end_weight
+        8 + // start_ts
+        8 + // end_ts
+        8 // exit_reserved
     }
 }
 
@@ -0,0 +1,303 @@
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+use primitive_types::U256;
+
+/// 份额精度（1e18）
+pub const JIT_SHARE_PRECISION: u64 = 1_000_000_000_000_000_000;
+
+/// JIT 流动性结算结果
+pub struct JitSettlement {
+    // JIT 提供者占 swap 时池子的份额（1e18 精度）
+    pub share: u64,
+    // 每个 token 应返还给 JIT 提供者的数量
+    pub payouts: Vec<u64>,
+}
+
+impl JitSettlement {
+    /// 每个 token 的返还中属于本金的部分（用于 record_principal_flow）
+    ///
+    /// JIT 提供者按份额分得 swap 中归 LP 的手续费：fee_share_i = lp_fee_i * share / 1e18（向下取整），
+    /// 这部分从累计的手续费中支付，返还的其余部分按本金流出
+    pub fn principal_payouts(&self, lp_fees: &[u64]) -> Result<Vec<u64>> {
+        require!(lp_fees.len() == self.payouts.len(), ErrorCode::InvalidTokenCount);
+        let precision = U256::from(JIT_SHARE_PRECISION);
+        Ok(self
+            .payouts
+            .iter()
+            .zip(lp_fees.iter())
+            .map(|(&payout, &fee)| {
+                let fee_share = (U256::from(fee) * U256::from(self.share) / precision).as_u64();
+                payout.saturating_sub(fee_share)
+            })
+            .collect())
+    }
+}
+
+/// 结算 JIT（just-in-time）流动性
+///
+/// JIT 提供者在 swap 之前向 vault 注入流动性，swap 之后按份额取回
+///
+/// 公式：
+/// - deposit_i = after_deposit_i - before_i
+/// - share = min(deposit_i / after_deposit_i)，即按比例注入的部分
+/// - 超出比例的部分原样退回：excess_i = deposit_i - after_deposit_i * share
+/// - payout_i = after_swap_i * share + excess_i
+///
+/// 所有计算向下取整，余数留在池子中
+///
+/// Args:
+///     vaults_before: JIT 注入前的储备
+///     vaults_after_deposit: JIT 注入后、swap 前的储备
+///     vaults_after_swap: swap 之后的储备
+pub fn settle_jit_inner(
+    vaults_before: &[u64],
+    vaults_after_deposit: &[u64],
+    vaults_after_swap: &[u64],
+) -> Result<JitSettlement> {
+    let token_count = vaults_before.len();
+    require!(token_count > 0, ErrorCode::InvalidTokenCount);
+    require!(
+        vaults_after_deposit.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+    require!(
+        vaults_after_swap.len() == token_count,
+        ErrorCode::InvalidTokenCount
+    );
+
+    let precision = U256::from(JIT_SHARE_PRECISION);
+
+    // 计算每个 token 的注入量，JIT 不允许从 vault 中取走 token
+    let mut deposits = Vec::with_capacity(token_count);
+    for i in 0..token_count {
+        let deposit = vaults_after_deposit[i]
+            .checked_sub(vaults_before[i])
+            .ok_or(ErrorCode::InvalidJitDeposit)?;
+        deposits.push(deposit);
+    }
+
+    // share = min(deposit_i * 1e18 / after_deposit_i)
+    let mut share = precision;
+    for i in 0..token_count {
+        if vaults_after_deposit[i] == 0 {
+            continue;
+        }
+        let ratio = U256::from(deposits[i]) * precision / U256::from(vaults_after_deposit[i]);
+        if ratio < share {
+            share = ratio;
+        }
+    }
+
+    let mut payouts = Vec::with_capacity(token_count);
+    for i in 0..token_count {
+        // 按比例部分：参与 swap，按 swap 后储备取回
+        let proportional = U256::from(vaults_after_swap[i]) * share / precision;
+        // 超出比例的部分：未参与 swap，原样退回
+        let used = U256::from(vaults_after_deposit[i]) * share / precision;
+        let excess = U256::from(deposits[i]).saturating_sub(used);
+        let payout = proportional + excess;
+        require!(
+            payout <= U256::from(vaults_after_swap[i]),
+            ErrorCode::InsufficientReserves
+        );
+        payouts.push(payout.as_u64());
+    }
+
+    Ok(JitSettlement {
+        share: share.as_u64(),
+        payouts,
+    })
+}
+
+/// 校验 JIT 收款账户：mint 与对应的 token 一致，且所有者是签名的 JIT 提供者
+///
+/// 收款账户由构造交易的一方传入，不校验所有者时 swap 发起人可以把 JIT 提供者的本金和手续费分成转入自己的账户
+pub fn check_jit_payout(
+    payout_mint: &Pubkey,
+    payout_owner: &Pubkey,
+    mint: &Pubkey,
+    provider: &Pubkey,
+) -> Result<()> {
+    require!(payout_mint == mint, ErrorCode::InvalidTokenMint);
+    require!(payout_owner == provider, ErrorCode::InvalidJitPayout);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::swap::swap_inner;
+
+    #[test]
+    fn test_jit_payout_must_belong_to_provider() {
+        let mint = Pubkey::new_unique();
+        let provider = Pubkey::new_unique();
+        check_jit_payout(&mint, &provider, &mint, &provider).unwrap();
+
+        // swap 发起人把收款账户换成自己的账户
+        let swapper = Pubkey::new_unique();
+        assert_eq!(
+            check_jit_payout(&mint, &swapper, &mint, &provider).unwrap_err(),
+            error!(ErrorCode::InvalidJitPayout)
+        );
+        // mint 不一致
+        assert_eq!(
+            check_jit_payout(&Pubkey::new_unique(), &provider, &mint, &provider).unwrap_err(),
+            error!(ErrorCode::InvalidTokenMint)
+        );
+    }
+
+    #[test]
+    fn test_jit_add_swap_remove() {
+        // 池子储备 [1M, 1M]，权重相同
+        let vaults_before = vec![1_000_000u64, 1_000_000u64];
+        let weights = vec![50u64, 50u64];
+
+        // JIT 提供者按比例注入与池子相同的流动性（份额 50%）
+        let deposits = [1_000_000u64, 1_000_000u64];
+        let vaults_after_deposit: Vec<u64> = vaults_before
+            .iter()
+            .zip(deposits.iter())
+            .map(|(v, d)| v + d)
+            .collect();
+
+        // 用户用 token 0 换 token 1
+        let is_in = vec![true, false];
+        let amount_tolerance = vec![100_000u64, 1u64];
+        let user_vaults_amount = vec![100_000u64, 0u64];
+        let result = swap_inner(
+            &is_in,
+            &amount_tolerance,
+            &user_vaults_amount,
+            &vaults_after_deposit,
+            &weights,
+            3,
+            10000,
+        )
+        .unwrap();
+
+        // 手续费随输入转入 vault
+        let vaults_after_swap = vec![
+            vaults_after_deposit[0] + result.amounts[0] + result.burn_fees[0],
+            vaults_after_deposit[1] - result.amounts[1],
+        ];
+
+        let settlement =
+            settle_jit_inner(&vaults_before, &vaults_after_deposit, &vaults_after_swap).unwrap();
+        assert_eq!(settlement.share, JIT_SHARE_PRECISION / 2);
+
+        // JIT 提供者取回 swap 后储备的一半
+        assert_eq!(settlement.payouts[0], vaults_after_swap[0] / 2);
+        assert_eq!(settlement.payouts[1], vaults_after_swap[1] / 2);
+
+        // JIT 提供者的净头寸：多得 token 0（含一半手续费），少得 token 1，与池子一起承担这笔 swap
+        let net_in = settlement.payouts[0] as i128 - deposits[0] as i128;
+        let net_out = deposits[1] as i128 - settlement.payouts[1] as i128;
+        assert_eq!(net_in, (result.amounts[0] + result.burn_fees[0]) as i128 / 2);
+        assert_eq!(net_out, (result.amounts[1] as i128 + 1) / 2);
+
+        // 池子剩余的储备不少于 JIT 注入前，加上另一半的 swap 变化
+        let remaining_0 = vaults_after_swap[0] - settlement.payouts[0];
+        let remaining_1 = vaults_after_swap[1] - settlement.payouts[1];
+        assert!(remaining_0 >= vaults_before[0]);
+        assert!(remaining_1 + result.amounts[1] >= vaults_before[1]);
+
+        // 返还中属于手续费分成的部分不计入本金流出
+        let principal = settlement.principal_payouts(&result.burn_fees).unwrap();
+        assert_eq!(principal[0], settlement.payouts[0] - result.burn_fees[0] / 2);
+        assert_eq!(principal[1], settlement.payouts[1]);
+        assert!(settlement.principal_payouts(&result.burn_fees[..1]).is_err());
+    }
+
+    #[test]
+    fn test_jit_excess_refunded() {
+        // 不按比例注入：token 1 多注入的部分应原样退回
+        let vaults_before = vec![1_000_000u64, 1_000_000u64];
+        let vaults_after_deposit = vec![2_000_000u64, 2_500_000u64];
+        // swap 没有改变储备
+        let settlement = settle_jit_inner(
+            &vaults_before,
+            &vaults_after_deposit,
+            &vaults_after_deposit,
+        )
+        .unwrap();
+
+        assert_eq!(settlement.share, JIT_SHARE_PRECISION / 2);
+        assert_eq!(settlement.payouts, vec![1_000_000u64, 1_500_000u64]);
+    }
+
+    #[test]
+    fn test_jit_no_deposit() {
+        let vaults = vec![1_000_000u64, 2_000_000u64];
+        let settlement = settle_jit_inner(&vaults, &vaults, &vaults).unwrap();
+        assert_eq!(settlement.share, 0);
+        assert_eq!(settlement.payouts, vec![0u64, 0u64]);
+    }
+
+    #[test]
+    fn test_jit_withdraw_rejected() {
+        // JIT 回调不能从 vault 中取走 token
+        let vaults_before = vec![1_000_000u64, 1_000_000u64];
+        let vaults_after_deposit = vec![900_000u64, 1_000_000u64];
+        assert!(settle_jit_inner(&vaults_before, &vaults_after_deposit, &vaults_after_deposit)
+            .is_err());
+    }
+
+    #[test]
+    fn test_jit_shifted_reserves_breach_output_minimum() {
+        use crate::state::AnySwapPool;
+
+        let mut pool: Box<AnySwapPool> = Box::new(bytemuck::Zeroable::zeroed());
+        pool.set_fee(3, 1000).unwrap();
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        for mint in mints.iter() {
+            pool.add_token(mint, &Pubkey::new_unique(), 50).unwrap();
+        }
+        let is_in = [true, false];
+        let weights = [50u64, 50];
+        let vaults_before = [1_000_000u64, 1_000_000];
+        let user = [100_000u64, 0];
+
+        // 用户按 JIT 之前的储备报价，并以报价作为最小输出
+        let quoted = pool
+            .checked_swap(&mints, &is_in, &[100_000, 1], &user, &vaults_before, &weights)
+            .unwrap();
+        let tolerance = [100_000u64, quoted.amounts[1]];
+        assert!(pool
+            .checked_swap(&mints, &is_in, &tolerance, &user, &vaults_before, &weights)
+            .is_ok());
+
+        // JIT 只注入输入 token，储备偏移后输出低于用户的下限，整笔 swap 失败
+        let vaults_after_deposit = [2_000_000u64, 1_000_000];
+        assert_eq!(
+            pool.checked_swap(&mints, &is_in, &tolerance, &user, &vaults_after_deposit, &weights)
+                .map(|result| result.amounts)
+                .unwrap_err(),
+            error!(ErrorCode::SlippageExceeded)
+        );
+
+        // 只注入输出 token 时输出更多，满足下限
+        let vaults_after_deposit = [1_000_000u64, 2_000_000];
+        let result = pool
+            .checked_swap(&mints, &is_in, &tolerance, &user, &vaults_after_deposit, &weights)
+            .unwrap();
+        assert!(result.amounts[1] > quoted.amounts[1]);
+
+        // 与 swap_anyswap 相同的 pool 级检查
+        pool.set_max_tokens_per_swap(1);
+        assert_eq!(
+            pool.check_swap_allowed(2).unwrap_err(),
+            error!(ErrorCode::TooManyTokensInSwap)
+        );
+        pool.set_max_tokens_per_swap(0);
+        assert!(pool.check_swap_allowed(2).is_ok());
+        assert!(pool.check_swap_allowed(0).is_err());
+        pool.set_min_swap_amount(200_000);
+        assert_eq!(
+            pool.checked_swap(&mints, &is_in, &tolerance, &user, &vaults_after_deposit, &weights)
+                .map(|result| result.amounts)
+                .unwrap_err(),
+            error!(ErrorCode::AmountTooSmall)
+        );
+    }
+}
@@ -1,4 +1,7 @@
 use crate::{error::ErrorCode, state::AnySwapPool};
+use crate::math::fixedpoint::FixedPoint;
+use crate::math::scale::E18;
+use crate::state::swap::MAX_DOMAIN_RESERVE;
 use anchor_lang::prelude::*;
 use primitive_types::U256;
 
@@ -25,7 +28,7 @@ pub trait LiquidityProtocol {
         &self,
         token_vaults_amount: &[u64],
         amounts_in: &[u64],
-        total_lp_supply: u64,
+        total_lp_supply: u128,
         fee_numerator: u64,
         fee_denominator: u64,
     ) -> Result<AddLiquidityResult>;
@@ -34,19 +37,44 @@ pub trait LiquidityProtocol {
         &self,
         token_vaults_amount: &[u64],
         lp_to_burn: u64,
-        total_lp_supply: u64,
+        total_lp_supply: u128,
         fee_numerator: u64,
         fee_denominator: u64,
     ) -> Result<RemoveLiquidityResult>;
 }
 
+/// 添加流动性，首次添加时所有 token 等权重（见 add_liquidity_inner_with_weights）
+pub fn add_liquidity_inner(
+    token_vaults_amount: &[u64],
+    amounts_in: &[u64],
+    total_lp_supply: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<AddLiquidityResult> {
+    add_liquidity_inner_with_weights(
+        token_vaults_amount,
+        amounts_in,
+        total_lp_supply,
+        fee_numerator,
+        fee_denominator,
+        &vec![1; token_vaults_amount.len()],
+    )
+}
+
 /// 添加流动性（CPMM模型）
 ///
+/// total_lp_supply 使用 u128，LP 总量超过 u64::MAX 后仍可继续加入；
+/// 单次铸造的 LP 数量仍为 u64（SPL mint 的限制）
+///
 /// 用户按当前池子的比例提供所有token，铸造LP按比例计算
 ///
 /// 公式：
-/// - 首次添加：LP = 第一个token的数量（扣费后）
-/// - 后续添加：LP = total_LP * (提供的token数量 / 该token当前储备)
+/// - 首次添加：LP = 扣费后数量的加权几何平均 Π(amount_i ^ w_i)（w_i 为归一化权重，见 FixedPoint::geometric_mean），
+///   每个 token 扣费后都必须大于 0，且铸造的 LP 不能为 0
+/// - 后续添加：LP = total_LP * min_i(amounts_in_i / vault_i)，比例最小的 token 为约束
+///
+/// 后续添加时 amounts_used 只包含按该比例需要的数量（可能小于 amounts_in），
+/// 任何 token 都不会超出比例存入，多提供的部分留在用户账户中
 ///
 /// Args:
 ///     token_vaults_amount: 当前储备列表
@@ -54,16 +82,18 @@ pub trait LiquidityProtocol {
 ///     total_lp_supply: 当前LP token总供应量
 ///     fee_numerator: 费率分子
 ///     fee_denominator: 费率分母
-pub fn add_liquidity_inner(
+///     weights: 每个 token 的权重（相对值），只在首次添加时用于计算几何平均
+pub fn add_liquidity_inner_with_weights(
     token_vaults_amount: &[u64],
     amounts_in: &[u64],
-    total_lp_supply: u64,
+    total_lp_supply: u128,
     fee_numerator: u64,
     fee_denominator: u64,
+    weights: &[u64],
 ) -> Result<AddLiquidityResult> {
     let token_count = token_vaults_amount.len();
     require!(
-        amounts_in.len() == token_count,
+        amounts_in.len() == token_count && weights.len() == token_count,
         ErrorCode::InvalidTokenCount
     );
 
@@ -84,14 +114,30 @@ pub fn add_liquidity_inner(
 
     // 计算LP铸造数量和实际使用的token数量
     let (lp_minted, amounts_in_pool, amounts_used_from_user) = if total_lp_supply == 0 {
-        // 首次添加流动性：LP = 第一个token的数量（扣费后）
+        // 首次添加流动性：LP = 扣费后数量的加权几何平均（向下取整）
+        // 与 token 的排列顺序无关，第一个 LP 得到确定的数量
         // amounts_in_pool = 扣费后加入池子的量
         // amounts_used_from_user = 用户提供的总量（包括费用）
+        // 任一 token 扣费后为 0 时几何平均为 0，池子会以 0 LP 和非空 vault 初始化，直接拒绝
+        require!(
+            amounts_after_fee.iter().all(|&amount| amount > 0),
+            ErrorCode::InsufficientTokenAmount
+        );
         let mut used_from_user = Vec::with_capacity(token_count);
         for i in 0..token_count {
             used_from_user.push(amounts_after_fee[i] + burn_fees[i]);
         }
-        (amounts_after_fee[0], amounts_after_fee.clone(), used_from_user)
+        let reserves = amounts_after_fee
+            .iter()
+            .map(|&amount| U256::from(amount) * E18)
+            .collect::<Vec<U256>>();
+        let weights = weights.iter().map(|&w| U256::from(w)).collect::<Vec<U256>>();
+        let lp = FixedPoint::geometric_mean(&reserves, &weights)? / E18;
+        // 几何平均不超过最大的数量，ln/exp 的误差可能略微超出
+        require!(lp <= U256::from(u64::MAX), ErrorCode::MathOverflow);
+        // 数量过小时几何平均可能向下取整为 0，不能铸造 0 LP
+        require!(!lp.is_zero(), ErrorCode::InsufficientLiquidity);
+        (lp.as_u64(), amounts_after_fee.clone(), used_from_user)
     } else {
         // 后续添加：找到最小比例，按最小比例计算
         // 计算每个token的比例 ratio_i = amount_i / vault_i
@@ -99,14 +145,16 @@ pub fn add_liquidity_inner(
         let mut min_ratio_index = 0;
 
         for i in 0..token_count {
-            if token_vaults_amount[i] == 0 {
-                continue;
-            }
+            // 已有 LP 时每个 vault 都应有余额；为 0 说明池子状态不一致，不能按比例计算
+            require!(
+                token_vaults_amount[i] > 0,
+                ErrorCode::InconsistentPoolState
+            );
             let amount = U256::from(amounts_after_fee[i]);
             let vault = U256::from(token_vaults_amount[i]);
             
             // ratio = amount * 1e18 / vault（放大1e18避免精度丢失）
-            let ratio = (amount * U256::from(1_000_000_000_000_000_000u64)) / vault;
+            let ratio = (amount * E18) / vault;
             
             if ratio < min_ratio {
                 min_ratio = ratio;
@@ -130,15 +178,22 @@ pub fn add_liquidity_inner(
         for i in 0..token_count {
             let vault = U256::from(token_vaults_amount[i]);
             let amount_in_pool = (vault * lp) / total_lp;
-            amounts_in_pool_vec.push(amount_in_pool.as_u64());
             
             // 计算从用户拿走的总量（包括费用）
             // fee = amount_in_pool * fee_rate / (1 - fee_rate)
             let amount_before_fee = (amount_in_pool * U256::from(fee_denominator)) 
                 / U256::from(fee_denominator - fee_numerator);
+            // 加入后 vault 余额不能超过 swap 的定义域上限
+            require!(
+                vault + amount_before_fee <= U256::from(MAX_DOMAIN_RESERVE),
+                ErrorCode::ReserveOutOfDomain
+            );
+            amounts_in_pool_vec.push(amount_in_pool.as_u64());
             amounts_used_vec.push(amount_before_fee.as_u64());
         }
 
+        // 单次铸造的 LP 数量受 SPL mint 的 u64 限制
+        require!(lp <= U256::from(u64::MAX), ErrorCode::MathOverflow);
         (lp.as_u64(), amounts_in_pool_vec, amounts_used_vec)
     };
 
@@ -150,6 +205,235 @@ pub fn add_liquidity_inner(
     })
 }
 
+/// 单 token 加入流动性时铸造的 LP（Balancer 的 calcBptOutGivenExactTokenIn）
+///
+/// 只存入一个 token 相当于先按比例存入 normalized_weight 的部分，再把其余部分换成其他 token，
+/// 因此只对超出比例的部分收取 swap 手续费：
+/// - balance_ratio_with_fee = (balance + amount_in) / balance
+/// - invariant_ratio_with_fees = balance_ratio_with_fee * w + (1 - w)
+/// - non_taxable = balance * (invariant_ratio_with_fees - 1)，taxable = amount_in - non_taxable
+/// - amount_in_without_fee = non_taxable + taxable * (1 - fee)
+/// - LP = total_lp * ((1 + amount_in_without_fee / balance) ^ w - 1)
+///
+/// normalized_weight: token 的归一化权重（18 位定点数）
+/// 返回 (铸造的 LP 数量, 手续费)，LP 向下取整
+pub fn lp_out_given_exact_token_in(
+    balance: u64,
+    normalized_weight: u64,
+    amount_in: u64,
+    total_lp_supply: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<(u64, u64)> {
+    require!(
+        balance > 0 && total_lp_supply > 0,
+        ErrorCode::InsufficientLiquidity
+    );
+    require!(
+        normalized_weight > 0 && U256::from(normalized_weight) <= FixedPoint::ONE,
+        ErrorCode::InvalidTokenCount
+    );
+    require!(fee_denominator > 0, ErrorCode::InvalidFee);
+    require!(
+        balance as u128 + amount_in as u128 <= MAX_DOMAIN_RESERVE as u128,
+        ErrorCode::ReserveOutOfDomain
+    );
+
+    let weight = U256::from(normalized_weight);
+    let balance_u256 = U256::from(balance);
+    let amount = U256::from(amount_in);
+
+    let balance_ratio_with_fee = FixedPoint::div_down(balance_u256 + amount, balance_u256)?;
+    let invariant_ratio_with_fees = FixedPoint::add(
+        FixedPoint::mul_down(balance_ratio_with_fee, weight)?,
+        FixedPoint::complement(weight)?,
+    )?;
+
+    // 超出比例的部分按 swap 收费
+    let fee = if balance_ratio_with_fee > invariant_ratio_with_fees {
+        let non_taxable = if invariant_ratio_with_fees > FixedPoint::ONE {
+            FixedPoint::mul_down(balance_u256, invariant_ratio_with_fees - FixedPoint::ONE)?
+        } else {
+            U256::zero()
+        };
+        let taxable = amount.saturating_sub(non_taxable);
+        let fee_numerator = taxable * U256::from(fee_numerator);
+        let fee_denominator = U256::from(fee_denominator);
+        (fee_numerator + fee_denominator - U256::one()) / fee_denominator
+    } else {
+        U256::zero()
+    };
+    let amount_without_fee = amount.checked_sub(fee).ok_or(ErrorCode::MathOverflow)?;
+
+    let balance_ratio = FixedPoint::div_down(balance_u256 + amount_without_fee, balance_u256)?;
+    let invariant_ratio = FixedPoint::pow_down(balance_ratio, weight)?;
+    let lp = if invariant_ratio > FixedPoint::ONE {
+        FixedPoint::mul_down(U256::from(total_lp_supply), invariant_ratio - FixedPoint::ONE)?
+    } else {
+        U256::zero()
+    };
+
+    // 单次铸造的 LP 数量受 SPL mint 的 u64 限制
+    require!(lp <= U256::from(u64::MAX), ErrorCode::MathOverflow);
+    Ok((lp.as_u64(), fee.as_u64()))
+}
+
+/// 单 token 移除流动性时得到的 token 数量（Balancer 的 calcTokenOutGivenExactBptIn）
+///
+/// 只取出一个 token 相当于先按比例取出，再把其他 token 换成该 token，
+/// 因此只对超出比例的 (1 - w) 部分收取 swap 手续费：
+/// - balance_ratio = ((total_lp - lp_in) / total_lp) ^ (1 / w)，向上取整
+/// - amount_out_without_fee = balance * (1 - balance_ratio)
+/// - taxable = amount_out_without_fee * (1 - w)
+/// - amount_out = amount_out_without_fee - taxable * fee
+///
+/// normalized_weight: token 的归一化权重（18 位定点数）
+/// 返回 (得到的 token 数量, 手续费)，数量向下取整；结果超过 vault 余额时返回 InsufficientLiquidity
+pub fn token_out_given_exact_lp_in(
+    balance: u64,
+    normalized_weight: u64,
+    lp_in: u64,
+    total_lp_supply: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<(u64, u64)> {
+    require!(
+        balance > 0 && (lp_in as u128) < total_lp_supply,
+        ErrorCode::InsufficientLiquidity
+    );
+    require!(
+        normalized_weight > 0 && U256::from(normalized_weight) <= FixedPoint::ONE,
+        ErrorCode::InvalidTokenCount
+    );
+    require!(fee_denominator > 0, ErrorCode::InvalidFee);
+
+    let weight = U256::from(normalized_weight);
+    let balance_u256 = U256::from(balance);
+    let total_lp = U256::from(total_lp_supply);
+
+    let invariant_ratio = FixedPoint::div_up(total_lp - U256::from(lp_in), total_lp)?;
+    let balance_ratio = FixedPoint::pow_up(invariant_ratio, FixedPoint::div_down(FixedPoint::ONE, weight)?)?;
+    let amount_out_without_fee =
+        FixedPoint::mul_down(balance_u256, FixedPoint::complement(balance_ratio)?)?;
+
+    // 超出比例的部分按 swap 收费
+    let taxable = FixedPoint::mul_up(amount_out_without_fee, FixedPoint::complement(weight)?)?;
+    let fee_numerator = taxable * U256::from(fee_numerator);
+    let fee_denominator = U256::from(fee_denominator);
+    let fee = (fee_numerator + fee_denominator - U256::one()) / fee_denominator;
+    let amount_out = amount_out_without_fee.saturating_sub(fee);
+
+    require!(amount_out <= balance_u256, ErrorCode::InsufficientLiquidity);
+    Ok((amount_out.as_u64(), fee.min(amount_out_without_fee).as_u64()))
+}
+
+/// 检查铸造的 LP 不少于 min_lp_out（滑点保护），min_lp_out 为 0 表示不限制
+pub fn check_min_lp_out(lp_minted: u64, min_lp_out: u64) -> Result<()> {
+    require!(lp_minted >= min_lp_out, ErrorCode::SlippageExceeded);
+    Ok(())
+}
+
+/// 按比例加入时，铸造 lp 个 LP 所需的最少 amounts_in（扣费前）
+///
+/// 每个 token 扣费后至少为 ceil(lp * vault_i / total_lp)；
+/// 实际铸造的数量由 add_liquidity_inner 按最小比例计算，可能略多于 lp
+pub fn amounts_for_lp(
+    token_vaults_amount: &[u64],
+    lp: u64,
+    total_lp_supply: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<Vec<u64>> {
+    require!(total_lp_supply > 0, ErrorCode::InsufficientLiquidity);
+    require!(fee_numerator < fee_denominator, ErrorCode::MathOverflow);
+    let total_lp = U256::from(total_lp_supply);
+    let numerator = U256::from(fee_numerator);
+    let denominator = U256::from(fee_denominator);
+    let after_fee = |amount: U256| amount - amount * numerator / denominator;
+
+    token_vaults_amount
+        .iter()
+        .map(|&vault| {
+            let target = (U256::from(lp) * U256::from(vault) + total_lp - 1) / total_lp;
+            let keep = denominator - numerator;
+            let mut amount = (target * denominator + keep - 1) / keep;
+            // 上面的估计不会偏小，最多多出 1 个单位
+            while !amount.is_zero() && after_fee(amount - 1) >= target {
+                amount -= U256::one();
+            }
+            require!(amount <= U256::from(u64::MAX), ErrorCode::MathOverflow);
+            Ok(amount.as_u64())
+        })
+        .collect()
+}
+
+/// 不超过上限的最大按比例加入，返回 (amounts_in, lp_minted)
+///
+/// 加入时受两个上限约束（由 add_liquidity_inner 检查）：
+/// - 单次铸造的 LP 不超过 u64::MAX（SPL mint 的限制）
+/// - 加入后每个 vault 的余额不超过 MAX_DOMAIN_RESERVE
+///
+/// 先按上限直接算出 LP 的上界，上界不可行时（取整导致实际铸造更多）再二分查找
+pub fn max_joinable_inner(
+    token_vaults_amount: &[u64],
+    total_lp_supply: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<(Vec<u64>, u64)> {
+    require!(total_lp_supply > 0, ErrorCode::InsufficientLiquidity);
+    require!(fee_numerator < fee_denominator, ErrorCode::MathOverflow);
+    let total_lp = U256::from(total_lp_supply);
+    let keep = U256::from(fee_denominator - fee_numerator);
+    let denominator = U256::from(fee_denominator);
+
+    // vault_i + floor(floor(vault_i * lp / total_lp) * den / keep) <= MAX_DOMAIN_RESERVE
+    let mut upper = U256::from(u64::MAX);
+    for &vault in token_vaults_amount.iter() {
+        require!(vault > 0, ErrorCode::InconsistentPoolState);
+        let room = U256::from(MAX_DOMAIN_RESERVE - vault);
+        let max_in_pool = ((room + 1) * keep - 1) / denominator;
+        let max_lp = ((max_in_pool + 1) * total_lp - 1) / U256::from(vault);
+        upper = upper.min(max_lp);
+    }
+
+    let join = |lp: u64| -> Option<(Vec<u64>, u64)> {
+        let amounts = amounts_for_lp(
+            token_vaults_amount,
+            lp,
+            total_lp_supply,
+            fee_numerator,
+            fee_denominator,
+        )
+        .ok()?;
+        let result = add_liquidity_inner(
+            token_vaults_amount,
+            &amounts,
+            total_lp_supply,
+            fee_numerator,
+            fee_denominator,
+        )
+        .ok()?;
+        Some((amounts, result.lp_minted))
+    };
+
+    let mut high = upper.as_u64();
+    if let Some(found) = join(high) {
+        return Ok(found);
+    }
+    // join(0) 总是可行；join(high) 不可行
+    let mut low = 0u64;
+    high -= 1;
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if join(mid).is_some() {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    join(low).ok_or(ErrorCode::MathOverflow.into())
+}
+
 /// 移除流动性（CPMM模型）
 ///
 /// 用户销毁LP token，按比例获得所有token
@@ -167,14 +451,14 @@ pub fn add_liquidity_inner(
 pub fn remove_liquidity_inner(
     token_vaults_amount: &[u64],
     lp_to_burn: u64,
-    total_lp_supply: u64,
+    total_lp_supply: u128,
     fee_numerator: u64,
     fee_denominator: u64,
 ) -> Result<RemoveLiquidityResult> {
     let token_count = token_vaults_amount.len();
 
     require!(
-        lp_to_burn <= total_lp_supply,
+        lp_to_burn as u128 <= total_lp_supply,
         ErrorCode::InsufficientLiquidity
     );
     require!(total_lp_supply > 0, ErrorCode::InsufficientLiquidity);
@@ -207,12 +491,148 @@ pub fn remove_liquidity_inner(
     })
 }
 
+/// 上架新 token 并注入初始流动性时应铸造给管理员的 LP 数量（向下取整）
+///
+/// 加权池中每个 token 占池子总价值的比例等于 weight / weight_sum，上架权重为 new_weight 的 token 后，
+/// 原有 token 的价值占比变为 weight_sum / (weight_sum + new_weight)。
+/// 按 LP = total_lp_supply * new_weight / weight_sum 铸造，原有 LP 每份对应的价值保持不变
+///
+/// 前提是注入的流动性确实值池子价值的 new_weight / weight_sum，由 check_listing_value 校验
+///
+/// 池子还没有 LP（total_lp_supply 为 0）或原有权重之和为 0 时不铸造，由之后的首次添加流动性确定 LP
+pub fn listing_lp_to_mint(total_lp_supply: u128, weight_sum: u64, new_weight: u64) -> Result<u64> {
+    if total_lp_supply == 0 || weight_sum == 0 {
+        return Ok(0);
+    }
+    let lp = U256::from(total_lp_supply) * U256::from(new_weight) / U256::from(weight_sum);
+    require!(lp <= U256::from(u64::MAX), ErrorCode::MathOverflow);
+    Ok(lp.as_u64())
+}
+
+/// 校验上架 token 的初始流动性按 pool 现货价格计价后不低于新 token 权重对应的价值
+///
+/// pool 无法为新 token 定价，由管理员声明初始流动性按某个已有 token（quote token）计价的价值 liquidity_value。
+/// 加权池中 quote token 的余额占池子总价值的 quote_weight / weight_sum，
+/// 因此按铸造的 listing LP（池子价值的 new_weight / weight_sum）计算，
+/// 初始流动性至少应值 quote_balance * new_weight / quote_weight（向上取整），不足时返回 ListingUnderfunded。
+/// 通过校验时 listing_lp_to_mint 铸造的 LP 不超过 total_lp_supply * liquidity_value / 池子价值，原有 LP 不被稀释
+pub fn check_listing_value(
+    quote_balance: u64,
+    quote_weight: u64,
+    new_weight: u64,
+    liquidity_value: u64,
+) -> Result<()> {
+    require!(quote_balance > 0, ErrorCode::InsufficientLiquidity);
+    require!(quote_weight > 0, ErrorCode::InvalidWeight);
+    let numerator = U256::from(quote_balance) * U256::from(new_weight);
+    let required = (numerator + U256::from(quote_weight - 1)) / U256::from(quote_weight);
+    require!(
+        U256::from(liquidity_value) >= required,
+        ErrorCode::ListingUnderfunded
+    );
+    Ok(())
+}
+
+/// 移除仍有流动性的 token 时管理员需要销毁的 LP 数量（向上取整）
+///
+/// 被移除 token 的价值占比为 removed_weight / weight_sum（见 listing_lp_to_mint），
+/// 管理员取走该 token 的 vault 余额，同时销毁 total_lp_supply * removed_weight / weight_sum 的 LP，
+/// 剩余 LP 每份对应的价值保持不变
+pub fn removed_token_lp_to_burn(total_lp_supply: u128, weight_sum: u64, removed_weight: u64) -> Result<u64> {
+    require!(
+        weight_sum > 0 && removed_weight <= weight_sum,
+        ErrorCode::InvalidWeight
+    );
+    let numerator = U256::from(total_lp_supply) * U256::from(removed_weight);
+    let lp = (numerator + U256::from(weight_sum - 1)) / U256::from(weight_sum);
+    require!(lp <= U256::from(u64::MAX), ErrorCode::MathOverflow);
+    Ok(lp.as_u64())
+}
+
+impl AnySwapPool {
+    /// 按当前 LP 总量和费率预览 add_liquidity 实际从用户拿走的每个 token 数量（含手续费）
+    ///
+    /// 与 add_liquidity 使用同一计算，储备和 LP 总量不变时两者完全一致，可直接作为授权额度
+    pub fn preview_amounts_used(&self, vault_balances: &[u64], amounts_in: &[u64]) -> Result<Vec<u64>> {
+        let result = add_liquidity_inner(
+            vault_balances,
+            amounts_in,
+            self.get_total_amount_minted(),
+            self.get_fee_numerator(),
+            self.get_fee_denominator(),
+        )?;
+        Ok(result.amounts_used)
+    }
+
+    /// 单 token 加入流动性（见 lp_out_given_exact_token_in），使用 now 时刻的归一化权重（见 current_normalized_weight）和 pool 费率
+    ///
+    /// 返回的 AddLiquidityResult 中只有 token_index 一项非 0：
+    /// amounts_used 为用户转入的全部数量，amounts_in 为扣除手续费后计入本金的数量
+    pub fn add_liquidity_single(
+        &self,
+        token_index: usize,
+        balance: u64,
+        amount_in: u64,
+        now: i64,
+    ) -> Result<AddLiquidityResult> {
+        let token_count = self.get_token_count();
+        let (lp_minted, fee) = lp_out_given_exact_token_in(
+            balance,
+            self.current_normalized_weight(token_index, now)?,
+            amount_in,
+            self.get_total_amount_minted(),
+            self.get_fee_numerator(),
+            self.get_fee_denominator(),
+        )?;
+
+        let mut result = AddLiquidityResult {
+            lp_minted,
+            burn_fees: vec![0; token_count],
+            amounts_used: vec![0; token_count],
+            amounts_in: vec![0; token_count],
+        };
+        result.burn_fees[token_index] = fee;
+        result.amounts_used[token_index] = amount_in;
+        result.amounts_in[token_index] = amount_in - fee;
+        Ok(result)
+    }
+
+    /// 单 token 移除流动性（见 token_out_given_exact_lp_in），使用 now 时刻的归一化权重（见 current_normalized_weight）和 pool 费率
+    ///
+    /// 返回的 RemoveLiquidityResult 中只有 token_index 一项非 0
+    pub fn remove_liquidity_single(
+        &self,
+        token_index: usize,
+        balance: u64,
+        burn_amount: u64,
+        now: i64,
+    ) -> Result<RemoveLiquidityResult> {
+        let token_count = self.get_token_count();
+        let (amount_out, fee) = token_out_given_exact_lp_in(
+            balance,
+            self.current_normalized_weight(token_index, now)?,
+            burn_amount,
+            self.get_total_amount_minted(),
+            self.get_fee_numerator(),
+            self.get_fee_denominator(),
+        )?;
+
+        let mut result = RemoveLiquidityResult {
+            amounts_out: vec![0; token_count],
+            burn_fees: vec![0; token_count],
+        };
+        result.amounts_out[token_index] = amount_out;
+        result.burn_fees[token_index] = fee;
+        Ok(result)
+    }
+}
+
 impl LiquidityProtocol for AnySwapPool {
     fn add_liquidity<'info>(
         &self,
         token_vaults_amount: &[u64],
         amounts_in: &[u64],
-        total_lp_supply: u64,
+        total_lp_supply: u128,
         fee_numerator: u64,
         fee_denominator: u64,
     ) -> Result<AddLiquidityResult> {
@@ -229,7 +649,7 @@ impl LiquidityProtocol for AnySwapPool {
         &self,
         token_vaults_amount: &[u64],
         lp_to_burn: u64,
-        total_lp_supply: u64,
+        total_lp_supply: u128,
         fee_numerator: u64,
         fee_denominator: u64,
     ) -> Result<RemoveLiquidityResult> {
@@ -247,6 +667,108 @@ impl LiquidityProtocol for AnySwapPool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_single_sided_matches_proportional_path() {
+        const HALF: u64 = 500_000_000_000_000_000;
+        // 50/50 的池子，储备各 1e9，LP 总量 1e9
+        let balances = [1_000_000_000u64, 1_000_000_000];
+        let supply = 1_000_000_000u128;
+        let proportional =
+            add_liquidity_inner(&balances, &[1_000_000, 1_000_000], supply, 0, 1).unwrap();
+
+        // 无手续费时依次单边存入两个 token，得到的 LP 与按比例存入一致（误差来自取整）
+        let (lp_0, fee_0) =
+            lp_out_given_exact_token_in(balances[0], HALF, 1_000_000, supply, 0, 1).unwrap();
+        let (lp_1, _) = lp_out_given_exact_token_in(
+            balances[1],
+            HALF,
+            1_000_000,
+            supply + lp_0 as u128,
+            0,
+            1,
+        )
+        .unwrap();
+        assert_eq!(fee_0, 0);
+        let single_total = lp_0 + lp_1;
+        assert!(
+            single_total <= proportional.lp_minted && proportional.lp_minted - single_total <= 2,
+            "single {} proportional {}",
+            single_total,
+            proportional.lp_minted
+        );
+
+        // 有手续费时只对超出比例的一半收费：0.3% * 1e6 * 0.5 = 1500
+        let (lp_fee, fee) =
+            lp_out_given_exact_token_in(balances[0], HALF, 1_000_000, supply, 3, 1000).unwrap();
+        assert_eq!(fee, 1_500);
+        assert!(lp_fee < lp_0);
+    }
+
+    #[test]
+    fn test_single_sided_remove_round_trips_add() {
+        const QUARTER: u64 = 250_000_000_000_000_000;
+        // 80/20 池子中权重为 0.25 的 token，储备 1e9，LP 总量 1e9
+        let balance = 1_000_000_000u64;
+        let supply = 1_000_000_000u128;
+
+        // 无手续费：单边加入再单边取出，数量几乎不变（误差来自 pow 的取整）
+        let (lp, _) = lp_out_given_exact_token_in(balance, QUARTER, 10_000_000, supply, 0, 1).unwrap();
+        let (out, fee) = token_out_given_exact_lp_in(
+            balance + 10_000_000,
+            QUARTER,
+            lp,
+            supply + lp as u128,
+            0,
+            1,
+        )
+        .unwrap();
+        assert_eq!(fee, 0);
+        assert!(out <= 10_000_000 && 10_000_000 - out < 1_000, "out {}", out);
+
+        // 0.3% 手续费：两次都只对超出比例的 75% 收费，往返损失约 2 * 0.3% * 75%
+        let (lp, add_fee) =
+            lp_out_given_exact_token_in(balance, QUARTER, 10_000_000, supply, 3, 1000).unwrap();
+        let (out, remove_fee) = token_out_given_exact_lp_in(
+            balance + 10_000_000,
+            QUARTER,
+            lp,
+            supply + lp as u128,
+            3,
+            1000,
+        )
+        .unwrap();
+        assert!(add_fee > 0 && remove_fee > 0);
+        let loss = 10_000_000 - out;
+        assert!(loss > 40_000 && loss < 50_000, "loss {}", loss);
+
+        // 销毁全部 LP 或从空 vault 取出
+        assert!(token_out_given_exact_lp_in(balance, QUARTER, 1_000_000_000, supply, 0, 1).is_err());
+        assert!(token_out_given_exact_lp_in(0, QUARTER, 1, supply, 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_add_liquidity_unbalanced_caps_to_limiting_token() {
+        // 储备 1:2:4，用户提供的数量严重失衡，token 1 为约束（比例 1%）
+        let vaults = vec![1_000_000u64, 2_000_000, 4_000_000];
+        let amounts_in = vec![50_000u64, 20_000, 400_000];
+        let total_lp = 1_000_000u128;
+        let result = add_liquidity_inner(&vaults, &amounts_in, total_lp, 0, 1).unwrap();
+
+        // LP 按最小比例铸造
+        assert_eq!(result.lp_minted, 10_000);
+        // 每个 token 只按比例使用，不超过用户提供的数量
+        assert_eq!(result.amounts_used, vec![10_000, 20_000, 40_000]);
+        for (used, provided) in result.amounts_used.iter().zip(amounts_in.iter()) {
+            assert!(used <= provided);
+        }
+
+        // min_lp_out 的滑点保护
+        assert!(check_min_lp_out(result.lp_minted, 10_000).is_ok());
+        assert!(check_min_lp_out(result.lp_minted, 0).is_ok());
+        let err = check_min_lp_out(result.lp_minted, 10_001).unwrap_err();
+        assert_eq!(err, ErrorCode::SlippageExceeded.into());
+    }
+
     #[test]
     fn test_add_liquidity_bootstrap() {
         // 测试首次添加流动性
@@ -266,7 +788,7 @@ mod tests {
             3_000_000,
             4_000_000,
         ];
-        let total_lp_supply = 0u64;
+        let total_lp_supply = 0u128;
         let fee_numerator = 3u64;
         let fee_denominator = 10000u64;
 
@@ -279,10 +801,9 @@ mod tests {
             )
         .unwrap();
 
-        // 验证LP铸造数量 = 第一个token扣费后的数量
-        let expected_lp = amounts_in[0] - (amounts_in[0] * fee_numerator / fee_denominator);
-        assert_eq!(result.lp_minted, expected_lp);
-        assert_eq!(result.lp_minted, 999_700);
+        // 验证LP铸造数量 = 扣费后数量的几何平均（等权重）
+        // (999_700 * 4_998_500 * 9_997_000 * 1_999_400 * 2_999_100 * 3_998_800)^(1/6) ≈ 3_258_866.47
+        assert_eq!(result.lp_minted, 3_258_866);
 
         // 验证费用
         for i in 0..amounts_in.len() {
@@ -294,6 +815,117 @@ mod tests {
         println!("  铸造LP: {}", result.lp_minted);
     }
 
+    #[test]
+    fn test_add_liquidity_bootstrap_geometric_mean() {
+        // 3 token 等权重：LP = (1e9 * 4e9 * 2e9)^(1/3) = 2e9
+        let amounts_in = [1_000_000_000u64, 4_000_000_000, 2_000_000_000];
+        let result = add_liquidity_inner(&[0, 0, 0], &amounts_in, 0, 0, 1).unwrap();
+        assert!(result.lp_minted.abs_diff(2_000_000_000) <= 1, "lp = {}", result.lp_minted);
+        assert_eq!(result.amounts_in, amounts_in.to_vec());
+        assert_eq!(result.amounts_used, amounts_in.to_vec());
+
+        // 结果与 token 的顺序无关，且是确定的
+        let reordered = add_liquidity_inner(&[0, 0, 0], &[2_000_000_000, 1_000_000_000, 4_000_000_000], 0, 0, 1).unwrap();
+        assert_eq!(reordered.lp_minted, result.lp_minted);
+
+        // 等量存入时 LP 与每个 token 的数量一致（误差不超过 1）
+        let equal = add_liquidity_inner(&[0, 0, 0], &[5_000_000; 3], 0, 0, 1).unwrap();
+        assert!(equal.lp_minted.abs_diff(5_000_000) <= 1, "lp = {}", equal.lp_minted);
+
+        // 权重 80:20：LP = 16e8^0.8 * 1e8^0.2 = 1e8 * 16^0.8 ≈ 9.19e8
+        let weighted =
+            add_liquidity_inner_with_weights(&[0, 0], &[1_600_000_000, 100_000_000], 0, 0, 1, &[80, 20]).unwrap();
+        let expected = 100_000_000f64 * 16f64.powf(0.8);
+        assert!((weighted.lp_minted as f64 - expected).abs() <= 1.0, "lp = {}", weighted.lp_minted);
+
+        // 任一 token 数量为 0 时拒绝，而不是以 0 LP 初始化池子
+        let err = add_liquidity_inner(&[0, 0, 0], &[0, 1_000, 1_000], 0, 0, 1).err().unwrap();
+        assert_eq!(err, ErrorCode::InsufficientTokenAmount.into());
+        for amounts in [[1_000u64, 1_000, 0], [0, 0, 0]] {
+            let err = add_liquidity_inner(&[0, 0, 0], &amounts, 0, 3, 1000).err().unwrap();
+            assert_eq!(err, ErrorCode::InsufficientTokenAmount.into());
+        }
+        // 最小的非零数量仍然铸造出 LP
+        assert_eq!(add_liquidity_inner(&[0, 0], &[1, 1], 0, 0, 1).unwrap().lp_minted, 1);
+    }
+
+    #[test]
+    fn test_add_liquidity_supply_past_u64_max() {
+        // LP 总供应量已超过 u64::MAX，继续加入流动性仍然成功
+        let vaults = vec![1_000_000_000u64, 2_000_000_000u64];
+        let total_lp_supply = u64::MAX as u128 * 4;
+        let amounts_in = vec![1_000u64, 2_000u64];
+
+        let result = add_liquidity_inner(&vaults, &amounts_in, total_lp_supply, 0, 10000).unwrap();
+
+        // LP = total_LP * amount / vault = 4 * u64::MAX * 1e3 / 1e9
+        let expected_lp = (total_lp_supply * 1_000 / 1_000_000_000) as u64;
+        assert_eq!(result.lp_minted, expected_lp);
+        // 向下取整，偏向池子
+        assert_eq!(result.amounts_in, vec![999u64, 1_999u64]);
+
+        let new_supply = total_lp_supply
+            .checked_add(result.lp_minted as u128)
+            .unwrap();
+        assert!(new_supply > u64::MAX as u128);
+
+        // 按超出 u64 的总量移除流动性
+        let vaults = vec![vaults[0] + 999, vaults[1] + 1_999];
+        let removed = remove_liquidity_inner(&vaults, result.lp_minted, new_supply, 0, 10000).unwrap();
+        assert!(removed.amounts_out[0] <= 999 && removed.amounts_out[0] >= 998);
+        assert!(removed.amounts_out[1] <= 1_999 && removed.amounts_out[1] >= 1_998);
+    }
+
+    #[test]
+    fn test_add_liquidity_single_mint_exceeds_u64() {
+        // 单次铸造超过 u64::MAX 时拒绝（SPL mint 限制）
+        let vaults = vec![1u64, 1u64];
+        let total_lp_supply = u64::MAX as u128;
+        let amounts_in = vec![2u64, 2u64];
+        assert!(add_liquidity_inner(&vaults, &amounts_in, total_lp_supply, 0, 10000).is_err());
+    }
+
+    #[test]
+    fn test_max_joinable() {
+        // (vaults, total_lp_supply)：前者受 vault 余额上限约束，后者受单次铸造 u64::MAX 约束
+        let cases: Vec<(Vec<u64>, u128)> = vec![
+            (vec![u64::MAX / 2, u64::MAX / 4, 1_000_000_000], 1_000_000_000_000),
+            (vec![1_000, 3_000], 1u128 << 70),
+        ];
+        for (vaults, total_lp_supply) in cases {
+            let (amounts_in, lp) = max_joinable_inner(&vaults, total_lp_supply, 3, 1000).unwrap();
+            assert!(lp > 0);
+
+            // 按返回的最大值加入成功
+            let result = add_liquidity_inner(&vaults, &amounts_in, total_lp_supply, 3, 1000).unwrap();
+            assert_eq!(result.lp_minted, lp);
+            for (i, &used) in result.amounts_used.iter().enumerate() {
+                assert!(vaults[i] as u128 + used as u128 <= u64::MAX as u128);
+            }
+
+            // 多铸造 1 个 LP 就会超过上限
+            let more = amounts_for_lp(&vaults, lp + 1, total_lp_supply, 3, 1000).unwrap();
+            assert!(add_liquidity_inner(&vaults, &more, total_lp_supply, 3, 1000).is_err());
+        }
+
+        // 空池子没有比例可言
+        assert!(max_joinable_inner(&[1_000, 1_000], 0, 3, 1000).is_err());
+    }
+
+    #[test]
+    fn test_add_liquidity_zero_vault_inconsistent() {
+        // 已有 LP 但某个 vault 余额为 0：返回 InconsistentPoolState 而不是除零
+        let vaults = vec![1_000_000u64, 0, 2_000_000];
+        let amounts_in = vec![10_000u64, 10_000, 20_000];
+        let err = add_liquidity_inner(&vaults, &amounts_in, 1_000_000, 3, 10000)
+            .err()
+            .unwrap();
+        assert_eq!(err, ErrorCode::InconsistentPoolState.into());
+
+        // 首次添加（total_lp_supply 为 0）不受影响
+        assert!(add_liquidity_inner(&[0, 0], &[1_000, 2_000], 0, 3, 10000).is_ok());
+    }
+
     #[test]
     fn test_add_liquidity_subsequent() {
         // 测试第二次添加流动性
@@ -309,7 +941,7 @@ mod tests {
         let amounts_in = vec![
             500_000u64, 2_500_000, 5_000_000, 1_000_000, 1_500_000, 2_000_000,
         ];
-        let total_lp_supply = 999_700u64;
+        let total_lp_supply = 999_700u128;
         let fee_numerator = 3u64;
         let fee_denominator = 10000u64;
 
@@ -326,7 +958,7 @@ mod tests {
         // LP = total_LP * (amount_0_after_fee / vault_0)
         let amount_0_after_fee = amounts_in[0] - (amounts_in[0] * fee_numerator / fee_denominator);
         let expected_lp =
-            (amount_0_after_fee as u128 * total_lp_supply as u128 / vaults[0] as u128) as u64;
+            (amount_0_after_fee as u128 * total_lp_supply / vaults[0] as u128) as u64;
 
         assert_eq!(result.lp_minted, expected_lp);
         assert_eq!(result.lp_minted, 45_428);
@@ -349,7 +981,7 @@ mod tests {
             32_999_100,
             43_998_800,
         ];
-        let total_lp_supply = 999_700u64;
+        let total_lp_supply = 999_700u128;
         
         // 用户提供不等比例的token（故意让比例不一致）
         // 正常比例应该是 5:25:50:10:15:20
@@ -757,7 +1389,7 @@ mod tests {
             45_998_200,
         ];
         let lp_to_burn = 499_850u64; // 第一次LP的50%
-        let total_lp_supply = 1_045_128u64; // 999_700 + 45_428
+        let total_lp_supply = 1_045_128u128; // 999_700 + 45_428
         let fee_numerator = 3u64;
         let fee_denominator = 10000u64;
 
@@ -818,7 +1450,7 @@ mod tests {
             30_000_000,
             40_000_000,
         ];
-        let mut total_lp_supply = 0u64;
+        let mut total_lp_supply = 0u128;
 
         println!("\n1. 初始状态:");
         println!("   储备: {:?}", vaults);
@@ -846,7 +1478,7 @@ mod tests {
         for i in 0..vaults.len() {
             vaults[i] += result_1.amounts_in[i];
         }
-        total_lp_supply += result_1.lp_minted;
+        total_lp_supply += result_1.lp_minted as u128;
 
         println!("\n2. 第一次添加后:");
         println!("   铸造LP: {}", result_1.lp_minted);
@@ -869,7 +1501,7 @@ mod tests {
         for i in 0..vaults.len() {
             vaults[i] += result_2.amounts_in[i];
         }
-        total_lp_supply += result_2.lp_minted;
+        total_lp_supply += result_2.lp_minted as u128;
 
         println!("\n3. 第二次添加后:");
         println!("   铸造LP: {}", result_2.lp_minted);
@@ -890,7 +1522,7 @@ mod tests {
         for i in 0..vaults.len() {
             vaults[i] -= result_3.amounts_out[i] + result_3.burn_fees[i];
         }
-        total_lp_supply -= lp_to_burn;
+        total_lp_supply -= lp_to_burn as u128;
 
         println!("\n4. 移除流动性后:");
         println!("   销毁LP: {}", lp_to_burn);
@@ -900,4 +1532,140 @@ mod tests {
 
         println!("\n✅ 完整流动性周期测试通过！");
     }
+
+    /// 以 token0 计价的每份 LP 价值（18 位定点数），token i 的价格取池子的现货价格
+    fn value_per_share(vaults: &[u64], weights: &[u64], lp_supply: u128) -> U256 {
+        let total: U256 = vaults
+            .iter()
+            .zip(weights.iter())
+            .map(|(&balance, &weight)| {
+                let price = crate::state::swap::spot_price(vaults[0], weights[0], balance, weight).unwrap();
+                U256::from(balance) * U256::from(price)
+            })
+            .fold(U256::zero(), |acc, v| acc + v);
+        total / U256::from(lp_supply)
+    }
+
+    #[test]
+    fn test_listing_keeps_existing_share_value() {
+        let mut vaults = vec![1_000_000_000u64, 2_000_000_000];
+        let mut weights = vec![50u64, 50];
+        let lp_supply = 1_000_000_000u128;
+        let before = value_per_share(&vaults, &weights, lp_supply);
+
+        // 上架权重 25 的新 token，并注入 5 亿单位的初始流动性
+        let weight_sum: u64 = weights.iter().sum();
+        let minted = listing_lp_to_mint(lp_supply, weight_sum, 25).unwrap();
+        assert_eq!(minted, 250_000_000);
+        vaults.push(500_000_000);
+        weights.push(25);
+        let after = value_per_share(&vaults, &weights, lp_supply + minted as u128);
+
+        // 原有 LP 每份的价值不变（只差现货价格的取整误差）
+        let diff = if after > before { after - before } else { before - after };
+        assert!(diff * U256::from(1_000_000_000u64) <= before, "before {} after {}", before, after);
+
+        // 不铸造时原有 LP 的价值被上架的 token 抬高
+        let undiluted = value_per_share(&vaults, &weights, lp_supply);
+        assert!(undiluted > before * U256::from(12) / U256::from(10));
+    }
+
+    #[test]
+    fn test_removed_token_burn_keeps_remaining_share_value() {
+        let vaults = vec![1_000_000_000u64, 2_000_000_000, 500_000_000];
+        let weights = vec![50u64, 50, 25];
+        let lp_supply = 1_250_000_000u128;
+        let before = value_per_share(&vaults, &weights, lp_supply);
+
+        // 移除权重 25 的 token，管理员取走其 vault 余额并销毁 1/5 的 LP
+        let burned = removed_token_lp_to_burn(lp_supply, 125, 25).unwrap();
+        assert_eq!(burned, 250_000_000);
+        let after = value_per_share(&vaults[..2], &weights[..2], lp_supply - burned as u128);
+
+        // 剩余 LP 每份的价值不变（只差现货价格的取整误差）
+        let diff = if after > before { after - before } else { before - after };
+        assert!(diff * U256::from(1_000_000_000u64) <= before, "before {} after {}", before, after);
+
+        // 不销毁 LP 时被移除 token 的份额全部归管理员，剩余 LP 每份的价值下降
+        let diluted = value_per_share(&vaults[..2], &weights[..2], lp_supply);
+        assert!(diluted < before * U256::from(9) / U256::from(10));
+    }
+
+    #[test]
+    fn test_removed_token_burn_rounds_up() {
+        // 向上取整，偏向剩余的 LP
+        assert_eq!(removed_token_lp_to_burn(10, 3, 1).unwrap(), 4);
+        assert_eq!(removed_token_lp_to_burn(0, 3, 1).unwrap(), 0);
+        assert_eq!(removed_token_lp_to_burn(1_000, 100, 100).unwrap(), 1_000);
+        assert!(removed_token_lp_to_burn(1_000, 0, 0).is_err());
+        assert!(removed_token_lp_to_burn(1_000, 10, 11).is_err());
+    }
+
+    #[test]
+    fn test_underfunded_listing_is_rejected() {
+        // 市场价格（以 token0 计，乘 2 避免小数）：token0 = 1，token1 = 0.5（与 pool 现货价格一致），新 token = 2
+        let prices_x2 = [2u128, 1, 4];
+        let vaults = [1_000_000_000u64, 2_000_000_000];
+        let weights = [50u64, 50];
+        let lp_supply = 1_000_000_000u128;
+        let minted = listing_lp_to_mint(lp_supply, 100, 25).unwrap() as u128;
+        let value_x2 = |new_liquidity: u64| {
+            vaults
+                .iter()
+                .zip(prices_x2.iter())
+                .map(|(&balance, &price)| balance as u128 * price)
+                .sum::<u128>()
+                + new_liquidity as u128 * prices_x2[2]
+        };
+
+        // 足额上架：2.5 亿个新 token 值 5 亿 token0，原有 LP 每份的价值不变
+        check_listing_value(vaults[0], weights[0], 25, 500_000_000).unwrap();
+        assert_eq!(value_x2(250_000_000) * lp_supply, value_x2(0) * (lp_supply + minted));
+
+        // 只注入 1 个新 token（值 2 个 token0）：按权重铸造 LP 会稀释原有 LP，按声明的价值校验不通过
+        assert!(value_x2(1) * lp_supply < value_x2(0) * (lp_supply + minted) * 9 / 10);
+        assert_eq!(
+            check_listing_value(vaults[0], weights[0], 25, 2).unwrap_err(),
+            error!(ErrorCode::ListingUnderfunded)
+        );
+        assert!(check_listing_value(vaults[0], weights[0], 25, 499_999_999).is_err());
+
+        // quote token 没有余额或权重时无法计价
+        assert!(check_listing_value(0, 50, 25, 1_000).is_err());
+        assert!(check_listing_value(1_000, 0, 25, 1_000).is_err());
+    }
+
+    #[test]
+    fn test_listing_without_lp_mints_nothing() {
+        assert_eq!(listing_lp_to_mint(0, 100, 25).unwrap(), 0);
+        assert_eq!(listing_lp_to_mint(1_000, 0, 25).unwrap(), 0);
+        // 向下取整，偏向原有 LP
+        assert_eq!(listing_lp_to_mint(10, 3, 1).unwrap(), 3);
+        assert!(listing_lp_to_mint(u128::MAX, 1, 2).is_err());
+    }
+
+    #[test]
+    fn test_preview_amounts_used_matches_join() {
+        let mut pool: Box<AnySwapPool> = Box::new(bytemuck::Zeroable::zeroed());
+        pool.set_fee(3, 1000).unwrap();
+        pool.set_total_amount_minted(1_000_000);
+        let mut vaults = vec![10_000_000u64, 20_000_000, 5_000_000];
+        // 第二个 token 给多了，实际只按最小比例使用
+        let amounts_in = vec![1_000_000u64, 5_000_000, 500_000];
+
+        let preview = pool.preview_amounts_used(&vaults, &amounts_in).unwrap();
+        assert!(preview[1] < amounts_in[1]);
+
+        // 随后的 add_liquidity 从用户拿走的数量与预览完全一致
+        let vaults_before = vaults.clone();
+        let result = add_liquidity_inner(&vaults, &amounts_in, 1_000_000, 3, 1000).unwrap();
+        for (vault, used) in vaults.iter_mut().zip(result.amounts_used.iter()) {
+            *vault += used;
+        }
+        let consumed: Vec<u64> = vaults.iter().zip(vaults_before.iter()).map(|(a, b)| a - b).collect();
+        assert_eq!(consumed, preview);
+        for (used, requested) in preview.iter().zip(amounts_in.iter()) {
+            assert!(used <= requested);
+        }
+    }
 }
@@ -2,12 +2,36 @@ pub mod item;
 pub mod pool;
 pub mod swap;
 pub mod liquidity;
+pub mod jit;
+pub mod position;
+pub mod setup;
+pub mod fees;
+pub mod exit;
+pub mod zap;
+pub mod token_account;
+pub mod pda;
+pub mod fee_snapshot;
+pub mod twap;
+pub mod flash_loan;
+pub mod version;
+pub mod route;
 
 pub use item::AnySwapItem;
 pub use pool::MAX_TOKENS;
+pub use pool::DEFAULT_MAX_TOKENS_PER_SWAP;
 pub use pool::AnySwapPool;
 pub use liquidity::LiquidityProtocol;
 pub use liquidity::AddLiquidityResult;
 pub use liquidity::RemoveLiquidityResult;
 pub use swap::SwapProtocol;
-pub use swap::SwapResult;
\ No newline at end of file
+pub use swap::SwapResult;
+pub use swap::SwapRounding;
+pub use jit::JitSettlement;
+pub use position::LpPosition;
+pub use setup::SetupValidation;
+pub use fees::FeeBreakdown;
+pub use exit::PendingExit;
+pub use fee_snapshot::FeeSnapshot;
+pub use twap::TwapObservation;
+pub use version::ProgramVersion;
+pub use route::RouteResult;
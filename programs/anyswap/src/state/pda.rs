@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+/// 计算 (pool, mint) 对应的 vault 地址
+/// seeds 与 add_token_to_pool 创建 vault 时一致：[b"vault", pool, mint]
+/// 纯计算，客户端可以直接调用，不必重复实现 seed 逻辑
+pub fn derive_vault(pool: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", pool.as_ref(), mint.as_ref()], &crate::ID)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_vault_matches_add_token_seeds() {
+        let pool = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (vault, bump) = derive_vault(&pool, &mint);
+
+        // 与 add_token_to_pool 的 init 约束 seeds = [b"vault", pool, mint] + bump 一致
+        let created = Pubkey::create_program_address(
+            &[b"vault", pool.as_ref(), mint.as_ref(), &[bump]],
+            &crate::ID,
+        )
+        .unwrap();
+        assert_eq!(vault, created);
+        assert!(!vault.is_on_curve());
+
+        // 不同的 mint 或 pool 得到不同的 vault
+        assert_ne!(derive_vault(&pool, &Pubkey::new_unique()).0, vault);
+        assert_ne!(derive_vault(&Pubkey::new_unique(), &mint).0, vault);
+    }
+}
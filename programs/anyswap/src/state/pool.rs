@@ -1,12 +1,58 @@
 use anchor_lang::prelude::*;
+use solana_sha256_hasher::hashv;
 use crate::error::ErrorCode;
 use super::item::AnySwapItem;
+use super::twap::{TwapPair, MAX_TWAP_PAIRS};
+use super::fees::BPS_DENOMINATOR;
+use super::swap::{
+    calc_in_given_out, directional_fee_rates, spot_price, spot_price_with_fee, two_token_reserve_out,
+    SwapRounding,
+};
+use crate::math::fixedpoint::FixedPoint;
+use crate::math::scale::E18;
+use crate::math::stable::{MAX_AMPLIFICATION, MIN_AMPLIFICATION};
+use primitive_types::U256;
 use static_assertions::const_assert_eq;
 use std::mem::size_of;
 
 /// 池中最多支持的 token 数量（用于多 token 互相转换）
 pub const MAX_TOKENS: usize = 1024;
 
+/// swap 和增减流动性要求 pool 中至少有的 token 数量
+pub const MIN_ACTIVE_TOKENS: usize = 2;
+
+/// create_pool 时一次操作最多涉及的 token 数量（见 check_tokens_per_swap），管理员可以调整或显式设为 0 关闭
+pub const DEFAULT_MAX_TOKENS_PER_SWAP: u16 = 8;
+
+/// 可加入 pool 的 token 的最大精度，超过 18 位时放大到 18 位精度会变成缩小并损失精度
+pub const MAX_TOKEN_DECIMALS: u8 = 18;
+
+/// token 的最小归一化权重（0.01，18 位定点数），见 validate_weights
+pub const MIN_WEIGHT: u64 = 10_000_000_000_000_000;
+
+/// create_pool 未指定 LP 精度时使用的默认值
+pub const DEFAULT_LP_DECIMALS: u8 = 9;
+
+/// 手续费分母的上限：分母过大时可以表示的费率远小于最小计价单位，
+/// 常规数量下 calculate_fee 会向下取整为 0，配置的手续费实际不生效
+pub const MAX_FEE_DENOMINATOR: u64 = 1_000_000_000;
+
+/// pool 账户的魔数，客户端可通过 getProgramAccounts 的 memcmp 过滤出所有 pool
+pub const POOL_MAGIC: [u8; 4] = *b"ANYS";
+/// pool 账户布局版本，布局变化时递增
+pub const POOL_LAYOUT_VERSION: u8 = 18;
+/// 魔数在账户数据中的偏移（8 字节 discriminator + 2 字节 token_count）
+pub const POOL_MAGIC_OFFSET: usize = 8 + 2;
+
+/// 暂停标志位：swap
+pub const PAUSE_SWAP: u64 = 1 << 0;
+/// 暂停标志位：添加流动性
+pub const PAUSE_ADD_LIQUIDITY: u64 = 1 << 1;
+/// 暂停标志位：移除流动性
+pub const PAUSE_REMOVE_LIQUIDITY: u64 = 1 << 2;
+/// 所有可暂停的操作
+pub const PAUSE_ALL: u64 = PAUSE_SWAP | PAUSE_ADD_LIQUIDITY | PAUSE_REMOVE_LIQUIDITY;
+
 /// AnySwap 池结构
 /// 
 /// 用于存储 token 列表（items 是内部数据，不是程序地址）
@@ -17,33 +63,90 @@ pub const MAX_TOKENS: usize = 1024;
 pub struct AnySwapPool {
     /// 实际使用的 token 数量（账户长度）
     pub token_count: u16,
-    /// 填充字节（确保 admin 8 字节对齐）
-    pub padding: [u8; 6],
+    /// 魔数（POOL_MAGIC），与 layout_version、lp_decimals 一起占用原填充字节，确保 admin 8 字节对齐
+    pub magic: [u8; 4],
+    /// 账户布局版本（POOL_LAYOUT_VERSION）
+    pub layout_version: u8,
+    /// LP mint 的精度，liquidity 指令会校验传入的 pool_mint 与之一致
+    pub lp_decimals: u8,
     /// Pool 管理员 - 用于所有操作的权限控制
     pub admin: Pubkey,
-    /// LP token 总发行量（用于跟踪流动性提供者的份额）
+    /// LP token 总发行量的低 64 位（用于跟踪流动性提供者的份额）
+    /// 完整的 u128 值通过 get_total_amount_minted 读取
     pub total_amount_minted: u64,
     /// 手续费分子
     pub fee_numerator: u64,
     /// 手续费分母
     pub fee_denominator: u64,
+    /// 最短持有期（秒）：添加流动性后需等待该时长才能移除，0 表示不限制
+    pub min_hold_seconds: i64,
+    /// Guardian - 只能暂停操作，不能修改费率、权重等（默认公钥表示未设置）
+    pub guardian: Pubkey,
+    /// 已暂停的操作（PAUSE_* 标志位）
+    pub paused_ops: u64,
+    /// LP token 总发行量的高 64 位
+    /// 与 total_amount_minted 组成 u128，低位保持原偏移以兼容只读取 u64 的客户端
+    pub total_amount_minted_hi: u64,
+    /// LP mint 迁移的目标 mint（默认公钥表示没有进行中的迁移）
+    /// 迁移期间旧 mint（当前的 LP mint，见 lp_mint）和新 mint 同时有效，total_amount_minted 为两者之和
+    pub migration_lp_mint: Pubkey,
+    /// 新 mint 上的 LP 发行量
+    pub migration_lp_supply: u64,
+    /// pool 创建时间（unix 时间戳）
+    pub created_at: i64,
+    /// 最近一次 swap / 添加流动性 / 移除流动性的时间（unix 时间戳），创建时等于 created_at
+    pub last_activity_at: i64,
+    /// 所有 token 配置权重（weight，渐变期间为目标权重）之和（缓存），由 add_token / remove_token / modify_token_weight 维护
+    /// 权重渐变期间实际权重随时间变化，读取当前值用 weight_sum_at；
+    /// 手动迁移数据后可能失效，recompute_weight_sum 从 token 列表重新计算
+    pub weight_sum: u64,
+    /// 稳定组的放大系数 A，0 表示没有稳定组（见 set_stable_group）
+    pub amplification: u64,
+    /// 待接受的新管理员（默认公钥表示没有进行中的管理员转移，见 propose_admin / accept_admin）
+    pub pending_admin: Pubkey,
+    /// TWAP 累加器最近一次推进的时间（unix 时间戳），创建时等于 created_at
+    pub twap_last_update_ts: i64,
+    /// 被跟踪 TWAP 的 token 对（见 set_twap_pair），未使用的槽位 mint_a 为默认公钥
+    pub twap_pairs: [TwapPair; MAX_TWAP_PAIRS],
+    /// 协议费分子：swap 手续费中 protocol_fee_numerator / protocol_fee_denominator 归协议，0 表示不收取
+    pub protocol_fee_numerator: u64,
+    /// 协议费分母
+    pub protocol_fee_denominator: u64,
+    /// 协议费接收者（token 账户的 owner），collect_protocol_fees 将计提的协议费转入其 token 账户
+    pub protocol_fee_recipient: Pubkey,
+    /// 填充，保持 max_tokens_per_swap 之后字段的偏移不变
+    pub _padding: [u8; 2],
+    /// 一次 swap / 增减流动性最多涉及的 token 数量，create_pool 时为 DEFAULT_MAX_TOKENS_PER_SWAP，
+    /// 0 表示不限制（只能由管理员显式设置，见 check_tokens_per_swap）
+    pub max_tokens_per_swap: u16,
+    /// 已发起、尚未全部支付的退出数量（见 request_exit / process_exit）
+    /// 不为 0 时不能移除 token（会改变 token 索引并取走 vault 余额），也不能关闭 pool
+    pub pending_exit_count: u32,
+    /// swap 中每个输入 token 扣费后的最小数量，0 表示不限制（见 check_min_swap_amount）
+    pub min_swap_amount: u64,
+    /// 当前的 LP mint（新的流动性铸造到该 mint），默认公钥表示 pool_mint PDA
+    /// finish_lp_migration 将其换成迁移的目标 mint（见 canonical_lp_mint）
+    pub lp_mint: Pubkey,
     /// Token 配置数组，最多支持 1024 个 token（固定大小）
     /// 每个 item 是内部数据，不是程序地址
     pub tokens: [AnySwapItem; MAX_TOKENS],
 }
 
 // 验证结构体大小和对齐（Solana 要求 8 字节对齐）
-// 计算：2 + 6 + 32 + 8 + 8 + 8 + (72 * 1024) = 73792 bytes
+// 计算：2 + 4 + 1 + 1 + 32 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + (128 * 4) + 8 + 8 + 32 + 8 + 8 + 32 + (144 * 1024) = 148296 bytes
 const_assert_eq!(
     size_of::<AnySwapPool>(),
-    2 + 6 + 32 + 8 + 8 + 8 + (size_of::<AnySwapItem>() * MAX_TOKENS)
+    2 + 4 + 1 + 1 + 32 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 32 + 8
+        + (size_of::<TwapPair>() * MAX_TWAP_PAIRS) + 8 + 8 + 32 + 2 + 2 + 4 + 8 + 32 + (size_of::<AnySwapItem>() * MAX_TOKENS)
 );
-const_assert_eq!(size_of::<AnySwapPool>(), 73792);
+const_assert_eq!(size_of::<AnySwapPool>(), 148296);
 const_assert_eq!(size_of::<AnySwapPool>() % 8, 0); // 必须是 8 的倍数
 
 impl AnySwapPool {
     /// 验证管理员权限
+    /// 无管理员的 pool 拒绝所有管理操作
     pub fn verify_admin(&self, admin: &Pubkey) -> Result<()> {
+        require!(!self.is_admin_less(), ErrorCode::InvalidAdmin);
         require!(
             *admin == self.admin,
             crate::error::ErrorCode::InvalidAdmin
@@ -51,11 +154,85 @@ impl AnySwapPool {
         Ok(())
     }
 
+    /// 是否为无管理员的 pool（admin 为默认公钥）
+    pub fn is_admin_less(&self) -> bool {
+        self.admin == Pubkey::default()
+    }
+
+    /// 确定创建 pool 时写入的管理员
+    /// admin_less 为 true 时显式创建无管理员的 pool，否则拒绝默认公钥作为管理员
+    pub fn resolve_admin(admin: &Pubkey, admin_less: bool) -> Result<Pubkey> {
+        if admin_less {
+            return Ok(Pubkey::default());
+        }
+        require!(*admin != Pubkey::default(), ErrorCode::InvalidAdmin);
+        Ok(*admin)
+    }
+
+    /// 确定创建 pool 时 LP mint 的精度，未指定时为 DEFAULT_LP_DECIMALS
+    pub fn lp_decimals_or_default(lp_decimals: Option<u8>) -> u8 {
+        lp_decimals.unwrap_or(DEFAULT_LP_DECIMALS)
+    }
+
+    /// 确定创建 pool 时写入的费率
+    /// zero_fee 为 true 时显式创建零手续费的 pool（分子必须为 0，分母为 0 时按 1 记录），
+    /// 否则拒绝分母为 0 的费率，避免创建出计算手续费时除以 0 的 pool
+    pub fn resolve_fee(fee_numerator: u64, fee_denominator: u64, zero_fee: bool) -> Result<(u64, u64)> {
+        Self::check_fee_denominator(fee_denominator)?;
+        if zero_fee {
+            require!(fee_numerator == 0, ErrorCode::ZeroFeeMismatch);
+            return Ok((0, fee_denominator.max(1)));
+        }
+        Self::check_fee(fee_numerator, fee_denominator)?;
+        Ok((fee_numerator, fee_denominator))
+    }
+
+    /// 校验一组费率：分母大于 0 且不超过 MAX_FEE_DENOMINATOR，分子不超过分母
+    pub fn check_fee(fee_numerator: u64, fee_denominator: u64) -> Result<()> {
+        Self::check_fee_denominator(fee_denominator)?;
+        require!(fee_denominator > 0, ErrorCode::InvalidFee);
+        require!(fee_numerator <= fee_denominator, ErrorCode::MathOverflow);
+        Ok(())
+    }
+
+    /// 检查手续费分母不超过 MAX_FEE_DENOMINATOR（create_pool / modify_fee 设置费率时调用）
+    pub fn check_fee_denominator(fee_denominator: u64) -> Result<()> {
+        require!(
+            fee_denominator <= MAX_FEE_DENOMINATOR,
+            ErrorCode::FeeDenominatorTooLarge
+        );
+        Ok(())
+    }
+
+    /// 写入魔数和布局版本（创建 pool 时调用）
+    pub fn init_header(&mut self) {
+        self.magic = POOL_MAGIC;
+        self.layout_version = POOL_LAYOUT_VERSION;
+    }
+
+    /// 账户是否带有 pool 魔数
+    pub fn has_valid_magic(&self) -> bool {
+        self.magic == POOL_MAGIC
+    }
+
     /// 获取实际使用的 token 数量
     pub fn get_token_count(&self) -> usize {
         self.token_count as usize
     }
 
+    /// 按索引顺序遍历 pool 中的有效 token（索引 0..token_count）
+    /// 调用方不必假设有效 token 在数组中连续存放
+    pub fn active_tokens(&self) -> impl Iterator<Item = (usize, &AnySwapItem)> {
+        self.tokens[..self.get_token_count()].iter().enumerate()
+    }
+
+    /// 按索引顺序返回 pool 中有效 token 的 mint
+    pub fn active_mints(&self) -> Vec<Pubkey> {
+        self.active_tokens()
+            .map(|(_, token)| *token.mint_pubkey())
+            .collect()
+    }
+
     /// 根据 mint 地址查找 token 索引
     pub fn find_token_index(&self, mint: &Pubkey) -> Option<usize> {
         for i in 0..self.get_token_count() {
@@ -77,22 +254,12 @@ impl AnySwapPool {
 
     /// 根据 mint 地址获取 token item（不可变引用）
     pub fn get_token_by_mint(&self, mint: &Pubkey) -> Option<&AnySwapItem> {
-        for i in 0..self.get_token_count() {
-            if self.tokens[i].mint_account == *mint {
-                return Some(&self.tokens[i]);
-            }
-        }
-        None
+        self.find_token_index(mint).map(|i| &self.tokens[i])
     }
 
-    /// 根据 mint 地址获取 token 索引
+    /// 根据 mint 地址获取 token 索引（与 find_token_index 相同，只扫描 0..token_count）
     pub fn get_token_index_by_mint(&self, mint: &Pubkey) -> Option<usize> {
-        for i in 0..self.get_token_count() {
-            if self.tokens[i].mint_account == *mint {
-                return Some(i);
-            }
-        }
-        None
+        self.find_token_index(mint)
     }
 
     /// 根据索引获取 token item（不可变引用）
@@ -104,6 +271,18 @@ impl AnySwapPool {
         }
     }
 
+    /// 根据索引获取 token item（不可变引用），越界时返回 InvalidTokenIndex
+    pub fn get_token_checked(&self, index: usize) -> Result<&AnySwapItem> {
+        self.get_token(index)
+            .ok_or_else(|| error!(ErrorCode::InvalidTokenIndex))
+    }
+
+    /// 根据索引获取 token item（可变引用），越界时返回 InvalidTokenIndex
+    pub fn get_token_mut_checked(&mut self, index: usize) -> Result<&mut AnySwapItem> {
+        self.get_token_mut(index)
+            .ok_or_else(|| error!(ErrorCode::InvalidTokenIndex))
+    }
+
     /// 添加新的 token（返回索引）
     /// weight: 该 token 的权重，作为不变量保持不变
     pub fn add_token(&mut self, mint: &Pubkey, vault: &Pubkey, weight: u64) -> Result<usize> {
@@ -118,116 +297,1014 @@ impl AnySwapPool {
         token.set_mint_account(mint);
         token.set_vault_account(vault);
         token.set_weight(weight);
+        // 槽位可能残留 remove_token 之前的数据
+        token.set_principal_reserve(0);
+        token.set_directional_fees(0, 0);
+        token.set_stable(false);
+        token.set_decimals(0);
+        token.set_accrued_protocol_fee(0);
+        token.set_exit_reserved(0);
 
         self.token_count += 1;
+        self.recompute_weight_sum()?;
         Ok(index)
     }
 
+    /// 移除索引为 index 的 token：将最后一个 token 移到该位置（swap-and-pop），并重新计算权重之和
+    pub fn remove_token(&mut self, index: usize) -> Result<()> {
+        let token_count = self.get_token_count();
+        require!(index < token_count, ErrorCode::InvalidTokenIndex);
+        // 待处理的退出按索引记录每个 token 的份额
+        require!(self.pending_exit_count == 0, ErrorCode::ExitsPending);
+        let last_index = token_count - 1;
+        let mint = self.tokens[index].mint_account;
+        self.clear_twap_pairs_for(&mint);
+        if index != last_index {
+            self.tokens[index] = self.tokens[last_index];
+        }
+        self.token_count -= 1;
+        self.recompute_weight_sum()?;
+        Ok(())
+    }
+
+    /// 校验待加入 token 的 mint 精度不超过 MAX_TOKEN_DECIMALS
+    pub fn check_token_decimals(decimals: u8) -> Result<()> {
+        require!(
+            decimals <= MAX_TOKEN_DECIMALS,
+            ErrorCode::UnsupportedDecimals
+        );
+        Ok(())
+    }
+
+    /// 记录 token 的 mint 精度（add_token_to_pool 从 mint 账户读取后调用）
+    pub fn set_token_decimals(&mut self, index: usize, decimals: u8) -> Result<()> {
+        Self::check_token_decimals(decimals)?;
+        require!(index < self.get_token_count(), ErrorCode::InvalidTokenIndex);
+        self.tokens[index].set_decimals(decimals);
+        Ok(())
+    }
+
+    /// 设置稳定组：group 中的 token 之间 1 进 1 出的 swap 使用放大系数为 amplification 的 StableSwap 曲线
+    /// group 为空时取消稳定组（amplification 被忽略）
+    ///
+    /// StableSwap 曲线按原始数量 1:1 锚定，组内 token 的精度必须相同，
+    /// 否则 6 位和 9 位精度的稳定币之间会按 1000 倍的错误价格交换
+    pub fn set_stable_group(&mut self, group: &[usize], amplification: u64) -> Result<()> {
+        let token_count = self.get_token_count();
+        if !group.is_empty() {
+            require!(group.len() >= 2, ErrorCode::InvalidStableGroup);
+            require!(
+                (MIN_AMPLIFICATION..=MAX_AMPLIFICATION).contains(&amplification),
+                ErrorCode::InvalidAmplification
+            );
+            for (k, &index) in group.iter().enumerate() {
+                require!(
+                    index < token_count && !group[..k].contains(&index),
+                    ErrorCode::InvalidStableGroup
+                );
+                require!(
+                    self.tokens[index].get_decimals() == self.tokens[group[0]].get_decimals(),
+                    ErrorCode::InvalidStableGroup
+                );
+            }
+        }
+
+        for token in self.tokens[..token_count].iter_mut() {
+            token.set_stable(false);
+        }
+        for &index in group.iter() {
+            self.tokens[index].set_stable(true);
+        }
+        self.amplification = if group.is_empty() { 0 } else { amplification };
+        Ok(())
+    }
+
+    /// 获取稳定组的放大系数，0 表示没有稳定组
+    pub fn get_amplification(&self) -> u64 {
+        self.amplification
+    }
+
+    /// swap 是否为稳定组内的 1 进 1 出，是则返回放大系数
+    pub fn stable_pair_amplification(&self, mints: &[Pubkey], is_in: &[bool]) -> Option<u64> {
+        if self.amplification == 0 || mints.len() != 2 || is_in.len() != 2 || is_in[0] == is_in[1] {
+            return None;
+        }
+        let all_stable = mints.iter().all(|mint| {
+            self.get_token_by_mint(mint)
+                .map(|token| token.is_stable())
+                .unwrap_or(false)
+        });
+        all_stable.then_some(self.amplification)
+    }
+
+    /// 获取缓存的权重之和（配置权重之和，见 weight_sum）
+    pub fn get_weight_sum(&self) -> u64 {
+        self.weight_sum
+    }
+
+    /// 从 token 列表计算配置权重之和（不读取缓存）
+    pub fn compute_weight_sum(&self) -> Result<u64> {
+        self.tokens[..self.get_token_count()]
+            .iter()
+            .try_fold(0u64, |sum, token| sum.checked_add(token.weight))
+            .ok_or(ErrorCode::MathOverflow.into())
+    }
+
+    /// now 时刻是否有 token 的权重仍在渐变中
+    pub fn is_any_ramp_active(&self, now: i64) -> bool {
+        self.active_tokens().any(|(_, token)| token.is_ramp_active(now))
+    }
+
+    /// now 时刻每个 token 的实际权重（按 token 顺序，见 AnySwapItem::weight_at）
+    pub fn weights_at(&self, now: i64) -> Vec<u64> {
+        self.active_tokens()
+            .map(|(_, token)| token.weight_at(now))
+            .collect()
+    }
+
+    /// 从 token 列表计算 now 时刻实际权重之和（不读取缓存）
+    pub fn compute_weight_sum_at(&self, now: i64) -> Result<u64> {
+        self.active_tokens()
+            .try_fold(0u64, |sum, (_, token)| sum.checked_add(token.weight_at(now)))
+            .ok_or(ErrorCode::MathOverflow.into())
+    }
+
+    /// now 时刻的权重之和：没有进行中的权重渐变时读取缓存，否则按实际权重计算
+    pub fn weight_sum_at(&self, now: i64) -> Result<u64> {
+        if !self.is_any_ramp_active(now) {
+            return Ok(self.weight_sum);
+        }
+        self.compute_weight_sum_at(now)
+    }
+
+    /// 重新计算并写入权重之和，同时刷新归一化权重的缓存，返回新值
+    /// 直接修改 token 权重后（modify_token_weight、remove_token）必须调用
+    pub fn recompute_weight_sum(&mut self) -> Result<u64> {
+        self.weight_sum = self.compute_weight_sum()?;
+        self.refresh_normalized_weights()?;
+        Ok(self.weight_sum)
+    }
+
+    /// 计算 token 的归一化权重 weight * 1e18 / weight_sum（不读取缓存，向下取整）
+    pub fn compute_normalized_weight(weight: u64, weight_sum: u64) -> Result<u64> {
+        require!(weight_sum > 0, ErrorCode::InvalidTokenCount);
+        let normalized = U256::from(weight) * E18 / U256::from(weight_sum);
+        require!(normalized <= U256::from(u64::MAX), ErrorCode::MathOverflow);
+        Ok(normalized.as_u64())
+    }
+
+    /// 按缓存的权重之和刷新所有 token 的归一化权重（配置权重 / 配置权重之和）
+    /// 权重之和变化时每个 token 的归一化权重都会变化，因此总是整体刷新
+    pub fn refresh_normalized_weights(&mut self) -> Result<()> {
+        let weight_sum = self.weight_sum;
+        let token_count = self.get_token_count();
+        for token in self.tokens[..token_count].iter_mut() {
+            token.normalized_weight = if weight_sum == 0 {
+                0
+            } else {
+                Self::compute_normalized_weight(token.weight, weight_sum)?
+            };
+        }
+        Ok(())
+    }
+
+    /// now 时刻 token 的归一化权重 weight_i * 1e18 / sum(weights)（按实际权重计算，不读取缓存）
+    pub fn normalized_weight(&self, index: usize, now: i64) -> Result<u64> {
+        let weight = self.get_token_checked(index)?.weight_at(now);
+        Self::compute_normalized_weight(weight, self.compute_weight_sum_at(now)?)
+    }
+
+    /// 校验配置权重（权重渐变期间为目标权重，即渐变结束后的配置）是一组有意义的归一化权重：
+    /// 权重之和不溢出，且每个 token 的归一化权重在 [MIN_WEIGHT, 1e18] 内
+    ///
+    /// 权重过小的 token 在加权乘积公式中几乎不起作用，价格对其储备极不敏感
+    pub fn validate_weights(&self) -> Result<()> {
+        let weights = self
+            .active_tokens()
+            .map(|(_, token)| token.weight)
+            .collect::<Vec<u64>>();
+        Self::check_weights(&weights)
+    }
+
+    fn check_weights(weights: &[u64]) -> Result<()> {
+        let weight_sum = weights
+            .iter()
+            .try_fold(0u64, |sum, &weight| sum.checked_add(weight))
+            .ok_or(ErrorCode::InvalidWeight)?;
+        for &weight in weights {
+            let normalized = Self::compute_normalized_weight(weight, weight_sum)
+                .map_err(|_| error!(ErrorCode::InvalidWeight))?;
+            require!(
+                (MIN_WEIGHT..=E18.as_u64()).contains(&normalized),
+                ErrorCode::InvalidWeight
+            );
+        }
+        Ok(())
+    }
+
+    /// now 时刻的归一化权重：没有进行中的权重渐变时读取缓存；
+    /// 渐变期间实际权重随时间变化，与缓存的配置权重不同，按 now 时刻的权重重新计算
+    pub fn current_normalized_weight(&self, index: usize, now: i64) -> Result<u64> {
+        let token = self.get_token_checked(index)?;
+        if self.is_any_ramp_active(now) {
+            self.normalized_weight(index, now)
+        } else {
+            Ok(token.get_normalized_weight())
+        }
+    }
+
+    /// 管理员开始 token 的权重渐变：从 now 时的权重在 duration 秒内线性变化到 end_weight
+    ///
+    /// 立即修改权重（modify_token_weight）会使价格跳变，留下套利空间；渐变期间 swap 读取
+    /// 按时间插值的权重（见 AnySwapItem::weight_at），价格逐步变化。
+    /// 该 token 已在渐变中时从当前插值权重重新开始；渐变结束后的权重配置必须有效（见 validate_weights）
+    pub fn start_weight_ramp(
+        &mut self,
+        admin: &Pubkey,
+        mint: &Pubkey,
+        end_weight: u64,
+        duration: i64,
+        now: i64,
+    ) -> Result<()> {
+        self.verify_admin(admin)?;
+        require!(duration > 0, ErrorCode::InvalidWeightRamp);
+        require!(end_weight > 0, ErrorCode::InvalidWeight);
+        let index = self
+            .find_token_index(mint)
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        let end_ts = now.checked_add(duration).ok_or(ErrorCode::MathOverflow)?;
+        let token = self.get_token_mut_checked(index)?;
+        let start_weight = token.weight_at(now);
+        token.start_weight_ramp(start_weight, end_weight, now, end_ts);
+        self.validate_weights()?;
+        self.recompute_weight_sum()?;
+        Ok(())
+    }
+
+    /// 管理员一次设置所有 token 的权重（按 token 索引顺序）
+    ///
+    /// 先整体校验新的权重配置（每个权重大于 0，见 validate_weights），再一起写入，
+    /// 不会出现逐个修改时中间状态的权重组合；同时取消所有进行中的权重渐变
+    pub fn rebalance_weights(&mut self, admin: &Pubkey, new_weights: &[u64]) -> Result<()> {
+        self.verify_admin(admin)?;
+        require!(
+            new_weights.len() == self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+        require!(!new_weights.contains(&0), ErrorCode::InvalidWeight);
+        Self::check_weights(new_weights)?;
+        let token_count = self.get_token_count();
+        for (token, &weight) in self.tokens[..token_count].iter_mut().zip(new_weights.iter()) {
+            token.set_weight(weight);
+        }
+        self.recompute_weight_sum()?;
+        Ok(())
+    }
+
+    /// 设置 token 的方向费率（bps），0 表示使用 pool 费率
+    pub fn set_token_fees(&mut self, mint: &Pubkey, buy_fee_bps: u16, sell_fee_bps: u16) -> Result<()> {
+        require!(
+            buy_fee_bps as u64 <= BPS_DENOMINATOR && sell_fee_bps as u64 <= BPS_DENOMINATOR,
+            ErrorCode::MathOverflow
+        );
+        let index = self
+            .find_token_index(mint)
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        self.tokens[index].set_directional_fees(buy_fee_bps, sell_fee_bps);
+        Ok(())
+    }
+
+    /// 按 swap 涉及的 token 解析每个 token 的费率（见 directional_fee_rates）
+    pub fn swap_fee_rates(&self, mints: &[Pubkey], is_in: &[bool]) -> Result<Vec<(u64, u64)>> {
+        let mut buy_fee_bps = Vec::with_capacity(mints.len());
+        let mut sell_fee_bps = Vec::with_capacity(mints.len());
+        for mint in mints.iter() {
+            let (buy, sell) = self
+                .get_token_by_mint(mint)
+                .ok_or(ErrorCode::InvalidTokenMint)?
+                .get_directional_fees();
+            buy_fee_bps.push(buy);
+            sell_fee_bps.push(sell);
+        }
+        directional_fee_rates(
+            is_in,
+            &buy_fee_bps,
+            &sell_fee_bps,
+            self.get_fee_numerator(),
+            self.get_fee_denominator(),
+        )
+    }
+
+    /// 按一次操作的资金流更新 token 的本金储备（见 AnySwapItem::apply_principal_change）
+    pub fn record_principal_flow(
+        &mut self,
+        index: usize,
+        vault_in: u64,
+        vault_out: u64,
+        fee: u64,
+    ) -> Result<()> {
+        self.get_token_mut_checked(index)?
+            .apply_principal_change(vault_in, vault_out, fee);
+        Ok(())
+    }
+
+    /// 前 count 个 token 的 mint 列表的哈希
+    /// request_exit 时记录在 PendingExit 中，process_exit 时比对，确保退出记录中的索引仍指向同一组 token；
+    /// add_token 只在末尾追加，不影响前 count 个 token 的哈希
+    pub fn mints_hash(&self, count: usize) -> Result<[u8; 32]> {
+        require!(count <= self.get_token_count(), ErrorCode::InvalidTokenCount);
+        let mints: Vec<&[u8]> = self.tokens[..count]
+            .iter()
+            .map(|token| token.mint_account.as_ref())
+            .collect();
+        Ok(hashv(&mints).to_bytes())
+    }
+
+    /// 发起退出：将退出份额从 LP 储备中划出（见 request_exit）
+    /// amounts_out / burn_fees 与前 amounts_out.len() 个 token 一一对应；
+    /// 本金按一次性移除流动性扣减，amounts_out 计入 exit_reserved，直到 process_exit 支付
+    pub fn reserve_exit(&mut self, amounts_out: &[u64], burn_fees: &[u64]) -> Result<()> {
+        require!(
+            amounts_out.len() == burn_fees.len() && amounts_out.len() <= self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+        for (i, (&amount, &fee)) in amounts_out.iter().zip(burn_fees.iter()).enumerate() {
+            let token = &mut self.tokens[i];
+            token.apply_principal_change(0, amount, fee);
+            let reserved = token
+                .get_exit_reserved()
+                .checked_add(amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+            token.set_exit_reserved(reserved);
+        }
+        self.pending_exit_count = self
+            .pending_exit_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// 支付退出份额：索引为 index 的 token 已转给用户 amount，从 exit_reserved 中扣除
+    pub fn release_exit(&mut self, index: usize, amount: u64) -> Result<()> {
+        let token = self.get_token_mut_checked(index)?;
+        let reserved = token
+            .get_exit_reserved()
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        token.set_exit_reserved(reserved);
+        Ok(())
+    }
+
+    /// 一笔退出的所有 token 都已支付
+    pub fn finish_exit(&mut self) -> Result<()> {
+        self.pending_exit_count = self
+            .pending_exit_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// 按 swap 结果更新本金储备
+    /// 输入的手续费随输入一起转入 vault（见 amounts_with_fees），按手续费记录，本金只增加扣费后的数量；
+    /// burn_fees 与 mints 一一对应，输出 token 为 0
+    pub fn record_swap_principal(
+        &mut self,
+        mints: &[Pubkey],
+        is_in: &[bool],
+        amounts: &[u64],
+        burn_fees: &[u64],
+    ) -> Result<()> {
+        require!(burn_fees.len() == mints.len(), ErrorCode::InvalidTokenCount);
+        for (i, mint) in mints.iter().enumerate() {
+            let index = self
+                .find_token_index(mint)
+                .ok_or(ErrorCode::InvalidTokenMint)?;
+            if is_in[i] {
+                let vault_in = amounts[i]
+                    .checked_add(burn_fees[i])
+                    .ok_or(ErrorCode::MathOverflow)?;
+                self.record_principal_flow(index, vault_in, 0, burn_fees[i])?;
+            } else {
+                self.record_principal_flow(index, 0, amounts[i], 0)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 将累计的手续费并入本金：本金设为当前 vault 余额（不含未领取的协议费），返回每个 token 并入的手续费
+    pub fn compound_fees(&mut self, vault_balances: &[u64]) -> Result<Vec<u64>> {
+        require!(
+            vault_balances.len() == self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+        let mut compounded = Vec::with_capacity(vault_balances.len());
+        for (i, &balance) in vault_balances.iter().enumerate() {
+            let token = self.get_token_mut_checked(i)?;
+            compounded.push(token.accrued_fee(balance));
+            token.set_principal_reserve(token.lp_balance(balance));
+        }
+        Ok(compounded)
+    }
+
     /// 获取 LP token 总发行量
-    pub fn get_total_amount_minted(&self) -> u64 {
-        self.total_amount_minted
+    pub fn get_total_amount_minted(&self) -> u128 {
+        ((self.total_amount_minted_hi as u128) << 64) | self.total_amount_minted as u128
     }
 
     /// 设置 LP token 总发行量
-    pub fn set_total_amount_minted(&mut self, amount: u64) {
-        self.total_amount_minted = amount;
+    pub fn set_total_amount_minted(&mut self, amount: u128) {
+        self.total_amount_minted = amount as u64;
+        self.total_amount_minted_hi = (amount >> 64) as u64;
+    }
+
+    /// 是否有进行中的 LP mint 迁移
+    pub fn is_migrating_lp_mint(&self) -> bool {
+        self.migration_lp_mint != Pubkey::default()
+    }
+
+    /// pool_mint PDA 的地址（create_pool 创建的 LP mint）
+    pub fn find_lp_mint_pda(pool: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"pool_mint", pool.as_ref()], program_id).0
+    }
+
+    /// 当前的 LP mint：lp_mint 未设置时为 pool_mint PDA（pda_mint）
+    pub fn canonical_lp_mint(&self, pda_mint: &Pubkey) -> Pubkey {
+        if self.lp_mint == Pubkey::default() {
+            *pda_mint
+        } else {
+            self.lp_mint
+        }
+    }
+
+    /// 校验 mint 为当前的 LP mint（铸造 LP 的指令调用），迁移的目标 mint 在 finish_lp_migration 之前不能铸造
+    pub fn check_canonical_lp_mint(&self, mint: &Pubkey, pda_mint: &Pubkey) -> Result<()> {
+        require!(
+            *mint == self.canonical_lp_mint(pda_mint),
+            ErrorCode::LpMintMismatch
+        );
+        Ok(())
+    }
+
+    /// 开始 LP mint 迁移
+    /// 新 mint 由调用方校验（mint authority、精度、供应量为 0），不能是当前的 LP mint
+    pub fn start_lp_migration(&mut self, new_mint: &Pubkey, pda_mint: &Pubkey) -> Result<()> {
+        require!(!self.is_migrating_lp_mint(), ErrorCode::LpMintMismatch);
+        require!(
+            *new_mint != Pubkey::default() && *new_mint != self.canonical_lp_mint(pda_mint),
+            ErrorCode::LpMintMismatch
+        );
+        self.migration_lp_mint = *new_mint;
+        self.migration_lp_supply = 0;
+        Ok(())
+    }
+
+    /// 完成 LP mint 迁移：旧 mint 上的 LP 已全部换成新 LP（或已销毁）后，新 mint 成为当前的 LP mint
+    /// 之后新的流动性铸造新 mint，迁移状态清空，可以关闭 pool 或开始下一次迁移
+    pub fn finish_lp_migration(&mut self) -> Result<()> {
+        require!(self.is_migrating_lp_mint(), ErrorCode::LpMintMismatch);
+        require!(
+            self.get_total_amount_minted() == self.migration_lp_supply as u128,
+            ErrorCode::LpMigrationIncomplete
+        );
+        self.lp_mint = self.migration_lp_mint;
+        self.migration_lp_mint = Pubkey::default();
+        self.migration_lp_supply = 0;
+        Ok(())
+    }
+
+    /// 判断 mint 是否为迁移的目标 mint；既不是当前的 LP mint 也不是目标 mint 时报错
+    /// pda_mint 为 pool_mint PDA（见 canonical_lp_mint）
+    pub fn resolve_lp_mint(&self, mint: &Pubkey, pda_mint: &Pubkey) -> Result<bool> {
+        if *mint == self.canonical_lp_mint(pda_mint) {
+            return Ok(false);
+        }
+        require!(
+            self.is_migrating_lp_mint() && *mint == self.migration_lp_mint,
+            ErrorCode::LpMintMismatch
+        );
+        Ok(true)
+    }
+
+    /// 记录旧 LP 1:1 换成新 LP，LP 总量不变
+    pub fn record_lp_swap(&mut self, amount: u64) -> Result<()> {
+        require!(self.is_migrating_lp_mint(), ErrorCode::LpMintMismatch);
+        self.migration_lp_supply = self
+            .migration_lp_supply
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// 记录销毁新 mint 上的 LP（移除流动性时）
+    pub fn record_migrated_lp_burn(&mut self, amount: u64) -> Result<()> {
+        self.migration_lp_supply = self
+            .migration_lp_supply
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// 按 LP mint 销毁前后的实际供应量更新 total_amount_minted
+    ///
+    /// 使用 mint 实际减少的数量，而不是预先计算的 burn_amount，保证记账与 mint 供应量一致；
+    /// 实际销毁少于 burn_amount 时（用户已按 burn_amount 取回 token）拒绝
+    ///
+    /// Returns:
+    ///     实际销毁的 LP 数量
+    pub fn record_lp_burn(
+        &mut self,
+        supply_before: u64,
+        supply_after: u64,
+        burn_amount: u64,
+    ) -> Result<u64> {
+        let burned = supply_before
+            .checked_sub(supply_after)
+            .ok_or(ErrorCode::LpSupplyMismatch)?;
+        require!(burned >= burn_amount, ErrorCode::LpSupplyMismatch);
+        let total = self
+            .get_total_amount_minted()
+            .checked_sub(burned as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.set_total_amount_minted(total);
+        Ok(burned)
     }
 
     /// 计算账户所需的空间大小
     pub fn space() -> usize {
         8 + // discriminator
         2 + // token_count
-        6 + // padding
+        4 + // magic
+        1 + // layout_version
+        1 + // lp_decimals
         32 + // admin (Pubkey)
         8 + // total_amount_minted
         8 + // fee_numerator
         8 + // fee_denominator
+        8 + // min_hold_seconds
+        32 + // guardian (Pubkey)
+        8 + // paused_ops
+        8 + // total_amount_minted_hi
+        32 + // migration_lp_mint (Pubkey)
+        8 + // migration_lp_supply
+        8 + // created_at
+        8 + // last_activity_at
+        8 + // weight_sum
+        8 + // amplification
+        32 + // pending_admin (Pubkey)
+        8 + // twap_last_update_ts
+        (MAX_TWAP_PAIRS * TwapPair::space()) + // twap_pairs
+        8 + // protocol_fee_numerator
+        8 + // protocol_fee_denominator
+        32 + // protocol_fee_recipient (Pubkey)
+        2 + // _padding
+        2 + // max_tokens_per_swap
+        4 + // pending_exit_count
+        8 + // min_swap_amount
+        32 + // lp_mint (Pubkey)
         (MAX_TOKENS * AnySwapItem::space()) // 固定大小数组
     }
 
-    /// 获取手续费分子
-    pub fn get_fee_numerator(&self) -> u64 {
-        self.fee_numerator
+    /// 获取一次操作最多涉及的 token 数量，0 表示不限制
+    pub fn get_max_tokens_per_swap(&self) -> u16 {
+        self.max_tokens_per_swap
     }
 
-    /// 获取手续费分母
-    pub fn get_fee_denominator(&self) -> u64 {
-        self.fee_denominator
+    /// 设置一次操作最多涉及的 token 数量，0 表示不限制
+    pub fn set_max_tokens_per_swap(&mut self, max_tokens_per_swap: u16) {
+        self.max_tokens_per_swap = max_tokens_per_swap;
     }
 
-    /// 设置费率
-    pub fn set_fee(&mut self, fee_numerator: u64, fee_denominator: u64) {
-        self.fee_numerator = fee_numerator;
-        self.fee_denominator = fee_denominator;
+    /// 检查一次 swap / 增减流动性涉及的 token 数量不超过 max_tokens_per_swap
+    ///
+    /// 每个 token 都要读取账户、计算 ln/exp 并转账，数量过多时会在循环中途耗尽计算预算，
+    /// 在读取账户之前拒绝，而不是让交易执行到一半失败
+    pub fn check_tokens_per_swap(&self, token_count: usize) -> Result<()> {
+        require!(
+            self.max_tokens_per_swap == 0 || token_count <= self.max_tokens_per_swap as usize,
+            ErrorCode::TooManyTokensInSwap
+        );
+        Ok(())
     }
 
-    /// 计算手续费
-    /// amount: 输入金额
-    /// 返回: (手续费金额, 扣除手续费后的金额)
-    pub fn calculate_fee(&self, amount: u64) -> Result<(u64, u64)> {
-        let amount_u128 = amount as u128;
-        let fee_amount = amount_u128
-            .checked_mul(self.fee_numerator as u128)
-            .ok_or(ErrorCode::MathOverflow)?
-            .checked_div(self.fee_denominator as u128)
-            .ok_or(ErrorCode::MathOverflow)?;
-        let amount_after_fee = amount_u128
-            .checked_sub(fee_amount)
-            .ok_or(ErrorCode::MathOverflow)?;
-        
-        Ok((fee_amount as u64, amount_after_fee as u64))
+    /// 检查 pool 可以关闭（close_pool）：没有 token，没有流通的 LP，也没有进行中的 LP mint 迁移或待处理的退出
+    pub fn check_can_close(&self) -> Result<()> {
+        require!(self.pending_exit_count == 0, ErrorCode::ExitsPending);
+        require!(
+            self.get_token_count() == 0
+                && self.get_total_amount_minted() == 0
+                && self.migration_lp_mint == Pubkey::default(),
+            ErrorCode::PoolNotEmpty
+        );
+        Ok(())
     }
 
-    /// 计算两个 token 之间的交换输出（使用恒定乘积和公式）
-    /// 公式: Σ(vault_i * weight_i) = constant
-    /// 在交换时，保持这个和不变
-    /// 
-    /// 对于两个 token 的交换：
-    /// (vault_in + amount_in) * weight_in + (vault_out - amount_out) * weight_out = 
-    /// vault_in * weight_in + vault_out * weight_out
-    /// 
-    /// 因此：amount_in * weight_in = amount_out * weight_out
-    /// amount_out = (amount_in * weight_in) / weight_out
-    pub fn calculate_swap_output(
-        &self,
-        token_in_index: usize,
-        token_out_index: usize,
-        amount_in: u64,
-    ) -> Result<u64> {
+    /// 记录创建时间（创建 pool 时调用），最近活动时间和 TWAP 更新时间同时初始化为创建时间
+    pub fn init_timestamps(&mut self, now: i64) {
+        self.created_at = now;
+        self.last_activity_at = now;
+        self.twap_last_update_ts = now;
+    }
+
+    /// 获取 pool 的创建时间和最近活动时间
+    pub fn get_activity(&self) -> (i64, i64) {
+        (self.created_at, self.last_activity_at)
+    }
+
+    /// 记录一次 swap / 添加流动性 / 移除流动性
+    /// 时钟回退时不更新，保证 last_activity_at 单调不减
+    pub fn record_activity(&mut self, now: i64) {
+        if now > self.last_activity_at {
+            self.last_activity_at = now;
+        }
+    }
+
+    /// 管理员提议将管理权转移给 new_admin（两步转移的第一步）
+    /// 在 new_admin 调用 accept_admin 之前 admin 不变，再次提议会覆盖之前的提议；
+    /// 传入默认公钥表示取消进行中的转移
+    pub fn propose_admin(&mut self, admin: &Pubkey, new_admin: &Pubkey) -> Result<()> {
+        self.verify_admin(admin)?;
+        self.pending_admin = *new_admin;
+        Ok(())
+    }
+
+    /// 待接受的管理员接受管理权（两步转移的第二步），成功后清空 pending_admin
+    pub fn accept_admin(&mut self, signer: &Pubkey) -> Result<()> {
         require!(
-            token_in_index < self.get_token_count() && token_out_index < self.get_token_count(),
-            ErrorCode::InvalidTokenIndex
+            self.has_pending_admin() && *signer == self.pending_admin,
+            ErrorCode::NotPendingAdmin
         );
-        require!(token_in_index != token_out_index, ErrorCode::SameTokenSwap);
-
-        let token_in = self.get_token(token_in_index)
-            .ok_or(ErrorCode::InvalidTokenIndex)?;
-        let token_out = self.get_token(token_out_index)
-            .ok_or(ErrorCode::InvalidTokenIndex)?;
+        self.admin = self.pending_admin;
+        self.pending_admin = Pubkey::default();
+        Ok(())
+    }
 
-        let weight_in = token_in.get_weight();
-        let weight_out = token_out.get_weight();
+    /// 是否有进行中的管理员转移
+    pub fn has_pending_admin(&self) -> bool {
+        self.pending_admin != Pubkey::default()
+    }
 
-        require!(weight_in > 0 && weight_out > 0, ErrorCode::InvalidTokenCount);
+    /// 获取待接受的管理员（默认公钥表示没有）
+    pub fn get_pending_admin(&self) -> &Pubkey {
+        &self.pending_admin
+    }
 
-        // 使用恒定乘积和公式: amount_in * weight_in = amount_out * weight_out
-        let amount_in_u128 = amount_in as u128;
-        let weight_in_u128 = weight_in as u128;
-        let weight_out_u128 = weight_out as u128;
+    /// 是否设置了 guardian
+    pub fn has_guardian(&self) -> bool {
+        self.guardian != Pubkey::default()
+    }
 
-        let numerator = amount_in_u128
-            .checked_mul(weight_in_u128)
-            .ok_or(ErrorCode::MathOverflow)?;
-        let amount_out = numerator
-            .checked_div(weight_out_u128)
-            .ok_or(ErrorCode::MathOverflow)?;
+    /// 设置 guardian（默认公钥表示移除）
+    pub fn set_guardian(&mut self, guardian: &Pubkey) {
+        self.guardian = *guardian;
+    }
 
-        Ok(amount_out as u64)
+    /// 获取已暂停的操作
+    pub fn get_paused_ops(&self) -> u64 {
+        self.paused_ops
     }
 
-    /// 计算池的恒定乘积和（用于验证）
-    /// 返回 Σ(vault_i * weight_i)
-    pub fn calculate_invariant(&self, reserves: &[u64]) -> Result<u128> {
+    /// 检查 pool 是否至少有 MIN_ACTIVE_TOKENS 个 token
+    /// 只有 1 个 token 的 pool 无法 swap，也没有意义提供流动性（管理员的 add_token/remove_token 不受限制）
+    pub fn check_min_tokens(&self) -> Result<()> {
+        require!(
+            self.get_token_count() >= MIN_ACTIVE_TOKENS,
+            ErrorCode::InsufficientTokens
+        );
+        Ok(())
+    }
+
+    /// 检查 swap 的参与 token（稀疏参与）
+    ///
+    /// swap 只传入参与的 token，按 mint 定位到 pool 中的 token，因此顺序任意；
+    /// 参与数量不能超过 pool 的 token 数量，每个 mint 必须属于 pool 且不能重复
+    pub fn check_swap_mints(&self, mints: &[Pubkey]) -> Result<()> {
+        require!(
+            !mints.is_empty() && mints.len() <= self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+        for (k, mint) in mints.iter().enumerate() {
+            require!(
+                self.find_token_index(mint).is_some(),
+                ErrorCode::InvalidTokenMint
+            );
+            require!(!mints[..k].contains(mint), ErrorCode::DuplicateSwapToken);
+        }
+        Ok(())
+    }
+
+    /// 检查操作是否被暂停
+    pub fn check_not_paused(&self, op: u64) -> Result<()> {
+        require!(self.paused_ops & op == 0, ErrorCode::PoolPaused);
+        Ok(())
+    }
+
+    /// 设置暂停的操作
+    /// 管理员可以任意设置；guardian 只能增加暂停的操作，不能恢复
+    pub fn set_paused_ops(&mut self, authority: &Pubkey, paused_ops: u64) -> Result<()> {
+        require!(paused_ops & !PAUSE_ALL == 0, ErrorCode::InvalidPausedOps);
+        if self.verify_admin(authority).is_ok() {
+            self.paused_ops = paused_ops;
+            return Ok(());
+        }
+        require!(
+            self.has_guardian() && *authority == self.guardian,
+            ErrorCode::InvalidGuardian
+        );
+        require!(
+            paused_ops & self.paused_ops == self.paused_ops,
+            ErrorCode::InvalidGuardian
+        );
+        self.paused_ops = paused_ops;
+        Ok(())
+    }
+
+    /// 管理员的紧急开关：paused 为 true 时暂停 swap 和添加流动性，
+    /// allow_withdraw_when_paused 为 false 时同时暂停移除流动性；paused 为 false 时恢复所有操作
+    /// 只由管理员调用（guardian 使用 set_paused_ops 增加暂停的操作）
+    pub fn set_pause(
+        &mut self,
+        admin: &Pubkey,
+        paused: bool,
+        allow_withdraw_when_paused: bool,
+    ) -> Result<()> {
+        self.verify_admin(admin)?;
+        self.paused_ops = match (paused, allow_withdraw_when_paused) {
+            (false, _) => 0,
+            (true, true) => PAUSE_SWAP | PAUSE_ADD_LIQUIDITY,
+            (true, false) => PAUSE_ALL,
+        };
+        Ok(())
+    }
+
+    /// 获取最短持有期（秒）
+    pub fn get_min_hold_seconds(&self) -> i64 {
+        self.min_hold_seconds
+    }
+
+    /// 设置最短持有期（秒）
+    pub fn set_min_hold_seconds(&mut self, min_hold_seconds: i64) {
+        self.min_hold_seconds = min_hold_seconds;
+    }
+
+    /// 获取 swap 输入的最小数量（扣费后），0 表示不限制
+    pub fn get_min_swap_amount(&self) -> u64 {
+        self.min_swap_amount
+    }
+
+    /// 设置 swap 输入的最小数量（扣费后），0 表示不限制
+    pub fn set_min_swap_amount(&mut self, min_swap_amount: u64) {
+        self.min_swap_amount = min_swap_amount;
+    }
+
+    /// 拒绝粉尘 swap：每个输入 token 按各自费率（见 swap_fee_rates）扣费后的数量不能小于 min_swap_amount
+    ///
+    /// 在计算输出之前调用；过小的输入扣费后的输出会向下取整为 0（见 check_outputs_not_zero），白白消耗交易费
+    pub fn check_min_swap_amount(&self, mints: &[Pubkey], is_in: &[bool], amounts: &[u64]) -> Result<()> {
+        require!(amounts.len() == is_in.len(), ErrorCode::InvalidTokenCount);
+        if self.min_swap_amount == 0 {
+            return Ok(());
+        }
+        let fee_rates = self.swap_fee_rates(mints, is_in)?;
+        for (i, &amount) in amounts.iter().enumerate() {
+            if !is_in[i] {
+                continue;
+            }
+            let (fee_numerator, fee_denominator) = fee_rates[i];
+            let fee = (amount as u128 * fee_numerator as u128) / fee_denominator as u128;
+            require!(
+                amount as u128 - fee >= self.min_swap_amount as u128,
+                ErrorCode::AmountTooSmall
+            );
+        }
+        Ok(())
+    }
+
+    /// 获取 LP mint 的精度
+    pub fn get_lp_decimals(&self) -> u8 {
+        self.lp_decimals
+    }
+
+    /// 校验传入的 LP mint 精度与 pool 记录的一致
+    /// pool_mint 的地址已由 seeds 约束，这里防止迁移后精度不一致
+    pub fn check_lp_mint(&self, decimals: u8) -> Result<()> {
+        require!(decimals == self.lp_decimals, ErrorCode::LpMintMismatch);
+        Ok(())
+    }
+
+    /// 获取手续费分子
+    pub fn get_fee_numerator(&self) -> u64 {
+        self.fee_numerator
+    }
+
+    /// 获取手续费分母
+    pub fn get_fee_denominator(&self) -> u64 {
+        self.fee_denominator
+    }
+
+    /// 设置费率：先校验整组费率，校验通过后一次写入分子和分母，
+    /// 校验失败时不修改任何字段，不会出现分子大于分母的中间状态
+    pub fn set_fee(&mut self, fee_numerator: u64, fee_denominator: u64) -> Result<()> {
+        Self::check_fee(fee_numerator, fee_denominator)?;
+        (self.fee_numerator, self.fee_denominator) = (fee_numerator, fee_denominator);
+        Ok(())
+    }
+
+    /// 管理员修改费率（modify_fee），分子必须大于 0（零手续费只能在创建时设置）
+    pub fn modify_fee(&mut self, admin: &Pubkey, fee_numerator: u64, fee_denominator: u64) -> Result<()> {
+        self.verify_admin(admin)?;
+        require!(fee_numerator > 0, ErrorCode::MathOverflow);
+        self.set_fee(fee_numerator, fee_denominator)
+    }
+
+    /// 检查本次 swap 实际使用的费率不超过用户可接受的最大费率
+    /// 按 swap_fee_rates 解析每个输入 token 的费率（含 buy_fee_bps / sell_fee_bps 覆盖），手续费只从输入中扣除；
+    /// max_fee_bps 为 None 时不检查；比较不做取整，费率只要高出一点就拒绝
+    pub fn check_max_fee(&self, max_fee_bps: Option<u16>, mints: &[Pubkey], is_in: &[bool]) -> Result<()> {
+        if let Some(max_fee_bps) = max_fee_bps {
+            let fee_rates = self.swap_fee_rates(mints, is_in)?;
+            for (&x, &(fee_numerator, fee_denominator)) in is_in.iter().zip(fee_rates.iter()) {
+                require!(
+                    !x || fee_numerator as u128 * BPS_DENOMINATOR as u128
+                        <= max_fee_bps as u128 * fee_denominator as u128,
+                    ErrorCode::FeeExceedsMax
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// 计算手续费
+    /// amount: 输入金额
+    /// 返回: (手续费金额, 扣除手续费后的金额)
+    /// 分母为 0（未设置费率）时返回 InvalidFee，而不是在除法处失败
+    pub fn calculate_fee(&self, amount: u64) -> Result<(u64, u64)> {
+        require!(self.fee_denominator > 0, ErrorCode::InvalidFee);
+        let amount_u128 = amount as u128;
+        let fee_amount = amount_u128
+            .checked_mul(self.fee_numerator as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(self.fee_denominator as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let amount_after_fee = amount_u128
+            .checked_sub(fee_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        Ok((fee_amount as u64, amount_after_fee as u64))
+    }
+
+    /// 计算两个 token 之间的交换输出（加权恒定乘积，与 Balancer 的 calcOutGivenIn 一致）
+    /// 不变量: Σ(weight_i * ln(vault_i)) = constant
+    ///
+    /// 对于两个 token 的交换：
+    /// amount_out = vault_out * (1 - (vault_in / (vault_in + amount_in)) ^ (weight_in / weight_out))
+    ///
+    /// reserves: pool 中每个 token 的 vault 余额（按 token 顺序）
+    /// amount_in: 扣费后的输入数量（不计算手续费）
+    /// now: 当前时间，权重渐变期间按该时刻的权重计算（见 AnySwapItem::weight_at）
+    ///
+    /// 输出按 SwapRounding::POOL_FAVORABLE 向下取整，与执行 swap 时的取整方向一致
+    pub fn calculate_swap_output(
+        &self,
+        token_in_index: usize,
+        token_out_index: usize,
+        amount_in: u64,
+        reserves: &[u64],
+        now: i64,
+    ) -> Result<u64> {
+        self.calculate_swap_output_with_rounding(
+            token_in_index,
+            token_out_index,
+            amount_in,
+            reserves,
+            SwapRounding::POOL_FAVORABLE,
+            now,
+        )
+    }
+
+    /// 计算两个 token 之间的交换输出，可指定取整方向
+    /// 输入由用户给定，只有 round_down_output 影响结果
+    ///
+    /// 储备和权重在 LogExpMath 中按 18 位定点数计算，输出不超过 vault_out
+    ///
+    /// 储备直接使用各 token 的原始数量，不按 decimals 归一化：加权公式只依赖 vault_in 的增长比例，
+    /// 输出与 vault_out 成正比，把两边放大到 18 位精度再把输出缩回得到的结果相同。
+    /// 价格因此自动以各自的最小单位计价（见 test_swap_output_mixed_decimals）
+    pub fn calculate_swap_output_with_rounding(
+        &self,
+        token_in_index: usize,
+        token_out_index: usize,
+        amount_in: u64,
+        reserves: &[u64],
+        rounding: SwapRounding,
+        now: i64,
+    ) -> Result<u64> {
+        require!(
+            token_in_index < self.get_token_count() && token_out_index < self.get_token_count(),
+            ErrorCode::InvalidTokenIndex
+        );
+        require!(token_in_index != token_out_index, ErrorCode::SameTokenSwap);
+        require!(
+            reserves.len() == self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+
+        let token_in = self.get_token_checked(token_in_index)?;
+        let token_out = self.get_token_checked(token_out_index)?;
+
+        let weight_in = token_in.weight_at(now);
+        let weight_out = token_out.weight_at(now);
+
+        require!(weight_in > 0 && weight_out > 0, ErrorCode::InvalidTokenCount);
+
+        let vault_in = reserves[token_in_index];
+        let vault_out = reserves[token_out_index];
+        let reserve_out_after =
+            two_token_reserve_out(vault_in, amount_in, vault_out, weight_in, weight_out, rounding)?;
+
+        // 新储备不会超过 vault_out，输出最多取走整个 vault
+        Ok(vault_out.saturating_sub(reserve_out_after))
+    }
+
+    /// 精确输出：得到 amount_out 的 token_out 需要的（扣费后）token_in 输入（Balancer 的 calcInGivenOut）
+    /// amount_in = vault_in * ((vault_out / (vault_out - amount_out)) ^ (weight_out / weight_in) - 1)
+    ///
+    /// reserves: pool 中每个 token 的 vault 余额（按 token 顺序）；now: 当前时间（见 calculate_swap_output）
+    ///
+    /// 输入向上取整（对池子有利）；amount_out 不小于 vault_out 时返回 InsufficientLiquidity
+    pub fn calc_in_given_out(
+        &self,
+        token_in_index: usize,
+        token_out_index: usize,
+        amount_out: u64,
+        reserves: &[u64],
+        now: i64,
+    ) -> Result<u64> {
+        require!(
+            token_in_index < self.get_token_count() && token_out_index < self.get_token_count(),
+            ErrorCode::InvalidTokenIndex
+        );
+        require!(token_in_index != token_out_index, ErrorCode::SameTokenSwap);
+        require!(
+            reserves.len() == self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+
+        calc_in_given_out(
+            reserves[token_in_index],
+            reserves[token_out_index],
+            self.get_token_checked(token_in_index)?.weight_at(now),
+            self.get_token_checked(token_out_index)?.weight_at(now),
+            amount_out,
+        )
+    }
+
+    /// 现货价格（18 位定点数）：(vault_in / weight_in) / (vault_out / weight_out)，即每单位 token_out 的 token_in 价格
+    /// reserves: pool 中每个 token 的 vault 余额（按 token 顺序）；now: 当前时间（见 calculate_swap_output）
+    pub fn get_spot_price(
+        &self,
+        token_in_index: usize,
+        token_out_index: usize,
+        reserves: &[u64],
+        now: i64,
+    ) -> Result<u128> {
+        require!(
+            token_in_index < self.get_token_count() && token_out_index < self.get_token_count(),
+            ErrorCode::InvalidTokenIndex
+        );
+        require!(token_in_index != token_out_index, ErrorCode::SameTokenSwap);
+        require!(
+            reserves.len() == self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+        spot_price(
+            reserves[token_in_index],
+            self.get_token_checked(token_in_index)?.weight_at(now),
+            reserves[token_out_index],
+            self.get_token_checked(token_out_index)?.weight_at(now),
+        )
+    }
+
+    /// 含手续费的现货价格：get_spot_price * fee_denominator / (fee_denominator - fee_numerator)
+    /// 费率为该方向实际生效的费率（考虑 token 的买入 / 卖出费率）
+    pub fn get_spot_price_after_fee(
+        &self,
+        token_in_index: usize,
+        token_out_index: usize,
+        reserves: &[u64],
+        now: i64,
+    ) -> Result<u128> {
+        let price = self.get_spot_price(token_in_index, token_out_index, reserves, now)?;
+        let mints = [
+            *self.get_token_checked(token_in_index)?.mint_pubkey(),
+            *self.get_token_checked(token_out_index)?.mint_pubkey(),
+        ];
+        let (fee_numerator, fee_denominator) = self.swap_fee_rates(&mints, &[true, false])?[0];
+        spot_price_with_fee(price, fee_numerator, fee_denominator)
+    }
+
+    /// 计算池的恒定乘积和（用于验证）
+    /// 返回 Σ(vault_i * weight_i)，权重为 now 时刻的权重
+    pub fn calculate_invariant(&self, reserves: &[u64], now: i64) -> Result<u128> {
         require!(
             reserves.len() == self.get_token_count(),
             ErrorCode::InvalidTokenCount
@@ -235,8 +1312,8 @@ impl AnySwapPool {
 
         let mut invariant = 0u128;
         for i in 0..self.get_token_count() {
-            let token = self.get_token(i).ok_or(ErrorCode::InvalidTokenIndex)?;
-            let weight = token.get_weight();
+            let token = self.get_token_checked(i)?;
+            let weight = token.weight_at(now);
             let reserve = reserves[i] as u128;
             
             let product = reserve
@@ -249,4 +1326,1271 @@ impl AnySwapPool {
 
         Ok(invariant)
     }
+
+    /// 加权乘积池真正守恒的不变量：Π(reserve_i ^ normalized_weight_i)
+    ///
+    /// reserve 先放大为 18 位小数的定点数，返回值同样是 18 位小数的定点数；
+    /// 每一项用 FixedPoint::pow_down 计算，因此结果不会高于精确值。
+    /// 归一化权重之和为 1，不变量与 reserve 同量级：储备等比例放大 k 倍时不变量也放大 k 倍
+    /// 任一 reserve 为 0 时不变量为 0；权重为 now 时刻的权重
+    pub fn calculate_weighted_invariant(&self, reserves: &[u64], now: i64) -> Result<U256> {
+        require!(
+            reserves.len() == self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+        if reserves.contains(&0) {
+            return Ok(U256::zero());
+        }
+
+        let weights = self.weights_at(now);
+        let weight_sum = self.compute_weight_sum_at(now)?;
+        let mut invariant = FixedPoint::ONE;
+        for (&weight, &reserve) in weights.iter().zip(reserves.iter()) {
+            let weight = Self::compute_normalized_weight(weight, weight_sum)?;
+            let term = FixedPoint::pow_down(U256::from(reserve) * E18, U256::from(weight))?;
+            invariant = FixedPoint::mul_down(invariant, term)?;
+        }
+        Ok(invariant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::reference::reference_swap_out;
+
+    #[test]
+    fn test_resolve_admin_default_rejected() {
+        // 未显式请求无管理员模式时，默认公钥不能作为管理员
+        assert!(AnySwapPool::resolve_admin(&Pubkey::default(), false).is_err());
+    }
+
+    #[test]
+    fn test_resolve_admin_admin_less() {
+        // 显式请求无管理员模式
+        let admin = AnySwapPool::resolve_admin(&Pubkey::default(), true).unwrap();
+        assert_eq!(admin, Pubkey::default());
+
+        // 即使传入了管理员，无管理员模式下也不记录
+        let admin = AnySwapPool::resolve_admin(&Pubkey::new_unique(), true).unwrap();
+        assert_eq!(admin, Pubkey::default());
+    }
+
+    #[test]
+    fn test_resolve_fee_zero_denominator_rejected() {
+        // 未显式请求零手续费时，分母为 0 被拒绝
+        assert!(AnySwapPool::resolve_fee(0, 0, false).is_err());
+        assert!(AnySwapPool::resolve_fee(3, 0, false).is_err());
+        assert!(AnySwapPool::resolve_fee(3, 2, false).is_err());
+        assert_eq!(AnySwapPool::resolve_fee(3, 1000, false).unwrap(), (3, 1000));
+    }
+
+    #[test]
+    fn test_resolve_fee_zero_fee() {
+        // 显式零手续费：分子和分母都为 0 时记录为 0/1，之后计算手续费不会除以 0
+        let (numerator, denominator) = AnySwapPool::resolve_fee(0, 0, true).unwrap();
+        assert_eq!((numerator, denominator), (0, 1));
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.set_fee(numerator, denominator).unwrap();
+        assert_eq!(pool.calculate_fee(1_000_000).unwrap(), (0, 1_000_000));
+
+        // 分母非 0 时保留；零手续费模式下分子必须为 0
+        assert_eq!(AnySwapPool::resolve_fee(0, 1000, true).unwrap(), (0, 1000));
+        assert!(AnySwapPool::resolve_fee(3, 1000, true).is_err());
+
+        // 分母为 0 的 pool 计算手续费返回错误而不是 panic
+        let pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        assert_eq!(pool.get_fee_denominator(), 0);
+        assert_eq!(
+            pool.calculate_fee(1_000_000).unwrap_err(),
+            ErrorCode::InvalidFee.into()
+        );
+        assert_eq!(
+            AnySwapPool::resolve_fee(0, 0, false).unwrap_err(),
+            ErrorCode::InvalidFee.into()
+        );
+    }
+
+    #[test]
+    fn test_record_lp_burn_tracks_mint_supply() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let mut mint_supply = 1_000u64;
+        pool.set_total_amount_minted(mint_supply as u128);
+
+        // 正常销毁：记账与 mint 供应量一致
+        let supply_after = mint_supply - 100;
+        assert_eq!(pool.record_lp_burn(mint_supply, supply_after, 100).unwrap(), 100);
+        mint_supply = supply_after;
+        assert_eq!(pool.get_total_amount_minted(), mint_supply as u128);
+
+        // 销毁时 mint 实际减少得更多（例如 transfer hook），记账跟随实际供应量
+        let supply_after = mint_supply - 110;
+        assert_eq!(pool.record_lp_burn(mint_supply, supply_after, 100).unwrap(), 110);
+        mint_supply = supply_after;
+        assert_eq!(pool.get_total_amount_minted(), mint_supply as u128);
+
+        // 实际销毁少于 burn_amount 或供应量反而增加时拒绝，记账不变
+        assert!(pool.record_lp_burn(mint_supply, mint_supply - 50, 100).is_err());
+        assert!(pool.record_lp_burn(mint_supply, mint_supply + 1, 0).is_err());
+        assert_eq!(pool.get_total_amount_minted(), mint_supply as u128);
+    }
+
+    #[test]
+    fn test_active_tokens_after_add_and_remove() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let mints: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        for (i, mint) in mints.iter().enumerate() {
+            pool.add_token(mint, &Pubkey::new_unique(), 10 + i as u64).unwrap();
+        }
+        assert_eq!(pool.active_mints(), mints);
+
+        // 移除中间的 token：最后一个 token 移到该位置
+        pool.remove_token(1).unwrap();
+        assert_eq!(pool.active_mints(), vec![mints[0], mints[4], mints[2], mints[3]]);
+        // 移除最后一个 token
+        pool.remove_token(3).unwrap();
+        assert_eq!(pool.active_mints(), vec![mints[0], mints[4], mints[2]]);
+        // 移除后再加入，复用空出的槽位
+        let new_mint = Pubkey::new_unique();
+        pool.add_token(&new_mint, &Pubkey::new_unique(), 7).unwrap();
+        pool.remove_token(0).unwrap();
+        assert_eq!(pool.active_mints(), vec![new_mint, mints[4], mints[2]]);
+        assert!(pool.remove_token(3).is_err());
+
+        // 索引与 get_token 一致，权重之和只包含有效 token
+        let active: Vec<(usize, Pubkey, u64)> = pool
+            .active_tokens()
+            .map(|(i, token)| (i, *token.mint_pubkey(), token.weight_at(0)))
+            .collect();
+        assert_eq!(active.len(), pool.get_token_count());
+        for (i, mint, _) in active.iter() {
+            assert_eq!(pool.get_token(*i).unwrap().mint_pubkey(), mint);
+            assert_eq!(pool.find_token_index(mint), Some(*i));
+        }
+        let weight_sum: u64 = active.iter().map(|(_, _, w)| w).sum();
+        assert_eq!(weight_sum, 7 + 14 + 12);
+        assert_eq!(pool.get_weight_sum(), weight_sum);
+    }
+
+    #[test]
+    fn test_fee_denominator_too_large() {
+        // 1 / 1e12 的费率在 100 万单位的交易下向下取整为 0，设置时即被拒绝
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        assert!(pool.set_fee(1, 1_000_000_000_000).is_err());
+        pool.fee_numerator = 1;
+        pool.fee_denominator = 1_000_000_000_000;
+        assert_eq!(pool.calculate_fee(1_000_000).unwrap().0, 0);
+        assert!(AnySwapPool::resolve_fee(1, 1_000_000_000_000, false).is_err());
+        assert!(AnySwapPool::check_fee_denominator(1_000_000_000_000).is_err());
+        assert!(AnySwapPool::check_fee_denominator(MAX_FEE_DENOMINATOR + 1).is_err());
+
+        // 上限处的最小费率在常规数量下仍然生效
+        assert!(AnySwapPool::check_fee_denominator(MAX_FEE_DENOMINATOR).is_ok());
+        let (numerator, denominator) = AnySwapPool::resolve_fee(1, MAX_FEE_DENOMINATOR, false).unwrap();
+        pool.set_fee(numerator, denominator).unwrap();
+        assert_eq!(pool.calculate_fee(1_000_000_000).unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_set_fee_validates_pair() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.set_fee(3, 1000).unwrap();
+
+        // 分子大于分母、分母为 0、分母过大都被拒绝，且不修改已有费率
+        assert!(pool.set_fee(1001, 1000).is_err());
+        assert!(pool.set_fee(0, 0).is_err());
+        assert!(pool.set_fee(1, MAX_FEE_DENOMINATOR + 1).is_err());
+        assert_eq!((pool.get_fee_numerator(), pool.get_fee_denominator()), (3, 1000));
+
+        pool.set_fee(1000, 1000).unwrap();
+        assert_eq!((pool.get_fee_numerator(), pool.get_fee_denominator()), (1000, 1000));
+    }
+
+    #[test]
+    fn test_modify_fee_uses_atomic_setter() {
+        let admin = Pubkey::new_unique();
+        let mut pool = new_pool(&admin, &Pubkey::default());
+        pool.set_fee(3, 1000).unwrap();
+
+        // 新分子大于旧分母：合法的一组费率一次写入
+        pool.modify_fee(&admin, 5_000, 1_000_000).unwrap();
+        assert_eq!((pool.get_fee_numerator(), pool.get_fee_denominator()), (5_000, 1_000_000));
+
+        // 非法的一组费率不会留下部分写入的状态
+        assert!(pool.modify_fee(&admin, 2_000_000, 1_000_000).is_err());
+        assert!(pool.modify_fee(&admin, 1, 0).is_err());
+        assert!(pool.modify_fee(&admin, 0, 1000).is_err());
+        assert!(pool.modify_fee(&Pubkey::new_unique(), 3, 1000).is_err());
+        assert_eq!((pool.get_fee_numerator(), pool.get_fee_denominator()), (5_000, 1_000_000));
+    }
+
+    fn new_pool(admin: &Pubkey, guardian: &Pubkey) -> Box<AnySwapPool> {
+        let mut pool: Box<AnySwapPool> = Box::new(bytemuck::Zeroable::zeroed());
+        pool.admin = *admin;
+        pool.set_guardian(guardian);
+        pool
+    }
+
+    #[test]
+    fn test_reserved_exit_excluded_from_lp_balance() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        for _ in 0..3 {
+            pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 50).unwrap();
+        }
+        for i in 0..3 {
+            pool.tokens[i].set_principal_reserve(1_000_000);
+        }
+
+        // 退出份额划出后不再计入储备，之后的 swap 和增减流动性看不到这部分余额
+        pool.reserve_exit(&[100_000, 200_000, 300_000], &[10, 20, 30]).unwrap();
+        assert_eq!(pool.pending_exit_count, 1);
+        assert_eq!(pool.tokens[0].lp_balance(1_000_000), 900_000);
+        assert_eq!(pool.tokens[2].lp_balance(1_000_000), 700_000);
+        assert_eq!(pool.tokens[1].get_principal_reserve(), 1_000_000 - 200_000 - 20);
+
+        // 有待处理的退出时不能移除 token，也不能关闭 pool
+        assert_eq!(pool.remove_token(2).err(), Some(error!(ErrorCode::ExitsPending)));
+        assert_eq!(pool.check_can_close().err(), Some(error!(ErrorCode::ExitsPending)));
+
+        // 支付后 vault 余额与划出的份额同时减少，储备不变
+        pool.release_exit(0, 100_000).unwrap();
+        assert_eq!(pool.tokens[0].lp_balance(900_000), 900_000);
+        assert!(pool.release_exit(0, 1).is_err());
+        pool.finish_exit().unwrap();
+        assert!(pool.finish_exit().is_err());
+        pool.remove_token(2).unwrap();
+    }
+
+    #[test]
+    fn test_mints_hash_detects_reordering() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let mints: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        for mint in mints.iter() {
+            pool.add_token(mint, &Pubkey::new_unique(), 50).unwrap();
+        }
+        let hash = pool.mints_hash(3).unwrap();
+        assert!(pool.mints_hash(4).is_err());
+
+        // 末尾追加 token 不影响前 3 个 token 的哈希
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 50).unwrap();
+        assert_eq!(pool.mints_hash(3).unwrap(), hash);
+
+        // 移除 token 后最后一个 token 移到该位置，token 数量相同时也能发现变化
+        pool.remove_token(1).unwrap();
+        assert_eq!(pool.get_token_count(), 3);
+        assert_ne!(pool.mints_hash(3).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_guardian_can_pause_but_not_modify_fee() {
+        let admin = Pubkey::new_unique();
+        let guardian = Pubkey::new_unique();
+        let mut pool = new_pool(&admin, &guardian);
+
+        // guardian 可以暂停
+        pool.set_paused_ops(&guardian, PAUSE_SWAP).unwrap();
+        assert!(pool.check_not_paused(PAUSE_SWAP).is_err());
+        assert!(pool.check_not_paused(PAUSE_ADD_LIQUIDITY).is_ok());
+        pool.set_paused_ops(&guardian, PAUSE_ALL).unwrap();
+        assert_eq!(pool.get_paused_ops(), PAUSE_ALL);
+
+        // guardian 不能恢复
+        assert!(pool.set_paused_ops(&guardian, PAUSE_SWAP).is_err());
+        assert!(pool.set_paused_ops(&guardian, 0).is_err());
+
+        // guardian 不能执行管理员操作（modify_fee 等通过 verify_admin 校验）
+        assert!(pool.verify_admin(&guardian).is_err());
+    }
+
+    #[test]
+    fn test_admin_can_pause_and_modify_fee() {
+        let admin = Pubkey::new_unique();
+        let guardian = Pubkey::new_unique();
+        let mut pool = new_pool(&admin, &guardian);
+
+        pool.set_paused_ops(&admin, PAUSE_ALL).unwrap();
+        assert!(pool.check_not_paused(PAUSE_REMOVE_LIQUIDITY).is_err());
+        // 管理员可以恢复
+        pool.set_paused_ops(&admin, 0).unwrap();
+        assert!(pool.check_not_paused(PAUSE_ALL).is_ok());
+
+        assert!(pool.verify_admin(&admin).is_ok());
+    }
+
+    #[test]
+    fn test_two_step_admin_transfer() {
+        let admin = Pubkey::new_unique();
+        let new_admin = Pubkey::new_unique();
+        let mut pool = new_pool(&admin, &Pubkey::default());
+
+        // 只有当前管理员可以提议
+        assert!(pool.propose_admin(&new_admin, &new_admin).is_err());
+        pool.propose_admin(&admin, &new_admin).unwrap();
+        assert_eq!(*pool.get_pending_admin(), new_admin);
+        // 接受之前 admin 不变
+        assert!(pool.verify_admin(&admin).is_ok());
+        assert!(pool.verify_admin(&new_admin).is_err());
+
+        pool.accept_admin(&new_admin).unwrap();
+        assert!(pool.verify_admin(&new_admin).is_ok());
+        assert!(pool.verify_admin(&admin).is_err());
+        assert!(!pool.has_pending_admin());
+        // pending 已清空，不能重复接受
+        assert!(pool.accept_admin(&new_admin).is_err());
+    }
+
+    #[test]
+    fn test_accept_admin_rejects_non_pending_signer() {
+        let admin = Pubkey::new_unique();
+        let new_admin = Pubkey::new_unique();
+        let mut pool = new_pool(&admin, &Pubkey::default());
+
+        // 没有进行中的转移时任何人都不能接受（包括默认公钥）
+        assert!(pool.accept_admin(&Pubkey::default()).is_err());
+        assert!(pool.accept_admin(&admin).is_err());
+
+        pool.propose_admin(&admin, &new_admin).unwrap();
+        assert!(pool.accept_admin(&Pubkey::new_unique()).is_err());
+        assert!(pool.accept_admin(&admin).is_err());
+        assert_eq!(pool.admin, admin);
+
+        // 传入默认公钥取消转移
+        pool.propose_admin(&admin, &Pubkey::default()).unwrap();
+        assert!(pool.accept_admin(&new_admin).is_err());
+        assert_eq!(pool.admin, admin);
+    }
+
+    #[test]
+    fn test_set_pause_blocks_swaps_until_unpaused() {
+        let admin = Pubkey::new_unique();
+        let guardian = Pubkey::new_unique();
+        let mut pool = new_pool(&admin, &guardian);
+
+        pool.set_pause(&admin, true, false).unwrap();
+        assert!(pool.check_not_paused(PAUSE_SWAP).is_err());
+        assert!(pool.check_not_paused(PAUSE_ADD_LIQUIDITY).is_err());
+        assert!(pool.check_not_paused(PAUSE_REMOVE_LIQUIDITY).is_err());
+
+        // 允许暂停期间移除流动性
+        pool.set_pause(&admin, true, true).unwrap();
+        assert!(pool.check_not_paused(PAUSE_SWAP).is_err());
+        assert!(pool.check_not_paused(PAUSE_REMOVE_LIQUIDITY).is_ok());
+
+        // 只有管理员可以使用开关
+        assert!(pool.set_pause(&guardian, false, false).is_err());
+        assert!(pool.check_not_paused(PAUSE_SWAP).is_err());
+
+        pool.set_pause(&admin, false, false).unwrap();
+        assert!(pool.check_not_paused(PAUSE_ALL).is_ok());
+    }
+
+    #[test]
+    fn test_paused_ops_rejects_unknown_bits_and_strangers() {
+        let admin = Pubkey::new_unique();
+        let mut pool = new_pool(&admin, &Pubkey::default());
+
+        assert!(pool.set_paused_ops(&admin, 1 << 10).is_err());
+        // 未设置 guardian 时，其他账户不能暂停
+        assert!(pool.set_paused_ops(&Pubkey::new_unique(), PAUSE_SWAP).is_err());
+        assert!(pool.set_paused_ops(&Pubkey::default(), PAUSE_SWAP).is_err());
+    }
+
+    #[test]
+    fn test_total_amount_minted_u128() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let amount = u64::MAX as u128 + 12345;
+        pool.set_total_amount_minted(amount);
+        assert_eq!(pool.get_total_amount_minted(), amount);
+        assert_eq!(pool.total_amount_minted, 12344);
+        assert_eq!(pool.total_amount_minted_hi, 1);
+
+        pool.set_total_amount_minted(42);
+        assert_eq!(pool.get_total_amount_minted(), 42);
+        assert_eq!(pool.total_amount_minted_hi, 0);
+    }
+
+    #[test]
+    fn test_lp_mint_migration_keeps_exit_value() {
+        use crate::state::liquidity::remove_liquidity_inner;
+
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let old_mint = Pubkey::new_unique();
+        let new_mint = Pubkey::new_unique();
+        let vaults = [1_000_000u64, 4_000_000u64];
+        pool.set_total_amount_minted(2_000_000);
+        let holder_lp = 300_000u64;
+
+        let before = remove_liquidity_inner(
+            &vaults,
+            holder_lp,
+            pool.get_total_amount_minted(),
+            3,
+            1000,
+        )
+        .unwrap();
+
+        // 迁移前只接受旧 mint
+        assert!(!pool.resolve_lp_mint(&old_mint, &old_mint).unwrap());
+        assert!(pool.resolve_lp_mint(&new_mint, &old_mint).is_err());
+
+        // 不能迁移到当前的 LP mint
+        assert!(pool.start_lp_migration(&old_mint, &old_mint).is_err());
+        pool.start_lp_migration(&new_mint, &old_mint).unwrap();
+        assert!(pool.start_lp_migration(&Pubkey::new_unique(), &old_mint).is_err());
+        // 完成迁移前只能向旧 mint 铸造
+        assert!(pool.check_canonical_lp_mint(&old_mint, &old_mint).is_ok());
+        assert!(pool.check_canonical_lp_mint(&new_mint, &old_mint).is_err());
+
+        // 持有人把旧 LP 换成新 LP：LP 总量不变，新 mint 供应量增加
+        pool.record_lp_swap(holder_lp).unwrap();
+        assert_eq!(pool.get_total_amount_minted(), 2_000_000);
+        assert_eq!(pool.migration_lp_supply, holder_lp);
+
+        // 迁移期间两个 mint 都可以用于移除流动性
+        assert!(!pool.resolve_lp_mint(&old_mint, &old_mint).unwrap());
+        assert!(pool.resolve_lp_mint(&new_mint, &old_mint).unwrap());
+        assert!(pool.resolve_lp_mint(&Pubkey::new_unique(), &old_mint).is_err());
+
+        // 用新 LP 退出的价值不变
+        let after = remove_liquidity_inner(
+            &vaults,
+            holder_lp,
+            pool.get_total_amount_minted(),
+            3,
+            1000,
+        )
+        .unwrap();
+        assert_eq!(after.amounts_out, before.amounts_out);
+
+        pool.record_migrated_lp_burn(holder_lp).unwrap();
+        assert_eq!(pool.migration_lp_supply, 0);
+        assert!(pool.record_migrated_lp_burn(1).is_err());
+    }
+
+    #[test]
+    fn test_finish_lp_migration() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let old_mint = Pubkey::new_unique();
+        let new_mint = Pubkey::new_unique();
+        pool.set_total_amount_minted(1_000);
+        assert!(pool.finish_lp_migration().is_err());
+
+        pool.start_lp_migration(&new_mint, &old_mint).unwrap();
+        pool.record_lp_swap(600).unwrap();
+        // 旧 mint 上还有 400 LP，不能完成
+        assert_eq!(
+            pool.finish_lp_migration().err(),
+            Some(error!(ErrorCode::LpMigrationIncomplete))
+        );
+        assert_eq!(pool.check_can_close().err(), Some(error!(ErrorCode::PoolNotEmpty)));
+
+        pool.record_lp_swap(400).unwrap();
+        pool.finish_lp_migration().unwrap();
+        assert!(!pool.is_migrating_lp_mint());
+        assert_eq!(pool.canonical_lp_mint(&old_mint), new_mint);
+
+        // 新 mint 成为当前的 LP mint：铸造和销毁都只接受新 mint，旧 mint 不再有效
+        assert!(pool.check_canonical_lp_mint(&new_mint, &old_mint).is_ok());
+        assert!(pool.check_canonical_lp_mint(&old_mint, &old_mint).is_err());
+        assert!(!pool.resolve_lp_mint(&new_mint, &old_mint).unwrap());
+        assert!(pool.resolve_lp_mint(&old_mint, &old_mint).is_err());
+
+        // 全部 LP 移除后可以关闭
+        pool.set_total_amount_minted(0);
+        assert!(pool.check_can_close().is_ok());
+
+        // 可以再迁移到另一个 mint，但不能迁移回当前的 LP mint
+        assert!(pool.start_lp_migration(&new_mint, &old_mint).is_err());
+        pool.start_lp_migration(&Pubkey::new_unique(), &old_mint).unwrap();
+    }
+
+    #[test]
+    fn test_check_max_fee() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let token = Pubkey::new_unique();
+        let quote = Pubkey::new_unique();
+        pool.add_token(&token, &Pubkey::new_unique(), 50).unwrap();
+        pool.add_token(&quote, &Pubkey::new_unique(), 50).unwrap();
+        let sell = [token, quote];
+        let is_in = [true, false];
+        pool.set_fee(3, 1000).unwrap(); // 30 bps
+        assert!(pool.check_max_fee(None, &sell, &is_in).is_ok());
+        assert!(pool.check_max_fee(Some(30), &sell, &is_in).is_ok());
+        assert!(pool.check_max_fee(Some(50), &sell, &is_in).is_ok());
+
+        // 费率被上调后超过用户可接受的上限，swap 被拒绝
+        pool.set_fee(31, 10000).unwrap();
+        assert_eq!(
+            pool.check_max_fee(Some(30), &sell, &is_in).unwrap_err(),
+            error!(ErrorCode::FeeExceedsMax)
+        );
+        // 不足 1 bps 的差距也会被拒绝
+        pool.set_fee(30_001, 10_000_000).unwrap();
+        assert!(pool.check_max_fee(Some(30), &sell, &is_in).is_err());
+    }
+
+    #[test]
+    fn test_check_max_fee_uses_directional_fees() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let token = Pubkey::new_unique();
+        let quote = Pubkey::new_unique();
+        pool.add_token(&token, &Pubkey::new_unique(), 50).unwrap();
+        pool.add_token(&quote, &Pubkey::new_unique(), 50).unwrap();
+        pool.set_fee(3, 1000).unwrap(); // pool 费率 30 bps
+
+        // 卖出 token 的费率被覆盖为 100%：pool 费率满足上限，但实际费率超过
+        pool.set_token_fees(&token, 0, 10000).unwrap();
+        assert_eq!(
+            pool.check_max_fee(Some(30), &[token, quote], &[true, false]).unwrap_err(),
+            error!(ErrorCode::FeeExceedsMax)
+        );
+        // 反方向（卖出 quote、买入 token）仍使用 pool 费率
+        assert!(pool.check_max_fee(Some(30), &[quote, token], &[true, false]).is_ok());
+
+        // 买入 token 的覆盖同样计入输入 token 的费率
+        pool.set_token_fees(&token, 40, 0).unwrap();
+        assert!(pool.check_max_fee(Some(30), &[quote, token], &[true, false]).is_err());
+        assert!(pool.check_max_fee(Some(40), &[quote, token], &[true, false]).is_ok());
+    }
+
+    #[test]
+    fn test_pool_magic() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        assert!(!pool.has_valid_magic());
+        pool.init_header();
+        assert!(pool.has_valid_magic());
+        assert_eq!(pool.layout_version, POOL_LAYOUT_VERSION);
+
+        // 魔数和偏移是客户端过滤条件的一部分，不能改变
+        assert_eq!(&POOL_MAGIC, b"ANYS");
+        assert_eq!(POOL_MAGIC_OFFSET, 10);
+        assert_eq!(8 + std::mem::offset_of!(AnySwapPool, magic), POOL_MAGIC_OFFSET);
+
+        // 账户数据中（不含 discriminator）魔数紧跟 token_count
+        let bytes = bytemuck::bytes_of(pool.as_ref());
+        let offset = POOL_MAGIC_OFFSET - 8;
+        assert_eq!(&bytes[offset..offset + 4], b"ANYS");
+        assert_eq!(bytes[offset + 4], POOL_LAYOUT_VERSION);
+    }
+
+    #[test]
+    fn test_activity_timestamps() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.init_timestamps(1_000);
+        assert_eq!(pool.get_activity(), (1_000, 1_000));
+
+        // swap 之后最近活动时间前进，创建时间不变
+        pool.record_activity(1_500);
+        assert_eq!(pool.get_activity(), (1_000, 1_500));
+
+        // 时钟回退时不倒退
+        pool.record_activity(1_200);
+        assert_eq!(pool.get_activity(), (1_000, 1_500));
+    }
+
+    #[test]
+    fn test_principal_and_compound_fees() {
+        use crate::state::liquidity::remove_liquidity_inner;
+        use crate::state::swap::swap_inner;
+
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        for mint in mints.iter() {
+            let index = pool.add_token(mint, &Pubkey::new_unique(), 50).unwrap();
+            pool.record_principal_flow(index, 1_000_000, 0, 0).unwrap();
+        }
+        let mut vaults = [1_000_000u64, 1_000_000u64];
+        let accrued = |pool: &AnySwapPool, vaults: &[u64; 2]| -> Vec<u64> {
+            (0..2).map(|i| pool.tokens[i].accrued_fee(vaults[i])).collect()
+        };
+
+        // swap：手续费随输入转入 vault，本金只计入扣费后的输入，差值即手续费
+        let is_in = [true, false];
+        let result = swap_inner(&is_in, &[10_000, 1], &[10_000, 0], &vaults, &[50, 50], 3, 1000).unwrap();
+        vaults[0] += result.amounts[0] + result.burn_fees[0];
+        vaults[1] -= result.amounts[1];
+        pool.record_swap_principal(&mints, &is_in, &result.amounts, &result.burn_fees).unwrap();
+        assert_eq!(accrued(&pool, &vaults), vec![30u64, 0]);
+
+        // zap 内部 swap 的手续费留在 vault 中：vault 增加了 portion，本金只增加扣费后的部分
+        let portion = 20_000u64;
+        let leg = swap_inner(&is_in, &[portion, 1], &[portion, 0], &vaults, &[50, 50], 3, 1000).unwrap();
+        vaults[0] += portion;
+        vaults[1] -= leg.amounts[1];
+        pool.record_principal_flow(0, portion, 0, leg.burn_fees[0]).unwrap();
+        pool.record_principal_flow(1, 0, leg.amounts[1], 0).unwrap();
+        assert_eq!(accrued(&pool, &vaults), vec![result.burn_fees[0] + leg.burn_fees[0], 0]);
+
+        // 移除流动性的手续费同样留在 vault 中
+        let removed = remove_liquidity_inner(&vaults, 100_000, 1_000_000, 3, 1000).unwrap();
+        for (i, vault) in vaults.iter_mut().enumerate() {
+            *vault -= removed.amounts_out[i];
+            pool.record_principal_flow(i, 0, removed.amounts_out[i], removed.burn_fees[i]).unwrap();
+        }
+        let fees = accrued(&pool, &vaults);
+        assert!(fees.iter().all(|&fee| fee > 0));
+        assert_eq!(fees[1], removed.burn_fees[1]);
+
+        // compound 之后手续费并入本金，差值归零
+        let compounded = pool.compound_fees(&vaults).unwrap();
+        assert_eq!(compounded, fees);
+        assert_eq!(accrued(&pool, &vaults), vec![0u64, 0]);
+        for (i, &vault) in vaults.iter().enumerate() {
+            assert_eq!(pool.tokens[i].get_principal_reserve(), vault);
+        }
+        assert!(pool.compound_fees(&vaults[..1]).is_err());
+    }
+
+    #[test]
+    fn test_check_token_decimals() {
+        assert!(AnySwapPool::check_token_decimals(18).is_ok());
+        assert!(AnySwapPool::check_token_decimals(6).is_ok());
+        assert!(AnySwapPool::check_token_decimals(0).is_ok());
+        // create_pool 的 LP 精度：未指定时为 9，指定时原样使用
+        assert_eq!(AnySwapPool::lp_decimals_or_default(None), DEFAULT_LP_DECIMALS);
+        assert_eq!(AnySwapPool::lp_decimals_or_default(Some(6)), 6);
+        assert_eq!(
+            AnySwapPool::check_token_decimals(20).unwrap_err(),
+            ErrorCode::UnsupportedDecimals.into()
+        );
+    }
+
+    #[test]
+    fn test_stable_group_lower_slippage() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.fee_numerator = 3;
+        pool.fee_denominator = 10000;
+        let usdc = Pubkey::new_unique();
+        let usdt = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+        for mint in [usdc, usdt, sol] {
+            pool.add_token(&mint, &Pubkey::new_unique(), 50).unwrap();
+        }
+        let vaults = [1_000_000_000u64, 1_000_000_000];
+        let weights = [50u64, 50];
+        let amount = 50_000_000u64;
+        let swap = |pool: &AnySwapPool, mints: [Pubkey; 2]| {
+            pool.swap_directional(&mints, &[true, false], &[amount, 0], &[amount, 0], &vaults, &weights)
+                .unwrap()
+        };
+
+        let weighted = swap(&pool, [usdc, usdt]);
+        pool.set_stable_group(&[0, 1], 200).unwrap();
+        assert!(pool.tokens[0].is_stable() && pool.tokens[1].is_stable() && !pool.tokens[2].is_stable());
+
+        // 储备相等时稳定组内的 swap 滑点更小，手续费相同
+        let stable = swap(&pool, [usdc, usdt]);
+        assert_eq!(stable.burn_fees, weighted.burn_fees);
+        assert!(stable.amounts[1] > weighted.amounts[1]);
+        let amount_after_fee = amount - stable.burn_fees[0];
+        assert!(stable.amounts[1] <= amount_after_fee);
+        assert!(amount_after_fee - stable.amounts[1] < (amount_after_fee - weighted.amounts[1]) / 10);
+
+        // 跨组的 swap 仍使用加权恒定乘积
+        assert_eq!(swap(&pool, [usdc, sol]).amounts, weighted.amounts);
+        assert!(pool.stable_pair_amplification(&[usdc, sol], &[true, false]).is_none());
+
+        // 无效配置
+        assert!(pool.set_stable_group(&[0], 200).is_err());
+        assert!(pool.set_stable_group(&[0, 0], 200).is_err());
+        assert!(pool.set_stable_group(&[0, 3], 200).is_err());
+        assert!(pool.set_stable_group(&[0, 1], 0).is_err());
+
+        // 取消稳定组
+        pool.set_stable_group(&[], 0).unwrap();
+        assert_eq!(pool.get_amplification(), 0);
+        assert_eq!(swap(&pool, [usdc, usdt]).amounts, weighted.amounts);
+    }
+
+    #[test]
+    fn test_normalized_weight_cache_tracks_weight_changes() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let check = |pool: &AnySwapPool| {
+            let weight_sum = pool.compute_weight_sum().unwrap();
+            for (_, token) in pool.active_tokens() {
+                let fresh = AnySwapPool::compute_normalized_weight(token.weight_at(0), weight_sum).unwrap();
+                assert_eq!(token.get_normalized_weight(), fresh);
+            }
+        };
+
+        for weight in [20u64, 30, 50] {
+            pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), weight).unwrap();
+            check(&pool);
+        }
+        assert_eq!(pool.tokens[2].get_normalized_weight(), 500_000_000_000_000_000);
+
+        // modify_token_weight：修改权重后重新计算权重之和
+        pool.get_token_mut_checked(0).unwrap().set_weight(70);
+        pool.recompute_weight_sum().unwrap();
+        check(&pool);
+        assert_eq!(pool.tokens[2].get_normalized_weight(), 333_333_333_333_333_333);
+
+        pool.remove_token(1).unwrap();
+        check(&pool);
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 7).unwrap();
+        check(&pool);
+        let total: u64 = pool.active_tokens().map(|(_, t)| t.get_normalized_weight()).sum();
+        assert!(total <= 1_000_000_000_000_000_000 && total > 1_000_000_000_000_000_000 - 3);
+    }
+
+    #[test]
+    fn test_validate_weights() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        for weight in [20u64, 30, 50] {
+            pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), weight).unwrap();
+        }
+        pool.validate_weights().unwrap();
+        assert_eq!(pool.normalized_weight(1, 0).unwrap(), 300_000_000_000_000_000);
+        let total: u64 = (0..3).map(|i| pool.normalized_weight(i, 0).unwrap()).sum();
+        assert_eq!(total, 1_000_000_000_000_000_000);
+        assert!(pool.normalized_weight(3, 0).is_err());
+
+        // 1 / 1001 < 0.01，低于 MIN_WEIGHT
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 1000).unwrap();
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 1).unwrap();
+        assert!(pool.normalized_weight(1, 0).unwrap() < MIN_WEIGHT);
+        assert_eq!(pool.validate_weights().unwrap_err(), ErrorCode::InvalidWeight.into());
+
+        // 权重之和溢出
+        pool.get_token_mut_checked(0).unwrap().set_weight(u64::MAX);
+        assert_eq!(pool.validate_weights().unwrap_err(), ErrorCode::InvalidWeight.into());
+    }
+
+    #[test]
+    fn test_swap_output_mixed_decimals() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        // 6 位精度的 USDC 和 9 位精度的 token，储备价值相等（各 100 万个单位）
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 50).unwrap();
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 50).unwrap();
+        pool.set_token_decimals(0, 6).unwrap();
+        pool.set_token_decimals(1, 9).unwrap();
+        assert_eq!(pool.tokens[1].get_decimals(), 9);
+        let reserves = [1_000_000 * 1_000_000u64, 1_000_000 * 1_000_000_000u64];
+
+        // 100 USDC 换出约 100 个 token（只差 0.01% 的滑点）
+        let out = pool.calculate_swap_output(0, 1, 100 * 1_000_000, &reserves, 0).unwrap();
+        let expected = 100 * 1_000_000_000u64;
+        assert!(out < expected && out > expected - expected / 5_000, "out {}", out);
+
+        // 反方向同样约为 1:1
+        let back = pool.calculate_swap_output(1, 0, 100 * 1_000_000_000, &reserves, 0).unwrap();
+        let expected = 100 * 1_000_000u64;
+        assert!(back < expected && back > expected - expected / 5_000, "back {}", back);
+
+        // 精度不同的 token 不能组成稳定组，精度超过上限的记录被拒绝
+        assert!(pool.set_stable_group(&[0, 1], 200).is_err());
+        assert!(pool.set_token_decimals(0, MAX_TOKEN_DECIMALS + 1).is_err());
+        assert!(pool.set_token_decimals(2, 6).is_err());
+    }
+
+    #[test]
+    fn test_recompute_weight_sum() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.fee_numerator = 3;
+        pool.fee_denominator = 1000;
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        for (mint, weight) in mints.iter().zip([20u64, 30, 50]) {
+            pool.add_token(mint, &Pubkey::new_unique(), weight).unwrap();
+        }
+        assert_eq!(pool.get_weight_sum(), 100);
+
+        let vaults = [1_000_000u64, 2_000_000];
+        let weights = [20u64, 30];
+        let quote = |pool: &AnySwapPool| {
+            pool.swap_directional(&mints[..2], &[true, false], &[10_000, 0], &[10_000, 0], &vaults, &weights)
+                .unwrap()
+                .amounts
+        };
+        let before = quote(&pool);
+
+        // 手动迁移后缓存失效，重新计算恢复正确值
+        pool.weight_sum = 7;
+        assert_eq!(pool.recompute_weight_sum().unwrap(), 100);
+        assert_eq!(pool.get_weight_sum(), 100);
+
+        // swap 按每个 token 的权重定价，恢复后结果不变
+        assert_eq!(quote(&pool), before);
+
+        // 修改权重后重新计算
+        pool.get_token_mut_checked(0).unwrap().set_weight(70);
+        assert_eq!(pool.compute_weight_sum().unwrap(), 150);
+        assert_eq!(pool.recompute_weight_sum().unwrap(), 150);
+    }
+
+    #[test]
+    fn test_directional_fees_buy_cheaper_than_sell() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.fee_numerator = 30;
+        pool.fee_denominator = 10000;
+        let token = Pubkey::new_unique();
+        let quote = Pubkey::new_unique();
+        pool.add_token(&token, &Pubkey::new_unique(), 50).unwrap();
+        pool.add_token(&quote, &Pubkey::new_unique(), 50).unwrap();
+        let vaults = [10_000_000u64, 10_000_000u64];
+        let weights = [50u64, 50u64];
+        let amount = 100_000u64;
+
+        // 未设置时两个方向都使用 pool 费率
+        let sell = pool
+            .swap_directional(&[token, quote], &[true, false], &[amount, 0], &[amount, 0], &vaults, &weights)
+            .unwrap();
+        assert_eq!(sell.burn_fees[0], 300);
+
+        // 买入 10 bps，卖出 50 bps
+        pool.set_token_fees(&token, 10, 50).unwrap();
+        let buy = pool
+            .swap_directional(&[quote, token], &[true, false], &[amount, 0], &[amount, 0], &vaults, &weights)
+            .unwrap();
+        let sell = pool
+            .swap_directional(&[token, quote], &[true, false], &[amount, 0], &[amount, 0], &vaults, &weights)
+            .unwrap();
+        assert_eq!(buy.burn_fees[0], 100);
+        assert_eq!(sell.burn_fees[0], 500);
+        assert!(buy.amounts[1] > sell.amounts[1]);
+
+        // 输入 token 的卖出费率优先于输出 token 的买入费率
+        pool.set_token_fees(&quote, 0, 20).unwrap();
+        let buy = pool
+            .swap_directional(&[quote, token], &[true, false], &[amount, 0], &[amount, 0], &vaults, &weights)
+            .unwrap();
+        assert_eq!(buy.burn_fees[0], 200);
+
+        // 重置为 0 回退到 pool 费率；超过 10000 bps 或未知 mint 拒绝
+        pool.set_token_fees(&token, 0, 0).unwrap();
+        pool.set_token_fees(&quote, 0, 0).unwrap();
+        let rates = pool.swap_fee_rates(&[token, quote], &[true, false]).unwrap();
+        assert_eq!(rates, vec![(30, 10000); 2]);
+        assert!(pool.set_token_fees(&token, 10001, 0).is_err());
+        assert!(pool.set_token_fees(&Pubkey::new_unique(), 10, 10).is_err());
+    }
+
+    #[test]
+    fn test_calculate_swap_output_rounding() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 20).unwrap();
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 30).unwrap();
+        let reserves = [1_000_000_000_000u64, 2_000_000_000_000];
+        let user_favorable = SwapRounding {
+            round_up_input: false,
+            round_down_output: false,
+        };
+
+        for amount_in in [1u64, 2, 7, 100, 12_345, 999_999_999] {
+            let preview = pool.calculate_swap_output(0, 1, amount_in, &reserves, 0).unwrap();
+            let unrounded = pool
+                .calculate_swap_output_with_rounding(0, 1, amount_in, &reserves, user_favorable, 0)
+                .unwrap();
+            // 预览从不乐观，最多比另一方向少一个取整单位
+            assert!(preview <= unrounded);
+            assert!(unrounded - preview <= 1);
+            let (_, expected) =
+                reference_swap_out(reserves[0], reserves[1], 20, 30, amount_in, 0, 1);
+            assert!(preview <= expected && expected - preview <= 1);
+        }
+        assert_eq!(pool.calculate_swap_output(0, 1, 0, &reserves, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_calculate_swap_output_weighted() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 80).unwrap();
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 20).unwrap();
+        let reserves = [1_000_000_000u64, 1_000_000_000];
+
+        // 80/20 pool：out = 1e9 * (1 - (1e9 / 1.1e9)^4) = 316_986_544.6...
+        let out = pool.calculate_swap_output(0, 1, 100_000_000, &reserves, 0).unwrap();
+        assert!(out <= 316_986_544 && 316_986_544 - out <= 1);
+        // 反方向：out = 1e9 * (1 - (1e9 / 1.1e9)^(1/4))
+        let out = pool.calculate_swap_output(1, 0, 100_000_000, &reserves, 0).unwrap();
+        let (_, expected) = reference_swap_out(1_000_000_000, 1_000_000_000, 20, 80, 100_000_000, 0, 1);
+        assert!(out <= expected && expected - out <= 1);
+
+        // 巨额输入也不会超过 vault 余额
+        let out = pool.calculate_swap_output(0, 1, u64::MAX - reserves[0], &reserves, 0).unwrap();
+        assert!(out < reserves[1]);
+
+        assert_eq!(
+            pool.calculate_swap_output(0, 0, 1, &reserves, 0).unwrap_err(),
+            error!(ErrorCode::SameTokenSwap)
+        );
+        assert_eq!(
+            pool.calculate_swap_output(0, 2, 1, &reserves, 0).unwrap_err(),
+            error!(ErrorCode::InvalidTokenIndex)
+        );
+        assert!(pool.calculate_swap_output(0, 1, 1, &reserves[..1], 0).is_err());
+    }
+
+    #[test]
+    fn test_calc_in_given_out_round_trip() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 80).unwrap();
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 20).unwrap();
+        let reserves = [1_000_000_000_000u64, 2_000_000_000_000];
+
+        for amount_out in [1_000u64, 1_000_000, 123_456_789_012, 1_000_000_000_000] {
+            let amount_in = pool.calc_in_given_out(0, 1, amount_out, &reserves, 0).unwrap();
+            let out = pool.calculate_swap_output(0, 1, amount_in, &reserves, 0).unwrap();
+            // 1 个单位输入约换 8 个单位输出，往返的差不超过这个取整粒度
+            assert!(out.abs_diff(amount_out) <= 9 + amount_out / 1_000_000_000_000);
+        }
+
+        assert_eq!(
+            pool.calc_in_given_out(0, 1, reserves[1], &reserves, 0).unwrap_err(),
+            error!(ErrorCode::InsufficientLiquidity)
+        );
+        assert_eq!(
+            pool.calc_in_given_out(1, 1, 1, &reserves, 0).unwrap_err(),
+            error!(ErrorCode::SameTokenSwap)
+        );
+        assert_eq!(
+            pool.calc_in_given_out(0, 2, 1, &reserves, 0).unwrap_err(),
+            error!(ErrorCode::InvalidTokenIndex)
+        );
+    }
+
+    #[test]
+    fn test_get_spot_price() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.fee_numerator = 3;
+        pool.fee_denominator = 1000;
+        let mint_a = Pubkey::new_unique();
+        pool.add_token(&mint_a, &Pubkey::new_unique(), 80).unwrap();
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 20).unwrap();
+        let reserves = [4_000_000u64, 1_000_000];
+
+        // (4e6 / 80) / (1e6 / 20) = 1
+        assert_eq!(pool.get_spot_price(0, 1, &reserves, 0).unwrap(), 1_000_000_000_000_000_000);
+        assert_eq!(pool.get_spot_price(1, 0, &reserves, 0).unwrap(), 1_000_000_000_000_000_000);
+        let reserves = [8_000_000u64, 1_000_000];
+        assert_eq!(pool.get_spot_price(0, 1, &reserves, 0).unwrap(), 2_000_000_000_000_000_000);
+        assert_eq!(pool.get_spot_price(1, 0, &reserves, 0).unwrap(), 500_000_000_000_000_000);
+
+        // 含手续费：乘以 1000 / 997（向上取整）
+        let after_fee = pool.get_spot_price_after_fee(0, 1, &reserves, 0).unwrap();
+        assert_eq!(after_fee, (2_000_000_000_000_000_000u128 * 1000).div_ceil(997));
+        // 卖出 token A 的覆盖费率优先
+        pool.set_token_fees(&mint_a, 0, 100).unwrap();
+        let after_fee = pool.get_spot_price_after_fee(0, 1, &reserves, 0).unwrap();
+        assert_eq!(after_fee, (2_000_000_000_000_000_000u128 * 10_000).div_ceil(9_900));
+
+        assert!(pool.get_spot_price(0, 0, &reserves, 0).is_err());
+        assert!(pool.get_spot_price(0, 2, &reserves, 0).is_err());
+        assert!(pool.get_spot_price(0, 1, &[0, 1], 0).is_err());
+    }
+
+    #[test]
+    fn test_check_swap_mints() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let mints: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        for mint in mints.iter() {
+            pool.add_token(mint, &Pubkey::new_unique(), 10).unwrap();
+        }
+
+        // 全部参与和部分参与（顺序任意）都允许
+        assert!(pool.check_swap_mints(&mints).is_ok());
+        assert!(pool.check_swap_mints(&[mints[2], mints[0]]).is_ok());
+
+        // 长度超过 pool 的 token 数量
+        let too_many = [mints[0], mints[1], mints[2], Pubkey::new_unique()];
+        assert_eq!(
+            pool.check_swap_mints(&too_many).unwrap_err(),
+            error!(ErrorCode::InvalidTokenCount)
+        );
+        assert!(pool.check_swap_mints(&[]).is_err());
+        // 重复的 token 会让同一个 vault 被计算两次
+        assert_eq!(
+            pool.check_swap_mints(&[mints[1], mints[1]]).unwrap_err(),
+            error!(ErrorCode::DuplicateSwapToken)
+        );
+        assert_eq!(
+            pool.check_swap_mints(&[mints[0], Pubkey::new_unique()]).unwrap_err(),
+            error!(ErrorCode::InvalidTokenMint)
+        );
+    }
+
+    #[test]
+    fn test_check_lp_mint_decimals() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.lp_decimals = 9;
+        assert!(pool.check_lp_mint(9).is_ok());
+        assert_eq!(
+            pool.check_lp_mint(6).unwrap_err(),
+            error!(ErrorCode::LpMintMismatch)
+        );
+    }
+
+    #[test]
+    fn test_check_min_tokens() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        assert_eq!(pool.check_min_tokens().unwrap_err(), error!(ErrorCode::InsufficientTokens));
+
+        // 只有 1 个 token 时拒绝 swap / 增减流动性，但管理员仍可继续添加 token
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 50).unwrap();
+        assert_eq!(pool.check_min_tokens().unwrap_err(), error!(ErrorCode::InsufficientTokens));
+
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 50).unwrap();
+        assert!(pool.check_min_tokens().is_ok());
+    }
+
+    #[test]
+    fn test_get_token_checked() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let mint = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        pool.add_token(&mint, &vault, 50).unwrap();
+
+        // 范围内
+        assert_eq!(*pool.get_token_checked(0).unwrap().mint_pubkey(), mint);
+        pool.get_token_mut_checked(0).unwrap().set_weight(80);
+        assert_eq!(pool.get_token_checked(0).unwrap().weight_at(0), 80);
+
+        // 越界（包括未使用的槽位）
+        let err = pool.get_token_checked(1).unwrap_err();
+        assert_eq!(err, error!(ErrorCode::InvalidTokenIndex));
+        let err = pool.get_token_mut_checked(MAX_TOKENS).unwrap_err();
+        assert_eq!(err, error!(ErrorCode::InvalidTokenIndex));
+    }
+
+    #[test]
+    fn test_get_token_by_mint() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let vaults = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        for (mint, vault) in mints.iter().zip(vaults.iter()) {
+            pool.add_token(mint, vault, 10).unwrap();
+        }
+
+        for i in 0..3 {
+            assert_eq!(pool.get_token_index_by_mint(&mints[i]), Some(i));
+            assert_eq!(pool.get_token_by_mint(&mints[i]).unwrap().vault_pubkey(), &vaults[i]);
+        }
+        assert!(pool.get_token_by_mint(&Pubkey::new_unique()).is_none());
+        // 未使用的槽位 mint 为默认公钥，不能被查到
+        assert!(pool.get_token_index_by_mint(&Pubkey::default()).is_none());
+
+        // 移除后最后一个 token 移到被移除的位置
+        pool.remove_token(0).unwrap();
+        assert!(pool.get_token_by_mint(&mints[0]).is_none());
+        assert_eq!(pool.get_token_index_by_mint(&mints[2]), Some(0));
+        assert_eq!(pool.get_token_by_mint(&mints[2]).unwrap().vault_pubkey(), &vaults[2]);
+    }
+
+    #[test]
+    fn test_weighted_invariant_scales_with_proportional_deposit() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        for weight in [50u64, 30, 20] {
+            pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), weight).unwrap();
+        }
+        let reserves = [1_000_000u64, 2_000_000, 4_000_000];
+        let invariant = pool.calculate_weighted_invariant(&reserves, 0).unwrap();
+        // 1e6^0.5 * 2e6^0.3 * 4e6^0.2 ≈ 1.6245e6
+        assert!(invariant > U256::from(1_624_000u64) * E18);
+        assert!(invariant < U256::from(1_625_000u64) * E18);
+
+        // 按比例存入 1/4：不变量同样增长 1/4（误差来自 pow_down 的向下取整）
+        let deposited: Vec<u64> = reserves.iter().map(|r| r + r / 4).collect();
+        let after = pool.calculate_weighted_invariant(&deposited, 0).unwrap();
+        let expected = invariant * 5 / 4;
+        let diff = if after > expected { after - expected } else { expected - after };
+        assert!(diff * U256::from(1_000_000_000_000u64) < expected);
+
+        assert_eq!(pool.calculate_weighted_invariant(&[0, 1, 1], 0).unwrap(), U256::zero());
+        assert!(pool.calculate_weighted_invariant(&reserves[..2], 0).is_err());
+    }
+
+    #[test]
+    fn test_weight_ramp_interpolates_linearly() {
+        let admin = Pubkey::new_unique();
+        let mut pool = new_pool(&admin, &Pubkey::default());
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        for mint in mints.iter() {
+            pool.add_token(mint, &Pubkey::new_unique(), 50).unwrap();
+        }
+
+        assert!(pool.start_weight_ramp(&Pubkey::new_unique(), &mints[0], 90, 100, 1_000).is_err());
+        assert_eq!(
+            pool.start_weight_ramp(&admin, &mints[0], 90, 0, 1_000).unwrap_err(),
+            ErrorCode::InvalidWeightRamp.into()
+        );
+        // 渐变结束后的权重配置也必须有效：另一个 token 的归一化权重 50 / 5_000_050 低于 MIN_WEIGHT
+        assert_eq!(
+            pool.start_weight_ramp(&admin, &mints[0], 5_000_000, 100, 1_000).unwrap_err(),
+            ErrorCode::InvalidWeight.into()
+        );
+        pool.get_token_mut_checked(0).unwrap().set_weight(50);
+
+        pool.start_weight_ramp(&admin, &mints[0], 90, 100, 1_000).unwrap();
+        let token = pool.get_token_checked(0).unwrap();
+        assert_eq!(token.weight_at(900), 50);
+        assert_eq!(token.weight_at(1_000), 50);
+        // 中点为起止权重的平均值
+        assert_eq!(token.weight_at(1_050), 70);
+        assert_eq!(token.weight_at(1_025), 60);
+        assert_eq!(token.weight_at(1_100), 90);
+        assert_eq!(token.weight_at(2_000), 90);
+        // 配置的 weight 为渐变的目标权重
+        assert_eq!(token.weight, 90);
+        assert!(token.is_ramp_active(1_099));
+        assert!(!token.is_ramp_active(1_100));
+
+        // 渐变中途重新开始：从当前插值权重出发，权重可以下降
+        pool.start_weight_ramp(&admin, &mints[0], 30, 40, 1_050).unwrap();
+        let token = pool.get_token_checked(0).unwrap();
+        assert_eq!(token.weight_at(1_050), 70);
+        assert_eq!(token.weight_at(1_070), 50);
+        assert_eq!(token.weight_at(1_090), 30);
+        assert!(pool.current_normalized_weight(0, 1_070).is_ok());
+
+        // 立即修改权重会取消渐变
+        pool.get_token_mut_checked(0).unwrap().set_weight(60);
+        let token = pool.get_token_checked(0).unwrap();
+        assert!(!token.is_weight_ramping());
+        assert_eq!(token.weight_at(1_070), 60);
+    }
+
+    #[test]
+    fn test_swap_at_weight_ramp_midpoint() {
+        let admin = Pubkey::new_unique();
+        let mut pool = new_pool(&admin, &Pubkey::default());
+        pool.set_fee(0, 1000).unwrap();
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        for mint in mints.iter() {
+            pool.add_token(mint, &Pubkey::new_unique(), 50).unwrap();
+        }
+        pool.start_weight_ramp(&admin, &mints[0], 90, 100, 1_000).unwrap();
+
+        // 中点的实际权重为 70:50；缓存的是配置（目标）权重 90:50，渐变期间按实际权重重新计算
+        let mid = 1_050;
+        assert_eq!(pool.weights_at(mid), vec![70, 50]);
+        assert_eq!(pool.get_weight_sum(), 140);
+        assert_eq!(pool.weight_sum_at(mid).unwrap(), 120);
+        assert_eq!(pool.current_normalized_weight(0, mid).unwrap(), 583_333_333_333_333_333);
+
+        // swap 按中点的权重计算
+        let is_in = [true, false];
+        let vaults = [1_000_000_000u64, 1_000_000_000];
+        let tolerance = [10_000_000u64, 1];
+        let swap = |weights: &[u64]| {
+            pool.checked_swap(&mints, &is_in, &tolerance, &[tolerance[0], 0], &vaults, weights)
+                .map(|result| result.amounts[1])
+                .unwrap()
+        };
+        let out = swap(&pool.weights_at(mid));
+        let (_, expected) = reference_swap_out(vaults[0], vaults[1], 70, 50, tolerance[0], 0, 1);
+        assert!(out <= expected && expected - out <= 1);
+        // 介于渐变起点（50:50）和目标（90:50）的输出之间
+        assert!(swap(&[50, 50]) < out && out < swap(&[90, 50]));
+
+        // 渐变结束后实际权重等于配置权重，缓存重新有效
+        let end = 1_100;
+        assert!(!pool.is_any_ramp_active(end));
+        assert_eq!(pool.weights_at(end), vec![90, 50]);
+        assert_eq!(pool.weight_sum_at(end).unwrap(), pool.get_weight_sum());
+        assert_eq!(
+            pool.current_normalized_weight(0, end).unwrap(),
+            pool.get_token_checked(0).unwrap().get_normalized_weight()
+        );
+    }
+
+    #[test]
+    fn test_min_swap_amount_rejects_dust() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.set_fee(3, 1000).unwrap();
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        for mint in mints.iter() {
+            pool.add_token(mint, &Pubkey::new_unique(), 50).unwrap();
+        }
+        let is_in = [true, false];
+        let vaults = [1_000_000_000u64, 1_000_000_000];
+        let swap = |pool: &AnySwapPool, amount_in: u64| -> Result<Vec<u64>> {
+            let tolerance = [amount_in, 0];
+            pool.check_min_swap_amount(&mints, &is_in, &tolerance)?;
+            let result = pool.swap_directional(&mints, &is_in, &tolerance, &tolerance, &vaults, &[50, 50])?;
+            Ok(result.amounts.to_vec())
+        };
+
+        // 未设置下限时 1 lamport 的输出向下取整为 0
+        assert_eq!(swap(&pool, 1).unwrap_err(), ErrorCode::OutputRoundsToZero.into());
+
+        pool.set_min_swap_amount(1_000);
+        assert_eq!(swap(&pool, 1).unwrap_err(), ErrorCode::AmountTooSmall.into());
+        // 1003 扣费 3 后恰好为 1000，1002 扣费 3 后为 999
+        assert_eq!(swap(&pool, 1_002).unwrap_err(), ErrorCode::AmountTooSmall.into());
+        let amounts = swap(&pool, 1_003).unwrap();
+        assert_eq!(amounts[0], 1_000);
+        assert!(amounts[1] > 0);
+
+        // 输出 token 不受下限约束
+        assert!(pool.check_min_swap_amount(&mints, &is_in, &[1_003, 1]).is_ok());
+    }
+
+    #[test]
+    fn test_max_tokens_per_swap() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        // 显式设为 0 时不限制
+        pool.check_tokens_per_swap(MAX_TOKENS).unwrap();
+
+        // create_pool 的默认值：8 对账户通过，9 对账户被拒绝
+        pool.set_max_tokens_per_swap(DEFAULT_MAX_TOKENS_PER_SWAP);
+        pool.check_tokens_per_swap(8).unwrap();
+        assert_eq!(
+            pool.check_tokens_per_swap(9).unwrap_err(),
+            ErrorCode::TooManyTokensInSwap.into()
+        );
+
+        pool.set_max_tokens_per_swap(0);
+        pool.check_tokens_per_swap(9).unwrap();
+    }
+
+    #[test]
+    fn test_rebalance_weights_updates_all_tokens() {
+        let admin = Pubkey::new_unique();
+        let mut pool = new_pool(&admin, &Pubkey::default());
+        for weight in [10, 20, 30] {
+            pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), weight).unwrap();
+        }
+        pool.get_token_mut_checked(1).unwrap().start_weight_ramp(20, 40, 1_000, 2_000);
+
+        pool.rebalance_weights(&admin, &[50, 30, 20]).unwrap();
+        let weights = pool.active_tokens().map(|(_, token)| token.weight_at(0)).collect::<Vec<u64>>();
+        assert_eq!(weights, vec![50, 30, 20]);
+        assert_eq!(pool.get_weight_sum(), 100);
+        assert_eq!(pool.get_token_checked(0).unwrap().get_normalized_weight(), E18.as_u64() / 2);
+        assert!(!pool.get_token_checked(1).unwrap().is_weight_ramping());
+
+        // 失败时所有权重都保持不变
+        assert_eq!(
+            pool.rebalance_weights(&admin, &[1, 2]).unwrap_err(),
+            ErrorCode::InvalidTokenCount.into()
+        );
+        assert_eq!(
+            pool.rebalance_weights(&admin, &[1, 0, 2]).unwrap_err(),
+            ErrorCode::InvalidWeight.into()
+        );
+        assert!(pool.rebalance_weights(&Pubkey::new_unique(), &[1, 1, 1]).is_err());
+        let weights = pool.active_tokens().map(|(_, token)| token.weight_at(0)).collect::<Vec<u64>>();
+        assert_eq!(weights, vec![50, 30, 20]);
+    }
+
+    #[test]
+    fn test_check_can_close() {
+        let mut pool = new_pool(&Pubkey::new_unique(), &Pubkey::default());
+        pool.check_can_close().unwrap();
+
+        pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), 10).unwrap();
+        assert_eq!(pool.check_can_close().unwrap_err(), ErrorCode::PoolNotEmpty.into());
+        pool.remove_token(0).unwrap();
+        pool.check_can_close().unwrap();
+
+        // token 都已移除但仍有 LP 在流通
+        pool.set_total_amount_minted(1);
+        assert_eq!(pool.check_can_close().unwrap_err(), ErrorCode::PoolNotEmpty.into());
+    }
+
+    #[test]
+    fn test_resolve_admin_normal() {
+        let key = Pubkey::new_unique();
+        assert_eq!(AnySwapPool::resolve_admin(&key, false).unwrap(), key);
+    }
 }
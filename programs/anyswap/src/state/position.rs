@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+
+/// LP 持仓记录（每个 pool、每个用户一个 PDA）
+/// seeds = [b"lp_position", pool, owner]
+/// 用于记录用户最近一次添加流动性的时间，配合 pool 的 min_hold_seconds 限制立即退出
+/// 由添加流动性或 open_lp_position 创建；移除流动性要求记录已存在，没有记录的钱包不能退出
+#[account]
+#[derive(Debug)]
+pub struct LpPosition {
+    /// 所属 pool
+    pub pool: Pubkey,
+    /// 持仓用户
+    pub owner: Pubkey,
+    /// 最近一次添加流动性（或 open_lp_position）的时间戳（秒）
+    pub last_join_ts: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl LpPosition {
+    /// 计算账户所需的空间大小
+    pub fn space() -> usize {
+        8 + // discriminator
+        32 + // pool
+        32 + // owner
+        8 + // last_join_ts
+        1 // bump
+    }
+
+    /// 检查持有期是否已满足
+    /// min_hold_seconds 为 0 时不做限制
+    pub fn check_hold_period(&self, min_hold_seconds: i64, now: i64) -> Result<()> {
+        if min_hold_seconds <= 0 {
+            return Ok(());
+        }
+        let unlock_ts = self
+            .last_join_ts
+            .checked_add(min_hold_seconds)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(now >= unlock_ts, ErrorCode::HoldPeriodNotMet);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(last_join_ts: i64) -> LpPosition {
+        LpPosition {
+            pool: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            last_join_ts,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_hold_period_immediate_exit_fails() {
+        // 加入后立即退出，应被拒绝
+        let pos = position(1_000);
+        assert!(pos.check_hold_period(3_600, 1_000).is_err());
+        assert!(pos.check_hold_period(3_600, 4_599).is_err());
+    }
+
+    #[test]
+    fn test_hold_period_exit_after_period() {
+        // 持有期满后可以退出
+        let pos = position(1_000);
+        assert!(pos.check_hold_period(3_600, 4_600).is_ok());
+        assert!(pos.check_hold_period(3_600, 10_000).is_ok());
+    }
+
+    #[test]
+    fn test_hold_period_disabled() {
+        // min_hold_seconds 为 0 时不限制
+        let pos = position(1_000);
+        assert!(pos.check_hold_period(0, 1_000).is_ok());
+    }
+}
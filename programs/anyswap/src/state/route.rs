@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use super::pool::AnySwapPool;
+
+/// 池内多跳 swap 的结果，每一跳一项（第 k 跳为 path[k] -> path[k + 1]）
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteResult {
+    /// 每一跳扣费后的输入
+    pub hop_amounts_in: Vec<u64>,
+    /// 每一跳的手续费（以该跳的输入 token 计）
+    pub hop_fees: Vec<u64>,
+    /// 每一跳的输出，最后一项为整条路径的输出
+    pub hop_amounts_out: Vec<u64>,
+}
+
+impl RouteResult {
+    /// 整条路径的输出
+    pub fn amount_out(&self) -> u64 {
+        self.hop_amounts_out.last().copied().unwrap_or(0)
+    }
+}
+
+impl AnySwapPool {
+    /// 检查 swap 路径：至少 2 个 token，索引有效且不重复
+    pub fn check_route(&self, path: &[usize]) -> Result<()> {
+        require!(path.len() >= 2, ErrorCode::InvalidRoute);
+        for (i, &index) in path.iter().enumerate() {
+            require!(index < self.get_token_count(), ErrorCode::InvalidTokenIndex);
+            require!(!path[..i].contains(&index), ErrorCode::InvalidRoute);
+        }
+        Ok(())
+    }
+
+    /// 沿 path 逐跳计算池内多跳 swap（A -> B -> C），每一跳使用 calculate_swap_output
+    ///
+    /// 每一跳按该方向的费率（见 swap_fee_rates）从输入中扣除手续费，上一跳的输出作为下一跳的输入。
+    /// 第一跳与 swap_anyswap 一致，手续费从用户的输入中扣除；中间 token 不离开 vault，
+    /// 中间跳的手续费留在中间 token 的 vault 中归 LP。
+    /// reserves 按 pool 中 token 的顺序，每一跳之后按曲线更新，后面的跳看到前面的跳造成的价格变化；
+    /// 所有跳都使用 now 时刻的权重
+    ///
+    /// 稳定组内的 token 对不支持路由（与 swap_exact_out 一致）
+    pub fn swap_route(
+        &self,
+        path: &[usize],
+        amount_in: u64,
+        reserves: &[u64],
+        now: i64,
+    ) -> Result<RouteResult> {
+        self.check_route(path)?;
+        require!(amount_in > 0, ErrorCode::InconsistentSwapParams);
+        require!(
+            reserves.len() == self.get_token_count(),
+            ErrorCode::InvalidTokenCount
+        );
+
+        let is_in = [true, false];
+        let mut reserves = reserves.to_vec();
+        let mut result = RouteResult::default();
+        let mut amount = amount_in;
+        for hop in path.windows(2) {
+            let (index_in, index_out) = (hop[0], hop[1]);
+            let mints = [
+                *self.get_token_checked(index_in)?.mint_pubkey(),
+                *self.get_token_checked(index_out)?.mint_pubkey(),
+            ];
+            require!(
+                self.stable_pair_amplification(&mints, &is_in).is_none(),
+                ErrorCode::InvalidStableGroup
+            );
+            let (fee_numerator, fee_denominator) = self.swap_fee_rates(&mints, &is_in)?[0];
+            let fee = (amount as u128 * fee_numerator as u128 / fee_denominator as u128) as u64;
+            let amount_after_fee = amount - fee;
+            let amount_out =
+                self.calculate_swap_output(index_in, index_out, amount_after_fee, &reserves, now)?;
+            require!(amount_out > 0, ErrorCode::OutputRoundsToZero);
+
+            reserves[index_in] = reserves[index_in]
+                .checked_add(amount_after_fee)
+                .ok_or(ErrorCode::MathOverflow)?;
+            reserves[index_out] -= amount_out;
+            result.hop_amounts_in.push(amount_after_fee);
+            result.hop_fees.push(fee);
+            result.hop_amounts_out.push(amount_out);
+            amount = amount_out;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_with_weights(weights: &[u64]) -> Box<AnySwapPool> {
+        let mut pool: Box<AnySwapPool> = Box::new(bytemuck::Zeroable::zeroed());
+        pool.set_fee(3, 1000).unwrap();
+        for &weight in weights {
+            pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), weight).unwrap();
+        }
+        pool
+    }
+
+    #[test]
+    fn test_two_hop_route_compounds_hops() {
+        let pool = pool_with_weights(&[50, 30, 20]);
+        let reserves = [10_000_000u64, 20_000_000, 40_000_000];
+        let amount_in = 1_000_000u64;
+
+        let route = pool.swap_route(&[0, 1, 2], amount_in, &reserves, 0).unwrap();
+
+        // 逐跳手工计算：第二跳以第一跳的输出为输入，并看到第一跳之后的储备
+        let fee_ab = amount_in * 3 / 1000;
+        let out_ab = pool
+            .calculate_swap_output(0, 1, amount_in - fee_ab, &reserves, 0)
+            .unwrap();
+        let after_ab = [reserves[0] + amount_in - fee_ab, reserves[1] - out_ab, reserves[2]];
+        let fee_bc = out_ab * 3 / 1000;
+        let out_bc = pool
+            .calculate_swap_output(1, 2, out_ab - fee_bc, &after_ab, 0)
+            .unwrap();
+
+        assert_eq!(route.hop_fees, vec![fee_ab, fee_bc]);
+        assert_eq!(route.hop_amounts_in, vec![amount_in - fee_ab, out_ab - fee_bc]);
+        assert_eq!(route.hop_amounts_out, vec![out_ab, out_bc]);
+        assert_eq!(route.amount_out(), out_bc);
+
+        // 每一跳都收手续费，两跳的输出低于不收手续费的同一路径
+        let mut no_fee = pool_with_weights(&[50, 30, 20]);
+        no_fee.set_fee(0, 1000).unwrap();
+        assert!(no_fee.swap_route(&[0, 1, 2], amount_in, &reserves, 0).unwrap().amount_out() > out_bc);
+    }
+
+    #[test]
+    fn test_route_validation() {
+        let pool = pool_with_weights(&[50, 30, 20]);
+        let reserves = [10_000_000u64, 20_000_000, 40_000_000];
+        let err = |path: &[usize]| pool.swap_route(path, 1_000, &reserves, 0).unwrap_err();
+
+        assert_eq!(err(&[0]), ErrorCode::InvalidRoute.into());
+        assert_eq!(err(&[]), ErrorCode::InvalidRoute.into());
+        assert_eq!(err(&[0, 1, 0]), ErrorCode::InvalidRoute.into());
+        assert_eq!(err(&[0, 3]), ErrorCode::InvalidTokenIndex.into());
+        assert!(pool.swap_route(&[0, 1], 0, &reserves, 0).is_err());
+        assert!(pool.swap_route(&[2, 0], 1_000, &reserves, 0).is_ok());
+    }
+}
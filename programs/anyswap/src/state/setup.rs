@@ -0,0 +1,235 @@
+use anchor_lang::prelude::*;
+use primitive_types::U256;
+use super::pool::{MAX_FEE_DENOMINATOR, MAX_TOKENS};
+use crate::error::ErrorCode;
+
+/// 单个 token 的最大权重，保证 MAX_TOKENS 个权重之和不会溢出 u64
+pub const MAX_WEIGHT: u64 = u64::MAX / MAX_TOKENS as u64;
+
+/// 最大与最小权重之比的上限（比例过大时输出会因取整严重失真）
+pub const MAX_WEIGHT_RATIO: u64 = 10_000;
+
+/// weights_for_prices 归一化后的权重之和
+pub const NORMALIZED_WEIGHT_SUM: u64 = 1_000_000_000;
+
+/// pool 配置校验的结果码
+pub mod setup_reason {
+    /// 配置有效
+    pub const OK: u8 = 0;
+    /// token 数量为 0 或超过 MAX_TOKENS
+    pub const INVALID_TOKEN_COUNT: u8 = 1;
+    /// 存在为 0 的权重
+    pub const ZERO_WEIGHT: u8 = 2;
+    /// 权重超过 MAX_WEIGHT
+    pub const WEIGHT_TOO_LARGE: u8 = 3;
+    /// 最大/最小权重之比超过 MAX_WEIGHT_RATIO
+    pub const WEIGHT_RATIO_TOO_LARGE: u8 = 4;
+    /// 权重之和溢出
+    pub const WEIGHT_SUM_OVERFLOW: u8 = 5;
+    /// 手续费分母为 0
+    pub const ZERO_FEE_DENOMINATOR: u8 = 6;
+    /// 手续费分子大于分母
+    pub const FEE_TOO_LARGE: u8 = 7;
+    /// 手续费分母超过 MAX_FEE_DENOMINATOR
+    pub const FEE_DENOMINATOR_TOO_LARGE: u8 = 8;
+}
+
+/// pool 配置校验结果（通过 return data 返回）
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SetupValidation {
+    /// 配置是否有效
+    pub valid: bool,
+    /// 结果码，见 setup_reason
+    pub reason: u8,
+}
+
+impl SetupValidation {
+    fn fail(reason: u8) -> Self {
+        SetupValidation { valid: false, reason }
+    }
+}
+
+/// 校验一组 token 权重和费率配置（不修改任何状态）
+///
+/// 依次检查：token 数量、权重范围、权重比例、权重之和、费率范围，
+/// 返回第一个不满足的检查对应的结果码
+pub fn validate_setup_inner(
+    weights: &[u64],
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> SetupValidation {
+    if weights.is_empty() || weights.len() > MAX_TOKENS {
+        return SetupValidation::fail(setup_reason::INVALID_TOKEN_COUNT);
+    }
+
+    if weights.contains(&0) {
+        return SetupValidation::fail(setup_reason::ZERO_WEIGHT);
+    }
+    if weights.iter().any(|&w| w > MAX_WEIGHT) {
+        return SetupValidation::fail(setup_reason::WEIGHT_TOO_LARGE);
+    }
+
+    let max_weight = *weights.iter().max().unwrap();
+    let min_weight = *weights.iter().min().unwrap();
+    if max_weight as u128 > min_weight as u128 * MAX_WEIGHT_RATIO as u128 {
+        return SetupValidation::fail(setup_reason::WEIGHT_RATIO_TOO_LARGE);
+    }
+
+    let mut sum = 0u64;
+    for &w in weights.iter() {
+        match sum.checked_add(w) {
+            Some(s) => sum = s,
+            None => return SetupValidation::fail(setup_reason::WEIGHT_SUM_OVERFLOW),
+        }
+    }
+
+    if fee_denominator == 0 {
+        return SetupValidation::fail(setup_reason::ZERO_FEE_DENOMINATOR);
+    }
+    if fee_numerator > fee_denominator {
+        return SetupValidation::fail(setup_reason::FEE_TOO_LARGE);
+    }
+    if fee_denominator > MAX_FEE_DENOMINATOR {
+        return SetupValidation::fail(setup_reason::FEE_DENOMINATOR_TOO_LARGE);
+    }
+
+    SetupValidation {
+        valid: true,
+        reason: setup_reason::OK,
+    }
+}
+
+/// 根据计划注入的储备和目标价格计算权重，使 pool 的现货价格等于目标价格
+///
+/// target_prices[i] 为 token i 以同一计价单位表示的价格（精度任意，但需一致）。
+/// 加权恒定乘积中每个 token 的价值占比等于其权重占比，因此
+/// weight_i = reserve_i * price_i / sum(reserve_j * price_j) * NORMALIZED_WEIGHT_SUM（向下取整），
+/// 此时 spot_price(i, j) = price_j / price_i。
+///
+/// 纯计算，可以在链下设计 pool 时使用；结果仍需通过 validate_setup_inner 的权重比例检查
+pub fn weights_for_prices(reserves: &[u64], target_prices: &[u64]) -> Result<Vec<u64>> {
+    require!(
+        !reserves.is_empty()
+            && reserves.len() <= MAX_TOKENS
+            && reserves.len() == target_prices.len(),
+        ErrorCode::InvalidTokenCount
+    );
+    require!(!reserves.contains(&0), ErrorCode::InsufficientLiquidity);
+    require!(!target_prices.contains(&0), ErrorCode::InvalidTargetPrice);
+
+    let values: Vec<U256> = reserves
+        .iter()
+        .zip(target_prices.iter())
+        .map(|(&reserve, &price)| U256::from(reserve) * U256::from(price))
+        .collect();
+    let total_value = values.iter().fold(U256::zero(), |acc, &v| acc + v);
+
+    values
+        .iter()
+        .map(|&value| {
+            let weight = value * U256::from(NORMALIZED_WEIGHT_SUM) / total_value;
+            // 价值占比过小，归一化后权重为 0
+            require!(!weight.is_zero(), ErrorCode::InvalidTargetPrice);
+            Ok(weight.as_u64())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::swap::spot_price;
+
+    #[test]
+    fn test_valid_setup() {
+        let result = validate_setup_inner(&[20, 30, 50], 3, 1000);
+        assert_eq!(
+            result,
+            SetupValidation {
+                valid: true,
+                reason: setup_reason::OK
+            }
+        );
+        // 恰好等于比例上限
+        assert!(validate_setup_inner(&[1, MAX_WEIGHT_RATIO], 0, 1).valid);
+    }
+
+    #[test]
+    fn test_invalid_setups() {
+        let cases: Vec<(Vec<u64>, u64, u64, u8)> = vec![
+            (vec![], 3, 1000, setup_reason::INVALID_TOKEN_COUNT),
+            (vec![1; MAX_TOKENS + 1], 3, 1000, setup_reason::INVALID_TOKEN_COUNT),
+            (vec![20, 0, 50], 3, 1000, setup_reason::ZERO_WEIGHT),
+            (vec![20, MAX_WEIGHT + 1], 3, 1000, setup_reason::WEIGHT_TOO_LARGE),
+            (vec![1, MAX_WEIGHT_RATIO + 1], 3, 1000, setup_reason::WEIGHT_RATIO_TOO_LARGE),
+            (vec![20, 30, 50], 3, 0, setup_reason::ZERO_FEE_DENOMINATOR),
+            (vec![20, 30, 50], 1001, 1000, setup_reason::FEE_TOO_LARGE),
+            (vec![20, 30, 50], 1, MAX_FEE_DENOMINATOR + 1, setup_reason::FEE_DENOMINATOR_TOO_LARGE),
+        ];
+
+        for (weights, fee_numerator, fee_denominator, reason) in cases {
+            let result = validate_setup_inner(&weights, fee_numerator, fee_denominator);
+            assert!(!result.valid);
+            assert_eq!(result.reason, reason, "token 数量 {}", weights.len());
+        }
+    }
+
+    #[test]
+    fn test_reason_codes_distinct() {
+        let codes = [
+            setup_reason::OK,
+            setup_reason::INVALID_TOKEN_COUNT,
+            setup_reason::ZERO_WEIGHT,
+            setup_reason::WEIGHT_TOO_LARGE,
+            setup_reason::WEIGHT_RATIO_TOO_LARGE,
+            setup_reason::WEIGHT_SUM_OVERFLOW,
+            setup_reason::ZERO_FEE_DENOMINATOR,
+            setup_reason::FEE_TOO_LARGE,
+            setup_reason::FEE_DENOMINATOR_TOO_LARGE,
+        ];
+        for i in 0..codes.len() {
+            for j in i + 1..codes.len() {
+                assert_ne!(codes[i], codes[j]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weights_for_prices_match_spot_prices() {
+        // 不同精度的储备和价格：价格以 1e6 为单位
+        let reserves = [1_000_000_000u64, 2_000_000_000_000, 500_000_000];
+        let prices = [1_000_000u64, 3_000, 2_500_000];
+        let weights = weights_for_prices(&reserves, &prices).unwrap();
+        assert!(validate_setup_inner(&weights, 3, 1000).valid);
+        let sum: u64 = weights.iter().sum();
+        assert!(sum <= NORMALIZED_WEIGHT_SUM && sum + 3 >= NORMALIZED_WEIGHT_SUM);
+
+        // 以计算出的权重注入储备后，现货价格等于目标价格之比
+        for i in 0..3 {
+            for j in 0..3 {
+                if i == j {
+                    continue;
+                }
+                let price = spot_price(reserves[i], weights[i], reserves[j], weights[j]).unwrap();
+                let target = prices[j] as u128 * 1_000_000_000_000_000_000 / prices[i] as u128;
+                let diff = price.abs_diff(target);
+                assert!(diff * 1_000_000 <= target, "{} -> {}: {} vs {}", i, j, price, target);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weights_for_prices_invalid() {
+        assert!(weights_for_prices(&[], &[]).is_err());
+        assert!(weights_for_prices(&[1_000, 1_000], &[1]).is_err());
+        assert!(weights_for_prices(&[1_000, 0], &[1, 1]).is_err());
+        assert!(weights_for_prices(&[1_000, 1_000], &[1, 0]).is_err());
+        // 某个 token 的价值占比低于 1 / NORMALIZED_WEIGHT_SUM
+        assert!(weights_for_prices(&[1, u64::MAX], &[1, u64::MAX]).is_err());
+        // 价值相同时权重相同
+        assert_eq!(
+            weights_for_prices(&[2_000, 1_000], &[1, 2]).unwrap(),
+            vec![NORMALIZED_WEIGHT_SUM / 2, NORMALIZED_WEIGHT_SUM / 2]
+        );
+    }
+}
@@ -1,6 +1,11 @@
 use crate::error::ErrorCode;
+use crate::math::fixedpoint::FixedPoint;
 use crate::math::i256::I256;
-use crate::math::logexpmath::LogExpMath;
+use crate::math::logexpmath::{LogExpMath, MAX_NATURAL_EXPONENT, MIN_NATURAL_EXPONENT};
+use crate::math::scale::E18;
+use crate::math::stable;
+use crate::state::fees::BPS_DENOMINATOR;
+use crate::state::pool::PAUSE_SWAP;
 use crate::state::AnySwapPool;
 use anchor_lang::prelude::*;
 use primitive_types::U256;
@@ -10,6 +15,53 @@ pub struct SwapResult {
     pub amounts: Vec<u64>,
 }
 
+/// swap 计算的取整方向
+///
+/// 执行 swap 时始终使用 POOL_FAVORABLE，预览（询价）默认使用相同的取整，
+/// 保证客户端按预览结果执行时不会得到比预览更差的结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapRounding {
+    /// 由池子计算出的输入数量向上取整（用户多付）
+    pub round_up_input: bool,
+    /// 由池子计算出的输出数量向下取整（用户少得）
+    pub round_down_output: bool,
+}
+
+impl SwapRounding {
+    /// 对池子有利的取整（执行 swap 时使用）
+    pub const POOL_FAVORABLE: SwapRounding = SwapRounding {
+        round_up_input: true,
+        round_down_output: true,
+    };
+}
+
+/// 将 1e18 精度的储备转换为整数
+/// round_up 为 true 时向上取整（输出 token 的新储备取大，即输出向下取整）
+pub(crate) fn scale_down_reserve(value_18: I256, round_up: bool) -> I256 {
+    let one = I256::from_raw(E18);
+    let floor = value_18 / one;
+    if round_up && floor * one < value_18 {
+        floor + I256::from(1u64)
+    } else {
+        floor
+    }
+}
+
+/// swap 中参与 ln/exp 计算的最小储备（ln(0) 无定义）
+pub const MIN_DOMAIN_RESERVE: u64 = 1;
+/// swap 中参与 ln/exp 计算的最大储备，放大 18 位后 ln 约为 44.3，在 exp 的定义域内
+pub const MAX_DOMAIN_RESERVE: u64 = u64::MAX;
+
+/// 检查储备是否在 ln/exp 的定义域内
+/// 在调用 LogExpMath 之前检查，超出时返回 ReserveOutOfDomain 而不是 LogExpMath 内部的 MathOverflow
+pub fn check_reserve_in_domain(reserve: U256) -> Result<()> {
+    require!(
+        reserve >= U256::from(MIN_DOMAIN_RESERVE) && reserve <= U256::from(MAX_DOMAIN_RESERVE),
+        ErrorCode::ReserveOutOfDomain
+    );
+    Ok(())
+}
+
 pub trait SwapProtocol {
     // 使用权重恒定乘积公式: a^wa * b^wb * c^wc * ... = K
     // 公式: sum(weight_i * ln(vault_i)) = constant
@@ -33,6 +85,304 @@ pub trait SwapProtocol {
     ) -> Result<SwapResult>;
 }
 
+/// 计算一次 swap 会执行的 ln / exp 次数，便于客户端估算 compute units
+///
+/// 与 swap_inner 的计算步骤一一对应：
+/// - 两 token swap 使用闭式解：一次 ln 和一次 exp
+///
+/// 三个及以上 token：
+/// - 交换前的恒定乘积：每个参与的 token 一次 ln
+/// - 每个输入 token：一次 ln
+/// - 除最后一个以外的输出 token：一次 ln
+/// - 最后一个输出 token：一次 exp
+///
+/// Args:
+///     token_count: 参与 swap 的 token 数
+///     is_in_token: 每个 token 是否为输入
+///
+/// Returns:
+///     (ln_calls, exp_calls)
+pub fn swap_op_count(token_count: usize, is_in_token: &[bool]) -> (usize, usize) {
+    let in_count = is_in_token
+        .iter()
+        .take(token_count)
+        .filter(|&&is_in| is_in)
+        .count();
+    let out_count = token_count - in_count;
+    if out_count == 0 {
+        // 没有输出 token 时 swap 不会执行
+        return (0, 0);
+    }
+    if token_count == 2 {
+        return (1, 1);
+    }
+    let ln_calls = token_count + in_count + (out_count - 1);
+    (ln_calls, 1)
+}
+
+/// 计算一次 swap 实际支付的手续费，以交易额的 bps（万分之一）表示
+///
+/// effective_fee_bps = sum(burn_fees) * 10000 / sum(输入 token 的数量)
+/// 交易额按输入 token 的原始数量（扣费前）相加，向下取整
+///
+/// Args:
+///     is_in: 每个 token 是否为输入
+///     amount_tolerance: 每个 token 的容差（输入为实际支付的数量）
+///     burn_fees: swap 返回的每个 token 的手续费
+pub fn effective_fee_bps(is_in: &[bool], amount_tolerance: &[u64], burn_fees: &[u64]) -> Result<u64> {
+    require!(
+        amount_tolerance.len() == is_in.len() && burn_fees.len() == is_in.len(),
+        ErrorCode::InvalidTokenCount
+    );
+
+    let mut notional = 0u128;
+    let mut total_fees = 0u128;
+    for i in 0..is_in.len() {
+        if !is_in[i] {
+            continue;
+        }
+        notional += amount_tolerance[i] as u128;
+        total_fees += burn_fees[i] as u128;
+    }
+    if notional == 0 {
+        return Ok(0);
+    }
+    let bps = total_fees * 10_000 / notional;
+    Ok(u64::try_from(bps).map_err(|_| ErrorCode::MathOverflow)?)
+}
+
+/// 按百分比滑点计算输出的下限（向上取整，实际允许的滑点不超过 slippage_bps）
+/// min_out = expected_out * (10000 - slippage_bps) / 10000
+pub fn min_output_for_slippage(expected_out: u64, slippage_bps: u16) -> Result<u64> {
+    require!(
+        slippage_bps as u64 <= BPS_DENOMINATOR,
+        ErrorCode::InvalidSlippage
+    );
+    let numerator = expected_out as u128 * (BPS_DENOMINATOR - slippage_bps as u64) as u128;
+    let min_out = numerator.div_ceil(BPS_DENOMINATOR as u128);
+    Ok(min_out as u64)
+}
+
+/// 检查每个输出 token 的实际输出不低于 expected_amounts 按滑点折算后的下限
+///
+/// Args:
+///     is_in: 每个 token 是否为输入（输入 token 不检查）
+///     expected_amounts: 询价得到的预期输出
+///     actual_amounts: 实际执行的 swap 结果
+pub fn check_output_slippage(
+    is_in: &[bool],
+    expected_amounts: &[u64],
+    actual_amounts: &[u64],
+    slippage_bps: u16,
+) -> Result<()> {
+    require!(
+        expected_amounts.len() == is_in.len() && actual_amounts.len() == is_in.len(),
+        ErrorCode::InvalidTokenCount
+    );
+    for i in 0..is_in.len() {
+        if is_in[i] {
+            continue;
+        }
+        let min_out = min_output_for_slippage(expected_amounts[i], slippage_bps)?;
+        require!(
+            actual_amounts[i] >= min_out,
+            ErrorCode::InsufficientOutputAmount
+        );
+    }
+    Ok(())
+}
+
+/// 检查 swap 结果满足 amount_tolerance：输入为上限，输出为下限
+///
+/// 除最后一个输出外，输出的容差就是精确的输出数量，检查总能通过；
+/// 最后一个输出由恒定乘积算出，它的容差是用户可接受的最小输出
+pub fn check_swap_tolerance(is_in: &[bool], amount_tolerance: &[u64], amounts: &[u64]) -> Result<()> {
+    require!(
+        amount_tolerance.len() == is_in.len() && amounts.len() == is_in.len(),
+        ErrorCode::InvalidTokenCount
+    );
+    for ((&is_in, &tolerance), &amount) in is_in.iter().zip(amount_tolerance).zip(amounts) {
+        let within = if is_in { amount <= tolerance } else { amount >= tolerance };
+        require!(within, ErrorCode::SlippageExceeded);
+    }
+    Ok(())
+}
+
+/// swap 附带的 memo 的最大长度（字节）
+pub const MAX_MEMO_LEN: usize = 256;
+
+/// 检查 swap 附带的 memo：非空且不超过 MAX_MEMO_LEN 字节
+pub fn check_memo(memo: &str) -> Result<()> {
+    require!(
+        !memo.is_empty() && memo.len() <= MAX_MEMO_LEN,
+        ErrorCode::InvalidMemo
+    );
+    Ok(())
+}
+
+/// 检查 is_in 与 amount_tolerance 是否一致
+///
+/// 不参与 swap 的 token 不应出现在参数中，因此容差为 0 的 token 不能被标记为输入或输出：
+/// - 输入的容差是实际支付的数量，必须大于 0
+/// - 除最后一个输出外，输出的容差是精确的输出数量，必须大于 0
+/// - 最后一个输出由恒定乘积算出，它的容差不参与计算，可以为 0
+///
+/// 至少需要一个输入和一个输出
+pub fn validate_swap_params(is_in: &[bool], amount_tolerance: &[u64]) -> Result<()> {
+    require!(
+        amount_tolerance.len() == is_in.len(),
+        ErrorCode::InvalidTokenCount
+    );
+    let last_out = is_in.iter().rposition(|&x| !x);
+    require!(
+        last_out.is_some() && is_in.contains(&true),
+        ErrorCode::InconsistentSwapParams
+    );
+    for (i, &tolerance) in amount_tolerance.iter().enumerate() {
+        require!(
+            tolerance > 0 || Some(i) == last_out,
+            ErrorCode::InconsistentSwapParams
+        );
+    }
+    Ok(())
+}
+
+/// 按方向解析 swap 中每个 token 的费率 (分子, 分母)
+///
+/// 手续费只从输入 token 中扣除，输入 token i 的费率按以下优先级确定：
+/// 1. 输入 token 自身的 sell_fee_bps（卖出该 token）
+/// 2. 输出 token 中最大的 buy_fee_bps（买入这些 token）
+/// 3. pool 的费率
+///
+/// bps 为 0 表示未设置，回退到下一级；输出 token 的费率不参与计算，返回 pool 费率
+pub fn directional_fee_rates(
+    is_in: &[bool],
+    buy_fee_bps: &[u16],
+    sell_fee_bps: &[u16],
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<Vec<(u64, u64)>> {
+    require!(
+        buy_fee_bps.len() == is_in.len() && sell_fee_bps.len() == is_in.len(),
+        ErrorCode::InvalidTokenCount
+    );
+    let buy_override = is_in
+        .iter()
+        .zip(buy_fee_bps.iter())
+        .filter(|(&x, _)| !x)
+        .map(|(_, &bps)| bps)
+        .max()
+        .unwrap_or(0);
+    Ok(is_in
+        .iter()
+        .zip(sell_fee_bps.iter())
+        .map(|(&x, &sell_bps)| {
+            if x && sell_bps > 0 {
+                (sell_bps as u64, BPS_DENOMINATOR)
+            } else if x && buy_override > 0 {
+                (buy_override as u64, BPS_DENOMINATOR)
+            } else {
+                (fee_numerator, fee_denominator)
+            }
+        })
+        .collect())
+}
+
+/// 两 token swap 的闭式解，返回输出 token 的新储备
+///
+/// balance_out_after = balance_out / ((balance_in + amount_in) / balance_in) ^ (weight_in / weight_out)
+///
+/// 与 N token 的通用路径等价，但只需要一次 ln 和一次 exp；
+/// 比例和新储备的取整方向与通用路径一致（按 rounding 对池子有利）
+pub(crate) fn two_token_reserve_out(
+    balance_in: u64,
+    amount_in: u64,
+    balance_out: u64,
+    weight_in: u64,
+    weight_out: u64,
+    rounding: SwapRounding,
+) -> Result<u64> {
+    check_reserve_in_domain(U256::from(balance_in))?;
+    check_reserve_in_domain(U256::from(balance_out))?;
+    let balance_in_after = U256::from(balance_in) + U256::from(amount_in);
+    check_reserve_in_domain(balance_in_after)?;
+
+    // growth = balance_in_after / balance_in >= 1（1e18 精度），向下取整时新储备更大
+    // 用倒数形式让 exp 的指数非负：exp 对负指数内部会再算一次 exp(-x)
+    let numerator = balance_in_after * E18;
+    let mut growth = numerator / U256::from(balance_in);
+    if !rounding.round_down_output && growth * U256::from(balance_in) < numerator {
+        growth += U256::one();
+    }
+
+    // ln(growth) >= 0，截断即向下取整
+    let exponent = LogExpMath::ln(I256::try_from(growth)?)? * I256::from(weight_in)
+        / I256::from(weight_out);
+
+    // 指数超出 exp 的定义域时新储备远不足 1 个单位，与通用路径一样按取整方向取 1 或 0
+    let reserve_out_after = if exponent > -MIN_NATURAL_EXPONENT {
+        U256::from(if rounding.round_down_output { 1u64 } else { 0u64 })
+    } else {
+        let power = LogExpMath::exp(exponent)?.to_u256()?;
+        let scaled = U256::from(balance_out) * E18;
+        let floor = scaled / power;
+        if rounding.round_down_output && floor * power < scaled {
+            floor + U256::one()
+        } else {
+            floor
+        }
+    };
+
+    require!(!reserve_out_after.is_zero(), ErrorCode::MathOverflow);
+    require!(
+        reserve_out_after <= U256::from(balance_out),
+        ErrorCode::InsufficientLiquidity
+    );
+    Ok(reserve_out_after.as_u64())
+}
+
+/// 两 token 的精确输出：得到 amount_out 需要的（扣费后）输入，与 Balancer 的 calcInGivenOut 一致
+///
+/// amount_in = vault_in * ((vault_out / (vault_out - amount_out)) ^ (weight_out / weight_in) - 1)
+///
+/// 每一步都使用 FixedPoint 的向上取整（pow_up 等），输入按对池子有利的方向向上取整
+pub fn calc_in_given_out(
+    vault_in: u64,
+    vault_out: u64,
+    weight_in: u64,
+    weight_out: u64,
+    amount_out: u64,
+) -> Result<u64> {
+    require!(weight_in > 0 && weight_out > 0, ErrorCode::InvalidTokenCount);
+    require!(amount_out < vault_out, ErrorCode::InsufficientLiquidity);
+    check_reserve_in_domain(U256::from(vault_in))?;
+
+    let base = FixedPoint::div_up(U256::from(vault_out), U256::from(vault_out - amount_out))?;
+    let exponent = FixedPoint::div_up(U256::from(weight_out), U256::from(weight_in))?;
+    let power = FixedPoint::pow_up(base, exponent)?;
+    let ratio = FixedPoint::sub(power, FixedPoint::ONE)?;
+    let amount_in = FixedPoint::mul_up(U256::from(vault_in), ratio)?;
+
+    require!(amount_in <= U256::from(u64::MAX), ErrorCode::MathOverflow);
+    Ok(amount_in.as_u64())
+}
+
+/// 扣费前的输入：gross = ceil(amount_after_fee * fee_denominator / (fee_denominator - fee_numerator))
+/// 扣除 fee = floor(gross * fee_numerator / fee_denominator) 后不少于 amount_after_fee
+pub fn gross_up_for_fee(
+    amount_after_fee: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<u64> {
+    require!(
+        fee_numerator < fee_denominator,
+        ErrorCode::MathOverflow
+    );
+    let numerator = amount_after_fee as u128 * fee_denominator as u128;
+    let gross = numerator.div_ceil((fee_denominator - fee_numerator) as u128);
+    u64::try_from(gross).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
 /// 实现多token交换，使用权重恒定乘积公式（对数形式）
 ///
 /// 公式: sum(weight_i * ln(vault_i)) = constant
@@ -43,7 +393,32 @@ pub trait SwapProtocol {
 /// 3. 计算输入token的增量
 /// 4. 对于前n-1个输出token，使用最小输出要求
 /// 5. 对于最后一个输出token，根据恒定乘积公式计算
-fn swap_inner<'info>(
+///
+/// 最后一个输出 token 的数量按 SwapRounding::POOL_FAVORABLE 向下取整
+pub(crate) fn swap_inner<'info>(
+    is_in: &[bool],
+    amount_tolerance: &[u64],
+    user_vaults_amount: &[u64],
+    token_vaults_amount: &[u64],
+    weights: &[u64],
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<SwapResult> {
+    swap_inner_with_rounding(
+        is_in,
+        amount_tolerance,
+        user_vaults_amount,
+        token_vaults_amount,
+        weights,
+        fee_numerator,
+        fee_denominator,
+        SwapRounding::POOL_FAVORABLE,
+    )
+}
+
+/// swap_inner 的实现，可指定取整方向（见 SwapRounding）
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn swap_inner_with_rounding(
     is_in: &[bool],
     amount_tolerance: &[u64],
     user_vaults_amount: &[u64],
@@ -51,7 +426,85 @@ fn swap_inner<'info>(
     weights: &[u64],
     fee_numerator: u64,
     fee_denominator: u64,
+    rounding: SwapRounding,
+) -> Result<SwapResult> {
+    let fee_rates = vec![(fee_numerator, fee_denominator); is_in.len()];
+    swap_inner_with_fee_rates(
+        is_in,
+        amount_tolerance,
+        user_vaults_amount,
+        token_vaults_amount,
+        weights,
+        &fee_rates,
+        rounding,
+    )
+}
+
+/// swap_inner 的实现，每个输入 token 使用各自的费率（见 directional_fee_rates）
+///
+/// fee_rates: 每个 token 的 (费率分子, 费率分母)，只对输入 token 生效
+pub(crate) fn swap_inner_with_fee_rates(
+    is_in: &[bool],
+    amount_tolerance: &[u64],
+    user_vaults_amount: &[u64],
+    token_vaults_amount: &[u64],
+    weights: &[u64],
+    fee_rates: &[(u64, u64)],
+    rounding: SwapRounding,
 ) -> Result<SwapResult> {
+    let token_count = is_in.len();
+    require!(weights.len() == token_count, ErrorCode::InvalidTokenCount);
+    let burn_fees = prepare_swap(
+        is_in,
+        amount_tolerance,
+        user_vaults_amount,
+        token_vaults_amount,
+        fee_rates,
+    )?;
+
+    // 两 token swap 使用闭式解，不需要 N token 的 ln 累加
+    let result = if token_count == 2 {
+        swap_two_tokens(
+            is_in,
+            amount_tolerance,
+            token_vaults_amount,
+            weights,
+            burn_fees,
+            rounding,
+        )?
+    } else {
+        swap_general(
+            is_in,
+            amount_tolerance,
+            token_vaults_amount,
+            weights,
+            burn_fees,
+            rounding,
+        )?
+    };
+    check_outputs_not_zero(is_in, &result.amounts)?;
+    Ok(result)
+}
+
+/// 检查每个输出 token 的数量都大于 0
+///
+/// 相对储备过小的输入（dust）经 pow 向下取整后输出可能为 0，此时拒绝 swap，
+/// 而不是收取输入和手续费却不给任何输出
+pub fn check_outputs_not_zero(is_in: &[bool], amounts: &[u64]) -> Result<()> {
+    for (&is_in, &amount) in is_in.iter().zip(amounts.iter()) {
+        require!(is_in || amount > 0, ErrorCode::OutputRoundsToZero);
+    }
+    Ok(())
+}
+
+/// swap 的公共校验（长度、方向、用户余额），返回每个 token 的手续费（只有输入 token 非 0）
+fn prepare_swap(
+    is_in: &[bool],
+    amount_tolerance: &[u64],
+    user_vaults_amount: &[u64],
+    token_vaults_amount: &[u64],
+    fee_rates: &[(u64, u64)],
+) -> Result<Vec<u64>> {
     let token_count = is_in.len();
     require!(
         amount_tolerance.len() == token_count,
@@ -65,29 +518,8 @@ fn swap_inner<'info>(
         token_vaults_amount.len() == token_count,
         ErrorCode::InvalidTokenCount
     );
-    require!(weights.len() == token_count, ErrorCode::InvalidTokenCount);
-    
-    // LogExpMath期望18位小数精度
-    // vault需要放大18位（因为ln需要18位精度输入）
-    // weight保持原始值（作为系数）
-    let constant_before = weights
-        .iter()
-        .enumerate()
-        .map(|(i, weight)| {
-            let vault_before = token_vaults_amount[i];
-            // 将vault放大18位
-            let vault_before_u256 = U256::from(vault_before) * U256::from(1_000_000_000_000_000_000u64);
-            let vault_before_i256 = I256::try_from(vault_before_u256).unwrap();
-            // weight不放大，ln返回1e18精度
-            let weight_i256 = I256::from(*weight);
-            let delta = weight_i256 * LogExpMath::ln(vault_before_i256).unwrap();
-            delta
-        })
-        .sum::<I256>();
-    let mut vaults_after = token_vaults_amount.iter().map(|x| *x).collect::<Vec<u64>>();
-
-    // 初始化输出数组
-    let mut outputs = vec![0u64; token_count];
+    require!(fee_rates.len() == token_count, ErrorCode::InvalidTokenCount);
+    validate_swap_params(is_in, amount_tolerance)?;
     
     // 计算费用：对输入token，从amount_tolerance中扣除费用
     // 先检查用户余额
@@ -100,7 +532,7 @@ fn swap_inner<'info>(
         }
     }
     
-    let burn_fees: Vec<u64> = amount_tolerance
+    let burn_fees = amount_tolerance
         .iter()
         .enumerate()
         .map(|(i, &tolerance)| {
@@ -108,12 +540,279 @@ fn swap_inner<'info>(
                 return 0;
             }
             // 计算费用
+            let (fee_numerator, fee_denominator) = fee_rates[i];
             let amount_u256 = U256::from(tolerance);
             let fee_amount = (amount_u256 * fee_numerator) / fee_denominator;
             fee_amount.as_u64()
         })
         .collect::<Vec<u64>>();
 
+    Ok(burn_fees)
+}
+
+/// 两 token swap（见 two_token_reserve_out），参数已通过 swap_inner_with_rounding 的校验
+fn swap_two_tokens(
+    is_in: &[bool],
+    amount_tolerance: &[u64],
+    token_vaults_amount: &[u64],
+    weights: &[u64],
+    burn_fees: Vec<u64>,
+    rounding: SwapRounding,
+) -> Result<SwapResult> {
+    let (in_idx, out_idx) = if is_in[0] { (0, 1) } else { (1, 0) };
+    let amount_in_after_fee = amount_tolerance[in_idx] - burn_fees[in_idx];
+    let reserve_out_after = two_token_reserve_out(
+        token_vaults_amount[in_idx],
+        amount_in_after_fee,
+        token_vaults_amount[out_idx],
+        weights[in_idx],
+        weights[out_idx],
+        rounding,
+    )?;
+    let mut outputs = vec![0u64; 2];
+    outputs[in_idx] = amount_in_after_fee;
+    outputs[out_idx] = token_vaults_amount[out_idx] - reserve_out_after;
+    Ok(SwapResult {
+        burn_fees,
+        amounts: outputs,
+    })
+}
+
+/// 稳定组内 1 进 1 出的 swap，使用 StableSwap 曲线（见 math::stable），权重不参与计算
+///
+/// 不变量只由参与 swap 的两个 token 的余额计算
+pub(crate) fn swap_stable_pair(
+    is_in: &[bool],
+    amount_tolerance: &[u64],
+    user_vaults_amount: &[u64],
+    token_vaults_amount: &[u64],
+    fee_rates: &[(u64, u64)],
+    amplification: u64,
+) -> Result<SwapResult> {
+    require!(is_in.len() == 2, ErrorCode::InvalidStableGroup);
+    let burn_fees = prepare_swap(
+        is_in,
+        amount_tolerance,
+        user_vaults_amount,
+        token_vaults_amount,
+        fee_rates,
+    )?;
+    let (in_idx, out_idx) = if is_in[0] { (0, 1) } else { (1, 0) };
+    let amount_in_after_fee = amount_tolerance[in_idx] - burn_fees[in_idx];
+    let amount_out = stable::compute_out_given_in(
+        amplification,
+        token_vaults_amount,
+        in_idx,
+        out_idx,
+        amount_in_after_fee,
+    )?;
+    let mut outputs = vec![0u64; 2];
+    outputs[in_idx] = amount_in_after_fee;
+    outputs[out_idx] = amount_out;
+    check_outputs_not_zero(is_in, &outputs)?;
+    Ok(SwapResult {
+        burn_fees,
+        amounts: outputs,
+    })
+}
+
+/// 一次询价曲线最多包含的点数，限制计算量
+pub const MAX_QUOTE_CURVE_POINTS: usize = 16;
+
+/// 同一对 token 在多个输入数量下的输出（询价曲线）
+///
+/// 每个点的结果与单独询价 1 进 1 出的 swap 完全一致。
+/// 储备和权重只读取一次，在所有点之间共享；两 token 的闭式解不需要储备的 ln，
+/// 每个点只计算一次 ln 和一次 exp
+///
+/// amplification: 两个 token 都在稳定组中时为稳定组的放大系数，此时使用 StableSwap 曲线
+#[allow(clippy::too_many_arguments)]
+pub fn swap_curve_inner(
+    vault_in: u64,
+    vault_out: u64,
+    weight_in: u64,
+    weight_out: u64,
+    amounts_in: &[u64],
+    fee_numerator: u64,
+    fee_denominator: u64,
+    amplification: Option<u64>,
+) -> Result<Vec<u64>> {
+    require!(
+        !amounts_in.is_empty() && amounts_in.len() <= MAX_QUOTE_CURVE_POINTS,
+        ErrorCode::InvalidQuotePoints
+    );
+
+    amounts_in
+        .iter()
+        .map(|&amount_in| {
+            let fee = ((U256::from(amount_in) * fee_numerator) / fee_denominator).as_u64();
+            if let Some(amplification) = amplification {
+                return Ok(stable::compute_out_given_in(
+                    amplification,
+                    &[vault_in, vault_out],
+                    0,
+                    1,
+                    amount_in - fee,
+                )?);
+            }
+            let reserve_out_after = two_token_reserve_out(
+                vault_in,
+                amount_in - fee,
+                vault_out,
+                weight_in,
+                weight_out,
+                SwapRounding::POOL_FAVORABLE,
+            )?;
+            Ok(vault_out - reserve_out_after)
+        })
+        .collect()
+}
+
+/// 现货价格（18 位定点数）：在无穷小交易下每单位 token_out 需要支付的 token_in 数量，不含手续费
+/// price = (balance_in / weight_in) / (balance_out / weight_out)
+///
+/// 按加权恒定乘积计算，向下取整
+pub fn spot_price(
+    balance_in: u64,
+    weight_in: u64,
+    balance_out: u64,
+    weight_out: u64,
+) -> Result<u128> {
+    require!(
+        balance_in > 0 && balance_out > 0,
+        ErrorCode::InsufficientLiquidity
+    );
+    require!(weight_in > 0 && weight_out > 0, ErrorCode::InvalidTokenCount);
+    let numerator = U256::from(balance_in) * U256::from(weight_out) * E18;
+    let denominator = U256::from(balance_out) * U256::from(weight_in);
+    let price = numerator / denominator;
+    require!(price <= U256::from(u128::MAX), ErrorCode::MathOverflow);
+    Ok(price.as_u128())
+}
+
+/// 含手续费的现货价格：price / (1 - fee)，向上取整
+///
+/// 手续费从输入中扣除，因此得到 1 单位 token_out 实际需要支付 price / (1 - fee) 的 token_in
+pub fn spot_price_with_fee(
+    spot_price: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<u128> {
+    require!(
+        fee_numerator < fee_denominator,
+        ErrorCode::MathOverflow
+    );
+    let numerator = U256::from(spot_price) * U256::from(fee_denominator);
+    let denominator = U256::from(fee_denominator - fee_numerator);
+    let price = (numerator + denominator - U256::one()) / denominator;
+    require!(price <= U256::from(u128::MAX), ErrorCode::MathOverflow);
+    Ok(price.as_u128())
+}
+
+/// 参与 swap 的 token 的加权对数不变量 sum(weight_i * ln(vault_i))（ln 为 18 位定点数）
+pub(crate) fn weighted_log_invariant(vaults: &[u64], weights: &[u64]) -> Result<I256> {
+    let mut invariant = I256::ZERO;
+    for (&vault, &weight) in vaults.iter().zip(weights.iter()) {
+        check_reserve_in_domain(U256::from(vault))?;
+        let ln = LogExpMath::ln(I256::try_from(U256::from(vault) * E18)?)?;
+        invariant += I256::from(weight) * ln;
+    }
+    Ok(invariant)
+}
+
+/// ln 的计算误差容忍度：每单位权重允许的加权对数不变量下降（18 位定点数）
+pub const INVARIANT_TOLERANCE_PER_WEIGHT: u64 = 100;
+
+/// 检查 swap 结果是否保持不变量（不转账的安全检查）
+///
+/// amounts 为 swap 结果：输入 token 转入 vault 的数量、输出 token 转出 vault 的数量。
+/// amplification 为 None 时使用加权对数不变量 sum(weight_i * ln(vault_i))（18 位定点数），
+/// 允许 INVARIANT_TOLERANCE_PER_WEIGHT * sum(weight) 的 ln 误差；
+/// 稳定组内的 swap 使用 StableSwap 的不变量 D，允许牛顿迭代 1 个单位的误差
+///
+/// Returns:
+///     (是否安全, invariant_after - invariant_before)
+pub fn check_swap_invariant(
+    is_in: &[bool],
+    amounts: &[u64],
+    token_vaults_amount: &[u64],
+    weights: &[u64],
+    amplification: Option<u64>,
+) -> Result<(bool, I256)> {
+    require!(
+        amounts.len() == is_in.len()
+            && token_vaults_amount.len() == is_in.len()
+            && weights.len() == is_in.len(),
+        ErrorCode::InvalidTokenCount
+    );
+    let vaults_after = token_vaults_amount
+        .iter()
+        .zip(amounts.iter())
+        .zip(is_in.iter())
+        .map(|((&vault, &amount), &x)| {
+            if x {
+                vault.checked_add(amount).ok_or(ErrorCode::ReserveOutOfDomain)
+            } else {
+                vault.checked_sub(amount).ok_or(ErrorCode::InsufficientLiquidity)
+            }
+        })
+        .collect::<std::result::Result<Vec<u64>, ErrorCode>>()?;
+
+    let (before, after, tolerance) = match amplification {
+        Some(amplification) => (
+            I256::try_from(stable::compute_invariant(amplification, token_vaults_amount)?)?,
+            I256::try_from(stable::compute_invariant(amplification, &vaults_after)?)?,
+            I256::from(1i128),
+        ),
+        None => {
+            let weight_sum: i128 = weights.iter().map(|&w| w as i128).sum();
+            (
+                weighted_log_invariant(token_vaults_amount, weights)?,
+                weighted_log_invariant(&vaults_after, weights)?,
+                I256::from(weight_sum) * I256::from(INVARIANT_TOLERANCE_PER_WEIGHT),
+            )
+        }
+    };
+    let delta = after - before;
+    Ok((delta + tolerance >= I256::ZERO, delta))
+}
+
+/// 通用的 N token swap：sum(weight_i * ln(vault_i)) 保持不变，由最后一个输出 token 补齐
+/// 参数已通过 swap_inner_with_rounding 的校验，burn_fees 为每个 token 的手续费
+fn swap_general(
+    is_in: &[bool],
+    amount_tolerance: &[u64],
+    token_vaults_amount: &[u64],
+    weights: &[u64],
+    burn_fees: Vec<u64>,
+    rounding: SwapRounding,
+) -> Result<SwapResult> {
+    let token_count = is_in.len();
+    let mut vaults_after = token_vaults_amount.iter().map(|x| *x).collect::<Vec<u64>>();
+
+    // 初始化输出数组
+    let mut outputs = vec![0u64; token_count];
+
+    // LogExpMath期望18位小数精度
+    // vault需要放大18位（因为ln需要18位精度输入）
+    // weight保持原始值（作为系数）
+    let constant_before = weights
+        .iter()
+        .enumerate()
+        .map(|(i, weight)| {
+            let vault_before = token_vaults_amount[i];
+            check_reserve_in_domain(U256::from(vault_before))?;
+            // 将vault放大18位
+            let vault_before_u256 = U256::from(vault_before) * E18;
+            let vault_before_i256 = I256::try_from(vault_before_u256)?;
+            // weight不放大，ln返回1e18精度
+            let weight_i256 = I256::from(*weight);
+            let delta = weight_i256 * LogExpMath::ln(vault_before_i256)?;
+            Ok(delta)
+        })
+        .collect::<Result<Vec<I256>>>()?
+        .into_iter()
+        .sum::<I256>();
     // amounts_in_after_fee是扣除费用后的实际输入金额
     let amounts_in_after_fee = amount_tolerance
         .iter()
@@ -164,9 +863,10 @@ fn swap_inner<'info>(
     {
         let idx = amounts_in_index[i];
         let vault_after = amount_after_fee + amount_in_pool;
+        check_reserve_in_domain(vault_after)?;
         vaults_after[idx] = vault_after.as_u64();
         // 将vault放大18位
-        let vault_after_u256 = vault_after * U256::from(1_000_000_000_000_000_000u64);
+        let vault_after_u256 = vault_after * E18;
         let vault_after_i256 = I256::try_from(vault_after_u256)?;
         // weight不放大
         let weight_i256 = I256::from(weights[idx]);
@@ -193,8 +893,9 @@ fn swap_inner<'info>(
             ErrorCode::InsufficientLiquidity
         );
         let vault_after = amount_out_pool - amount_out_min;
+        check_reserve_in_domain(vault_after)?;
         // 将vault放大18位
-        let vault_after_u256 = vault_after * U256::from(1_000_000_000_000_000_000u64);
+        let vault_after_u256 = vault_after * E18;
         let vault_after_i256 = I256::try_from(vault_after_u256)?;
         // weight不放大
         let weight_i256 = I256::from(weights[idx]);
@@ -214,8 +915,19 @@ fn swap_inner<'info>(
     // last_delta除以weight得到ln值（1e18精度）
     let last_ln_vault_after = last_delta / last_weight;
     // exp返回的是vault*1e18，需要除以1e18得到原始vault
-    let last_should_be_18 = LogExpMath::exp(last_ln_vault_after)?;
-    let last_should_be = last_should_be_18 / I256::from(1_000_000_000_000_000_000u64);
+    // 新储备向上取整时输出向下取整（对池子有利）
+    // ln 低于 exp 的定义域时新储备小于 e^-41（远不足 1 个单位），按取整方向直接取 1 或 0，
+    // 否则输入远大于储备时 swap 会因 exp 溢出而失败
+    // ln 超过 exp 的定义域上限时新储备远大于 MAX_DOMAIN_RESERVE，提前拒绝
+    require!(
+        last_ln_vault_after <= MAX_NATURAL_EXPONENT,
+        ErrorCode::ReserveOutOfDomain
+    );
+    let last_should_be = if last_ln_vault_after < MIN_NATURAL_EXPONENT {
+        I256::from(if rounding.round_down_output { 1u64 } else { 0u64 })
+    } else {
+        scale_down_reserve(LogExpMath::exp(last_ln_vault_after)?, rounding.round_down_output)
+    };
 
     #[cfg(test)]
     {
@@ -226,7 +938,6 @@ fn swap_inner<'info>(
         println!("last_delta: {:?}", last_delta);
         println!("last_weight: {:?}", last_weight);
         println!("last_ln_vault_after: {:?}", last_ln_vault_after);
-        println!("last_should_be_18: {:?}", last_should_be_18);
         println!("last_should_be: {:?}", last_should_be);
         println!(
             "token_vaults_amount[{}]: {}",
@@ -240,7 +951,10 @@ fn swap_inner<'info>(
     }
 
     require!(last_should_be > I256::ZERO, ErrorCode::MathOverflow);
-    require!(last_should_be <= I256::MAX, ErrorCode::MathOverflow);
+    require!(
+        last_should_be <= I256::from(MAX_DOMAIN_RESERVE),
+        ErrorCode::ReserveOutOfDomain
+    );
     require!(
         last_should_be.as_u64() <= token_vaults_amount[last_idx],
         ErrorCode::InsufficientLiquidity
@@ -255,6 +969,78 @@ fn swap_inner<'info>(
     })
 }
 
+impl AnySwapPool {
+    /// swap 前对 pool 的检查（swap_anyswap、simulate_swap、swap_with_jit 共用）：
+    /// 未暂停 swap、token 数量足够，参与的 token 数量在 [1, max_tokens_per_swap] 内
+    pub fn check_swap_allowed(&self, token_count: usize) -> Result<()> {
+        self.check_not_paused(PAUSE_SWAP)?;
+        self.check_min_tokens()?;
+        require!(token_count > 0, ErrorCode::InvalidTokenCount);
+        self.check_tokens_per_swap(token_count)
+    }
+
+    /// 带检查的 swap 计算（swap_anyswap、simulate_swap、swap_with_jit 共用）：
+    /// 扣费后的输入不低于 min_swap_amount，计算结果满足 amount_tolerance（输入为上限，输出为下限）
+    ///
+    /// token_vaults_amount 为实际参与 swap 的储备（JIT 注入之后的储备），
+    /// 因此储备在用户签名之后发生变化时，输出低于用户的下限同样以 SlippageExceeded 拒绝
+    pub fn checked_swap(
+        &self,
+        mints: &[Pubkey],
+        is_in: &[bool],
+        amount_tolerance: &[u64],
+        user_vaults_amount: &[u64],
+        token_vaults_amount: &[u64],
+        weights: &[u64],
+    ) -> Result<SwapResult> {
+        self.check_min_swap_amount(mints, is_in, amount_tolerance)?;
+        let result = self.swap_directional(
+            mints,
+            is_in,
+            amount_tolerance,
+            user_vaults_amount,
+            token_vaults_amount,
+            weights,
+        )?;
+        check_swap_tolerance(is_in, amount_tolerance, &result.amounts)?;
+        Ok(result)
+    }
+
+    /// 按每个 token 的方向费率执行 swap 计算（见 AnySwapPool::swap_fee_rates）
+    /// 稳定组内 1 进 1 出的 swap 使用 StableSwap 曲线，其余使用加权恒定乘积
+    /// mints: 每个参与 swap 的 token 的 mint，顺序与其他参数一致
+    pub fn swap_directional(
+        &self,
+        mints: &[Pubkey],
+        is_in: &[bool],
+        amount_tolerance: &[u64],
+        user_vaults_amount: &[u64],
+        token_vaults_amount: &[u64],
+        weights: &[u64],
+    ) -> Result<SwapResult> {
+        let fee_rates = self.swap_fee_rates(mints, is_in)?;
+        if let Some(amplification) = self.stable_pair_amplification(mints, is_in) {
+            return swap_stable_pair(
+                is_in,
+                amount_tolerance,
+                user_vaults_amount,
+                token_vaults_amount,
+                &fee_rates,
+                amplification,
+            );
+        }
+        swap_inner_with_fee_rates(
+            is_in,
+            amount_tolerance,
+            user_vaults_amount,
+            token_vaults_amount,
+            weights,
+            &fee_rates,
+            SwapRounding::POOL_FAVORABLE,
+        )
+    }
+}
+
 impl SwapProtocol for AnySwapPool {
     fn swap<'info>(
         &self,
@@ -309,6 +1095,311 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_effective_fee_bps_2in_1out() {
+        // 2进1出，费率 30/10000 = 30 bps
+        let is_in = [true, true, false];
+        let amount_tolerance = [1_000_000u64, 333_333u64, 1u64];
+        let result = swap_inner(
+            &is_in,
+            &amount_tolerance,
+            &[1_000_000u64, 333_333u64, 0u64],
+            &[10_000_000u64, 20_000_000u64, 30_000_000u64],
+            &[50u64, 30u64, 20u64],
+            30,
+            10000,
+        )
+        .unwrap();
+
+        let bps = effective_fee_bps(&is_in, &amount_tolerance, &result.burn_fees).unwrap();
+        // 每个输入的手续费向下取整，因此有效费率不超过配置费率，且最多低 1 bps
+        assert!((29..=30).contains(&bps), "effective_fee_bps = {}", bps);
+
+        // 整数倍的输入没有取整误差
+        let amount_tolerance = [1_000_000u64, 500_000u64, 1u64];
+        let result = swap_inner(
+            &is_in,
+            &amount_tolerance,
+            &[1_000_000u64, 500_000u64, 0u64],
+            &[10_000_000u64, 20_000_000u64, 30_000_000u64],
+            &[50u64, 30u64, 20u64],
+            30,
+            10000,
+        )
+        .unwrap();
+        assert_eq!(
+            effective_fee_bps(&is_in, &amount_tolerance, &result.burn_fees).unwrap(),
+            30
+        );
+    }
+
+    #[test]
+    fn test_swap_op_count() {
+        use crate::math::logexpmath::op_counter;
+
+        let token_vaults_amount = [
+            10_000_000u64,
+            50_000_000u64,
+            100_000_000u64,
+            20_000_000u64,
+            30_000_000u64,
+        ];
+        let weights = [20u64, 40u64, 80u64, 30u64, 50u64];
+        let user_vaults_amount = [1_000_000u64; 5];
+
+        // 代表性的方向组合：1进1出、1进多出、多进1出、多进多出
+        let masks: Vec<Vec<bool>> = vec![
+            vec![true, false],
+            vec![true, false, false, false],
+            vec![true, true, true, false],
+            vec![true, true, true, false, false],
+            vec![false, true, false, true, false],
+        ];
+
+        for is_in in masks {
+            let token_count = is_in.len();
+            let amount_tolerance: Vec<u64> = is_in
+                .iter()
+                .map(|&x| if x { 10_000u64 } else { 1_000u64 })
+                .collect();
+
+            op_counter::reset();
+            swap_inner(
+                &is_in,
+                &amount_tolerance,
+                &user_vaults_amount[..token_count],
+                &token_vaults_amount[..token_count],
+                &weights[..token_count],
+                3,
+                10000,
+            )
+            .unwrap();
+
+            assert_eq!(
+                swap_op_count(token_count, &is_in),
+                op_counter::get(),
+                "mask {:?} 的 ln/exp 次数不匹配",
+                is_in
+            );
+        }
+    }
+
+    #[test]
+    fn test_calc_in_given_out_round_trip() {
+        let cases = [
+            (1_000_000_000u64, 1_000_000_000u64, 80u64, 20u64),
+            (1_000_000_000, 1_000_000_000, 20, 80),
+            (5_000_000_000_000, 3_000_000_000, 1, 1),
+            (123_456_789, 987_654_321_000, 7, 3),
+        ];
+        for (vault_in, vault_out, weight_in, weight_out) in cases {
+            for amount_out in [1u64, vault_out / 1000, vault_out / 10, vault_out / 2] {
+                let amount_in =
+                    calc_in_given_out(vault_in, vault_out, weight_in, weight_out, amount_out)
+                        .unwrap();
+                let reserve_out_after = two_token_reserve_out(
+                    vault_in,
+                    amount_in,
+                    vault_out,
+                    weight_in,
+                    weight_out,
+                    SwapRounding::POOL_FAVORABLE,
+                )
+                .unwrap();
+                let out = vault_out - reserve_out_after;
+                // 两个方向各自按对池子有利的方向取整，往返结果与目标的差
+                // 不超过 1 个单位输入的边际输出加上 1e-12 的相对误差
+                let marginal = (vault_out as u128 * weight_in as u128)
+                    .div_ceil(vault_in as u128 * weight_out as u128) as u64;
+                assert!(out.abs_diff(amount_out) <= 1 + marginal + amount_out / 1_000_000_000_000);
+            }
+        }
+
+        assert_eq!(
+            calc_in_given_out(1_000, 1_000, 1, 1, 1_000).unwrap_err(),
+            error!(ErrorCode::InsufficientLiquidity)
+        );
+        assert!(calc_in_given_out(1_000, 1_000, 1, 1, 1_001).is_err());
+    }
+
+    #[test]
+    fn test_gross_up_for_fee() {
+        for amount in [0u64, 1, 997, 1_000, 123_456_789] {
+            let gross = gross_up_for_fee(amount, 3, 1000).unwrap();
+            let fee = gross * 3 / 1000;
+            assert!(gross - fee >= amount);
+            assert!(gross - fee - amount <= 1);
+        }
+        assert!(gross_up_for_fee(1, 1000, 1000).is_err());
+    }
+
+    #[test]
+    fn test_check_memo() {
+        assert!(check_memo("invoice #42").is_ok());
+        assert!(check_memo(&"a".repeat(MAX_MEMO_LEN)).is_ok());
+        assert!(check_memo(&"a".repeat(MAX_MEMO_LEN + 1)).is_err());
+        assert!(check_memo("").is_err());
+    }
+
+    #[test]
+    fn test_swap_invariant_safety() {
+        let is_in = [true, false];
+        let vaults = [1_000_000_000_000u64, 2_000_000_000_000];
+        let weights = [30u64, 70];
+        let tolerance = [50_000_000_000u64, 0];
+
+        // 正常的 swap（含手续费）：不变量不下降
+        let result = swap_inner(&is_in, &tolerance, &tolerance, &vaults, &weights, 3, 1000).unwrap();
+        let (safe, delta) =
+            check_swap_invariant(&is_in, &result.amounts, &vaults, &weights, None).unwrap();
+        assert!(safe);
+        assert!(!delta.is_negative());
+
+        // 多 token 的 swap 同样安全
+        let vaults3 = [1_000_000_000u64, 3_000_000_000, 5_000_000_000];
+        let weights3 = [20u64, 40, 40];
+        let is_in3 = [true, true, false];
+        let tolerance3 = [10_000_000u64, 20_000_000, 0];
+        let result = swap_inner(&is_in3, &tolerance3, &tolerance3, &vaults3, &weights3, 3, 1000).unwrap();
+        let (safe, _) =
+            check_swap_invariant(&is_in3, &result.amounts, &vaults3, &weights3, None).unwrap();
+        assert!(safe);
+
+        // 稳定组内的 swap 使用 StableSwap 不变量
+        let fee_rates = [(3u64, 1000u64); 2];
+        let stable_vaults = [1_000_000_000u64, 1_000_000_000];
+        let result = swap_stable_pair(&is_in, &[1_000_000, 0], &[1_000_000, 0], &stable_vaults, &fee_rates, 100).unwrap();
+        let (safe, _) =
+            check_swap_invariant(&is_in, &result.amounts, &stable_vaults, &[50, 50], Some(100)).unwrap();
+        assert!(safe);
+
+        // 人为扭曲的手续费：输出按全额输入计算，但 30% 的手续费没有进入 vault
+        let result = swap_inner(&is_in, &tolerance, &tolerance, &vaults, &weights, 0, 1).unwrap();
+        let skewed = [result.amounts[0] * 7 / 10, result.amounts[1]];
+        let (safe, delta) = check_swap_invariant(&is_in, &skewed, &vaults, &weights, None).unwrap();
+        assert!(!safe);
+        assert!(delta.is_negative());
+
+        // 输出超过 vault
+        assert!(check_swap_invariant(&is_in, &[1, vaults[1] + 1], &vaults, &weights, None).is_err());
+    }
+
+    #[test]
+    fn test_spot_price_with_fee() {
+        // 储备 1000 / 2000，权重相同：1 单位 token_out 需要 0.5 单位 token_in
+        let spot = spot_price(1_000, 50, 2_000, 50).unwrap();
+        assert_eq!(spot, 500_000_000_000_000_000);
+        // 权重影响价格：w_out 是 w_in 的 3 倍时价格乘以 3
+        assert_eq!(spot_price(1_000, 25, 2_000, 75).unwrap(), 3 * spot);
+
+        // 含手续费的价格恰好为 spot / (1 - fee)
+        let with_fee = spot_price_with_fee(spot, 3, 1000).unwrap();
+        assert!(with_fee > spot);
+        assert!(with_fee * 997 >= spot * 1000);
+        assert!(with_fee * 997 < spot * 1000 + 997);
+        assert_eq!(spot_price_with_fee(spot, 0, 1000).unwrap(), spot);
+
+        assert!(spot_price_with_fee(spot, 1000, 1000).is_err());
+        assert!(spot_price(0, 50, 2_000, 50).is_err());
+    }
+
+    #[test]
+    fn test_swap_curve_matches_individual_quotes() {
+        let (vault_in, vault_out, w_in, w_out) = (10_000_000u64, 40_000_000u64, 30u64, 70u64);
+        let amounts_in: Vec<u64> = vec![1, 10, 1_000, 50_000, 100_000, 1_000_000, 5_000_000, 50_000_000];
+
+        let curve = swap_curve_inner(vault_in, vault_out, w_in, w_out, &amounts_in, 3, 10000, None).unwrap();
+        assert_eq!(curve.len(), amounts_in.len());
+
+        // 输入越多输出越多
+        for pair in curve.windows(2) {
+            assert!(pair[0] <= pair[1], "曲线不单调: {:?}", curve);
+        }
+        assert!(curve.last().unwrap() > curve.first().unwrap());
+
+        // 每个点与单独询价（1 进 1 出）一致
+        for (&amount_in, &out) in amounts_in.iter().zip(curve.iter()) {
+            let single = swap_inner(
+                &[true, false],
+                &[amount_in, 0],
+                &[amount_in, 0],
+                &[vault_in, vault_out],
+                &[w_in, w_out],
+                3,
+                10000,
+            )
+            .unwrap();
+            assert_eq!(single.amounts[1], out, "输入 {} 的输出不一致", amount_in);
+        }
+
+        // 点数受限
+        assert!(swap_curve_inner(vault_in, vault_out, w_in, w_out, &[], 3, 10000, None).is_err());
+        let too_many = vec![1_000u64; MAX_QUOTE_CURVE_POINTS + 1];
+        assert!(swap_curve_inner(vault_in, vault_out, w_in, w_out, &too_many, 3, 10000, None).is_err());
+    }
+
+    #[test]
+    fn test_two_token_fast_path_matches_general() {
+        use crate::math::logexpmath::op_counter;
+
+        // (vault_in, vault_out, weight_in, weight_out, amount_in)
+        let cases: Vec<(u64, u64, u64, u64, u64)> = vec![
+            (1_000_000, 1_000_000, 50, 50, 1_000),
+            (10_000_000, 50_000_000, 20, 80, 250_000),
+            (1_000_000_000, 3_000_000, 70, 30, 5_000_000),
+            (5_000, 9_000_000_000, 1, 60, 4_999),
+            (123_456_789, 987_654_321, 33, 17, 100_000_000),
+        ];
+
+        for (vault_in, vault_out, w_in, w_out, amount_in) in cases {
+            for is_in in [vec![true, false], vec![false, true]] {
+                let (vaults, weights, tolerance) = if is_in[0] {
+                    (vec![vault_in, vault_out], vec![w_in, w_out], vec![amount_in, 0])
+                } else {
+                    (vec![vault_out, vault_in], vec![w_out, w_in], vec![0, amount_in])
+                };
+                let burn_fees: Vec<u64> = tolerance
+                    .iter()
+                    .zip(is_in.iter())
+                    .map(|(&t, &x)| if x { t * 3 / 10000 } else { 0 })
+                    .collect();
+
+                op_counter::reset();
+                let fast = swap_inner(&is_in, &tolerance, &tolerance, &vaults, &weights, 3, 10000)
+                    .unwrap();
+                let fast_ops = op_counter::get();
+
+                op_counter::reset();
+                let general = swap_general(
+                    &is_in,
+                    &tolerance,
+                    &vaults,
+                    &weights,
+                    burn_fees,
+                    SwapRounding::POOL_FAVORABLE,
+                )
+                .unwrap();
+                let general_ops = op_counter::get();
+
+                let out = if is_in[0] { 1 } else { 0 };
+                let (a, b) = (fast.amounts[out], general.amounts[out]);
+                let diff = a.abs_diff(b);
+                assert!(
+                    diff <= 1 || (diff as f64) / (b as f64) < 1e-12,
+                    "快速路径 {} 与通用路径 {} 偏差过大",
+                    a,
+                    b
+                );
+                assert_eq!(fast.burn_fees, general.burn_fees);
+
+                // 快速路径：1 次 ln + 1 次 exp；通用路径：3 次 ln + 1 次 exp
+                assert_eq!(fast_ops, (1, 1));
+                assert_eq!(general_ops, (3, 1));
+                assert!(fast_ops.0 + fast_ops.1 < general_ops.0 + general_ops.1);
+            }
+        }
+    }
+
     #[test]
     fn test_swap_6_tokens_3in_2out() {
         // 测试用例2：6 token swap，3进2出（权重不同、储备不同、输出不同）
@@ -582,4 +1673,245 @@ mod tests {
 
         println!("\n✅ 测试用例3通过：30 token swap，10进10出，包含大额交易（5万亿）！");
     }
+
+    #[test]
+    fn test_swap_output_rounds_down() {
+        // 执行 swap 的输出不多于不取整时的输出，且最多少一个取整单位
+        let user_favorable = SwapRounding {
+            round_up_input: false,
+            round_down_output: false,
+        };
+        for amount in [1u64, 10, 1_234, 1_000_000, 77_777_777] {
+            let executed = swap_inner(
+                &[true, false],
+                &[amount, 0],
+                &[amount, 0],
+                &[1_000_000_000, 3_000_000_000],
+                &[80, 20],
+                3,
+                1000,
+            )
+            .unwrap();
+            let unrounded = swap_inner_with_rounding(
+                &[true, false],
+                &[amount, 0],
+                &[amount, 0],
+                &[1_000_000_000, 3_000_000_000],
+                &[80, 20],
+                3,
+                1000,
+                user_favorable,
+            )
+            .unwrap();
+            assert!(executed.amounts[1] <= unrounded.amounts[1]);
+            assert!(unrounded.amounts[1] - executed.amounts[1] <= 1);
+            assert_eq!(executed.amounts[0], unrounded.amounts[0]);
+        }
+    }
+
+    #[test]
+    fn test_dust_swap_output_rounds_to_zero_is_rejected() {
+        // 输入相对储备过小：1000 个 token 0 在 1e12 / 1000 的储备下换不到 1 个 token 1
+        let result = swap_inner(
+            &[true, false],
+            &[1_000, 0],
+            &[1_000, 0],
+            &[1_000_000_000_000, 1_000],
+            &[50, 50],
+            3,
+            1000,
+        );
+        // 拒绝 swap（指令在转账前失败，用户保留输入），而不是收取输入却输出 0
+        let err = result.err().unwrap();
+        assert_eq!(err, ErrorCode::OutputRoundsToZero.into());
+
+        // 同样的储备下足够大的输入仍然可以成功
+        let result = swap_inner(
+            &[true, false],
+            &[10_000_000_000, 0],
+            &[10_000_000_000, 0],
+            &[1_000_000_000_000, 1_000],
+            &[50, 50],
+            3,
+            1000,
+        )
+        .unwrap();
+        assert!(result.amounts[1] > 0);
+    }
+
+    #[test]
+    fn test_check_swap_tolerance() {
+        let is_in = [true, false];
+        let vaults = [1_000_000u64, 1_000_000u64];
+        let weights = [50u64, 50u64];
+        let quote = swap_inner(&is_in, &[10_000, 0], &[10_000, 0], &vaults, &weights, 3, 1000).unwrap();
+        let out = quote.amounts[1];
+
+        // 下限等于实际输出时通过，高 1 个单位即拒绝
+        let result = swap_inner(&is_in, &[10_000, out], &[10_000, 0], &vaults, &weights, 3, 1000).unwrap();
+        assert!(check_swap_tolerance(&is_in, &[10_000, out], &result.amounts).is_ok());
+        let result = swap_inner(&is_in, &[10_000, out + 1], &[10_000, 0], &vaults, &weights, 3, 1000).unwrap();
+        assert_eq!(result.amounts[1], out);
+        assert_eq!(
+            check_swap_tolerance(&is_in, &[10_000, out + 1], &result.amounts).err(),
+            Some(error!(ErrorCode::SlippageExceeded))
+        );
+
+        // 输入不能超过上限
+        assert_eq!(
+            check_swap_tolerance(&is_in, &[9_999, 0], &[10_000, out]).err(),
+            Some(error!(ErrorCode::SlippageExceeded))
+        );
+        assert!(check_swap_tolerance(&is_in, &[10_000], &[10_000, out]).is_err());
+    }
+
+    #[test]
+    fn test_swap_with_slippage_check() {
+        let is_in = [true, false];
+        let vaults = [1_000_000u64, 1_000_000u64];
+        let weights = [50u64, 50u64];
+
+        // 询价
+        let quote = swap_inner(&is_in, &[10_000, 1], &[10_000, 0], &vaults, &weights, 3, 1000).unwrap();
+        let expected = [10_000u64, quote.amounts[1]];
+
+        // 执行前有人先做了同方向的 swap，输出 token 的储备减少约 2%
+        let moved = [1_020_000u64, 980_392u64];
+        let actual = swap_inner(&is_in, &expected, &[10_000, 0], &moved, &weights, 3, 1000).unwrap();
+        assert!(actual.amounts[1] < expected[1]);
+
+        // 0.5% 滑点拒绝，5% 滑点通过
+        assert_eq!(
+            check_output_slippage(&is_in, &expected, &actual.amounts, 50).err(),
+            Some(error!(ErrorCode::InsufficientOutputAmount))
+        );
+        assert!(check_output_slippage(&is_in, &expected, &actual.amounts, 500).is_ok());
+        // 储备未变化时 0 滑点也能通过
+        assert!(check_output_slippage(&is_in, &expected, &quote.amounts, 0).is_ok());
+
+        // 下限向上取整，滑点不能超过 100%
+        assert_eq!(min_output_for_slippage(1_000, 10).unwrap(), 999);
+        assert_eq!(min_output_for_slippage(999, 10).unwrap(), 999);
+        assert_eq!(min_output_for_slippage(999, 10_000).unwrap(), 0);
+        assert_eq!(
+            min_output_for_slippage(999, 10_001).err(),
+            Some(error!(ErrorCode::InvalidSlippage))
+        );
+    }
+
+    #[test]
+    fn test_zero_tolerance_must_not_participate() {
+        let inconsistent = Some(error!(ErrorCode::InconsistentSwapParams));
+        let vaults = [1_000_000u64, 1_000_000, 1_000_000];
+        let weights = [50u64, 30, 20];
+
+        // token 1 容差为 0 却被标记为输入
+        let result = swap_inner(
+            &[true, true, false],
+            &[10_000, 0, 0],
+            &[10_000, 0, 0],
+            &vaults,
+            &weights,
+            3,
+            1000,
+        );
+        assert_eq!(result.err(), inconsistent);
+
+        // 非最后一个输出的容差为 0
+        let result = swap_inner(
+            &[true, false, false],
+            &[10_000, 0, 0],
+            &[10_000, 0, 0],
+            &vaults,
+            &weights,
+            3,
+            1000,
+        );
+        assert_eq!(result.err(), inconsistent);
+
+        // 不参与的 token 不传入：只剩 token 0 -> token 2，最后一个输出的容差可以为 0
+        let result = swap_inner(
+            &[true, false],
+            &[10_000, 0],
+            &[10_000, 0],
+            &[vaults[0], vaults[2]],
+            &[weights[0], weights[2]],
+            3,
+            1000,
+        )
+        .unwrap();
+        assert!(result.amounts[1] > 0);
+
+        // 没有输入或没有输出
+        assert_eq!(validate_swap_params(&[true, true], &[1, 1]).err(), inconsistent);
+        assert_eq!(validate_swap_params(&[false, false], &[1, 1]).err(), inconsistent);
+    }
+
+    #[test]
+    fn test_reserve_out_of_domain() {
+        let domain_err = Some(error!(ErrorCode::ReserveOutOfDomain));
+
+        // 空 vault：ln(0) 无定义
+        let result = swap_inner(
+            &[true, false],
+            &[1_000, 1],
+            &[1_000, 0],
+            &[0, 1_000_000],
+            &[50, 50],
+            0,
+            1000,
+        );
+        assert_eq!(result.err(), domain_err);
+
+        // 输入后储备超过 u64
+        let result = swap_inner(
+            &[true, false],
+            &[u64::MAX, 1],
+            &[u64::MAX, 0],
+            &[u64::MAX / 2, 1_000_000],
+            &[50, 50],
+            0,
+            1000,
+        );
+        assert_eq!(result.err(), domain_err);
+
+        // 非最后一个输出被完全取空
+        let result = swap_inner(
+            &[true, false, false],
+            &[1_000, 1_000_000, 1],
+            &[1_000, 0, 0],
+            &[1_000_000, 1_000_000, 1_000_000],
+            &[50, 25, 25],
+            0,
+            1000,
+        );
+        assert_eq!(result.err(), domain_err);
+
+        // 最后一个 token 的新储备需要涨到远超 u64（输出容差远大于输入）
+        let result = swap_inner(
+            &[true, false, false],
+            &[1, 999_999, 1],
+            &[1, 0, 0],
+            &[1_000_000, 1_000_000, 1_000],
+            &[50, 1_000, 1],
+            0,
+            1000,
+        );
+        assert_eq!(result.err(), domain_err);
+
+        assert!(check_reserve_in_domain(U256::from(MIN_DOMAIN_RESERVE)).is_ok());
+        assert!(check_reserve_in_domain(U256::from(MAX_DOMAIN_RESERVE)).is_ok());
+        assert!(check_reserve_in_domain(U256::from(MAX_DOMAIN_RESERVE) + 1).is_err());
+    }
+
+    #[test]
+    fn test_scale_down_reserve() {
+        let one = I256::from(1_000_000_000_000_000_000u64);
+        let exact = I256::from(5u64) * one;
+        assert_eq!(scale_down_reserve(exact, true), I256::from(5u64));
+        assert_eq!(scale_down_reserve(exact, false), I256::from(5u64));
+        let inexact = exact + I256::from(1u64);
+        assert_eq!(scale_down_reserve(inexact, true), I256::from(6u64));
+        assert_eq!(scale_down_reserve(inexact, false), I256::from(5u64));
+    }
 }
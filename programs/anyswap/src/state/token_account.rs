@@ -0,0 +1,210 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::token::{spl_token, TokenAccount};
+
+use crate::error::ErrorCode;
+
+/// SPL Token 账户中 owner 字段的偏移（mint 32 字节之后）
+pub const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+/// SPL Token 账户中 amount 字段的偏移（mint 32 字节 + owner 32 字节）
+pub const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+/// SPL Token 账户中 state 字段的偏移
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+
+/// 只读取 token 账户的余额，不做完整的反序列化
+///
+/// 校验账户属于 token 程序、长度正确且已初始化，然后直接读取偏移 64 处的 amount。
+/// 只在账户地址已经与 pool 记录的 vault 比对过、只需要余额的地方使用
+pub fn read_token_amount(account_info: &AccountInfo) -> Result<u64> {
+    let data = borrow_token_account_data(account_info)?;
+    let mut amount = [0u8; 8];
+    amount.copy_from_slice(&data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8]);
+    Ok(u64::from_le_bytes(amount))
+}
+
+/// 反序列化 RemainingAccounts 中的 token 账户
+///
+/// 失败时返回 InvalidTokenAccount，并在错误中记录账户在 RemainingAccounts 中的索引，
+/// 而不是 Anchor 通用的反序列化错误
+pub fn load_token_account<'a>(
+    account_info: &'a AccountInfo<'a>,
+    index: usize,
+) -> Result<Account<'a, TokenAccount>> {
+    Account::<TokenAccount>::try_from(account_info).map_err(|_| {
+        error!(ErrorCode::InvalidTokenAccount)
+            .with_account_name(format!("remaining_accounts[{}]", index))
+    })
+}
+
+/// 检查 RemainingAccounts 中的账户可写
+///
+/// token 转账要求转出和转入账户都可写，转账前调用：只读账户返回带索引的 AccountNotWritable，
+/// 而不是让 token 程序的 CPI 以难以理解的错误失败
+pub fn check_writable(account_info: &AccountInfo, index: usize) -> Result<()> {
+    if !account_info.is_writable {
+        return Err(error!(ErrorCode::AccountNotWritable)
+            .with_account_name(format!("remaining_accounts[{}]", index)));
+    }
+    Ok(())
+}
+
+/// 检查 (user_token_account, vault_account) 账户对全部可写，pairs 从 RemainingAccounts 开头切出
+pub fn check_writable_pairs(pairs: &[AccountInfo]) -> Result<()> {
+    for (i, account_info) in pairs.iter().enumerate() {
+        check_writable(account_info, i)?;
+    }
+    Ok(())
+}
+
+/// 检查 vault 的 owner（转出时的签名 authority）是否为 pool_authority
+///
+/// 输出转账前调用：authority 不一致时返回 InvalidVaultAuthority，
+/// 而不是让 token 程序的转账以难以理解的错误失败
+pub fn check_vault_authority(vault_info: &AccountInfo, pool_authority: &Pubkey) -> Result<()> {
+    let data = borrow_token_account_data(vault_info)?;
+    let owner = Pubkey::try_from(&data[TOKEN_ACCOUNT_OWNER_OFFSET..TOKEN_ACCOUNT_OWNER_OFFSET + 32])
+        .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+    require_keys_eq!(owner, *pool_authority, ErrorCode::InvalidVaultAuthority);
+    Ok(())
+}
+
+/// 校验账户属于 token 程序、长度正确且已初始化，返回账户数据
+fn borrow_token_account_data<'a>(
+    account_info: &'a AccountInfo,
+) -> Result<std::cell::Ref<'a, &'a mut [u8]>> {
+    require_keys_eq!(
+        *account_info.owner,
+        spl_token::ID,
+        anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram
+    );
+    let data = account_info.try_borrow_data()?;
+    require!(
+        data.len() == spl_token::state::Account::LEN,
+        anchor_lang::error::ErrorCode::AccountDidNotDeserialize
+    );
+    // state 为 0 表示未初始化
+    require!(
+        data[TOKEN_ACCOUNT_STATE_OFFSET] != 0,
+        anchor_lang::error::ErrorCode::AccountNotInitialized
+    );
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token::state::{Account as SplAccount, AccountState};
+
+    fn pack_account(amount: u64, state: AccountState) -> Vec<u8> {
+        pack_account_with_owner(amount, state, Pubkey::new_unique())
+    }
+
+    fn pack_account_with_owner(amount: u64, state: AccountState, owner: Pubkey) -> Vec<u8> {
+        let account = SplAccount {
+            mint: Pubkey::new_unique(),
+            owner,
+            amount,
+            state,
+            ..SplAccount::default()
+        };
+        let mut data = vec![0u8; SplAccount::LEN];
+        account.pack_into_slice(&mut data);
+        data
+    }
+
+    #[test]
+    fn test_read_token_amount_matches_deserialize() {
+        for amount in [0u64, 1, 1_000_000, u64::MAX / 3, u64::MAX] {
+            let key = Pubkey::new_unique();
+            let mut lamports = 0u64;
+            let mut data = pack_account(amount, AccountState::Initialized);
+            let owner = spl_token::ID;
+            let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+            let full = Account::<TokenAccount>::try_from(&info).unwrap();
+            assert_eq!(read_token_amount(&info).unwrap(), full.amount);
+            assert_eq!(full.amount, amount);
+        }
+    }
+
+    #[test]
+    fn test_read_token_amount_rejects_invalid() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0u64;
+
+        // 不属于 token 程序
+        let mut data = pack_account(100, AccountState::Initialized);
+        let owner = Pubkey::new_unique();
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert!(read_token_amount(&info).is_err());
+
+        // 未初始化
+        let mut data = pack_account(100, AccountState::Uninitialized);
+        let owner = spl_token::ID;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert!(read_token_amount(&info).is_err());
+
+        // 长度不对（例如 mint 账户）
+        let mut data = vec![1u8; 82];
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert!(read_token_amount(&info).is_err());
+    }
+
+    #[test]
+    fn test_check_vault_authority() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0u64;
+        let owner = spl_token::ID;
+        let pool_authority = Pubkey::new_unique();
+
+        let mut data = pack_account_with_owner(100, AccountState::Initialized, pool_authority);
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        assert!(check_vault_authority(&info, &pool_authority).is_ok());
+
+        // vault 的 authority 不是 pool_authority：明确返回 InvalidVaultAuthority
+        let mut data = pack_account_with_owner(100, AccountState::Initialized, Pubkey::new_unique());
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        let err = check_vault_authority(&info, &pool_authority).unwrap_err();
+        assert_eq!(err, ErrorCode::InvalidVaultAuthority.into());
+    }
+
+    #[test]
+    fn test_load_token_account_reports_index() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0u64;
+        let mut data = pack_account(100, AccountState::Initialized);
+        let owner = spl_token::ID;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(load_token_account(&info, 0).unwrap().amount, 100);
+
+        // 在 vault 的位置传入系统账户
+        let mut lamports = 1_000_000u64;
+        let mut data = vec![];
+        let owner = anchor_lang::system_program::ID;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        let err = load_token_account(&info, 3).unwrap_err();
+        assert_eq!(err, ErrorCode::InvalidTokenAccount.into());
+        assert!(err.to_string().contains("remaining_accounts[3]"), "{}", err);
+    }
+
+    #[test]
+    fn test_check_writable_pairs_rejects_read_only_user_account() {
+        let owner = spl_token::ID;
+        let user_key = Pubkey::new_unique();
+        let vault_key = Pubkey::new_unique();
+        let (mut user_lamports, mut vault_lamports) = (0u64, 0u64);
+        let mut user_data = pack_account(100, AccountState::Initialized);
+        let mut vault_data = pack_account(100, AccountState::Initialized);
+
+        let vault = AccountInfo::new(&vault_key, false, true, &mut vault_lamports, &mut vault_data, &owner, false, 0);
+        let user = AccountInfo::new(&user_key, false, true, &mut user_lamports, &mut user_data, &owner, false, 0);
+        let mut pairs = vec![user, vault];
+        assert!(check_writable_pairs(&pairs).is_ok());
+
+        // 用户 token 账户以只读方式传入：转账前明确返回 AccountNotWritable
+        pairs[0].is_writable = false;
+        let err = check_writable_pairs(&pairs).unwrap_err();
+        assert_eq!(err, ErrorCode::AccountNotWritable.into());
+        assert!(err.to_string().contains("remaining_accounts[0]"), "{}", err);
+    }
+}
@@ -0,0 +1,359 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::math::i256::I256;
+use crate::math::logexpmath::LogExpMath;
+use crate::state::swap::spot_price;
+use crate::state::AnySwapPool;
+use static_assertions::const_assert_eq;
+use std::mem::size_of;
+
+/// pool 中最多可跟踪 TWAP 的 token 对数量
+///
+/// MAX_TOKENS 为 1024，无法为每个 token 都保存价格累加器，
+/// 因此只跟踪管理员通过 set_twap_pair 配置的少量 token 对
+pub const MAX_TWAP_PAIRS: usize = 4;
+
+/// 一个被跟踪的 token 对的 TWAP 累加器
+///
+/// 价格为 1 单位 mint_a 值多少 mint_b（现货价格，不含手续费），
+/// 累加器记录 sum(ln(price) * 持续秒数)，两次读数之差除以经过的秒数再取 exp 即为这段时间的
+/// 时间加权（几何）平均价格
+#[zero_copy]
+#[repr(C)]
+#[derive(Debug)]
+pub struct TwapPair {
+    /// 基础 token 的 mint（默认公钥表示该槽位未使用）
+    pub mint_a: Pubkey,
+    /// 计价 token 的 mint
+    pub mint_b: Pubkey,
+    /// 累计对数价格 sum(ln(price) * 秒数)，I256 小端字节，ln 为 18 位定点数
+    pub log_price_cumulative: [u8; 32],
+    /// 最近一次观察到的 ln(price)，I256 小端字节，18 位定点数
+    pub last_log_price: [u8; 32],
+}
+
+// 验证结构体大小和对齐
+const_assert_eq!(size_of::<TwapPair>(), 32 + 32 + 32 + 32); // 128 bytes
+const_assert_eq!(size_of::<TwapPair>() % 8, 0);
+
+impl TwapPair {
+    /// 检查槽位是否未使用
+    pub fn is_empty(&self) -> bool {
+        self.mint_a == Pubkey::default()
+    }
+
+    /// 获取累计对数价格
+    pub fn get_log_price_cumulative(&self) -> I256 {
+        I256::from_le_bytes(self.log_price_cumulative)
+    }
+
+    /// 获取最近一次观察到的 ln(price)
+    pub fn get_last_log_price(&self) -> I256 {
+        I256::from_le_bytes(self.last_log_price)
+    }
+
+    /// 计算单个槽位所需的空间大小
+    pub fn space() -> usize {
+        32 + // mint_a (Pubkey)
+        32 + // mint_b (Pubkey)
+        32 + // log_price_cumulative
+        32 // last_log_price
+    }
+
+    fn set_log_price_cumulative(&mut self, value: I256) {
+        self.log_price_cumulative = value.to_le_bytes();
+    }
+
+    fn set_last_log_price(&mut self, value: I256) {
+        self.last_log_price = value.to_le_bytes();
+    }
+}
+
+/// TWAP 观察点（每个 token 对、每个时间戳一个 PDA）
+/// seeds = [b"twap", pool, mint_a, mint_b, timestamp.to_le_bytes()]
+///
+/// 记录某一时刻 token 对的累计对数价格，query_twap 用它与当前累计值计算这段时间的 TWAP
+#[account]
+#[derive(Debug)]
+pub struct TwapObservation {
+    /// 所属 pool
+    pub pool: Pubkey,
+    /// 基础 token 的 mint
+    pub mint_a: Pubkey,
+    /// 计价 token 的 mint
+    pub mint_b: Pubkey,
+    /// 观察时间戳（秒），同时作为 PDA seed
+    pub timestamp: i64,
+    /// 观察时的累计对数价格
+    pub log_price_cumulative: I256,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl TwapObservation {
+    /// 计算账户所需的空间大小
+    pub fn space() -> usize {
+        8 + // discriminator
+        32 + // pool
+        32 + // mint_a
+        32 + // mint_b
+        8 + // timestamp
+        32 + // log_price_cumulative
+        1 // bump
+    }
+}
+
+/// ln(1 单位 token_a 值多少 token_b)，18 位定点数
+///
+/// 价格低于 18 位定点数的最小单位时按最小单位计，避免极端储备下 ln(0) 让 swap 失败
+pub fn log_spot_price(balance_a: u64, weight_a: u64, balance_b: u64, weight_b: u64) -> Result<I256> {
+    let price = spot_price(balance_b, weight_b, balance_a, weight_a)?.max(1);
+    Ok(LogExpMath::ln(I256::try_from(price)?)?)
+}
+
+/// 根据两次累计对数价格计算时间加权平均价格（18 位定点数）
+/// twap = exp((after - before) / elapsed_seconds)
+pub fn twap_from_cumulatives(before: I256, after: I256, elapsed_seconds: i64) -> Result<u128> {
+    require!(elapsed_seconds > 0, ErrorCode::InvalidTwapObservation);
+    let average = (after - before).checked_div(&I256::from(elapsed_seconds as i128))?;
+    let price = LogExpMath::exp(average)?;
+    u128::try_from(price.to_u256()?).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+impl AnySwapPool {
+    /// 获取 TWAP 槽位
+    pub fn get_twap_pair(&self, slot: usize) -> Result<&TwapPair> {
+        require!(slot < MAX_TWAP_PAIRS, ErrorCode::InvalidTwapPair);
+        Ok(&self.twap_pairs[slot])
+    }
+
+    /// 查找跟踪 (mint_a, mint_b) 的槽位，方向必须与配置时一致
+    pub fn find_twap_pair(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Option<usize> {
+        self.twap_pairs
+            .iter()
+            .position(|pair| !pair.is_empty() && pair.mint_a == *mint_a && pair.mint_b == *mint_b)
+    }
+
+    /// 管理员配置 TWAP 槽位（仅管理员）
+    ///
+    /// log_price 为当前的 ln(price)，累加器从 0 开始；mint_a 为默认公钥时清空该槽位。
+    /// 修改前先把其他槽位累加到 now，保证它们的累计值不受影响
+    pub fn set_twap_pair(
+        &mut self,
+        admin: &Pubkey,
+        slot: usize,
+        mint_a: &Pubkey,
+        mint_b: &Pubkey,
+        log_price: I256,
+        now: i64,
+    ) -> Result<()> {
+        self.verify_admin(admin)?;
+        require!(slot < MAX_TWAP_PAIRS, ErrorCode::InvalidTwapPair);
+        self.accumulate_twap(now)?;
+
+        if *mint_a == Pubkey::default() {
+            self.twap_pairs[slot] = bytemuck::Zeroable::zeroed();
+            return Ok(());
+        }
+        require!(mint_a != mint_b, ErrorCode::InvalidTwapPair);
+        require!(
+            self.find_token_index(mint_a).is_some() && self.find_token_index(mint_b).is_some(),
+            ErrorCode::InvalidTokenMint
+        );
+        require!(
+            self.find_twap_pair(mint_a, mint_b).is_none_or(|existing| existing == slot),
+            ErrorCode::InvalidTwapPair
+        );
+
+        let pair = &mut self.twap_pairs[slot];
+        pair.mint_a = *mint_a;
+        pair.mint_b = *mint_b;
+        pair.set_log_price_cumulative(I256::ZERO);
+        pair.set_last_log_price(log_price);
+        Ok(())
+    }
+
+    /// 按最近一次观察到的价格把所有槽位的累加器推进到 now
+    pub fn accumulate_twap(&mut self, now: i64) -> Result<()> {
+        if now <= self.twap_last_update_ts {
+            return Ok(());
+        }
+        let elapsed = I256::from((now - self.twap_last_update_ts) as i128);
+        for pair in self.twap_pairs.iter_mut().filter(|pair| !pair.is_empty()) {
+            let delta = pair.get_last_log_price().checked_mul(&elapsed)?;
+            pair.set_log_price_cumulative(pair.get_log_price_cumulative() + delta);
+        }
+        self.twap_last_update_ts = now;
+        Ok(())
+    }
+
+    /// 用 swap 后的储备刷新槽位的最近价格
+    ///
+    /// 只有两个 token 都参与本次 swap 的槽位会刷新（swap 只传入参与的 vault）；
+    /// 两 token pool 中每次 swap 都会刷新。只有一边参与交易时，该槽位沿用上一次的价格，
+    /// 直到下一次同时涉及两个 token 的 swap
+    pub fn observe_twap_prices(&mut self, mints: &[Pubkey], balances: &[u64], weights: &[u64]) -> Result<()> {
+        for pair in self.twap_pairs.iter_mut().filter(|pair| !pair.is_empty()) {
+            let index_a = mints.iter().position(|mint| *mint == pair.mint_a);
+            let index_b = mints.iter().position(|mint| *mint == pair.mint_b);
+            if let (Some(a), Some(b)) = (index_a, index_b) {
+                pair.set_last_log_price(log_spot_price(balances[a], weights[a], balances[b], weights[b])?);
+            }
+        }
+        Ok(())
+    }
+
+    /// swap 时更新 TWAP：先按 swap 前的价格累加经过的时间，再记录 swap 后的价格
+    /// vaults_before 为 swap 前参与 token 的 vault 余额，amounts 为实际转入 / 转出的数量
+    pub fn record_twap(
+        &mut self,
+        now: i64,
+        mints: &[Pubkey],
+        is_in_token: &[bool],
+        vaults_before: &[u64],
+        amounts: &[u64],
+        weights: &[u64],
+    ) -> Result<()> {
+        self.accumulate_twap(now)?;
+        if self.twap_pairs.iter().all(|pair| pair.is_empty()) {
+            return Ok(());
+        }
+        let vaults_after = vaults_before
+            .iter()
+            .zip(is_in_token.iter().zip(amounts.iter()))
+            .map(|(&vault, (&is_in, &amount))| {
+                if is_in {
+                    vault.saturating_add(amount)
+                } else {
+                    vault.saturating_sub(amount)
+                }
+            })
+            .collect::<Vec<u64>>();
+        self.observe_twap_prices(mints, &vaults_after, weights)
+    }
+
+    /// 槽位在 now 时的累计对数价格（按最近价格外推到 now，不修改状态）
+    pub fn twap_cumulative_at(&self, slot: usize, now: i64) -> Result<I256> {
+        let pair = self.get_twap_pair(slot)?;
+        require!(!pair.is_empty(), ErrorCode::InvalidTwapPair);
+        let elapsed = now.saturating_sub(self.twap_last_update_ts).max(0);
+        let delta = pair
+            .get_last_log_price()
+            .checked_mul(&I256::from(elapsed as i128))?;
+        Ok(pair.get_log_price_cumulative() + delta)
+    }
+
+    /// 查询 (mint_a, mint_b) 从 since_ts 到 now 的时间加权平均价格（18 位定点数）
+    /// since_cumulative 为 since_ts 时记录的累计对数价格（见 TwapObservation）
+    pub fn query_twap(
+        &self,
+        mint_a: &Pubkey,
+        mint_b: &Pubkey,
+        since_ts: i64,
+        since_cumulative: I256,
+        now: i64,
+    ) -> Result<u128> {
+        let slot = self
+            .find_twap_pair(mint_a, mint_b)
+            .ok_or(ErrorCode::InvalidTwapPair)?;
+        let cumulative = self.twap_cumulative_at(slot, now)?;
+        let elapsed = now
+            .checked_sub(since_ts)
+            .ok_or(ErrorCode::MathOverflow)?;
+        twap_from_cumulatives(since_cumulative, cumulative, elapsed)
+    }
+
+    /// 移除 token 时清空涉及该 token 的槽位
+    pub fn clear_twap_pairs_for(&mut self, mint: &Pubkey) {
+        for pair in self.twap_pairs.iter_mut() {
+            if pair.mint_a == *mint || pair.mint_b == *mint {
+                *pair = bytemuck::Zeroable::zeroed();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::logexpmath::ONE_18;
+
+    const ONE: u128 = 1_000_000_000_000_000_000;
+
+    fn two_token_pool(admin: &Pubkey) -> (Box<AnySwapPool>, Pubkey, Pubkey) {
+        let mut pool: Box<AnySwapPool> = Box::new(bytemuck::Zeroable::zeroed());
+        pool.admin = *admin;
+        let mint_a = Pubkey::new_unique();
+        let mint_b = Pubkey::new_unique();
+        pool.add_token(&mint_a, &Pubkey::new_unique(), 50).unwrap();
+        pool.add_token(&mint_b, &Pubkey::new_unique(), 50).unwrap();
+        (pool, mint_a, mint_b)
+    }
+
+    #[test]
+    fn test_twap_two_token_pool() {
+        let admin = Pubkey::new_unique();
+        let (mut pool, mint_a, mint_b) = two_token_pool(&admin);
+        let weights = [50, 50];
+
+        // t=1000：储备 1000 A / 2000 B，1 A = 2 B
+        let start = 1_000;
+        let log_price = log_spot_price(1_000_000, 50, 2_000_000, 50).unwrap();
+        pool.set_twap_pair(&admin, 0, &mint_a, &mint_b, log_price, start).unwrap();
+        let since_cumulative = pool.twap_cumulative_at(0, start).unwrap();
+        assert_eq!(since_cumulative, I256::ZERO);
+
+        // 100 秒后的 swap 把价格变为 1 A = 8 B：500_000 A / 4_000_000 B
+        pool.record_twap(
+            start + 100,
+            &[mint_b, mint_a],
+            &[true, false],
+            &[2_000_000, 1_000_000],
+            &[2_000_000, 500_000],
+            &weights,
+        )
+        .unwrap();
+        let spot = pool.query_twap(&mint_a, &mint_b, start, since_cumulative, start + 100).unwrap();
+        assert!(spot.abs_diff(2 * ONE) < ONE / 1_000_000, "twap {}", spot);
+
+        // 再过 100 秒：TWAP 为 2 和 8 的几何平均 4
+        let twap = pool.query_twap(&mint_a, &mint_b, start, since_cumulative, start + 200).unwrap();
+        assert!(twap.abs_diff(4 * ONE) < ONE / 1_000_000, "twap {}", twap);
+
+        // 只看后 100 秒：8
+        let mid_cumulative = pool.twap_cumulative_at(0, start + 100).unwrap();
+        let recent = pool.query_twap(&mint_a, &mint_b, start + 100, mid_cumulative, start + 200).unwrap();
+        assert!(recent.abs_diff(8 * ONE) < ONE / 1_000_000, "twap {}", recent);
+
+        // 反向的 token 对没有被跟踪
+        assert!(pool.query_twap(&mint_b, &mint_a, start, since_cumulative, start + 200).is_err());
+        // 没有经过时间时无法计算平均
+        assert!(pool.query_twap(&mint_a, &mint_b, start + 200, since_cumulative, start + 200).is_err());
+
+        // 外推不修改状态
+        assert_eq!(pool.twap_last_update_ts, start + 100);
+    }
+
+    #[test]
+    fn test_set_twap_pair_validation() {
+        let admin = Pubkey::new_unique();
+        let (mut pool, mint_a, mint_b) = two_token_pool(&admin);
+
+        assert!(pool
+            .set_twap_pair(&Pubkey::new_unique(), 0, &mint_a, &mint_b, ONE_18, 0)
+            .is_err());
+        assert!(pool.set_twap_pair(&admin, MAX_TWAP_PAIRS, &mint_a, &mint_b, ONE_18, 0).is_err());
+        assert!(pool.set_twap_pair(&admin, 0, &mint_a, &mint_a, ONE_18, 0).is_err());
+        assert!(pool
+            .set_twap_pair(&admin, 0, &mint_a, &Pubkey::new_unique(), ONE_18, 0)
+            .is_err());
+
+        pool.set_twap_pair(&admin, 0, &mint_a, &mint_b, ONE_18, 0).unwrap();
+        // 同一 token 对不能占用两个槽位
+        assert!(pool.set_twap_pair(&admin, 1, &mint_a, &mint_b, ONE_18, 0).is_err());
+
+        // 移除 token 后槽位被清空
+        pool.remove_token(1).unwrap();
+        assert!(pool.get_twap_pair(0).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+
+/// 功能位：StableSwap 稳定组（set_stable_group）
+pub const FEATURE_STABLE_SWAP: u64 = 1 << 0;
+/// 功能位：JIT 流动性（swap_with_jit）
+pub const FEATURE_JIT_LIQUIDITY: u64 = 1 << 1;
+/// 功能位：闪电贷（flash_loan）
+pub const FEATURE_FLASH_LOAN: u64 = 1 << 2;
+/// 功能位：TWAP 预言机（set_twap_pair / query_twap）
+pub const FEATURE_TWAP: u64 = 1 << 3;
+/// 功能位：Token-2022 mint（保留，当前版本不支持）
+pub const FEATURE_TOKEN_2022: u64 = 1 << 8;
+/// 功能位：协议手续费（set_protocol_fee / collect_protocol_fees）
+pub const FEATURE_PROTOCOL_FEES: u64 = 1 << 9;
+/// 功能位：LBP 权重渐变（start_weight_ramp）
+pub const FEATURE_LBP: u64 = 1 << 10;
+
+/// 程序版本和支持的功能（通过 return data 返回）
+///
+/// 版本号取自 Cargo.toml 的 package.version；客户端按 features 中的 FEATURE_* 位
+/// 判断部署的程序是否支持某个功能，不支持时降级处理
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgramVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    /// FEATURE_* 位的组合
+    pub features: u64,
+}
+
+impl ProgramVersion {
+    /// 当前编译的程序版本
+    pub fn current() -> Self {
+        Self {
+            major: parse_version_part(env!("CARGO_PKG_VERSION_MAJOR")),
+            minor: parse_version_part(env!("CARGO_PKG_VERSION_MINOR")),
+            patch: parse_version_part(env!("CARGO_PKG_VERSION_PATCH")),
+            features: enabled_features(),
+        }
+    }
+
+    /// 是否支持 feature 中的所有功能位
+    pub fn supports(&self, feature: u64) -> bool {
+        self.features & feature == feature
+    }
+}
+
+/// 编译进程序的功能位
+///
+/// 内置功能始终启用；保留位在对应功能实现后由其 cargo feature 打开
+pub const fn enabled_features() -> u64 {
+    FEATURE_STABLE_SWAP
+        | FEATURE_JIT_LIQUIDITY
+        | FEATURE_FLASH_LOAN
+        | FEATURE_TWAP
+        | FEATURE_PROTOCOL_FEES
+        | FEATURE_LBP
+}
+
+fn parse_version_part(part: &str) -> u16 {
+    part.parse().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_version_matches_package() {
+        let version = ProgramVersion::current();
+        let expected = format!("{}.{}.{}", version.major, version.minor, version.patch);
+        assert_eq!(expected, env!("CARGO_PKG_VERSION"));
+
+        // 内置功能都在位掩码中，保留的功能位未启用
+        assert!(version.supports(FEATURE_STABLE_SWAP | FEATURE_JIT_LIQUIDITY));
+        assert!(version.supports(FEATURE_FLASH_LOAN));
+        assert!(version.supports(FEATURE_TWAP));
+        assert!(version.supports(FEATURE_PROTOCOL_FEES));
+        assert!(!version.supports(FEATURE_TOKEN_2022));
+        assert!(version.supports(FEATURE_LBP));
+        assert_eq!(version.features, enabled_features());
+    }
+}
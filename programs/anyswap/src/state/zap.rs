@@ -0,0 +1,369 @@
+use anchor_lang::prelude::*;
+use super::fees::protocol_share;
+use super::liquidity::{add_liquidity_inner, remove_liquidity_inner};
+use super::swap::swap_inner;
+use crate::error::ErrorCode;
+
+/// zap_in 的结果
+#[derive(Debug)]
+pub struct ZapInResult {
+    /// 铸造的 LP 数量
+    pub lp_minted: u64,
+    /// 从用户拿走的输入 token 数量（内部 swap 的输入 + 加入流动性使用的部分）
+    pub amount_in_used: u64,
+    /// 内部 swap 得到但加入流动性时没有用完、需要退还给用户的数量（输入 token 为 0）
+    pub refunds: Vec<u64>,
+    /// 每个 token 留在 vault 中的手续费（内部 swap 的手续费 + 加入流动性的手续费，含协议费）
+    pub fees: Vec<u64>,
+    /// fees 中归协议的部分（只来自内部 swap），计提后不计入 LP 的储备
+    pub protocol_fees: Vec<u64>,
+}
+
+/// 单 token 加入流动性（zap in）
+///
+/// 按权重将输入拆分到 pool 中的每个 token：加权 pool 中 token j 的价值占比恒为 w_j / sum(w)，
+/// 因此按权重拆分即可匹配加入流动性所需的比例（忽略价格冲击）。
+/// 1. 对每个其他 token j，将 amount_in * w_j / sum(w) 通过内部 swap 换成 token j（收取 swap 手续费，手续费留在池中）
+/// 2. 用剩余的输入 token 和换得的 token 按比例加入流动性
+///
+/// 内部 swap 得到的 token 不离开 vault，加入流动性时没有用完的部分退还给用户。
+/// 内部 swap 手续费中归协议的部分与普通 swap 一样计提，不计入加入流动性时的储备
+///
+/// Args:
+///     input_index: 输入 token 在 pool 中的索引
+///     amount_in: 输入 token 的数量
+///     token_vaults_amount: 当前储备列表
+///     weights: 权重列表
+///     total_lp_supply: 当前LP token总供应量
+///     fee_numerator: 费率分子
+///     fee_denominator: 费率分母
+///     protocol_fee_rate: 协议费比例 (分子, 分母)，见 AnySwapPool::protocol_fee_rate
+#[allow(clippy::too_many_arguments)]
+pub fn zap_in_inner(
+    input_index: usize,
+    amount_in: u64,
+    token_vaults_amount: &[u64],
+    weights: &[u64],
+    total_lp_supply: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    protocol_fee_rate: (u64, u64),
+) -> Result<ZapInResult> {
+    let token_count = token_vaults_amount.len();
+    require!(weights.len() == token_count, ErrorCode::InvalidTokenCount);
+    require!(input_index < token_count, ErrorCode::InvalidTokenIndex);
+    require!(amount_in > 0, ErrorCode::InsufficientTokenAmount);
+    // 首次添加流动性需要所有 token，不能通过 zap 完成
+    require!(total_lp_supply > 0, ErrorCode::InsufficientLiquidity);
+
+    let total_weight: u128 = weights.iter().map(|&w| w as u128).sum();
+    require!(total_weight > 0, ErrorCode::InvalidTokenCount);
+
+    let mut vaults = token_vaults_amount.to_vec();
+    let mut amounts_join = vec![0u64; token_count];
+    let mut fees = vec![0u64; token_count];
+    let mut protocol_fees = vec![0u64; token_count];
+    let mut swapped_in = 0u64;
+
+    // 内部 swap：输入 token -> 其他每个 token
+    for j in 0..token_count {
+        if j == input_index {
+            continue;
+        }
+        let portion = (amount_in as u128 * weights[j] as u128 / total_weight) as u64;
+        if portion == 0 {
+            continue;
+        }
+        let result = swap_inner(
+            &[true, false],
+            &[portion, 0],
+            &[portion, 0],
+            &[vaults[input_index], vaults[j]],
+            &[weights[input_index], weights[j]],
+            fee_numerator,
+            fee_denominator,
+        )?;
+        // 手续费随输入一起留在池中，协议费部分不计入 LP 的储备
+        let protocol = protocol_share(result.burn_fees[0], protocol_fee_rate.0, protocol_fee_rate.1);
+        vaults[input_index] = vaults[input_index]
+            .checked_add(portion - protocol)
+            .ok_or(ErrorCode::MathOverflow)?;
+        vaults[j] = vaults[j]
+            .checked_sub(result.amounts[1])
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+        amounts_join[j] = result.amounts[1];
+        fees[input_index] += result.burn_fees[0];
+        protocol_fees[input_index] += protocol;
+        swapped_in += portion;
+    }
+    amounts_join[input_index] = amount_in - swapped_in;
+
+    // 用拆分后的数量加入流动性
+    let join = add_liquidity_inner(
+        &vaults,
+        &amounts_join,
+        total_lp_supply,
+        fee_numerator,
+        fee_denominator,
+    )?;
+
+    let mut refunds = vec![0u64; token_count];
+    for j in 0..token_count {
+        // 加入流动性时多收的部分（amounts_used - amounts_in）是手续费
+        let used = join.amounts_used[j].min(amounts_join[j]);
+        fees[j] += used.saturating_sub(join.amounts_in[j]);
+        if j == input_index {
+            continue;
+        }
+        refunds[j] = amounts_join[j].saturating_sub(join.amounts_used[j]);
+    }
+    let amount_in_used = swapped_in
+        .checked_add(join.amounts_used[input_index].min(amounts_join[input_index]))
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(ZapInResult {
+        lp_minted: join.lp_minted,
+        amount_in_used,
+        refunds,
+        fees,
+        protocol_fees,
+    })
+}
+
+/// zap_out 的结果
+#[derive(Debug)]
+pub struct ZapOutResult {
+    /// 发给用户的输出 token 数量
+    pub amount_out: u64,
+    /// 按比例移除流动性时每个 token 的数量（扣费后）
+    pub amounts_removed: Vec<u64>,
+    /// 每个 token 留在 vault 中的手续费（移除流动性的手续费 + 内部 swap 的手续费，含协议费）
+    pub fees: Vec<u64>,
+    /// fees 中归协议的部分（只来自内部 swap），计提后不计入 LP 的储备
+    pub protocol_fees: Vec<u64>,
+}
+
+/// 单 token 移除流动性（zap out）
+///
+/// 1. 销毁 LP，按比例移除所有 token（收取移除流动性的手续费）
+/// 2. 将除输出 token 外的每个 token 通过内部 swap 换成输出 token（收取 swap 手续费，手续费留在池中）
+///
+/// 内部 swap 的输入就是刚移除的 token，它们不离开 vault，因此最终只有输出 token 从 vault 转出。
+/// 内部 swap 手续费中归协议的部分与普通 swap 一样计提
+///
+/// Args:
+///     output_index: 输出 token 在 pool 中的索引
+///     lp_to_burn: 要销毁的LP token数量
+///     token_vaults_amount: 当前储备列表
+///     weights: 权重列表
+///     total_lp_supply: 当前LP token总供应量
+///     fee_numerator: 费率分子
+///     fee_denominator: 费率分母
+///     protocol_fee_rate: 协议费比例 (分子, 分母)，见 AnySwapPool::protocol_fee_rate
+#[allow(clippy::too_many_arguments)]
+pub fn zap_out_inner(
+    output_index: usize,
+    lp_to_burn: u64,
+    token_vaults_amount: &[u64],
+    weights: &[u64],
+    total_lp_supply: u128,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    protocol_fee_rate: (u64, u64),
+) -> Result<ZapOutResult> {
+    let token_count = token_vaults_amount.len();
+    require!(weights.len() == token_count, ErrorCode::InvalidTokenCount);
+    require!(output_index < token_count, ErrorCode::InvalidTokenIndex);
+
+    let removed = remove_liquidity_inner(
+        token_vaults_amount,
+        lp_to_burn,
+        total_lp_supply,
+        fee_numerator,
+        fee_denominator,
+    )?;
+
+    // 移除后的储备
+    let mut vaults = token_vaults_amount.to_vec();
+    for (vault, &out) in vaults.iter_mut().zip(removed.amounts_out.iter()) {
+        *vault = vault.checked_sub(out).ok_or(ErrorCode::InsufficientLiquidity)?;
+    }
+
+    let mut amount_out = removed.amounts_out[output_index];
+    let mut fees = removed.burn_fees.clone();
+    let mut protocol_fees = vec![0u64; token_count];
+
+    // 内部 swap：其他每个 token -> 输出 token
+    for j in 0..token_count {
+        let amount = removed.amounts_out[j];
+        if j == output_index || amount == 0 {
+            continue;
+        }
+        let result = swap_inner(
+            &[true, false],
+            &[amount, 0],
+            &[amount, 0],
+            &[vaults[j], vaults[output_index]],
+            &[weights[j], weights[output_index]],
+            fee_numerator,
+            fee_denominator,
+        )?;
+        // 手续费随输入一起留在池中，协议费部分不计入 LP 的储备
+        let protocol = protocol_share(result.burn_fees[0], protocol_fee_rate.0, protocol_fee_rate.1);
+        fees[j] += result.burn_fees[0];
+        protocol_fees[j] = protocol;
+        vaults[j] = vaults[j]
+            .checked_add(amount - protocol)
+            .ok_or(ErrorCode::MathOverflow)?;
+        vaults[output_index] = vaults[output_index]
+            .checked_sub(result.amounts[1])
+            .ok_or(ErrorCode::InsufficientLiquidity)?;
+        amount_out = amount_out
+            .checked_add(result.amounts[1])
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    Ok(ZapOutResult {
+        amount_out,
+        amounts_removed: removed.amounts_out,
+        fees,
+        protocol_fees,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 单 token 加入的理论最优值（Balancer 单边加入公式，不含手续费）：
+    /// LP = S * ((1 + a / B_k) ^ (w_k / W) - 1)
+    fn optimal_single_join(amount_in: u64, vault: u64, weight: u64, total_weight: u64, supply: u128) -> f64 {
+        let ratio = 1.0 + amount_in as f64 / vault as f64;
+        supply as f64 * (ratio.powf(weight as f64 / total_weight as f64) - 1.0)
+    }
+
+    #[test]
+    fn test_zap_in_close_to_optimal() {
+        let vaults = [10_000_000_000u64, 20_000_000_000u64, 30_000_000_000u64];
+        let weights = [50u64, 30u64, 20u64];
+        let supply = 10_000_000_000u128;
+        let amount_in = 100_000_000u64;
+
+        let result = zap_in_inner(0, amount_in, &vaults, &weights, supply, 0, 1000, (0, 1)).unwrap();
+        let optimal = optimal_single_join(amount_in, vaults[0], weights[0], 100, supply);
+
+        // 按权重拆分只差内部 swap 价格冲击带来的微小偏差（输入占池子 1%）
+        let diff = (result.lp_minted as f64 - optimal).abs() / optimal;
+        assert!(diff < 0.005, "lp_minted: {}, optimal: {}", result.lp_minted, optimal);
+        assert!(result.lp_minted as f64 <= optimal * 1.0001);
+        assert!(result.amount_in_used <= amount_in);
+        assert_eq!(result.refunds[0], 0);
+
+        // 收取手续费时 LP 更少
+        let with_fee = zap_in_inner(0, amount_in, &vaults, &weights, supply, 3, 1000, (0, 1)).unwrap();
+        assert!(with_fee.lp_minted < result.lp_minted);
+        assert!((result.lp_minted - with_fee.lp_minted) as f64 / (result.lp_minted as f64) < 0.01);
+    }
+
+    #[test]
+    fn test_zap_in_requires_liquidity() {
+        let vaults = [1_000u64, 1_000u64];
+        let weights = [50u64, 50u64];
+        assert!(zap_in_inner(0, 100, &vaults, &weights, 0, 3, 1000, (0, 1)).is_err());
+        assert!(zap_in_inner(2, 100, &vaults, &weights, 1_000, 3, 1000, (0, 1)).is_err());
+        assert!(zap_in_inner(0, 0, &vaults, &weights, 1_000, 3, 1000, (0, 1)).is_err());
+    }
+
+    #[test]
+    fn test_zap_out_matches_exit_then_swap() {
+        let vaults = [10_000_000_000u64, 20_000_000_000u64, 30_000_000_000u64];
+        let weights = [50u64, 30u64, 20u64];
+        let supply = 10_000_000_000u128;
+        let lp_to_burn = 100_000_000u64;
+
+        let result = zap_out_inner(2, lp_to_burn, &vaults, &weights, supply, 3, 1000, (0, 1)).unwrap();
+
+        // 手动：先按比例退出，再逐个 swap 成 token 2
+        let exit = remove_liquidity_inner(&vaults, lp_to_burn, supply, 3, 1000).unwrap();
+        let mut pool = vaults.to_vec();
+        for (v, &out) in pool.iter_mut().zip(exit.amounts_out.iter()) {
+            *v -= out;
+        }
+        let mut manual = exit.amounts_out[2];
+        for j in 0..2 {
+            let amount = exit.amounts_out[j];
+            let swap = swap_inner(
+                &[true, false],
+                &[amount, 0],
+                &[amount, 0],
+                &[pool[j], pool[2]],
+                &[weights[j], weights[2]],
+                3,
+                1000,
+            )
+            .unwrap();
+            pool[j] += amount;
+            pool[2] -= swap.amounts[1];
+            manual += swap.amounts[1];
+        }
+        assert_eq!(result.amount_out, manual);
+        assert_eq!(result.amounts_removed, exit.amounts_out);
+
+        // 与不收手续费的结果相比，差距在手续费范围内（退出 0.3% + swap 0.3%，另加价格冲击）
+        let no_fee = zap_out_inner(2, lp_to_burn, &vaults, &weights, supply, 0, 1000, (0, 1)).unwrap();
+        assert!(result.amount_out < no_fee.amount_out);
+        let diff = (no_fee.amount_out - result.amount_out) as f64 / no_fee.amount_out as f64;
+        assert!(diff < 0.006, "diff: {}", diff);
+
+        // 全部价值按现价折算成 token 2：LP 占 1%，token 2 的价值占比为 20%，即 token 2 储备的 5%
+        // 内部 swap 有价格冲击，实际略少
+        let expected = vaults[2] as f64 * 0.01 * 100.0 / 20.0;
+        assert!((no_fee.amount_out as f64) < expected);
+        assert!((expected - no_fee.amount_out as f64) / expected < 0.03);
+    }
+
+    #[test]
+    fn test_zap_accrues_protocol_fee() {
+        use crate::state::AnySwapPool;
+
+        let vaults = [10_000_000_000u64, 20_000_000_000u64, 30_000_000_000u64];
+        let weights = [50u64, 30u64, 20u64];
+        let supply = 10_000_000_000u128;
+
+        // zap in：内部 swap 的手续费都记在输入 token 上，协议拿 1/6
+        let amount_in = 100_000_000u64;
+        let lp_only = zap_in_inner(0, amount_in, &vaults, &weights, supply, 3, 1000, (0, 1)).unwrap();
+        let result = zap_in_inner(0, amount_in, &vaults, &weights, supply, 3, 1000, (1, 6)).unwrap();
+        assert_eq!(lp_only.protocol_fees, vec![0u64, 0, 0]);
+        // 拆分：30M 换 token 1、20M 换 token 2，手续费 90_000 + 60_000，协议各拿 1/6
+        assert_eq!(result.protocol_fees, vec![25_000u64, 0, 0]);
+        assert_eq!(result.fees[0], lp_only.fees[0]);
+        // 协议费不计入加入流动性时的储备，只轻微改变输入 token 的储备
+        let diff = (result.lp_minted as i128 - lp_only.lp_minted as i128).unsigned_abs();
+        assert!(diff * 10_000 < lp_only.lp_minted as u128, "diff: {}", diff);
+
+        // zap out：内部 swap 的输入是非输出 token，输出不受协议费影响
+        let lp_to_burn = 100_000_000u64;
+        let lp_only = zap_out_inner(2, lp_to_burn, &vaults, &weights, supply, 3, 1000, (0, 1)).unwrap();
+        let result = zap_out_inner(2, lp_to_burn, &vaults, &weights, supply, 3, 1000, (1, 6)).unwrap();
+        assert_eq!(result.amount_out, lp_only.amount_out);
+        assert_eq!(result.fees, lp_only.fees);
+        assert!(result.protocol_fees[0] > 0 && result.protocol_fees[1] > 0);
+        assert_eq!(result.protocol_fees[2], 0);
+
+        // 计提到 pool 后不计入 LP 的储备
+        let admin = Pubkey::new_unique();
+        let mut pool: Box<AnySwapPool> = Box::new(bytemuck::Zeroable::zeroed());
+        pool.admin = admin;
+        for &weight in weights.iter() {
+            pool.add_token(&Pubkey::new_unique(), &Pubkey::new_unique(), weight).unwrap();
+        }
+        pool.accrue_protocol_fees_in_order(&result.protocol_fees).unwrap();
+        for (i, &fee) in result.protocol_fees.iter().enumerate() {
+            let token = pool.get_token_checked(i).unwrap();
+            assert_eq!(token.get_accrued_protocol_fee(), fee);
+            assert_eq!(token.lp_balance(vaults[i] + fee), vaults[i]);
+        }
+        assert!(pool.accrue_protocol_fees_in_order(&result.protocol_fees[..2]).is_err());
+    }
+}
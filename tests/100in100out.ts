@@ -67,7 +67,7 @@ describe("100in100out - 极限性能测试", () => {
     );
 
     await program.methods
-      .createPool(new anchor.BN(FEE_NUMERATOR), new anchor.BN(FEE_DENOMINATOR))
+      .createPool(new anchor.BN(FEE_NUMERATOR), new anchor.BN(FEE_DENOMINATOR), false, false, null)
       .accounts({
         poolCreator: poolCreator.publicKey,
         pool: pool,
@@ -84,6 +84,13 @@ describe("100in100out - 极限性能测试", () => {
 
     console.log(`✅ Pool创建成功: ${pool.toString()}`);
 
+    // 250 个 token 加入流动性、200 个 token 参与 swap，超过默认的 max_tokens_per_swap（8），显式关闭上限
+    await program.methods
+      .modifyMaxTokensPerSwap(0)
+      .accountsPartial({ pool, admin: poolCreator.publicKey })
+      .signers([poolCreator])
+      .rpc();
+
     // 3. 创建250个tokens和vaults
     console.log("\n创建250个tokens和vaults（这可能需要几分钟）...");
     
@@ -182,7 +189,7 @@ describe("100in100out - 极限性能测试", () => {
 
     // 添加流动性（可能需要大量CU）
     const addLiquidityTx = await program.methods
-      .addLiquidity(liquidityAmounts.map(a => new anchor.BN(a)))
+      .addLiquidity(liquidityAmounts.map(a => new anchor.BN(a)), new anchor.BN(0))
       .accounts({
         pool: pool,
         poolMint: poolMint,
@@ -190,6 +197,7 @@ describe("100in100out - 极限性能测试", () => {
         lpTokenAccount: userLpAccount,
         owner: user.publicKey,
         tokenProgram: token.TOKEN_PROGRAM_ID,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
       })
       .remainingAccounts(liquidityRemainingAccounts)
       .preInstructions([
@@ -261,7 +269,7 @@ describe("100in100out - 极限性能测试", () => {
         console.log(`\n尝试 CU 限制: ${cuLimit.toLocaleString()}...`);
         
         const tx = await program.methods
-          .swapAnyswap(amounts_tolerance, is_in_token)
+          .swapAnyswap(amounts_tolerance, is_in_token, null, null)
           .accountsPartial({
             pool: pool,
             poolAuthority: poolAuthorityPda,
@@ -63,6 +63,13 @@ describe("10in10out - CU消耗测试", () => {
 
         console.log(`✅ Pool创建成功: ${pool.toString()}`);
 
+        // 20 个 token 参与 swap，超过默认的 max_tokens_per_swap（8），显式关闭上限
+        await program.methods
+            .modifyMaxTokensPerSwap(0)
+            .accountsPartial({ pool, admin: poolCreator.publicKey })
+            .signers([poolCreator])
+            .rpc();
+
         // 3. 创建30个mints
         console.log("\n创建30个mints...");
 
@@ -126,12 +133,16 @@ describe("10in10out - CU消耗测试", () => {
             );
 
             await program.methods
-                .addTokenToPool(new anchor.BN(TOKEN_WEIGHT), new anchor.BN(INITIAL_LIQUIDITY))
+                .addTokenToPool(new anchor.BN(TOKEN_WEIGHT), new anchor.BN(INITIAL_LIQUIDITY), 0, new anchor.BN(0))
                 .accountsPartial({
                     pool: pool,
+                    poolMint: poolMint,
+                    lpTokenProgram: token.TOKEN_PROGRAM_ID,
                     mint: mints[i],
                     vault: vault,
                     adminToken: adminTokenAccounts[i],
+                    adminLpToken: null,
+                    quoteVault: null,
                     admin: poolCreator.publicKey,
                     payer: poolCreator.publicKey,
                     associatedTokenProgram: token.ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -240,7 +251,7 @@ describe("10in10out - CU消耗测试", () => {
                 console.log(`\n尝试 CU 限制: ${cuLimit.toLocaleString()}...`);
 
                 const tx = await program.methods
-                    .swapAnyswap(amounts_tolerance, is_in_token)
+                    .swapAnyswap(amounts_tolerance, is_in_token, null, null)
                     .accountsPartial({
                         pool: pool,
                         owner: user.publicKey,
@@ -120,12 +120,16 @@ describe("3in3out - CU消耗测试", () => {
             );
 
             await program.methods
-                .addTokenToPool(new anchor.BN(TOKEN_WEIGHT), new anchor.BN(INITIAL_LIQUIDITY))
+                .addTokenToPool(new anchor.BN(TOKEN_WEIGHT), new anchor.BN(INITIAL_LIQUIDITY), 0, new anchor.BN(0))
                 .accountsPartial({
                     pool: pool,
+                    poolMint: poolMint,
+                    lpTokenProgram: token.TOKEN_PROGRAM_ID,
                     mint: mints[i],
                     vault: vault,
                     adminToken: adminTokenAccounts[i],
+                    adminLpToken: null,
+                    quoteVault: null,
                     admin: poolCreator.publicKey,
                     payer: poolCreator.publicKey,
                     associatedTokenProgram: token.ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -133,6 +137,10 @@ describe("3in3out - CU消耗测试", () => {
                 .signers([poolCreator])
                 .rpc();
 
+            // 初始流动性全部转入 vault
+            const vaultAccount = await token.getAccount(connection, vault);
+            expect(Number(vaultAccount.amount)).to.equal(INITIAL_LIQUIDITY);
+
             vaults.push(vault);
         }
 
@@ -240,7 +248,7 @@ describe("3in3out - CU消耗测试", () => {
                 console.log(`\n尝试 CU 限制: ${cuLimit.toLocaleString()}...`);
 
                 const tx = await program.methods
-                    .swapAnyswap(amounts_tolerance, is_in_token)
+                    .swapAnyswap(amounts_tolerance, is_in_token, null, null)
                     .accountsPartial({
                         pool: pool,
                         poolAuthority: poolAuthorityPda,
@@ -302,5 +310,41 @@ describe("3in3out - CU消耗测试", () => {
         console.log(`\n✅ 3 in, 3 out swap测试通过！`);
         console.log(`✅ 最低需要 CU: ${successfulCuLimit.toLocaleString()}`);
     });
+
+    it("默认 max_tokens_per_swap 为 8：9 个 token 的 swap 被拒绝", async () => {
+        // 5 进 4 出，共 9 对账户
+        const amounts_tolerance: anchor.BN[] = [];
+        const is_in_token: boolean[] = [];
+        const swapRemainingAccounts = [];
+        for (let i = 0; i < 9; i++) {
+            amounts_tolerance.push(new anchor.BN(i < 5 ? 1_000_000 : 0));
+            is_in_token.push(i < 5);
+            swapRemainingAccounts.push(
+                { pubkey: userTokenAccounts[i], isSigner: false, isWritable: true },
+                { pubkey: vaults[i], isSigner: false, isWritable: true }
+            );
+        }
+
+        try {
+            await program.methods
+                .swapAnyswap(amounts_tolerance, is_in_token, null, null)
+                .accountsPartial({
+                    pool: pool,
+                    poolAuthority: poolAuthorityPda,
+                    owner: user.publicKey,
+                    tokenProgram: token.TOKEN_PROGRAM_ID,
+                })
+                .remainingAccounts(swapRemainingAccounts)
+                .preInstructions([
+                    ComputeBudgetProgram.setComputeUnitLimit({ units: 1_000_000 })
+                ])
+                .signers([user])
+                .rpc();
+            expect.fail("9 个 token 的 swap 应该被拒绝");
+        } catch (error: any) {
+            expect(error.error?.errorCode?.code).to.equal("TooManyTokensInSwap");
+        }
+        console.log("✅ 9 个 token 的 swap 被 TooManyTokensInSwap 拒绝");
+    });
 });
 
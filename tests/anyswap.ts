@@ -166,9 +166,11 @@ describe("anyswap", () => {
 
     // 添加 token0（第一个 token，pool 为空，不需要流动性）
     await program.methods
-      .addTokenToPool(weight0)
+      .addTokenToPool(weight0, new anchor.BN(0), 0, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
+        poolMint: poolMint,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
         mint: mint0,
         vault: vault0,
         adminToken: adminToken0Account.address,
@@ -181,9 +183,11 @@ describe("anyswap", () => {
 
     // 添加 token1（pool 中已有 token0，但 vault0 为空，不需要流动性）
     await program.methods
-      .addTokenToPool(weight1)
+      .addTokenToPool(weight1, new anchor.BN(0), 0, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
+        poolMint: poolMint,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
         mint: mint1,
         vault: vault1,
         adminToken: adminToken1Account.address,
@@ -199,9 +203,11 @@ describe("anyswap", () => {
 
     // 添加 token2（pool 中已有 token0 和 token1，但 vault 都为空，不需要流动性）
     await program.methods
-      .addTokenToPool(weight2)
+      .addTokenToPool(weight2, new anchor.BN(0), 0, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
+        poolMint: poolMint,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
         mint: mint2,
         vault: vault2,
         adminToken: adminToken2Account.address,
@@ -313,7 +319,7 @@ describe("anyswap", () => {
 
     // 调用 add_liquidity
     const tx = await program.methods
-      .addLiquidity(amounts)
+      .addLiquidity(amounts, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
         poolAuthority: poolAuthorityPda,
@@ -321,6 +327,7 @@ describe("anyswap", () => {
         userPoolAta: payerPoolAta,
         owner: payer.publicKey,
         tokenProgram: token.TOKEN_PROGRAM_ID,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
       })
       .remainingAccounts([
         { pubkey: payerToken0Account, isSigner: false, isWritable: true },
@@ -426,7 +433,7 @@ describe("anyswap", () => {
     });
     
     await program.methods
-      .swapAnyswap(amounts_tolerance1, is_in_token1)
+      .swapAnyswap(amounts_tolerance1, is_in_token1, null, null)
       .accountsPartial({
         pool: pool,
         poolAuthority: poolAuthorityPda,
@@ -458,10 +465,10 @@ describe("anyswap", () => {
     const vault2BalanceAfterSwap1 = await token.getAccount(connection, vault2);
     const vault1Increase = Number(vault1BalanceAfterSwap1.amount) - Number(vault1BalanceBeforeSwap1.amount);
     const vault2Decrease = Number(vault2BalanceBeforeSwap1.amount) - Number(vault2BalanceAfterSwap1.amount);
-    const amountInAfterFee = swapAmount1 - expectedFee1; // 扣除手续费后进入vault的金额
-    expect(vault1Increase).to.equal(amountInAfterFee); // vault1 增加扣费后的金额
+    // 手续费随输入一起转入 vault，vault1 增加完整的输入
+    expect(vault1Increase).to.equal(swapAmount1);
     expect(Math.abs(vault2Decrease - expectedOut1)).to.be.lessThanOrEqual(1); // vault2 减少输出量（允许±1误差）
-    console.log("Swap1 后 Vault1 增加:", vault1Increase.toLocaleString(), "(扣费后)");
+    console.log("Swap1 后 Vault1 增加:", vault1Increase.toLocaleString(), "(含手续费)");
     console.log("Swap1 后 Vault2 减少:", vault2Decrease.toLocaleString());
 
     // 4. 管理员修改权重为 40, 20, 40
@@ -528,7 +535,7 @@ describe("anyswap", () => {
     });
     
     await program.methods
-      .swapAnyswap(amounts_tolerance2, is_in_token2)
+      .swapAnyswap(amounts_tolerance2, is_in_token2, null, null)
       .accountsPartial({
         pool: pool,
         poolAuthority: poolAuthorityPda,
@@ -563,8 +570,7 @@ describe("anyswap", () => {
     const vault2Increase = Number(vault2BalanceAfterSwap2.amount) - Number(vault2BalanceBeforeSwap2.amount);
     const vault0Decrease = Number(vault0BalanceBeforeSwap2.amount) - Number(vault0BalanceAfterSwap2.amount);
     
-    const amountIn2AfterFee = swapAmount2 - expectedFee2; // 扣除手续费后进入vault的金额
-    expect(Math.abs(vault2Increase - amountIn2AfterFee)).to.be.lessThanOrEqual(1); // vault2 增加扣费后的金额（允许±1误差）
+    expect(vault2Increase).to.equal(swapAmount2); // vault2 增加完整的输入（含手续费）
     expect(Math.abs(vault0Decrease - expectedOut2)).to.be.lessThanOrEqual(2); // vault0 减少输出量（允许±2误差）
     
     // 手续费保留在 vault 中：vault_in 收到的比应该收到的多（因为手续费）
@@ -701,9 +707,11 @@ describe("anyswap", () => {
     }
     
     await program.methods
-      .addTokenToPool(weight3)
+      .addTokenToPool(weight3, new anchor.BN(0), 0, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
+        poolMint: poolMint,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
         mint: mint3,
         vault: vault3,
         adminToken: adminToken3Account.address,
@@ -89,7 +89,7 @@ describe("anyswap LP 测试", () => {
       program.programId
     );
 
-    const poolSpace = 8 + 2 + 6 + 32 + 8 + 8 + 8 + (72 * 1024);
+    const poolSpace = 8 + 2 + 6 + 32 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + (128 * 4) + 8 + 8 + 32 + 8 + 8 + 32 + (144 * 1024);
     const lamports = await connection.getMinimumBalanceForRentExemption(poolSpace);
 
     const createAccountIx = SystemProgram.createAccount({
@@ -101,7 +101,7 @@ describe("anyswap LP 测试", () => {
     });
 
     const createPoolIx = await program.methods
-      .createPool(feeNumerator, feeDenominator)
+      .createPool(feeNumerator, feeDenominator, false, false, null)
       .accountsPartial({
         poolCreator: poolCreator.publicKey,
         pool: pool,
@@ -268,9 +268,11 @@ describe("anyswap LP 测试", () => {
 
     // 添加 token0（第一个 token，pool 为空，不需要流动性）
     await program.methods
-      .addTokenToPool(weight0)
+      .addTokenToPool(weight0, new anchor.BN(0), 0, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
+        poolMint: poolMint,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
         mint: mint0,
         vault: vault0,
         adminToken: adminToken0Account,
@@ -290,9 +292,11 @@ describe("anyswap LP 测试", () => {
 
     // 添加 token1
     await program.methods
-      .addTokenToPool(weight1)
+      .addTokenToPool(weight1, new anchor.BN(0), 0, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
+        poolMint: poolMint,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
         mint: mint1,
         vault: vault1,
         adminToken: adminToken1Account,
@@ -377,7 +381,7 @@ describe("anyswap LP 测试", () => {
     console.log("  - Token1:", amounts[1].toString());
 
     await program.methods
-      .addLiquidity(amounts)
+      .addLiquidity(amounts, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
         poolAuthority: poolAuthorityPda,
@@ -385,6 +389,7 @@ describe("anyswap LP 测试", () => {
         userPoolAta: adminPoolAta,
         owner: admin.publicKey,
         tokenProgram: token.TOKEN_PROGRAM_ID,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
       })
       .remainingAccounts([
         { pubkey: adminToken0Account, isSigner: false, isWritable: true },
@@ -483,7 +488,7 @@ describe("anyswap LP 测试", () => {
     console.log("  - Token1:", amounts[1].toString());
 
     await program.methods
-      .addLiquidity(amounts)
+      .addLiquidity(amounts, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
         poolAuthority: poolAuthorityPda,
@@ -491,6 +496,7 @@ describe("anyswap LP 测试", () => {
         userPoolAta: user1PoolAta,
         owner: user1.publicKey,
         tokenProgram: token.TOKEN_PROGRAM_ID,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
       })
       .remainingAccounts([
         { pubkey: user1Token0Account, isSigner: false, isWritable: true },
@@ -549,6 +555,7 @@ describe("anyswap LP 测试", () => {
         userPoolAta: adminPoolAta,
         owner: admin.publicKey,
         tokenProgram: token.TOKEN_PROGRAM_ID,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
       })
       .remainingAccounts([
         { pubkey: adminToken0Account, isSigner: false, isWritable: true },
@@ -586,6 +593,80 @@ describe("anyswap LP 测试", () => {
     console.log("  - Vault1 余额:", vault1BalanceAfter);
   });
 
+  it("步骤 5b: 转账获得 LP 的钱包必须先创建持仓记录，并同样等待持有期", async () => {
+    // 新钱包从 admin 收到 LP，没有加入流动性的记录
+    const receiver = Keypair.generate();
+    const receiverAirdrop = await connection.requestAirdrop(
+      receiver.publicKey,
+      2 * anchor.web3.LAMPORTS_PER_SOL
+    );
+    await connection.confirmTransaction(receiverAirdrop);
+
+    const receiverPoolAta = await token.createAccount(connection, payer.payer, poolMint, receiver.publicKey);
+    const receiverToken0Account = await token.createAccount(connection, payer.payer, mint0, receiver.publicKey);
+    const receiverToken1Account = await token.createAccount(connection, payer.payer, mint1, receiver.publicKey);
+    const lpAmount = 1_000;
+    await token.transfer(connection, payer.payer, adminPoolAta, receiverPoolAta, admin, lpAmount);
+
+    const removeLiquidity = () =>
+      program.methods
+        .removeLiquidity(new anchor.BN(lpAmount))
+        .accountsPartial({
+          pool: pool,
+          poolAuthority: poolAuthorityPda,
+          poolMint: poolMint,
+          userPoolAta: receiverPoolAta,
+          owner: receiver.publicKey,
+          tokenProgram: token.TOKEN_PROGRAM_ID,
+          lpTokenProgram: token.TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: receiverToken0Account, isSigner: false, isWritable: true },
+          { pubkey: vault0, isSigner: false, isWritable: true },
+          { pubkey: receiverToken1Account, isSigner: false, isWritable: true },
+          { pubkey: vault1, isSigner: false, isWritable: true },
+        ])
+        .signers([receiver])
+        .rpc();
+
+    // 没有持仓记录时不能移除流动性
+    try {
+      await removeLiquidity();
+      expect.fail("没有持仓记录的钱包不应能移除流动性");
+    } catch (error: any) {
+      expect(error.error?.errorCode?.code).to.equal("AccountNotInitialized");
+    }
+
+    // 创建持仓记录后从当前时间开始计算持有期
+    await program.methods
+      .modifyMinHoldSeconds(new anchor.BN(3600))
+      .accountsPartial({ pool, admin: admin.publicKey })
+      .signers([admin])
+      .rpc();
+    await program.methods
+      .openLpPosition()
+      .accountsPartial({ pool, owner: receiver.publicKey })
+      .signers([receiver])
+      .rpc();
+    try {
+      await removeLiquidity();
+      expect.fail("持有期内不应能移除流动性");
+    } catch (error: any) {
+      expect(error.error?.errorCode?.code).to.equal("HoldPeriodNotMet");
+    }
+
+    // 持有期为 0 时可以退出
+    await program.methods
+      .modifyMinHoldSeconds(new anchor.BN(0))
+      .accountsPartial({ pool, admin: admin.publicKey })
+      .signers([admin])
+      .rpc();
+    await removeLiquidity();
+    const receiverLpAfter = await token.getAccount(connection, receiverPoolAta);
+    expect(Number(receiverLpAfter.amount)).to.equal(0);
+    console.log("✓ 转账获得的 LP 需要持仓记录和持有期才能退出");
+  });
+
   it("步骤 6: Admin 添加 token2 到 pool", async () => {
     const weight2 = new anchor.BN(40);
 
@@ -635,9 +716,11 @@ describe("anyswap LP 测试", () => {
 
     // 添加 token2 到 pool
     await program.methods
-      .addTokenToPool(weight2)
+      .addTokenToPool(weight2, new anchor.BN(0), 0, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
+        poolMint: poolMint,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
         mint: mint2,
         vault: vault2,
         adminToken: adminToken2Account,
@@ -756,7 +839,7 @@ describe("anyswap LP 测试", () => {
     console.log("  - Token2:", amounts[2].toString());
 
     await program.methods
-      .addLiquidity(amounts)
+      .addLiquidity(amounts, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
         poolAuthority: poolAuthorityPda,
@@ -764,6 +847,7 @@ describe("anyswap LP 测试", () => {
         userPoolAta: user2PoolAta,
         owner: user2.publicKey,
         tokenProgram: token.TOKEN_PROGRAM_ID,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
       })
       .remainingAccounts([
         { pubkey: user2Token0Account, isSigner: false, isWritable: true },
@@ -840,6 +924,7 @@ describe("anyswap LP 测试", () => {
         userPoolAta: user1PoolAta,
         owner: user1.publicKey,
         tokenProgram: token.TOKEN_PROGRAM_ID,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
       })
       .remainingAccounts([
         { pubkey: user1Token0Account, isSigner: false, isWritable: true },
@@ -905,14 +990,35 @@ describe("anyswap LP 测试", () => {
     const vault2BalanceBefore = (await token.getAccount(connection, vault2)).amount;
     expect(Number(vault2BalanceBefore)).to.be.greaterThan(0);
     console.log("移除前 Vault2 余额:", vault2BalanceBefore.toString());
+    const adminToken2BalanceBefore = (await token.getAccount(connection, adminToken2Account)).amount;
+    const adminLpBefore = (await token.getAccount(connection, adminPoolAta)).amount;
+    const lpSupplyBefore = (await token.getMint(connection, poolMint)).supply;
+    const vault0Before = (await token.getAccount(connection, vault0)).amount;
+
+    // LP 每份的价值（以 token0 计价）：加权池中总价值 = vault0 * 权重之和 / weight0
+    const valuePerShare = (vault0Amount: bigint, weights: number[], supply: bigint) => {
+      const weightSum = weights.reduce((sum, w) => sum + w, 0);
+      return (Number(vault0Amount) * weightSum) / (weights[0] * Number(supply));
+    };
+    const weightsBefore = poolAccountBefore.tokens.slice(0, 3).map((t: any) => t.weight.toNumber());
+    const shareValueBefore = valuePerShare(vault0Before, weightsBefore, lpSupplyBefore);
+
+    // admin 需要销毁的 LP：LP 总量 * weight2 / 权重之和（向上取整）
+    const weightSumBefore = BigInt(weightsBefore.reduce((sum: number, w: number) => sum + w, 0));
+    const expectedBurn =
+      (lpSupplyBefore * BigInt(weightsBefore[2]) + weightSumBefore - 1n) / weightSumBefore;
 
-    // 移除 token2
+    // 移除 token2，vault2 的余额转给 admin，admin 按 token2 的价值占比销毁 LP
     await program.methods
       .removeTokenFromPool()
-      .accounts({
+      .accountsPartial({
         pool: pool,
         mint: mint2,
+        adminToken: adminToken2Account,
+        poolMint: poolMint,
+        adminPoolAta: adminPoolAta,
         admin: admin.publicKey,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
       })
       .signers([admin])
       .rpc();
@@ -924,11 +1030,30 @@ describe("anyswap LP 测试", () => {
     expect(poolAccountAfter.tokens[0].mintAccount.toString()).to.equal(mint0.toString());
     expect(poolAccountAfter.tokens[1].mintAccount.toString()).to.equal(mint1.toString());
 
+    // vault2 的余额全部转入 admin 的 token2 账户，vault2 已关闭
+    const adminToken2BalanceAfter = (await token.getAccount(connection, adminToken2Account)).amount;
+    expect(Number(adminToken2BalanceAfter - adminToken2BalanceBefore)).to.equal(Number(vault2BalanceBefore));
+    expect(await connection.getAccountInfo(vault2)).to.be.null;
+
+    // admin 销毁了 token2 价值占比的 LP，total_amount_minted 与 mint 供应量同步减少
+    const adminLpAfter = (await token.getAccount(connection, adminPoolAta)).amount;
+    const lpSupplyAfter = (await token.getMint(connection, poolMint)).supply;
+    expect((adminLpBefore - adminLpAfter).toString()).to.equal(expectedBurn.toString());
+    expect((lpSupplyBefore - lpSupplyAfter).toString()).to.equal(expectedBurn.toString());
+    expect(poolAccountAfter.totalAmountMinted.toString()).to.equal(lpSupplyAfter.toString());
+
+    // 剩余 LP（例如 user2）每份的价值不因移除 token2 而下降
+    const vault0After = (await token.getAccount(connection, vault0)).amount;
+    const weightsAfter = poolAccountAfter.tokens.slice(0, 2).map((t: any) => t.weight.toNumber());
+    const shareValueAfter = valuePerShare(vault0After, weightsAfter, lpSupplyAfter);
+    expect(shareValueAfter).to.be.at.least(shareValueBefore * (1 - 1e-9));
+
     console.log("✓ Token2 从 pool 移除成功");
     console.log("  - Token Count:", poolAccountAfter.tokenCount);
     console.log("  - Token0 mint:", poolAccountAfter.tokens[0].mintAccount.toString());
     console.log("  - Token1 mint:", poolAccountAfter.tokens[1].mintAccount.toString());
-    console.log("  - Vault2 余额（移除后，应该保持不变）:", vault2BalanceBefore.toString());
+    console.log("  - 转给 Admin 的 Token2:", vault2BalanceBefore.toString());
+    console.log("  - Admin 销毁的 LP:", expectedBurn.toString());
   });
 
   it("步骤 10: User2 退出一半流动性（此时 pool 中只有 token0 和 token1）", async () => {
@@ -970,6 +1095,7 @@ describe("anyswap LP 测试", () => {
         userPoolAta: user2PoolAta,
         owner: user2.publicKey,
         tokenProgram: token.TOKEN_PROGRAM_ID,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
       })
       .remainingAccounts([
         { pubkey: user2Token0Account, isSigner: false, isWritable: true },
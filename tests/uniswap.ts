@@ -4,6 +4,7 @@ import { Anyswap } from "../target/types/anyswap";
 import * as token from "@solana/spl-token";
 import { PublicKey, Keypair, SystemProgram, ComputeBudgetProgram } from "@solana/web3.js";
 import { expect } from "chai";
+import { createPoolOnClient } from "./utils";
 
 /**
  * Uniswap V2 兼容性测试
@@ -44,6 +45,11 @@ describe("Uniswap V2 Compatibility", () => {
   let payerTokenAAccount: PublicKey;
   let payerTokenBAccount: PublicKey;
 
+  // swap 用户及其 token 账户
+  let user: Keypair;
+  let userTokenAAccount: PublicKey;
+  let userTokenBAccount: PublicKey;
+
   const n_decimals = 9;
 
   it("创建 Uniswap 风格的池子", async () => {
@@ -67,7 +73,7 @@ describe("Uniswap V2 Compatibility", () => {
     poolMint = poolMint_;
 
     // 计算账户大小和租金
-    const poolSpace = 8 + 2 + 6 + 32 + 8 + 8 + 8 + (72 * 1024);
+    const poolSpace = 8 + 2 + 6 + 32 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + (128 * 4) + 8 + 8 + 32 + 8 + 8 + 32 + (144 * 1024);
     const lamports = await connection.getMinimumBalanceForRentExemption(poolSpace);
 
     // 创建账户
@@ -81,7 +87,7 @@ describe("Uniswap V2 Compatibility", () => {
 
     // 创建 pool
     const createPoolIx = await program.methods
-      .createPool(fee_numerator, fee_denominator)
+      .createPool(fee_numerator, fee_denominator, false, false, null)
       .accountsPartial({
         poolCreator: poolCreator.publicKey,
         pool: pool,
@@ -103,6 +109,13 @@ describe("Uniswap V2 Compatibility", () => {
     expect(poolAccount.feeNumerator.toNumber()).to.equal(3);
     expect(poolAccount.feeDenominator.toNumber()).to.equal(1000);
 
+    // LP mint 随 pool 一起创建：供应量为 0，mint authority 为 pool authority，默认精度 9
+    const poolMintAccount = await token.getMint(connection, poolMint);
+    expect(Number(poolMintAccount.supply)).to.equal(0);
+    expect(poolMintAccount.mintAuthority!.toString()).to.equal(poolAuthorityPda.toString());
+    expect(poolMintAccount.decimals).to.equal(9);
+    expect(poolAccount.lpDecimals).to.equal(9);
+
     console.log("✅ Uniswap风格池子创建成功 (0.3% 费率)");
   });
 
@@ -160,9 +173,11 @@ describe("Uniswap V2 Compatibility", () => {
 
     // 添加 TokenA
     await program.methods
-      .addTokenToPool(weight)
+      .addTokenToPool(weight, new anchor.BN(0), 0, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
+        poolMint: poolMint,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
         mint: mintA,
         vault: vaultA,
         adminToken: adminTokenAAccount.address,
@@ -175,9 +190,11 @@ describe("Uniswap V2 Compatibility", () => {
 
     // 添加 TokenB
     await program.methods
-      .addTokenToPool(weight)
+      .addTokenToPool(weight, new anchor.BN(0), 0, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
+        poolMint: poolMint,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
         mint: mintB,
         vault: vaultB,
         adminToken: adminTokenBAccount.address,
@@ -253,7 +270,7 @@ describe("Uniswap V2 Compatibility", () => {
     ];
 
     await program.methods
-      .addLiquidity(amounts)
+      .addLiquidity(amounts, new anchor.BN(0))
       .accountsPartial({
         pool: pool,
         poolAuthority: poolAuthorityPda,
@@ -261,6 +278,7 @@ describe("Uniswap V2 Compatibility", () => {
         userPoolAta: payerPoolAta,
         owner: payer.publicKey,
         tokenProgram: token.TOKEN_PROGRAM_ID,
+        lpTokenProgram: token.TOKEN_PROGRAM_ID,
       })
       .remainingAccounts([
         { pubkey: payerTokenAAccount, isSigner: false, isWritable: true },
@@ -287,7 +305,7 @@ describe("Uniswap V2 Compatibility", () => {
 
   it("执行 Uniswap 风格的 swap（TokenA -> TokenB）", async () => {
     // 创建新用户
-    const user = Keypair.generate();
+    user = Keypair.generate();
     const airdropSignature = await connection.requestAirdrop(
       user.publicKey,
       2 * anchor.web3.LAMPORTS_PER_SOL
@@ -295,7 +313,7 @@ describe("Uniswap V2 Compatibility", () => {
     await connection.confirmTransaction(airdropSignature);
 
     // 给用户 100 TokenA
-    const userTokenAAccount = await token.createAssociatedTokenAccount(
+    userTokenAAccount = await token.createAssociatedTokenAccount(
       connection,
       user,
       mintA,
@@ -313,7 +331,7 @@ describe("Uniswap V2 Compatibility", () => {
     );
 
     // 创建用户的 TokenB 账户
-    const userTokenBAccount = await token.createAssociatedTokenAccount(
+    userTokenBAccount = await token.createAssociatedTokenAccount(
       connection,
       user,
       mintB,
@@ -351,7 +369,7 @@ describe("Uniswap V2 Compatibility", () => {
     });
 
     const swapTx = await program.methods
-      .swapAnyswap(amounts_tolerance, is_in_token)
+      .swapAnyswap(amounts_tolerance, is_in_token, null, null)
       .accountsPartial({
         pool: pool,
         poolAuthority: poolAuthorityPda,
@@ -391,6 +409,218 @@ describe("Uniswap V2 Compatibility", () => {
     console.log("✅ Uniswap风格的swap成功执行");
   });
 
+  it("swap 附带 memo（写入 SPL Memo 程序）", async () => {
+    const MEMO_PROGRAM_ID = new PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+    const memo = "invoice #42";
+    const swapAmount = 1_000_000;
+    await token.mintTo(
+      connection,
+      payer.payer,
+      mintA,
+      userTokenAAccount,
+      payer.publicKey,
+      swapAmount
+    );
+
+    const swapTx = await program.methods
+      .swapAnyswap([new anchor.BN(swapAmount), new anchor.BN(0)], [true, false], null, memo)
+      .accountsPartial({
+        pool: pool,
+        poolAuthority: poolAuthorityPda,
+        owner: user.publicKey,
+        tokenProgram: token.TOKEN_PROGRAM_ID,
+        memoProgram: MEMO_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        { pubkey: userTokenAAccount, isSigner: false, isWritable: true },
+        { pubkey: vaultA, isSigner: false, isWritable: true },
+        { pubkey: userTokenBAccount, isSigner: false, isWritable: true },
+        { pubkey: vaultB, isSigner: false, isWritable: true },
+      ])
+      .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 400000 })])
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+
+    // memo 以 CPI 的形式出现在内部指令中，且只有一条
+    const tx = await connection.getParsedTransaction(swapTx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const memoInstructions = (tx?.meta?.innerInstructions ?? [])
+      .flatMap((inner) => inner.instructions)
+      .filter((ix) => ix.programId.equals(MEMO_PROGRAM_ID));
+    expect(memoInstructions.length).to.equal(1);
+    expect((memoInstructions[0] as any).parsed).to.equal(memo);
+    console.log("✅ swap 的 memo 已写入:", memo);
+  });
+
+  it("输出下限过高时 swap 失败且没有 token 转移", async () => {
+    const swapAmount = 1_000_000;
+    await token.mintTo(
+      connection,
+      payer.payer,
+      mintA,
+      userTokenAAccount,
+      payer.publicKey,
+      swapAmount
+    );
+    const balances = async () =>
+      Promise.all(
+        [userTokenAAccount, vaultA, userTokenBAccount, vaultB].map(async (account) =>
+          (await token.getAccount(connection, account)).amount.toString()
+        )
+      );
+    const before = await balances();
+
+    // 输出下限等于整个 vaultB，不可能满足
+    try {
+      await program.methods
+        .swapAnyswap([new anchor.BN(swapAmount), new anchor.BN(before[3])], [true, false], null, null)
+        .accountsPartial({
+          pool: pool,
+          poolAuthority: poolAuthorityPda,
+          owner: user.publicKey,
+          tokenProgram: token.TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: userTokenAAccount, isSigner: false, isWritable: true },
+          { pubkey: vaultA, isSigner: false, isWritable: true },
+          { pubkey: userTokenBAccount, isSigner: false, isWritable: true },
+          { pubkey: vaultB, isSigner: false, isWritable: true },
+        ])
+        .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 400000 })])
+        .signers([user])
+        .rpc();
+      expect.fail("输出下限过高的 swap 不应该成功");
+    } catch (error: any) {
+      expect(error.error?.errorCode?.code).to.equal("SlippageExceeded");
+    }
+
+    expect(await balances()).to.deep.equal(before);
+    console.log("✅ 滑点超限的 swap 被拒绝，余额未变化");
+  });
+
+  it("min_swap_amount：1 lamport 输入被拒绝，足够大的输入成功", async () => {
+    const minSwapAmount = 1_000;
+    await program.methods
+      .modifyMinSwapAmount(new anchor.BN(minSwapAmount))
+      .accountsPartial({ pool, admin: poolCreator.publicKey })
+      .signers([poolCreator])
+      .rpc();
+
+    // 扣除 0.3% 手续费后恰好为 minSwapAmount
+    const swapAmount = 1_003;
+    await token.mintTo(
+      connection,
+      payer.payer,
+      mintA,
+      userTokenAAccount,
+      payer.publicKey,
+      swapAmount + 1
+    );
+    const swap = (amountIn: number) =>
+      program.methods
+        .swapAnyswap([new anchor.BN(amountIn), new anchor.BN(0)], [true, false], null, null)
+        .accountsPartial({
+          pool: pool,
+          poolAuthority: poolAuthorityPda,
+          owner: user.publicKey,
+          tokenProgram: token.TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: userTokenAAccount, isSigner: false, isWritable: true },
+          { pubkey: vaultA, isSigner: false, isWritable: true },
+          { pubkey: userTokenBAccount, isSigner: false, isWritable: true },
+          { pubkey: vaultB, isSigner: false, isWritable: true },
+        ])
+        .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: 400000 })])
+        .signers([user])
+        .rpc();
+
+    try {
+      await swap(1);
+      expect.fail("1 lamport 的 swap 不应该成功");
+    } catch (error: any) {
+      expect(error.error?.errorCode?.code).to.equal("AmountTooSmall");
+    }
+
+    const userBBefore = await token.getAccount(connection, userTokenBAccount);
+    await swap(swapAmount);
+    const userBAfter = await token.getAccount(connection, userTokenBAccount);
+    expect(userBAfter.amount > userBBefore.amount).to.be.true;
+
+    // 恢复为不限制，避免影响后续测试
+    await program.methods
+      .modifyMinSwapAmount(new anchor.BN(0))
+      .accountsPartial({ pool, admin: poolCreator.publicKey })
+      .signers([poolCreator])
+      .rpc();
+    console.log("✅ min_swap_amount 边界：1 lamport 被拒绝，1003 成功");
+  });
+
+  it("simulate_swap 的结果与随后真实 swap 的转账一致", async () => {
+    const swapAmount = 5 * 10 ** n_decimals;
+    await token.mintTo(
+      connection,
+      payer.payer,
+      mintA,
+      userTokenAAccount,
+      payer.publicKey,
+      swapAmount
+    );
+
+    const amounts_tolerance = [new anchor.BN(swapAmount), new anchor.BN(0)];
+    const is_in_token = [true, false];
+    const accounts = {
+      pool: pool,
+      poolAuthority: poolAuthorityPda,
+      owner: user.publicKey,
+      tokenProgram: token.TOKEN_PROGRAM_ID,
+    };
+    const remainingAccounts = [
+      { pubkey: userTokenAAccount, isSigner: false, isWritable: true },
+      { pubkey: vaultA, isSigner: false, isWritable: true },
+      { pubkey: userTokenBAccount, isSigner: false, isWritable: true },
+      { pubkey: vaultB, isSigner: false, isWritable: true },
+    ];
+    const computeUnits = ComputeBudgetProgram.setComputeUnitLimit({ units: 400000 });
+
+    // 从模拟交易的日志中读取 return data
+    const simulated = await program.methods
+      .simulateSwap(amounts_tolerance, is_in_token)
+      .accountsPartial(accounts)
+      .remainingAccounts(remainingAccounts)
+      .preInstructions([computeUnits])
+      .signers([user])
+      .simulate();
+    const prefix = `Program return: ${program.programId.toBase58()} `;
+    const returnLog = simulated.raw.find((log) => log.startsWith(prefix));
+    expect(returnLog).to.not.be.undefined;
+    const simulation = program.coder.types.decode(
+      "SwapSimulation",
+      Buffer.from(returnLog!.slice(prefix.length), "base64")
+    );
+
+    const vaultABefore = await token.getAccount(connection, vaultA);
+    const userBBefore = await token.getAccount(connection, userTokenBAccount);
+    await program.methods
+      .swapAnyswap(amounts_tolerance, is_in_token, null, null)
+      .accountsPartial(accounts)
+      .remainingAccounts(remainingAccounts)
+      .preInstructions([computeUnits])
+      .signers([user])
+      .rpc();
+    const vaultAAfter = await token.getAccount(connection, vaultA);
+    const userBAfter = await token.getAccount(connection, userTokenBAccount);
+
+    expect(simulation.amounts[0].toString()).to.equal((vaultAAfter.amount - vaultABefore.amount).toString());
+    expect(simulation.amounts[1].toString()).to.equal((userBAfter.amount - userBBefore.amount).toString());
+    // 输入 token 转入完整的输入（手续费随输入进入 vault）
+    expect(simulation.amounts[0].toNumber()).to.equal(swapAmount);
+    expect(simulation.fees[0].toNumber()).to.equal(Math.floor(swapAmount * 3 / 1000));
+    console.log("✅ simulate_swap 与真实 swap 一致:", simulation.amounts.map((a) => a.toString()));
+  });
+
   it("验证与 Uniswap 的价格曲线一致性", async () => {
     const vaultABalance = await token.getAccount(connection, vaultA);
     const vaultBBalance = await token.getAccount(connection, vaultB);
@@ -421,5 +651,44 @@ describe("Uniswap V2 Compatibility", () => {
 
     console.log("✅ 价格曲线与Uniswap V2一致");
   });
+
+  it("close_pool：非空池子被拒绝，空池子关闭后租金退还给 admin", async () => {
+    // 当前池子仍有 token 和 LP，不能关闭
+    try {
+      await program.methods
+        .closePool()
+        .accountsPartial({ pool, admin: poolCreator.publicKey })
+        .signers([poolCreator])
+        .rpc();
+      expect.fail("非空池子不应该能关闭");
+    } catch (error: any) {
+      expect(error.error?.errorCode?.code).to.equal("PoolNotEmpty");
+    }
+
+    // 新建一个空池子并关闭
+    const emptyPool = await createPoolOnClient(
+      provider,
+      program,
+      connection,
+      payer,
+      poolCreator,
+      fee_numerator,
+      fee_denominator
+    );
+    const poolLamports = await connection.getBalance(emptyPool.pool);
+    const adminBefore = await connection.getBalance(poolCreator.publicKey);
+
+    await program.methods
+      .closePool()
+      .accountsPartial({ pool: emptyPool.pool, admin: poolCreator.publicKey })
+      .signers([poolCreator])
+      .rpc();
+
+    // poolCreator 只签名不付交易费，收到的正好是池子账户的租金
+    const adminAfter = await connection.getBalance(poolCreator.publicKey);
+    expect(adminAfter - adminBefore).to.equal(poolLamports);
+    expect(await connection.getAccountInfo(emptyPool.pool)).to.be.null;
+    console.log("✅ 空池子已关闭，退还租金:", poolLamports);
+  });
 });
 
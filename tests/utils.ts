@@ -54,8 +54,8 @@ export async function createPoolOnClient(
     console.log("Pool Authority PDA:", poolAuthorityPda.toString());
     console.log("Pool Mint:", poolMint.toString());
 
-    // 计算账户大小：8 (discriminator) + 2 + 6 + 32 + 8 + 8 + 8 + (72 * 1024) = 73792 bytes
-    const poolSpace = 8 + 2 + 6 + 32 + 8 + 8 + 8 + (72 * 1024); // 73792 bytes
+    // 计算账户大小：8 (discriminator) + 2 + 6 + 32 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + (128 * 4) + 8 + 8 + 32 + 8 + 8 + 32 + (144 * 1024) = 148296 bytes
+    const poolSpace = 8 + 2 + 6 + 32 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 32 + 8 + (128 * 4) + 8 + 8 + 32 + 8 + 8 + 32 + (144 * 1024); // 148296 bytes
     const lamports = await connection.getMinimumBalanceForRentExemption(poolSpace);
 
     // 在客户端预先创建 pool 账户（类似 Openbook 的 bids/asks）
@@ -71,7 +71,7 @@ export async function createPoolOnClient(
     // 创建 createPool 指令
     // admin 使用 poolCreator 作为管理员
     const createPoolIx = await program.methods
-        .createPool(feeNumerator, feeDenominator)
+        .createPool(feeNumerator, feeDenominator, false, false, null)
         .accountsPartial({
             poolCreator: poolCreator.publicKey,
             pool: pool,